@@ -1,13 +1,11 @@
 use cairo_felt::Felt252;
 use num_integer::Integer;
 use sha3::{Digest, Keccak256};
-use starknet_api::core::{
-    ContractAddress, EntryPointSelector, PatriciaKey, L2_ADDRESS_UPPER_BOUND,
-};
+use starknet_api::core::{ContractAddress, EntryPointSelector, L2_ADDRESS_UPPER_BOUND};
 use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
 use starknet_api::state::StorageKey;
 use starknet_api::StarknetApiError;
-use starknet_crypto::FieldElement;
+use starknet_crypto::{poseidon_permute_comp, FieldElement};
 
 use crate::abi::constants;
 use crate::execution::execution_utils::{felt_to_stark_felt, stark_felt_to_felt};
@@ -42,20 +40,16 @@ pub fn selector_from_name(entry_point_name: &str) -> EntryPointSelector {
 }
 
 /// Returns the storage address of a StarkNet storage variable given its name and arguments.
+/// For Cairo 1 contracts, this is the base address of the variable (with no arguments), or of its
+/// entry under the given key, if it is a storage map.
 pub fn get_storage_var_address(
     storage_var_name: &str,
     args: &[StarkFelt],
 ) -> Result<StorageKey, StarknetApiError> {
     let storage_var_name_hash = starknet_keccak(storage_var_name.as_bytes());
-    let storage_var_name_hash = felt_to_stark_felt(&storage_var_name_hash);
+    let storage_var_address = StorageKey::try_from(felt_to_stark_felt(&storage_var_name_hash))?;
 
-    let storage_key_hash =
-        args.iter().fold(storage_var_name_hash, |res, arg| pedersen_hash(&res, arg));
-
-    let storage_key = stark_felt_to_felt(storage_key_hash)
-        .mod_floor(&Felt252::from_bytes_be(&L2_ADDRESS_UPPER_BOUND.to_bytes_be()));
-
-    StorageKey::try_from(felt_to_stark_felt(&storage_key))
+    get_map_entry_address(storage_var_address, args)
 }
 
 /// Gets storage keys for a Uint256 storage variable.
@@ -64,14 +58,60 @@ pub fn get_uint256_storage_var_addresses(
     args: &[StarkFelt],
 ) -> Result<(StorageKey, StorageKey), StarknetApiError> {
     let low_key = get_storage_var_address(storage_var_name, args)?;
-    // TODO(Dori, 1/7/2023): When a standard representation for large integers is set, there may
-    //   be a better way to add 1 to the key.
-    let high_key = StorageKey(PatriciaKey::try_from(StarkFelt::from(
-        FieldElement::from(*low_key.0.key()) + FieldElement::ONE,
-    ))?);
+    let high_key = get_storage_address_at_offset(low_key, 1)?;
     Ok((low_key, high_key))
 }
 
+/// Returns the base address of the entry of a Cairo 1 storage map (`LegacyMap` or `Map`) stored at
+/// the given address, given the serialization of its key; e.g., `[low, high]` for a `u256` key, or
+/// the concatenated serializations of the members of a tuple key.
+/// The entries of nested maps are addressed by applying this once per level.
+pub fn get_map_entry_address(
+    map_address: StorageKey,
+    key: &[StarkFelt],
+) -> Result<StorageKey, StarknetApiError> {
+    let entry_hash =
+        key.iter().fold(*map_address.0.key(), |res, key_part| pedersen_hash(&res, key_part));
+
+    storage_base_address_from_felt(entry_hash)
+}
+
+/// Returns the address at the given offset from the given base address; e.g., of a struct member
+/// (members are laid out consecutively, by their sizes), or of the high word of a `u256`.
+pub fn get_storage_address_at_offset(
+    base_address: StorageKey,
+    offset: u8,
+) -> Result<StorageKey, StarknetApiError> {
+    let address = FieldElement::from(*base_address.0.key()) + FieldElement::from(offset);
+    StorageKey::try_from(StarkFelt::from(address))
+}
+
+/// Returns the base address of the given chunk of a `ByteArray` stored at the given address.
+/// The address holds the length of the byte array; its full 31-byte words, followed by its
+/// pending word, are laid out consecutively over chunks of 256 slots.
+pub fn get_byte_array_chunk_address(
+    address: StorageKey,
+    chunk: u64,
+) -> Result<StorageKey, StarknetApiError> {
+    let mut hades_state = [
+        FieldElement::from(*address.0.key()),
+        FieldElement::from(chunk),
+        FieldElement::from_hex_be(constants::BYTE_ARRAY_MAGIC)
+            .expect("The byte array magic should be a valid field element."),
+    ];
+    poseidon_permute_comp(&mut hades_state);
+
+    storage_base_address_from_felt(StarkFelt::from(hades_state[0]))
+}
+
+/// Reduces the given felt to a storage address, as done by Cairo 1 storage accessors.
+fn storage_base_address_from_felt(felt: StarkFelt) -> Result<StorageKey, StarknetApiError> {
+    let address = stark_felt_to_felt(felt)
+        .mod_floor(&Felt252::from_bytes_be(&L2_ADDRESS_UPPER_BOUND.to_bytes_be()));
+
+    StorageKey::try_from(felt_to_stark_felt(&address))
+}
+
 pub fn get_erc20_balance_var_addresses(
     contract_address: &ContractAddress,
 ) -> Result<(StorageKey, StorageKey), StarknetApiError> {
@@ -1,8 +1,14 @@
-use starknet_api::core::EntryPointSelector;
-use starknet_api::hash::StarkFelt;
-use starknet_api::stark_felt;
+use std::collections::HashSet;
 
-use crate::abi::abi_utils::selector_from_name;
+use starknet_api::core::{EntryPointSelector, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{
+    get_byte_array_chunk_address, get_map_entry_address, get_storage_address_at_offset,
+    get_storage_var_address, get_uint256_storage_var_addresses, selector_from_name,
+};
 use crate::abi::constants as abi_constants;
 use crate::transaction::constants as transaction_constants;
 
@@ -35,3 +41,36 @@ fn test_selector_from_name() {
     let expected_empty_selector = EntryPointSelector(stark_felt!(expected_empty_selector));
     assert_eq!(selector_from_name(""), expected_empty_selector);
 }
+
+#[test]
+fn test_storage_layout_addresses() {
+    let map_address = get_storage_var_address("balances", &[]).unwrap();
+    let key = stark_felt!("0x1234");
+    let inner_key = stark_felt!(7_u8);
+
+    // Map entries are addressed as storage variables with arguments; multi-part keys and nested
+    // maps hash their key parts in order.
+    let entry_address = get_map_entry_address(map_address, &[key]).unwrap();
+    assert_eq!(entry_address, get_storage_var_address("balances", &[key]).unwrap());
+    assert_eq!(
+        get_map_entry_address(entry_address, &[inner_key]).unwrap(),
+        get_map_entry_address(map_address, &[key, inner_key]).unwrap()
+    );
+    assert_eq!(get_map_entry_address(map_address, &[]).unwrap(), map_address);
+
+    // Struct members and multi-slot values follow their base address.
+    assert_eq!(
+        get_storage_address_at_offset(StorageKey(patricia_key!("0x10")), 2).unwrap(),
+        StorageKey(patricia_key!("0x12"))
+    );
+    assert_eq!(
+        get_uint256_storage_var_addresses("balances", &[key]).unwrap(),
+        (entry_address, get_storage_address_at_offset(entry_address, 1).unwrap())
+    );
+
+    // Byte array chunks are spread over distinct addresses.
+    let chunk_addresses: HashSet<StorageKey> =
+        (0..3).map(|chunk| get_byte_array_chunk_address(entry_address, chunk).unwrap()).collect();
+    assert_eq!(chunk_addresses.len(), 3);
+    assert!(!chunk_addresses.contains(&entry_address));
+}
@@ -3,6 +3,12 @@ pub const DEFAULT_ENTRY_POINT_NAME: &str = "__default__";
 pub const DEFAULT_ENTRY_POINT_SELECTOR: u64 = 0;
 pub const DEFAULT_L1_ENTRY_POINT_NAME: &str = "__l1_default__";
 
+// Cairo 1 storage layout-related constants.
+pub const BYTE_ARRAY_MAGIC: &str =
+    "0x46a6158a16a947e5916b2a2ca68501a45e93d7110e81aa2d6438b1c57c879a3";
+pub const BYTES_IN_BYTES31: usize = 31;
+pub const BYTE_ARRAY_CHUNK_SIZE: usize = 256;
+
 // The version is considered 0 for L1-Handler transaction hash calculation purposes.
 pub const L1_HANDLER_VERSION: u64 = 0;
 
@@ -4,6 +4,8 @@ use std::sync::Arc;
 use starknet_api::block::{BlockNumber, BlockTimestamp};
 use starknet_api::core::{ChainId, ContractAddress};
 
+use crate::transaction::objects::FeeUnit;
+
 #[derive(Clone, Debug)]
 pub struct BlockContext {
     pub chain_id: ChainId,
@@ -12,12 +14,48 @@ pub struct BlockContext {
 
     // Fee-related.
     pub sequencer_address: ContractAddress,
-    pub fee_token_address: ContractAddress,
+    pub fee_token_addresses: FeeTokenAddresses,
     pub vm_resource_fee_cost: Arc<HashMap<String, f64>>,
-    pub gas_price: u128, // In wei.
+    pub gas_prices: GasPrices,
 
     // Limits.
     pub invoke_tx_max_n_steps: u32,
     pub validate_max_n_steps: u32,
     pub max_recursion_depth: usize,
 }
+
+impl BlockContext {
+    pub fn fee_token_address(&self, fee_unit: FeeUnit) -> ContractAddress {
+        self.fee_token_addresses.get_by_fee_unit(fee_unit)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FeeTokenAddresses {
+    pub eth_fee_token_address: ContractAddress,
+    pub strk_fee_token_address: ContractAddress,
+}
+
+impl FeeTokenAddresses {
+    pub fn get_by_fee_unit(&self, fee_unit: FeeUnit) -> ContractAddress {
+        match fee_unit {
+            FeeUnit::Wei => self.eth_fee_token_address,
+            FeeUnit::Fri => self.strk_fee_token_address,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GasPrices {
+    pub eth_l1_gas_price: u128,  // In wei.
+    pub strk_l1_gas_price: u128, // In fri.
+}
+
+impl GasPrices {
+    pub fn get_by_fee_unit(&self, fee_unit: FeeUnit) -> u128 {
+        match fee_unit {
+            FeeUnit::Wei => self.eth_l1_gas_price,
+            FeeUnit::Fri => self.strk_l1_gas_price,
+        }
+    }
+}
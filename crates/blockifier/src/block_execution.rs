@@ -1,10 +1,18 @@
+use std::collections::HashSet;
+
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
 use starknet_api::block::{BlockHash, BlockNumber};
-use starknet_api::core::ContractAddress;
+use starknet_api::core::{ClassHash, ContractAddress};
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
 
 use crate::abi::constants;
-use crate::state::state_api::State;
+use crate::block_context::BlockContext;
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::state::state_api::{State, StateReader, StateResult};
+use crate::transaction::objects::{TransactionExecutionInfo, TransactionExecutionResult};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
 
 #[cfg(test)]
 #[path = "block_execution_test.rs"]
@@ -27,3 +35,122 @@ pub fn pre_process_block(
         );
     }
 }
+
+/// Executes the transactions of a block, one after the other, on top of the given state.
+///
+/// Each transaction is executed on a transactional state, which is committed if the execution
+/// succeeds and is accepted by the caller, and aborted otherwise; only committed transactions are
+/// part of the block.
+pub struct BlockExecutor<S: StateReader> {
+    pub block_context: BlockContext,
+    pub charge_fee: bool,
+    pub validate: bool,
+
+    // Maintained for counting purposes.
+    pub executed_class_hashes: HashSet<ClassHash>,
+
+    // State-related fields.
+    pub state: CachedState<S>,
+    tx_execution_infos: Vec<TransactionExecutionInfo>,
+}
+
+impl<S: StateReader> BlockExecutor<S> {
+    /// Creates an executor for the block described by the given context, and pre-processes the
+    /// block; see `pre_process_block`.
+    pub fn new(
+        mut state: CachedState<S>,
+        block_context: BlockContext,
+        old_block_number_and_hash: Option<(BlockNumber, BlockHash)>,
+    ) -> Self {
+        pre_process_block(&mut state, old_block_number_and_hash);
+
+        Self {
+            block_context,
+            charge_fee: true,
+            validate: true,
+            executed_class_hashes: HashSet::new(),
+            state,
+            tx_execution_infos: Vec::new(),
+        }
+    }
+
+    /// Executes the given transaction and commits it to the block, unless its execution fails.
+    pub fn execute(
+        &mut self,
+        tx: Transaction,
+    ) -> TransactionExecutionResult<&TransactionExecutionInfo> {
+        self.execute_if(tx, |_, _| true)
+            .map(|tx_execution_info| tx_execution_info.expect("Transaction should be accepted."))
+    }
+
+    /// Executes the given transaction, and commits it to the block if `accept` returns true for
+    /// its execution info and the estimated resources of the Casm hash calculation of the classes
+    /// it newly executes (e.g., if the block has enough room for it). Returns `None` if the
+    /// transaction was not accepted.
+    pub fn execute_if(
+        &mut self,
+        tx: Transaction,
+        accept: impl FnOnce(&TransactionExecutionInfo, &VmExecutionResources) -> bool,
+    ) -> TransactionExecutionResult<Option<&TransactionExecutionInfo>> {
+        let mut transactional_state = CachedState::create_transactional(&mut self.state);
+        // An error drops the transactional state, which aborts it.
+        let tx_execution_info = tx.execute_raw(
+            &mut transactional_state,
+            &self.block_context,
+            self.charge_fee,
+            self.validate,
+        )?;
+        let tx_executed_class_hashes = tx_execution_info.get_executed_class_hashes();
+        let casm_hash_calculation_resources = get_casm_hash_calculation_resources(
+            &mut transactional_state,
+            &self.executed_class_hashes,
+            &tx_executed_class_hashes,
+        )?;
+
+        if !accept(&tx_execution_info, &casm_hash_calculation_resources) {
+            transactional_state.abort();
+            return Ok(None);
+        }
+
+        transactional_state.commit();
+        self.executed_class_hashes.extend(tx_executed_class_hashes);
+        self.tx_execution_infos.push(tx_execution_info);
+        Ok(self.tx_execution_infos.last())
+    }
+
+    /// Returns the state diff resulting in executing the committed transactions, together with
+    /// their execution infos, in order.
+    pub fn finalize(
+        mut self,
+        is_pending_block: bool,
+    ) -> (CommitmentStateDiff, Vec<TransactionExecutionInfo>) {
+        // Do not cache classes that were declared during a pending block.
+        // They will be redeclared, and should not be cached since the content of this block is
+        // transient.
+        if !is_pending_block {
+            self.state.move_classes_to_global_cache();
+        }
+
+        (self.state.to_state_diff(), self.tx_execution_infos)
+    }
+}
+
+/// Returns the estimated VM resources for Casm hash calculation (done by the OS), of the newly
+/// executed classes by the current transaction.
+pub fn get_casm_hash_calculation_resources(
+    state: &mut impl StateReader,
+    executed_class_hashes: &HashSet<ClassHash>,
+    tx_executed_class_hashes: &HashSet<ClassHash>,
+) -> StateResult<VmExecutionResources> {
+    let newly_executed_class_hashes: HashSet<&ClassHash> =
+        tx_executed_class_hashes.difference(executed_class_hashes).collect();
+
+    let mut casm_hash_computation_resources = VmExecutionResources::default();
+
+    for class_hash in newly_executed_class_hashes {
+        let class = state.get_compiled_contract_class(class_hash)?;
+        casm_hash_computation_resources += &class.estimate_casm_hash_computation_resources();
+    }
+
+    Ok(casm_hash_computation_resources)
+}
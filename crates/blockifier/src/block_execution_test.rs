@@ -1,12 +1,22 @@
 use starknet_api::block::{BlockHash, BlockNumber};
-use starknet_api::core::ContractAddress;
-use starknet_api::hash::StarkFelt;
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, Fee};
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
 
+use crate::abi::abi_utils::selector_from_name;
 use crate::abi::constants;
-use crate::block_execution::pre_process_block;
+use crate::block_context::BlockContext;
+use crate::block_execution::{pre_process_block, BlockExecutor};
 use crate::state::state_api::StateReader;
-use crate::test_utils::create_test_state;
+use crate::test_utils::{
+    create_test_state, NonceManager, MAX_FEE, TEST_ACCOUNT_CONTRACT_ADDRESS, TEST_CONTRACT_ADDRESS,
+};
+use crate::transaction::test_utils::{
+    account_invoke_tx, create_state_with_trivial_validation_account,
+};
+use crate::transaction::transaction_execution::Transaction;
 
 #[test]
 fn test_pre_process_block() {
@@ -22,3 +32,57 @@ fn test_pre_process_block() {
     );
     assert_eq!(written_hash.unwrap(), block_hash);
 }
+
+#[test]
+fn test_block_executor() {
+    let block_context = BlockContext::create_for_account_testing();
+    let account_address = contract_address!(TEST_ACCOUNT_CONTRACT_ADDRESS);
+    let mut nonce_manager = NonceManager::default();
+    let mut write_tx = |value: u8| {
+        let execute_calldata = calldata![
+            stark_felt!(TEST_CONTRACT_ADDRESS), // Contract address.
+            selector_from_name("test_storage_read_write").0, // EP selector.
+            stark_felt!(2_u8),                  // Calldata length.
+            stark_felt!(value),                 // Calldata: address.
+            stark_felt!(value)                  // Calldata: value.
+        ];
+        Transaction::AccountTransaction(account_invoke_tx(
+            execute_calldata,
+            account_address,
+            &mut nonce_manager,
+            Fee(MAX_FEE),
+        ))
+    };
+    let (first_tx, second_tx, third_tx) = (write_tx(1), write_tx(2), write_tx(3));
+
+    let block_hash = stark_felt!(20_u8);
+    let mut executor = BlockExecutor::new(
+        create_state_with_trivial_validation_account(),
+        block_context,
+        Some((BlockNumber(10), BlockHash(block_hash))),
+    );
+    assert!(executor.execute(first_tx.clone()).unwrap().revert_error.is_none());
+    assert!(executor.execute_if(second_tx, |_, _| false).unwrap().is_none());
+    // The first transaction was committed; its nonce is already used.
+    assert!(executor.execute(first_tx).is_err());
+    // The second transaction was aborted; its nonce is not used.
+    assert!(executor.execute(third_tx).is_err());
+    assert!(executor.executed_class_hashes.len() > 1);
+
+    let (state_diff, tx_execution_infos) = executor.finalize(false);
+    assert_eq!(tx_execution_infos.len(), 1);
+    assert_eq!(state_diff.address_to_nonce.get(&account_address), Some(&Nonce(stark_felt!(1_u8))));
+    let test_contract_storage =
+        &state_diff.storage_updates[&contract_address!(TEST_CONTRACT_ADDRESS)];
+    assert_eq!(
+        test_contract_storage.get(&StorageKey(patricia_key!(1_u8))),
+        Some(&stark_felt!(1_u8))
+    );
+    assert!(!test_contract_storage.contains_key(&StorageKey(patricia_key!(2_u8))));
+    let block_hash_contract_storage =
+        &state_diff.storage_updates[&contract_address!(constants::BLOCK_HASH_CONTRACT_ADDRESS)];
+    assert_eq!(
+        block_hash_contract_storage.get(&StorageKey(patricia_key!(10_u8))),
+        Some(&block_hash)
+    );
+}
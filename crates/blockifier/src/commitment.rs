@@ -0,0 +1,4 @@
+pub mod class_commitment;
+pub mod errors;
+pub mod patricia_trie;
+pub mod state_commitment;
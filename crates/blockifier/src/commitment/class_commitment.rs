@@ -0,0 +1,79 @@
+use indexmap::IndexMap;
+use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_crypto::{poseidon_hash, FieldElement};
+
+use crate::commitment::patricia_trie::{
+    CommitmentResult, CommitmentStore, PatriciaTrie, TrieHashFunction, TrieNode,
+};
+
+#[cfg(test)]
+#[path = "class_commitment_test.rs"]
+mod test;
+
+/// The version prefix of the class trie leaves.
+pub const CONTRACT_CLASS_LEAF_V0: &[u8] = b"CONTRACT_CLASS_LEAF_V0";
+
+/// Returns the leaf of a class in the class trie: `poseidon("CONTRACT_CLASS_LEAF_V0",
+/// compiled_class_hash)`.
+pub fn class_leaf_hash(compiled_class_hash: CompiledClassHash) -> StarkHash {
+    let prefix = FieldElement::from_byte_slice_be(CONTRACT_CLASS_LEAF_V0)
+        .expect("A short string fits in a felt.");
+    poseidon_hash(prefix, FieldElement::from(compiled_class_hash.0)).into()
+}
+
+/// The commitment to the declared (Cairo 1) classes: a Poseidon trie mapping each class hash to
+/// the leaf derived from its compiled class hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClassCommitment {
+    trie: PatriciaTrie,
+}
+
+impl ClassCommitment {
+    /// Opens the commitment with the given root; use 0 if no class was declared.
+    pub fn new(root: StarkHash) -> Self {
+        Self { trie: PatriciaTrie::new(root, TrieHashFunction::Poseidon) }
+    }
+
+    pub fn root(&self) -> StarkHash {
+        self.trie.root()
+    }
+
+    /// Returns the leaf of the given class; 0 if it was not declared.
+    pub fn get_class_leaf(
+        &self,
+        store: &impl CommitmentStore,
+        class_hash: ClassHash,
+    ) -> CommitmentResult<StarkHash> {
+        self.trie.get(store, class_hash.0)
+    }
+
+    /// Adds the given declared classes (e.g.,
+    /// `CommitmentStateDiff::class_hash_to_compiled_class_hash` of a block); returns the new
+    /// root.
+    pub fn apply_declared_classes(
+        &mut self,
+        store: &mut impl CommitmentStore,
+        declared_classes: &IndexMap<ClassHash, CompiledClassHash>,
+    ) -> CommitmentResult<StarkHash> {
+        let class_leaves = declared_classes.iter().map(|(class_hash, &compiled_class_hash)| {
+            (class_hash.0, class_leaf_hash(compiled_class_hash))
+        });
+        self.trie.update(store, class_leaves)
+    }
+
+    /// Returns a proof of the leaf of the given class; see `patricia_trie::verify_proof`.
+    pub fn prove(
+        &self,
+        store: &impl CommitmentStore,
+        class_hash: ClassHash,
+    ) -> CommitmentResult<Vec<TrieNode>> {
+        self.trie.prove(store, class_hash.0)
+    }
+}
+
+impl Default for ClassCommitment {
+    fn default() -> Self {
+        Self::new(StarkFelt::default())
+    }
+}
@@ -0,0 +1,64 @@
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::{class_hash, stark_felt};
+use starknet_crypto::{poseidon_hash, FieldElement};
+
+use crate::commitment::class_commitment::{class_leaf_hash, ClassCommitment};
+use crate::commitment::patricia_trie::{
+    verify_proof, InMemoryCommitmentStore, PatriciaTrie, TrieHashFunction,
+};
+
+fn declared_classes(classes: &[(&str, &str)]) -> IndexMap<ClassHash, CompiledClassHash> {
+    classes
+        .iter()
+        .map(|(class_hash, compiled_class_hash)| {
+            (class_hash!(*class_hash), CompiledClassHash(stark_felt!(*compiled_class_hash)))
+        })
+        .collect()
+}
+
+#[test]
+fn leaf_hash() {
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x1234"));
+    // The short string "CONTRACT_CLASS_LEAF_V0".
+    let prefix =
+        FieldElement::from_hex_be("0x434f4e54524143545f434c4153535f4c4541465f5630").unwrap();
+    assert_eq!(
+        class_leaf_hash(compiled_class_hash),
+        StarkFelt::from(poseidon_hash(prefix, FieldElement::from(compiled_class_hash.0)))
+    );
+}
+
+#[test]
+fn apply_declared_classes() {
+    let mut store = InMemoryCommitmentStore::default();
+    let mut class_commitment = ClassCommitment::default();
+    assert_eq!(class_commitment.root(), StarkHash::default());
+
+    let first_block_classes = declared_classes(&[("0x10", "0x11"), ("0x20", "0x21")]);
+    class_commitment.apply_declared_classes(&mut store, &first_block_classes).unwrap();
+    let second_block_classes = declared_classes(&[("0x30", "0x31")]);
+    let root = class_commitment.apply_declared_classes(&mut store, &second_block_classes).unwrap();
+
+    // The class trie is a Poseidon trie of the class leaves.
+    let mut expected_trie = PatriciaTrie::empty(TrieHashFunction::Poseidon);
+    let expected_leaves = first_block_classes.iter().chain(&second_block_classes).map(
+        |(class_hash, &compiled_class_hash)| (class_hash.0, class_leaf_hash(compiled_class_hash)),
+    );
+    assert_eq!(expected_trie.update(&mut store, expected_leaves).unwrap(), root);
+
+    let class_hash = class_hash!("0x20");
+    let expected_leaf = class_leaf_hash(CompiledClassHash(stark_felt!("0x21")));
+    assert_eq!(class_commitment.get_class_leaf(&store, class_hash).unwrap(), expected_leaf);
+    let proof = class_commitment.prove(&store, class_hash).unwrap();
+    assert_eq!(
+        verify_proof(root, class_hash.0, &proof, TrieHashFunction::Poseidon).unwrap(),
+        expected_leaf
+    );
+    assert_eq!(
+        class_commitment.get_class_leaf(&store, class_hash!("0x40")).unwrap(),
+        StarkHash::default()
+    );
+}
@@ -0,0 +1,19 @@
+use starknet_api::hash::{StarkFelt, StarkHash};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommitmentError {
+    #[error("Proof does not match the root {root} for key {key}.")]
+    InvalidProof { root: StarkHash, key: StarkFelt },
+    #[error("Trie key {0} exceeds 251 bits.")]
+    KeyOutOfRange(StarkFelt),
+    #[error("Contract state {0} is missing from the commitment store.")]
+    MissingContractState(StarkHash),
+    #[error("Trie node {0} is missing from the commitment store.")]
+    MissingNode(StarkHash),
+    #[error("Trie node {0} is not a binary node.")]
+    UnexpectedNode(StarkHash),
+    /// Represents all unexpected errors of a persistent commitment store.
+    #[error("Failed to access the commitment store: {0}.")]
+    StoreError(String),
+}
@@ -0,0 +1,456 @@
+use std::collections::{BTreeMap, HashMap};
+
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_crypto::{poseidon_hash, FieldElement};
+
+use crate::commitment::errors::CommitmentError;
+use crate::commitment::state_commitment::ContractState;
+
+#[cfg(test)]
+#[path = "patricia_trie_test.rs"]
+mod test;
+
+pub type CommitmentResult<T> = Result<T, CommitmentError>;
+
+/// The height of Starknet's tries; keys are 251-bit.
+pub const TRIE_HEIGHT: usize = 251;
+
+/// The hash function of a trie; Starknet's contract and storage tries use Pedersen, and its
+/// class trie uses Poseidon.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrieHashFunction {
+    Pedersen,
+    Poseidon,
+}
+
+impl TrieHashFunction {
+    pub fn hash(&self, felt0: &StarkFelt, felt1: &StarkFelt) -> StarkHash {
+        match self {
+            TrieHashFunction::Pedersen => pedersen_hash(felt0, felt1),
+            TrieHashFunction::Poseidon => {
+                poseidon_hash(FieldElement::from(*felt0), FieldElement::from(*felt1)).into()
+            }
+        }
+    }
+}
+
+/// An inner node of a trie, stored under its hash. Leaves are the stored values themselves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrieNode {
+    Binary {
+        left: StarkHash,
+        right: StarkHash,
+    },
+    /// A path of `length` single-child nodes, whose directions are the bits of `path` (from its
+    /// most significant bit; 0 is left), down to `child`.
+    Edge {
+        child: StarkHash,
+        path: StarkFelt,
+        length: u8,
+    },
+}
+
+impl TrieNode {
+    pub fn hash(&self, hash_function: TrieHashFunction) -> StarkHash {
+        match self {
+            TrieNode::Binary { left, right } => hash_function.hash(left, right),
+            TrieNode::Edge { child, path, length } => {
+                let hash = hash_function.hash(child, path);
+                (FieldElement::from(hash) + FieldElement::from(*length)).into()
+            }
+        }
+    }
+}
+
+/// Holds the nodes of any number of tries, and the contract states committed to by the leaves of
+/// the contract trie; all are keyed by their hashes.
+pub trait CommitmentStore {
+    fn get_node(&self, hash: &StarkHash) -> CommitmentResult<Option<TrieNode>>;
+
+    fn set_node(&mut self, hash: StarkHash, node: TrieNode) -> CommitmentResult<()>;
+
+    fn get_contract_state(&self, hash: &StarkHash) -> CommitmentResult<Option<ContractState>>;
+
+    fn set_contract_state(
+        &mut self,
+        hash: StarkHash,
+        contract_state: ContractState,
+    ) -> CommitmentResult<()>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryCommitmentStore {
+    pub nodes: HashMap<StarkHash, TrieNode>,
+    pub contract_states: HashMap<StarkHash, ContractState>,
+}
+
+impl CommitmentStore for InMemoryCommitmentStore {
+    fn get_node(&self, hash: &StarkHash) -> CommitmentResult<Option<TrieNode>> {
+        Ok(self.nodes.get(hash).copied())
+    }
+
+    fn set_node(&mut self, hash: StarkHash, node: TrieNode) -> CommitmentResult<()> {
+        self.nodes.insert(hash, node);
+        Ok(())
+    }
+
+    fn get_contract_state(&self, hash: &StarkHash) -> CommitmentResult<Option<ContractState>> {
+        Ok(self.contract_states.get(hash).copied())
+    }
+
+    fn set_contract_state(
+        &mut self,
+        hash: StarkHash,
+        contract_state: ContractState,
+    ) -> CommitmentResult<()> {
+        self.contract_states.insert(hash, contract_state);
+        Ok(())
+    }
+}
+
+/// A binary Patricia-Merkle trie of height 251, identified by its root hash; the root of an empty
+/// trie is 0, and a leaf holding 0 is considered empty.
+/// Nodes are never overwritten nor deleted, so the roots of previous versions remain valid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PatriciaTrie {
+    root: StarkHash,
+    hash_function: TrieHashFunction,
+}
+
+impl PatriciaTrie {
+    pub fn new(root: StarkHash, hash_function: TrieHashFunction) -> Self {
+        Self { root, hash_function }
+    }
+
+    pub fn empty(hash_function: TrieHashFunction) -> Self {
+        Self::new(StarkHash::default(), hash_function)
+    }
+
+    pub fn root(&self) -> StarkHash {
+        self.root
+    }
+
+    /// Returns the value of the given leaf; 0 if it does not exist.
+    pub fn get(&self, store: &impl CommitmentStore, key: StarkFelt) -> CommitmentResult<StarkFelt> {
+        let key = Path::from_key(key)?;
+        let mut subtree = load_subtree(store, self.root, TRIE_HEIGHT)?;
+        let mut height = TRIE_HEIGHT;
+        loop {
+            let Subtree::Path { path, child } = subtree else { return Ok(StarkFelt::default()) };
+            if key.segment(height, path.length) != path {
+                return Ok(StarkFelt::default());
+            }
+
+            height -= path.length;
+            if height == 0 {
+                return Ok(child);
+            }
+            let (left, right) = load_binary_node(store, child)?;
+            let next_child = if key.bit(height - 1) { right } else { left };
+            height -= 1;
+            subtree = load_subtree(store, next_child, height)?;
+        }
+    }
+
+    /// Sets the given leaves (later ones take precedence), storing the new nodes; returns the new
+    /// root. Setting a leaf to 0 removes it.
+    pub fn update(
+        &mut self,
+        store: &mut impl CommitmentStore,
+        leaves: impl IntoIterator<Item = (StarkFelt, StarkFelt)>,
+    ) -> CommitmentResult<StarkHash> {
+        let leaves = leaves
+            .into_iter()
+            .map(|(key, value)| Ok((Path::from_key(key)?, value)))
+            .collect::<CommitmentResult<BTreeMap<_, _>>>()?;
+        let leaves: Vec<_> = leaves.into_iter().collect();
+
+        let root_subtree = load_subtree(store, self.root, TRIE_HEIGHT)?;
+        let updated_root_subtree =
+            self.update_subtree(store, root_subtree, TRIE_HEIGHT, &leaves)?;
+        self.root = self.commit(store, updated_root_subtree)?;
+        Ok(self.root)
+    }
+
+    /// Returns the nodes on the path from the root to the given leaf; the path ends early if the
+    /// leaf does not exist. See `verify_proof`.
+    pub fn prove(
+        &self,
+        store: &impl CommitmentStore,
+        key: StarkFelt,
+    ) -> CommitmentResult<Vec<TrieNode>> {
+        let key = Path::from_key(key)?;
+        let (mut hash, mut height) = (self.root, TRIE_HEIGHT);
+        let mut proof = Vec::new();
+        while height > 0 && hash != StarkHash::default() {
+            let node = store.get_node(&hash)?.ok_or(CommitmentError::MissingNode(hash))?;
+            proof.push(node);
+            match node {
+                TrieNode::Binary { left, right } => {
+                    hash = if key.bit(height - 1) { right } else { left };
+                    height -= 1;
+                }
+                TrieNode::Edge { child, path, length } => {
+                    let length = usize::from(length);
+                    if key.segment(height, length) != Path::new(path, length) {
+                        break;
+                    }
+                    hash = child;
+                    height -= length;
+                }
+            }
+        }
+
+        Ok(proof)
+    }
+
+    fn update_subtree(
+        &self,
+        store: &mut impl CommitmentStore,
+        subtree: Subtree,
+        height: usize,
+        leaves: &[(Path, StarkFelt)],
+    ) -> CommitmentResult<Subtree> {
+        if leaves.is_empty() {
+            return Ok(subtree);
+        }
+        if height == 0 {
+            let value = leaves[0].1;
+            return Ok(if value == StarkFelt::default() {
+                Subtree::Empty
+            } else {
+                Subtree::Path { path: Path::EMPTY, child: value }
+            });
+        }
+
+        let (left, right) = split_subtree(store, subtree, height)?;
+        let n_left_leaves = leaves.partition_point(|(key, _)| !key.bit(height - 1));
+        let (left_leaves, right_leaves) = leaves.split_at(n_left_leaves);
+        let left = self.update_subtree(store, left, height - 1, left_leaves)?;
+        let right = self.update_subtree(store, right, height - 1, right_leaves)?;
+
+        Ok(match (left, right) {
+            (Subtree::Empty, Subtree::Empty) => Subtree::Empty,
+            (Subtree::Path { path, child }, Subtree::Empty) => {
+                Subtree::Path { path: path.prepend(false), child }
+            }
+            (Subtree::Empty, Subtree::Path { path, child }) => {
+                Subtree::Path { path: path.prepend(true), child }
+            }
+            (left, right) => {
+                let node = TrieNode::Binary {
+                    left: self.commit(store, left)?,
+                    right: self.commit(store, right)?,
+                };
+                Subtree::Path { path: Path::EMPTY, child: self.store_node(store, node)? }
+            }
+        })
+    }
+
+    /// Returns the hash of the given subtree, storing its top edge node, if any.
+    fn commit(
+        &self,
+        store: &mut impl CommitmentStore,
+        subtree: Subtree,
+    ) -> CommitmentResult<StarkHash> {
+        match subtree {
+            Subtree::Empty => Ok(StarkHash::default()),
+            Subtree::Path { path, child } if path.length == 0 => Ok(child),
+            Subtree::Path { path, child } => {
+                let node = TrieNode::Edge {
+                    child,
+                    path: path.felt(),
+                    length: u8::try_from(path.length)
+                        .expect("Edge length exceeds the trie height."),
+                };
+                self.store_node(store, node)
+            }
+        }
+    }
+
+    fn store_node(
+        &self,
+        store: &mut impl CommitmentStore,
+        node: TrieNode,
+    ) -> CommitmentResult<StarkHash> {
+        let hash = node.hash(self.hash_function);
+        store.set_node(hash, node)?;
+        Ok(hash)
+    }
+}
+
+/// Returns the value proven by the given proof (see `PatriciaTrie::prove`) to be held by the given
+/// leaf, under the given root; 0 if the leaf does not exist.
+pub fn verify_proof(
+    root: StarkHash,
+    key: StarkFelt,
+    proof: &[TrieNode],
+    hash_function: TrieHashFunction,
+) -> CommitmentResult<StarkFelt> {
+    let invalid_proof = || CommitmentError::InvalidProof { root, key };
+    let key_path = Path::from_key(key)?;
+    let (mut expected_hash, mut height) = (root, TRIE_HEIGHT);
+    for (i, node) in proof.iter().enumerate() {
+        if height == 0 || node.hash(hash_function) != expected_hash {
+            return Err(invalid_proof());
+        }
+
+        match *node {
+            TrieNode::Binary { left, right } => {
+                expected_hash = if key_path.bit(height - 1) { right } else { left };
+                height -= 1;
+            }
+            TrieNode::Edge { child, path, length } => {
+                let length = usize::from(length);
+                if length > height {
+                    return Err(invalid_proof());
+                }
+                if key_path.segment(height, length) != Path::new(path, length) {
+                    // The leaf is not in the trie; this must be the last node.
+                    let is_last_node = i + 1 == proof.len();
+                    return if is_last_node {
+                        Ok(StarkFelt::default())
+                    } else {
+                        Err(invalid_proof())
+                    };
+                }
+                expected_hash = child;
+                height -= length;
+            }
+        }
+    }
+
+    match height {
+        0 => Ok(expected_hash),
+        _ if expected_hash == StarkHash::default() => Ok(StarkFelt::default()),
+        _ => Err(invalid_proof()),
+    }
+}
+
+/// A subtree in its canonical form: empty, or a (possibly empty) path down to a leaf or a binary
+/// node.
+#[derive(Clone, Copy, Debug)]
+enum Subtree {
+    Empty,
+    Path { path: Path, child: StarkHash },
+}
+
+/// Returns the subtree of the given height rooted at the given hash.
+fn load_subtree(
+    store: &impl CommitmentStore,
+    hash: StarkHash,
+    height: usize,
+) -> CommitmentResult<Subtree> {
+    if hash == StarkHash::default() {
+        return Ok(Subtree::Empty);
+    }
+    if height == 0 {
+        return Ok(Subtree::Path { path: Path::EMPTY, child: hash });
+    }
+
+    match store.get_node(&hash)?.ok_or(CommitmentError::MissingNode(hash))? {
+        TrieNode::Binary { .. } => Ok(Subtree::Path { path: Path::EMPTY, child: hash }),
+        TrieNode::Edge { child, path, length } => {
+            Ok(Subtree::Path { path: Path::new(path, usize::from(length)), child })
+        }
+    }
+}
+
+fn load_binary_node(
+    store: &impl CommitmentStore,
+    hash: StarkHash,
+) -> CommitmentResult<(StarkHash, StarkHash)> {
+    match store.get_node(&hash)?.ok_or(CommitmentError::MissingNode(hash))? {
+        TrieNode::Binary { left, right } => Ok((left, right)),
+        TrieNode::Edge { .. } => Err(CommitmentError::UnexpectedNode(hash)),
+    }
+}
+
+/// Returns the left and right subtrees of the given (non-leaf) subtree.
+fn split_subtree(
+    store: &impl CommitmentStore,
+    subtree: Subtree,
+    height: usize,
+) -> CommitmentResult<(Subtree, Subtree)> {
+    match subtree {
+        Subtree::Empty => Ok((Subtree::Empty, Subtree::Empty)),
+        Subtree::Path { path, child } if path.length == 0 => {
+            let (left, right) = load_binary_node(store, child)?;
+            Ok((load_subtree(store, left, height - 1)?, load_subtree(store, right, height - 1)?))
+        }
+        Subtree::Path { path, child } => {
+            let (is_right, path) = path.pop_top();
+            let subtree = Subtree::Path { path, child };
+            Ok(if is_right { (Subtree::Empty, subtree) } else { (subtree, Subtree::Empty) })
+        }
+    }
+}
+
+/// A sequence of up to 251 directions, as the bits of a big-endian number (1 is right); the first
+/// direction is the most significant bit.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Path {
+    // Compared first, so that full keys are ordered as numbers.
+    bits: [u8; 32],
+    length: usize,
+}
+
+impl Path {
+    const EMPTY: Path = Path { bits: [0; 32], length: 0 };
+
+    fn new(bits: StarkFelt, length: usize) -> Self {
+        let bits = bits.bytes().try_into().expect("A felt is 32 bytes long.");
+        Self { bits, length }
+    }
+
+    fn from_key(key: StarkFelt) -> CommitmentResult<Self> {
+        let path = Self::new(key, TRIE_HEIGHT);
+        // The 5 most significant bits must be 0.
+        if path.bits[0] >> 3 != 0 {
+            return Err(CommitmentError::KeyOutOfRange(key));
+        }
+        Ok(path)
+    }
+
+    fn felt(&self) -> StarkFelt {
+        StarkFelt::new(self.bits).expect("A path of up to 251 bits fits in a felt.")
+    }
+
+    /// Returns the bit at the given index, counting from the least significant bit.
+    fn bit(&self, index: usize) -> bool {
+        (self.bits[31 - index / 8] >> (index % 8)) & 1 == 1
+    }
+
+    fn set_bit(&mut self, index: usize, bit: bool) {
+        let mask = 1 << (index % 8);
+        if bit {
+            self.bits[31 - index / 8] |= mask;
+        } else {
+            self.bits[31 - index / 8] &= !mask;
+        }
+    }
+
+    /// Returns the `length` directions of a full key leading from the given height.
+    fn segment(&self, height: usize, length: usize) -> Path {
+        let mut segment = Path { bits: [0; 32], length };
+        for index in 0..length {
+            segment.set_bit(index, self.bit(height - length + index));
+        }
+        segment
+    }
+
+    /// Returns the first direction and the rest of the path.
+    fn pop_top(mut self) -> (bool, Path) {
+        let top_index = self.length - 1;
+        let top_bit = self.bit(top_index);
+        self.set_bit(top_index, false);
+        self.length -= 1;
+        (top_bit, self)
+    }
+
+    fn prepend(mut self, bit: bool) -> Path {
+        self.set_bit(self.length, bit);
+        self.length += 1;
+        self
+    }
+}
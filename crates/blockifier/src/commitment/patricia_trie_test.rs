@@ -0,0 +1,162 @@
+use assert_matches::assert_matches;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::stark_felt;
+use starknet_crypto::FieldElement;
+
+use crate::commitment::errors::CommitmentError;
+use crate::commitment::patricia_trie::{
+    verify_proof, InMemoryCommitmentStore, PatriciaTrie, TrieHashFunction, TrieNode,
+};
+
+fn edge_hash(child: StarkHash, path: StarkFelt, length: u8) -> StarkHash {
+    TrieNode::Edge { child, path, length }.hash(TrieHashFunction::Pedersen)
+}
+
+fn leaves(leaves: &[(&str, &str)]) -> Vec<(StarkFelt, StarkFelt)> {
+    leaves.iter().map(|(key, value)| (stark_felt!(*key), stark_felt!(*value))).collect()
+}
+
+#[test]
+fn node_hashes() {
+    let (child, path) = (stark_felt!("0x1234"), stark_felt!("0x5"));
+    let expected_edge_hash =
+        FieldElement::from(pedersen_hash(&child, &path)) + FieldElement::from(3_u8);
+    assert_eq!(edge_hash(child, path, 3), StarkFelt::from(expected_edge_hash));
+
+    let binary_node = TrieNode::Binary { left: child, right: path };
+    assert_eq!(binary_node.hash(TrieHashFunction::Pedersen), pedersen_hash(&child, &path));
+    assert_ne!(binary_node.hash(TrieHashFunction::Poseidon), pedersen_hash(&child, &path));
+}
+
+#[test]
+fn root_structure() {
+    let mut store = InMemoryCommitmentStore::default();
+    let mut trie = PatriciaTrie::empty(TrieHashFunction::Pedersen);
+    assert_eq!(trie.root(), StarkHash::default());
+
+    // A single leaf is committed to by an edge from the root.
+    let (key, value) = (stark_felt!("0x2"), stark_felt!("0x77"));
+    let root = trie.update(&mut store, [(key, value)]).unwrap();
+    assert_eq!(root, edge_hash(value, key, 251));
+
+    // Two sibling leaves are joined by a binary node, at the end of an edge from the root.
+    let sibling_value = stark_felt!("0x88");
+    let root = trie.update(&mut store, [(stark_felt!("0x3"), sibling_value)]).unwrap();
+    let binary_node_hash = pedersen_hash(&value, &sibling_value);
+    assert_eq!(root, edge_hash(binary_node_hash, stark_felt!("0x1"), 250));
+}
+
+#[rstest]
+fn updates(
+    #[values(TrieHashFunction::Pedersen, TrieHashFunction::Poseidon)]
+    hash_function: TrieHashFunction,
+) {
+    let all_leaves = leaves(&[
+        ("0x0", "0x10"),
+        ("0x1", "0x11"),
+        ("0x7ff", "0x12"),
+        ("0x5000", "0x13"),
+        ("0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", "0x14"),
+    ]);
+    let mut store = InMemoryCommitmentStore::default();
+
+    let mut trie = PatriciaTrie::empty(hash_function);
+    let root = trie.update(&mut store, all_leaves.clone()).unwrap();
+    for (key, value) in &all_leaves {
+        assert_eq!(trie.get(&store, *key).unwrap(), *value);
+    }
+    assert_eq!(trie.get(&store, stark_felt!("0x2")).unwrap(), StarkFelt::default());
+
+    // The root does not depend on the order of the updates.
+    let mut incremental_trie = PatriciaTrie::empty(hash_function);
+    for leaf in all_leaves.iter().rev() {
+        incremental_trie.update(&mut store, [*leaf]).unwrap();
+    }
+    assert_eq!(incremental_trie.root(), root);
+
+    // Later updates of the same leaf take precedence.
+    let mut overwritten_trie = PatriciaTrie::new(root, hash_function);
+    overwritten_trie.update(&mut store, leaves(&[("0x1", "0x99"), ("0x1", "0x11")])).unwrap();
+    assert_eq!(overwritten_trie.root(), root);
+
+    // Removing leaves restores the previous roots, which remain readable.
+    let mut removed_trie = PatriciaTrie::new(root, hash_function);
+    removed_trie.update(&mut store, leaves(&[("0x7ff", "0x0"), ("0x5000", "0x0")])).unwrap();
+    let mut partial_trie = PatriciaTrie::empty(hash_function);
+    partial_trie.update(&mut store, [all_leaves[0], all_leaves[1], all_leaves[4]]).unwrap();
+    assert_eq!(removed_trie.root(), partial_trie.root());
+    assert_eq!(trie.get(&store, stark_felt!("0x5000")).unwrap(), stark_felt!("0x13"));
+
+    let keys = all_leaves.iter().map(|(key, _)| (*key, StarkFelt::default()));
+    assert_eq!(removed_trie.update(&mut store, keys).unwrap(), StarkHash::default());
+}
+
+#[test]
+fn proofs() {
+    let mut store = InMemoryCommitmentStore::default();
+    let mut trie = PatriciaTrie::empty(TrieHashFunction::Pedersen);
+    let root = trie
+        .update(&mut store, leaves(&[("0x10", "0x1"), ("0x11", "0x2"), ("0x400", "0x3")]))
+        .unwrap();
+
+    for (key, expected_value) in
+        [("0x10", "0x1"), ("0x400", "0x3"), ("0x12", "0x0"), ("0x1", "0x0")]
+    {
+        let key = stark_felt!(key);
+        let proof = trie.prove(&store, key).unwrap();
+        assert_eq!(
+            verify_proof(root, key, &proof, TrieHashFunction::Pedersen).unwrap(),
+            stark_felt!(expected_value)
+        );
+    }
+
+    // A proof does not hold for another root, nor for another leaf.
+    let key = stark_felt!("0x10");
+    let proof = trie.prove(&store, key).unwrap();
+    assert_matches!(
+        verify_proof(stark_felt!("0x1"), key, &proof, TrieHashFunction::Pedersen),
+        Err(CommitmentError::InvalidProof { .. })
+    );
+    assert_matches!(
+        verify_proof(root, key, &proof[..proof.len() - 1], TrieHashFunction::Pedersen),
+        Err(CommitmentError::InvalidProof { .. })
+    );
+    assert_matches!(
+        verify_proof(root, stark_felt!("0x400"), &proof, TrieHashFunction::Pedersen),
+        Err(CommitmentError::InvalidProof { .. })
+    );
+
+    // An empty trie has an empty proof.
+    let empty_trie = PatriciaTrie::empty(TrieHashFunction::Pedersen);
+    let proof = empty_trie.prove(&store, key).unwrap();
+    assert_eq!(proof, []);
+    assert_eq!(
+        verify_proof(StarkHash::default(), key, &proof, TrieHashFunction::Pedersen).unwrap(),
+        StarkFelt::default()
+    );
+}
+
+#[test]
+fn invalid_keys() {
+    let mut store = InMemoryCommitmentStore::default();
+    let mut trie = PatriciaTrie::empty(TrieHashFunction::Pedersen);
+    let key = stark_felt!("0x800000000000000000000000000000000000000000000000000000000000000");
+    assert_matches!(
+        trie.update(&mut store, [(key, stark_felt!(1_u8))]),
+        Err(CommitmentError::KeyOutOfRange(invalid_key)) if invalid_key == key
+    );
+    assert_matches!(trie.get(&store, key), Err(CommitmentError::KeyOutOfRange(_)));
+}
+
+#[test]
+fn missing_nodes() {
+    let store = InMemoryCommitmentStore::default();
+    let root = stark_felt!("0x1234");
+    let trie = PatriciaTrie::new(root, TrieHashFunction::Pedersen);
+    assert_matches!(
+        trie.get(&store, stark_felt!(1_u8)),
+        Err(CommitmentError::MissingNode(missing_root)) if missing_root == root
+    );
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+
+use starknet_api::core::{ClassHash, ContractAddress, GlobalRoot, Nonce};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::commitment::class_commitment::ClassCommitment;
+use crate::commitment::errors::CommitmentError;
+use crate::commitment::patricia_trie::{
+    verify_proof, CommitmentResult, CommitmentStore, PatriciaTrie, TrieHashFunction, TrieNode,
+};
+use crate::state::cached_state::CommitmentStateDiff;
+
+#[cfg(test)]
+#[path = "state_commitment_test.rs"]
+mod test;
+
+/// The version prefix of the global root.
+pub const STARKNET_STATE_V0: &[u8] = b"STARKNET_STATE_V0";
+
+/// Returns the global root of a state: `poseidon("STARKNET_STATE_V0", contracts_root,
+/// classes_root)`, or the contract trie root alone if no class was declared.
+pub fn calculate_global_root(contracts_root: StarkHash, classes_root: StarkHash) -> GlobalRoot {
+    if classes_root == StarkHash::default() {
+        return GlobalRoot(contracts_root);
+    }
+
+    let prefix = FieldElement::from_byte_slice_be(STARKNET_STATE_V0)
+        .expect("A short string fits in a felt.");
+    GlobalRoot(
+        poseidon_hash_many(&[
+            prefix,
+            FieldElement::from(contracts_root),
+            FieldElement::from(classes_root),
+        ])
+        .into(),
+    )
+}
+
+/// The state of a contract, committed to by its leaf in the contract trie.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ContractState {
+    pub class_hash: ClassHash,
+    /// The root of the contract's storage trie.
+    pub storage_root: StarkHash,
+    pub nonce: Nonce,
+}
+
+impl ContractState {
+    /// Returns `H(H(H(class_hash, storage_root), nonce), 0)`, where `H` is the Pedersen hash;
+    /// the hash of an empty contract state is 0, so that it is not included in the trie.
+    pub fn hash(&self) -> StarkHash {
+        if *self == ContractState::default() {
+            return StarkHash::default();
+        }
+
+        let hash = pedersen_hash(&self.class_hash.0, &self.storage_root);
+        let hash = pedersen_hash(&hash, &self.nonce.0);
+        pedersen_hash(&hash, &StarkFelt::default())
+    }
+}
+
+/// A proof of the value of a storage cell, under a global root.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageProof {
+    /// A proof of the contract's leaf in the contract trie.
+    pub contract_proof: Vec<TrieNode>,
+    pub contract_state: ContractState,
+    /// A proof of the cell's leaf in the contract's storage trie.
+    pub storage_proof: Vec<TrieNode>,
+}
+
+impl StorageProof {
+    /// Returns the value proven to be held by the given storage cell, under the given contract
+    /// trie root.
+    pub fn verify(
+        &self,
+        contracts_root: StarkHash,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> CommitmentResult<StarkFelt> {
+        let address = *contract_address.0.key();
+        let contract_leaf = verify_proof(
+            contracts_root,
+            address,
+            &self.contract_proof,
+            TrieHashFunction::Pedersen,
+        )?;
+        if contract_leaf != self.contract_state.hash() {
+            return Err(CommitmentError::InvalidProof { root: contracts_root, key: address });
+        }
+
+        verify_proof(
+            self.contract_state.storage_root,
+            *key.0.key(),
+            &self.storage_proof,
+            TrieHashFunction::Pedersen,
+        )
+    }
+}
+
+/// The commitment to the state: a Pedersen trie mapping each contract address to the hash of its
+/// state, whose storage is in turn committed to by a Pedersen trie, and the class commitment.
+pub struct StateCommitment<S: CommitmentStore> {
+    pub store: S,
+    contracts_trie: PatriciaTrie,
+    class_commitment: ClassCommitment,
+}
+
+impl<S: CommitmentStore> StateCommitment<S> {
+    /// Opens the commitment with the given contract trie and class trie roots, whose nodes are in
+    /// the given store; use 0 for empty tries.
+    pub fn new(store: S, contracts_root: StarkHash, classes_root: StarkHash) -> Self {
+        Self {
+            store,
+            contracts_trie: PatriciaTrie::new(contracts_root, TrieHashFunction::Pedersen),
+            class_commitment: ClassCommitment::new(classes_root),
+        }
+    }
+
+    pub fn contracts_root(&self) -> StarkHash {
+        self.contracts_trie.root()
+    }
+
+    pub fn classes_root(&self) -> StarkHash {
+        self.class_commitment.root()
+    }
+
+    pub fn global_root(&self) -> GlobalRoot {
+        calculate_global_root(self.contracts_root(), self.classes_root())
+    }
+
+    pub fn get_contract_state(
+        &self,
+        contract_address: ContractAddress,
+    ) -> CommitmentResult<ContractState> {
+        let contract_leaf = self.contracts_trie.get(&self.store, *contract_address.0.key())?;
+        if contract_leaf == StarkHash::default() {
+            return Ok(ContractState::default());
+        }
+
+        self.store
+            .get_contract_state(&contract_leaf)?
+            .ok_or(CommitmentError::MissingContractState(contract_leaf))
+    }
+
+    pub fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> CommitmentResult<StarkFelt> {
+        let storage_root = self.get_contract_state(contract_address)?.storage_root;
+        PatriciaTrie::new(storage_root, TrieHashFunction::Pedersen).get(&self.store, *key.0.key())
+    }
+
+    /// Applies the given state diff on top of the current state; returns the new global root.
+    pub fn apply_state_diff(
+        &mut self,
+        state_diff: &CommitmentStateDiff,
+    ) -> CommitmentResult<GlobalRoot> {
+        let modified_contracts: BTreeSet<ContractAddress> = state_diff
+            .address_to_class_hash
+            .keys()
+            .chain(state_diff.address_to_nonce.keys())
+            .chain(state_diff.storage_updates.keys())
+            .copied()
+            .collect();
+
+        let mut contract_leaves = Vec::with_capacity(modified_contracts.len());
+        for contract_address in modified_contracts {
+            let mut contract_state = self.get_contract_state(contract_address)?;
+            if let Some(&class_hash) = state_diff.address_to_class_hash.get(&contract_address) {
+                contract_state.class_hash = class_hash;
+            }
+            if let Some(&nonce) = state_diff.address_to_nonce.get(&contract_address) {
+                contract_state.nonce = nonce;
+            }
+            if let Some(storage_updates) = state_diff.storage_updates.get(&contract_address) {
+                let mut storage_trie =
+                    PatriciaTrie::new(contract_state.storage_root, TrieHashFunction::Pedersen);
+                contract_state.storage_root = storage_trie.update(
+                    &mut self.store,
+                    storage_updates.iter().map(|(key, &value)| (*key.0.key(), value)),
+                )?;
+            }
+
+            let contract_leaf = contract_state.hash();
+            if contract_leaf != StarkHash::default() {
+                self.store.set_contract_state(contract_leaf, contract_state)?;
+            }
+            contract_leaves.push((*contract_address.0.key(), contract_leaf));
+        }
+
+        self.contracts_trie.update(&mut self.store, contract_leaves)?;
+        self.class_commitment.apply_declared_classes(
+            &mut self.store,
+            &state_diff.class_hash_to_compiled_class_hash,
+        )?;
+        Ok(self.global_root())
+    }
+
+    /// Returns a proof of the value of the given storage cell; see `StorageProof::verify`.
+    pub fn prove_storage(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> CommitmentResult<StorageProof> {
+        let contract_proof = self.contracts_trie.prove(&self.store, *contract_address.0.key())?;
+        let contract_state = self.get_contract_state(contract_address)?;
+        let storage_trie =
+            PatriciaTrie::new(contract_state.storage_root, TrieHashFunction::Pedersen);
+        let storage_proof = storage_trie.prove(&self.store, *key.0.key())?;
+        Ok(StorageProof { contract_proof, contract_state, storage_proof })
+    }
+}
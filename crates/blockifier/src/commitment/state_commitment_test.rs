@@ -0,0 +1,267 @@
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, GlobalRoot, Nonce, PatriciaKey,
+};
+use starknet_api::hash::{pedersen_hash, StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::commitment::class_commitment::ClassCommitment;
+use crate::commitment::errors::CommitmentError;
+use crate::commitment::patricia_trie::{
+    InMemoryCommitmentStore, PatriciaTrie, TrieHashFunction, TrieNode,
+};
+use crate::commitment::state_commitment::{calculate_global_root, ContractState, StateCommitment};
+use crate::state::cached_state::CommitmentStateDiff;
+
+fn state_diff(
+    class_hashes: &[(&str, &str)],
+    nonces: &[(&str, u8)],
+    storage_updates: &[(&str, &str, &str)],
+) -> CommitmentStateDiff {
+    let mut state_diff = CommitmentStateDiff {
+        address_to_class_hash: class_hashes
+            .iter()
+            .map(|(address, class_hash)| (contract_address!(*address), class_hash!(*class_hash)))
+            .collect(),
+        address_to_nonce: nonces
+            .iter()
+            .map(|(address, nonce)| (contract_address!(*address), Nonce(stark_felt!(*nonce))))
+            .collect(),
+        storage_updates: IndexMap::new(),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    for (address, key, value) in storage_updates {
+        state_diff
+            .storage_updates
+            .entry(contract_address!(*address))
+            .or_default()
+            .insert(StorageKey(patricia_key!(*key)), stark_felt!(*value));
+    }
+    state_diff
+}
+
+#[test]
+fn contract_state_hash() {
+    assert_eq!(ContractState::default().hash(), StarkHash::default());
+
+    let contract_state = ContractState {
+        class_hash: class_hash!("0x10"),
+        storage_root: stark_felt!("0x20"),
+        nonce: Nonce(stark_felt!(3_u8)),
+    };
+    let expected_hash = pedersen_hash(
+        &pedersen_hash(
+            &pedersen_hash(&stark_felt!("0x10"), &stark_felt!("0x20")),
+            &stark_felt!(3_u8),
+        ),
+        &StarkFelt::default(),
+    );
+    assert_eq!(contract_state.hash(), expected_hash);
+}
+
+#[test]
+fn apply_state_diff() {
+    let mut commitment = StateCommitment::new(
+        InMemoryCommitmentStore::default(),
+        StarkHash::default(),
+        StarkHash::default(),
+    );
+    let first_diff = state_diff(
+        &[("0x100", "0x10"), ("0x200", "0x20")],
+        &[("0x100", 1)],
+        &[("0x100", "0x1", "0x11"), ("0x100", "0x2", "0x12"), ("0x200", "0x1", "0x21")],
+    );
+    let first_root = commitment.apply_state_diff(&first_diff).unwrap();
+    let first_contracts_root = commitment.contracts_root();
+
+    // The global root commits to the contract trie, whose leaves commit to the storage tries.
+    let mut store = InMemoryCommitmentStore::default();
+    let storage_root = PatriciaTrie::empty(TrieHashFunction::Pedersen)
+        .update(&mut store, [(stark_felt!("0x1"), stark_felt!("0x21"))])
+        .unwrap();
+    let contract_state =
+        ContractState { class_hash: class_hash!("0x20"), storage_root, nonce: Nonce::default() };
+    assert_eq!(commitment.get_contract_state(contract_address!("0x200")).unwrap(), contract_state);
+    assert_eq!(
+        commitment
+            .get_storage_at(contract_address!("0x100"), StorageKey(patricia_key!("0x2")))
+            .unwrap(),
+        stark_felt!("0x12")
+    );
+
+    // Applying the diff in parts yields the same root.
+    let mut incremental_commitment = StateCommitment::new(
+        InMemoryCommitmentStore::default(),
+        StarkHash::default(),
+        StarkHash::default(),
+    );
+    incremental_commitment
+        .apply_state_diff(&state_diff(&[("0x200", "0x20")], &[], &[("0x100", "0x2", "0x12")]))
+        .unwrap();
+    let incremental_root = incremental_commitment
+        .apply_state_diff(&state_diff(
+            &[("0x100", "0x10")],
+            &[("0x100", 1)],
+            &[("0x100", "0x1", "0x11"), ("0x200", "0x1", "0x21")],
+        ))
+        .unwrap();
+    assert_eq!(incremental_root, first_root);
+
+    // Changes are applied on top of the given root, which remains readable.
+    let second_root = commitment
+        .apply_state_diff(&state_diff(&[], &[("0x100", 2)], &[("0x100", "0x1", "0x0")]))
+        .unwrap();
+    assert_ne!(second_root, first_root);
+    let previous_commitment =
+        StateCommitment::new(commitment.store.clone(), first_contracts_root, StarkHash::default());
+    assert_eq!(
+        previous_commitment.get_contract_state(contract_address!("0x100")).unwrap().nonce,
+        Nonce(stark_felt!(1_u8))
+    );
+
+    // Emptying a contract state removes it.
+    commitment
+        .apply_state_diff(&state_diff(
+            &[("0x100", "0x0"), ("0x200", "0x0")],
+            &[("0x100", 0)],
+            &[("0x100", "0x2", "0x0"), ("0x200", "0x1", "0x0")],
+        ))
+        .unwrap();
+    assert_eq!(commitment.global_root(), GlobalRoot::default());
+}
+
+#[test]
+fn global_root() {
+    let contracts_root = stark_felt!("0x1234");
+    assert_eq!(
+        calculate_global_root(contracts_root, StarkHash::default()),
+        GlobalRoot(contracts_root)
+    );
+
+    let classes_root = stark_felt!("0x5678");
+    let expected_global_root = poseidon_hash_many(&[
+        FieldElement::from_byte_slice_be(b"STARKNET_STATE_V0").unwrap(),
+        FieldElement::from(contracts_root),
+        FieldElement::from(classes_root),
+    ]);
+    assert_eq!(
+        calculate_global_root(contracts_root, classes_root),
+        GlobalRoot(expected_global_root.into())
+    );
+}
+
+#[test]
+fn declared_classes() {
+    let mut commitment = StateCommitment::new(
+        InMemoryCommitmentStore::default(),
+        StarkHash::default(),
+        StarkHash::default(),
+    );
+    let mut state_diff = state_diff(&[("0x100", "0x10")], &[], &[]);
+    state_diff.class_hash_to_compiled_class_hash =
+        IndexMap::from([(class_hash!("0x10"), CompiledClassHash(stark_felt!("0x20")))]);
+    let global_root = commitment.apply_state_diff(&state_diff).unwrap();
+
+    let mut store = InMemoryCommitmentStore::default();
+    let mut class_commitment = ClassCommitment::default();
+    let classes_root = class_commitment
+        .apply_declared_classes(&mut store, &state_diff.class_hash_to_compiled_class_hash)
+        .unwrap();
+    assert_eq!(commitment.classes_root(), classes_root);
+    assert_eq!(global_root, calculate_global_root(commitment.contracts_root(), classes_root));
+    assert_ne!(global_root, GlobalRoot(commitment.contracts_root()));
+}
+
+#[test]
+fn storage_proofs() {
+    let mut commitment = StateCommitment::new(
+        InMemoryCommitmentStore::default(),
+        StarkHash::default(),
+        StarkHash::default(),
+    );
+    commitment
+        .apply_state_diff(&state_diff(
+            &[("0x100", "0x10"), ("0x200", "0x20")],
+            &[],
+            &[("0x100", "0x1", "0x11"), ("0x100", "0x2", "0x12")],
+        ))
+        .unwrap();
+    let contracts_root = commitment.contracts_root();
+
+    for (address, key, expected_value) in [
+        ("0x100", "0x1", "0x11"),
+        ("0x100", "0x3", "0x0"),
+        ("0x200", "0x1", "0x0"),
+        ("0x300", "0x1", "0x0"),
+    ] {
+        let (address, key) = (contract_address!(address), StorageKey(patricia_key!(key)));
+        let proof = commitment.prove_storage(address, key).unwrap();
+        assert_eq!(
+            proof.verify(contracts_root, address, key).unwrap(),
+            stark_felt!(expected_value)
+        );
+    }
+
+    // A proof with a forged contract state or storage node is rejected.
+    let (address, key) = (contract_address!("0x100"), StorageKey(patricia_key!("0x1")));
+    let proof = commitment.prove_storage(address, key).unwrap();
+    let mut forged_proof = proof.clone();
+    forged_proof.contract_state.nonce = Nonce(stark_felt!(1_u8));
+    assert_matches!(
+        forged_proof.verify(contracts_root, address, key),
+        Err(CommitmentError::InvalidProof { .. })
+    );
+
+    let mut forged_proof = proof;
+    let forged_value = stark_felt!("0x99");
+    let last_node = forged_proof.storage_proof.last_mut().unwrap();
+    match last_node {
+        TrieNode::Binary { left, .. } => *left = forged_value,
+        TrieNode::Edge { child, .. } => *child = forged_value,
+    }
+    assert_matches!(
+        forged_proof.verify(contracts_root, address, key),
+        Err(CommitmentError::InvalidProof { .. })
+    );
+}
+
+#[test]
+fn known_answer() {
+    let mut commitment = StateCommitment::new(
+        InMemoryCommitmentStore::default(),
+        StarkHash::default(),
+        StarkHash::default(),
+    );
+    let mut state_diff = state_diff(&[("0x1", "0x10")], &[("0x1", 1)], &[("0x1", "0x5", "0x7")]);
+    state_diff.class_hash_to_compiled_class_hash =
+        IndexMap::from([(class_hash!("0x10"), CompiledClassHash(stark_felt!("0x20")))]);
+    let global_root = commitment.apply_state_diff(&state_diff).unwrap();
+
+    let contract_state = commitment.get_contract_state(contract_address!("0x1")).unwrap();
+    assert_eq!(
+        contract_state.storage_root,
+        stark_felt!("0x7a784e0f199a28e48d20631c49a4b8ff6fdf7da0aa34d1fb517771f7e4c47b8")
+    );
+    assert_eq!(
+        contract_state.hash(),
+        stark_felt!("0x2b00e6a87ea2fd8a61ee13f2858e73610537a30c1e97ce5012c15505c23cbff")
+    );
+    assert_eq!(
+        commitment.contracts_root(),
+        stark_felt!("0x333c3eecd0606bb249cfb2f453ef3727d0486e19d17f427b57b1a4c21ca9b19")
+    );
+    assert_eq!(
+        commitment.classes_root(),
+        stark_felt!("0x762d676eb3b55fb16db4fbf2d10e9268c075c00a092db16e44080ab1a0c3a57")
+    );
+    assert_eq!(
+        global_root,
+        GlobalRoot(stark_felt!(
+            "0x323772dbaea575883f933a14eeaf9593370d637a8cae68d1bd80b4869c0d3bf"
+        ))
+    );
+}
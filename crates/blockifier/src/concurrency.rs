@@ -0,0 +1,3 @@
+pub mod conflict_graph;
+pub mod parallel_executor;
+pub mod versioned_state;
@@ -0,0 +1,193 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::block_context::BlockContext;
+use crate::concurrency::parallel_executor::deferrable_sequencer_balance_cells;
+use crate::state::cached_state::{StateAccess, StateCell};
+use crate::state::state_api::StateResult;
+use crate::transaction::objects::TransactionExecutionInfo;
+
+#[cfg(test)]
+#[path = "conflict_graph_test.rs"]
+mod test;
+
+/// The kind of a conflict between two transactions accessing the same cell, named after the
+/// accesses of the later and the earlier transaction, respectively.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ConflictKind {
+    /// The later transaction reads a value written by the earlier one.
+    ReadAfterWrite,
+    /// Both transactions write the cell.
+    WriteAfterWrite,
+    /// The later transaction overwrites a value read by the earlier one.
+    WriteAfterRead,
+}
+
+/// A conflict between two transactions of a block; the later one must be executed after the
+/// earlier one.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Conflict {
+    pub earlier: usize,
+    pub later: usize,
+    pub cell: StateCell,
+    pub kind: ConflictKind,
+}
+
+/// The conflicts between the transactions of a block, indexed by their position in the block.
+/// Only direct conflicts are recorded; e.g., a transaction reading a cell conflicts with the last
+/// transaction preceding it that wrote the cell, but not with earlier writers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConflictGraph {
+    n_txs: usize,
+    // Sorted.
+    conflicts: Vec<Conflict>,
+}
+
+#[derive(Default)]
+struct CellAccesses {
+    last_writer: Option<usize>,
+    readers_since_last_write: Vec<usize>,
+    increasers_since_last_write: Vec<usize>,
+}
+
+impl ConflictGraph {
+    /// Builds the graph out of the state accesses of the block's transactions, given in order.
+    pub fn new(state_accesses: &[StateAccess]) -> Self {
+        Self::with_increases(state_accesses, &[])
+    }
+
+    /// Builds the graph out of the executions of the block's transactions, given in order.
+    /// As in `ParallelTransactionExecutor`, the sequencer balance accessed only by the fee
+    /// transfer does not make transactions conflict, since every fee transfer merely increases it.
+    pub fn from_executions(
+        block_context: &BlockContext,
+        executions: &[(TransactionExecutionInfo, StateAccess)],
+    ) -> StateResult<Self> {
+        let mut state_accesses = Vec::with_capacity(executions.len());
+        let mut increases = Vec::with_capacity(executions.len());
+        for (execution_info, state_access) in executions {
+            let sequencer_balance_cells =
+                deferrable_sequencer_balance_cells(block_context, execution_info)?;
+            state_accesses.push(state_access.clone());
+            increases.push(
+                sequencer_balance_cells.into_iter().flatten().map(StateCell::Storage).collect(),
+            );
+        }
+
+        Ok(Self::with_increases(&state_accesses, &increases))
+    }
+
+    /// Same as `new`, except that the cells in `increases[i]` are only increased by the `i`-th
+    /// transaction. Increases of a cell commute, and thus do not conflict with each other; they
+    /// conflict with the other reads and writes of the cell as writes do.
+    fn with_increases(state_accesses: &[StateAccess], increases: &[HashSet<StateCell>]) -> Self {
+        let mut cell_accesses: HashMap<StateCell, CellAccesses> = HashMap::new();
+        let mut conflicts = Vec::new();
+
+        for (tx_index, state_access) in state_accesses.iter().enumerate() {
+            let tx_increases = increases.get(tx_index);
+            let is_increased = |cell: &StateCell| tx_increases.is_some_and(|c| c.contains(cell));
+
+            // A transaction reads a cell before writing it, if at all.
+            for cell in state_access.reads.cells().filter(|cell| !is_increased(cell)) {
+                let accesses = cell_accesses.entry(cell).or_default();
+                let writers =
+                    accesses.last_writer.iter().chain(&accesses.increasers_since_last_write);
+                for &writer in writers {
+                    conflicts.push(Conflict {
+                        earlier: writer,
+                        later: tx_index,
+                        cell,
+                        kind: ConflictKind::ReadAfterWrite,
+                    });
+                }
+                accesses.readers_since_last_write.push(tx_index);
+            }
+
+            for cell in state_access.writes.cells().filter(|cell| !is_increased(cell)) {
+                let accesses = cell_accesses.entry(cell).or_default();
+                let writers =
+                    accesses.last_writer.iter().chain(&accesses.increasers_since_last_write);
+                for &writer in writers {
+                    conflicts.push(Conflict {
+                        earlier: writer,
+                        later: tx_index,
+                        cell,
+                        kind: ConflictKind::WriteAfterWrite,
+                    });
+                }
+                push_write_after_read_conflicts(&mut conflicts, accesses, tx_index, cell);
+                accesses.last_writer = Some(tx_index);
+                accesses.readers_since_last_write.clear();
+                accesses.increasers_since_last_write.clear();
+            }
+
+            for &cell in tx_increases.into_iter().flatten() {
+                let accesses = cell_accesses.entry(cell).or_default();
+                if let Some(writer) = accesses.last_writer {
+                    conflicts.push(Conflict {
+                        earlier: writer,
+                        later: tx_index,
+                        cell,
+                        kind: ConflictKind::WriteAfterWrite,
+                    });
+                }
+                push_write_after_read_conflicts(&mut conflicts, accesses, tx_index, cell);
+                accesses.increasers_since_last_write.push(tx_index);
+            }
+        }
+
+        conflicts.sort();
+        Self { n_txs: state_accesses.len(), conflicts }
+    }
+
+    /// Returns all conflicts, ordered by the earlier and then the later transaction.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Returns the (earlier) transactions the given transaction directly depends on.
+    pub fn dependencies(&self, tx_index: usize) -> BTreeSet<usize> {
+        self.conflicts
+            .iter()
+            .filter(|conflict| conflict.later == tx_index)
+            .map(|conflict| conflict.earlier)
+            .collect()
+    }
+
+    /// Groups the transactions into levels, such that each transaction depends only on
+    /// transactions of previous levels; the transactions of a level may be executed in parallel.
+    pub fn levels(&self) -> Vec<Vec<usize>> {
+        let mut tx_levels = vec![0; self.n_txs];
+        // Conflicts are sorted by the earlier transaction, whose level is final once reached.
+        for conflict in &self.conflicts {
+            tx_levels[conflict.later] =
+                tx_levels[conflict.later].max(tx_levels[conflict.earlier] + 1);
+        }
+
+        let n_levels = tx_levels.iter().max().map_or(0, |max_level| max_level + 1);
+        let mut levels = vec![Vec::new(); n_levels];
+        for (tx_index, level) in tx_levels.into_iter().enumerate() {
+            levels[level].push(tx_index);
+        }
+
+        levels
+    }
+}
+
+fn push_write_after_read_conflicts(
+    conflicts: &mut Vec<Conflict>,
+    accesses: &CellAccesses,
+    tx_index: usize,
+    cell: StateCell,
+) {
+    for &reader in &accesses.readers_since_last_write {
+        if reader != tx_index {
+            conflicts.push(Conflict {
+                earlier: reader,
+                later: tx_index,
+                cell,
+                kind: ConflictKind::WriteAfterRead,
+            });
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, Fee};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{get_erc20_balance_var_addresses, selector_from_name};
+use crate::block_context::BlockContext;
+use crate::concurrency::conflict_graph::{Conflict, ConflictGraph, ConflictKind};
+use crate::state::cached_state::{StateAccess, StateCell, StateMaps};
+use crate::test_utils::{
+    NonceManager, BALANCE, MAX_FEE, TEST_ACCOUNT_CONTRACT_ADDRESS,
+    TEST_ACCOUNT_CONTRACT_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+};
+use crate::transaction::test_utils::{
+    account_invoke_tx, create_state_with_trivial_validation_account,
+};
+use crate::transaction::transactions::ExecutableTransaction;
+
+fn storage_access(reads: &[&str], writes: &[&str]) -> StateAccess {
+    let to_storage = |keys: &[&str]| -> HashMap<_, _> {
+        keys.iter()
+            .map(|key| {
+                ((contract_address!("0x100"), StorageKey(patricia_key!(*key))), stark_felt!(1_u8))
+            })
+            .collect()
+    };
+
+    StateAccess {
+        reads: StateMaps { storage: to_storage(reads), ..Default::default() },
+        writes: StateMaps { storage: to_storage(writes), ..Default::default() },
+    }
+}
+
+fn storage_cell(key: &str) -> StateCell {
+    StateCell::Storage((contract_address!("0x100"), StorageKey(patricia_key!(key))))
+}
+
+#[test]
+fn conflicts() {
+    let state_accesses = [
+        storage_access(&["0x1"], &["0x1"]),
+        storage_access(&["0x1"], &[]),
+        storage_access(&["0x2"], &["0x1"]),
+        storage_access(&[], &["0x1", "0x2"]),
+        storage_access(&["0x3"], &["0x4"]),
+    ];
+    let graph = ConflictGraph::new(&state_accesses);
+
+    let conflict =
+        |earlier, later, key, kind| Conflict { earlier, later, cell: storage_cell(key), kind };
+    assert_eq!(
+        graph.conflicts(),
+        [
+            conflict(0, 1, "0x1", ConflictKind::ReadAfterWrite),
+            conflict(0, 2, "0x1", ConflictKind::WriteAfterWrite),
+            conflict(1, 2, "0x1", ConflictKind::WriteAfterRead),
+            conflict(2, 3, "0x1", ConflictKind::WriteAfterWrite),
+            conflict(2, 3, "0x2", ConflictKind::WriteAfterRead),
+        ]
+    );
+    assert_eq!(graph.dependencies(2), BTreeSet::from([0, 1]));
+    assert_eq!(graph.dependencies(4), BTreeSet::new());
+    assert_eq!(graph.levels(), vec![vec![0, 4], vec![1], vec![2], vec![3]]);
+}
+
+#[test]
+fn conflicts_across_cell_types() {
+    let account_address = contract_address!("0x200");
+    let nonce_access = |nonce: u8| StateAccess {
+        reads: StateMaps {
+            nonces: HashMap::from([(account_address, Nonce(stark_felt!(nonce)))]),
+            ..Default::default()
+        },
+        writes: StateMaps {
+            nonces: HashMap::from([(account_address, Nonce(stark_felt!(nonce + 1)))]),
+            ..Default::default()
+        },
+    };
+    let graph =
+        ConflictGraph::new(&[nonce_access(0), storage_access(&["0x1"], &[]), nonce_access(1)]);
+
+    let nonce_cell = StateCell::Nonce(account_address);
+    assert_eq!(
+        graph.conflicts(),
+        [
+            Conflict { earlier: 0, later: 2, cell: nonce_cell, kind: ConflictKind::ReadAfterWrite },
+            Conflict {
+                earlier: 0,
+                later: 2,
+                cell: nonce_cell,
+                kind: ConflictKind::WriteAfterWrite
+            },
+        ]
+    );
+    assert_eq!(graph.levels(), vec![vec![0, 1], vec![2]]);
+}
+
+#[test]
+fn empty_block() {
+    let graph = ConflictGraph::new(&[]);
+    assert!(graph.conflicts().is_empty());
+    assert!(graph.levels().is_empty());
+}
+
+#[test]
+fn increases_do_not_conflict() {
+    let cell = storage_cell("0x1");
+    let increase = HashSet::from([cell]);
+    let state_accesses = [
+        storage_access(&["0x1"], &["0x1"]),
+        storage_access(&["0x1"], &["0x1"]),
+        storage_access(&["0x1"], &[]),
+        storage_access(&["0x1"], &["0x1"]),
+    ];
+    let graph = ConflictGraph::with_increases(
+        &state_accesses,
+        &[increase.clone(), increase.clone(), HashSet::new(), increase],
+    );
+
+    let conflict = |earlier, later, kind| Conflict { earlier, later, cell, kind };
+    assert_eq!(
+        graph.conflicts(),
+        [
+            conflict(0, 2, ConflictKind::ReadAfterWrite),
+            conflict(1, 2, ConflictKind::ReadAfterWrite),
+            conflict(2, 3, ConflictKind::WriteAfterRead),
+        ]
+    );
+    assert_eq!(graph.levels(), vec![vec![0, 1], vec![2], vec![3]]);
+}
+
+#[test]
+fn independent_fee_charging_txs() {
+    let block_context = BlockContext::create_for_account_testing();
+    let mut state = create_state_with_trivial_validation_account();
+    let second_account_address = contract_address!("0x777");
+    let (balance_key, _) = get_erc20_balance_var_addresses(&second_account_address).unwrap();
+    state
+        .state
+        .address_to_class_hash
+        .insert(second_account_address, class_hash!(TEST_ACCOUNT_CONTRACT_CLASS_HASH));
+    state.state.storage_view.insert(
+        (block_context.fee_token_addresses.eth_fee_token_address, balance_key),
+        stark_felt!(BALANCE),
+    );
+
+    // Each account writes a different storage cell.
+    let mut nonce_manager = NonceManager::default();
+    let accounts_and_keys = [
+        (contract_address!(TEST_ACCOUNT_CONTRACT_ADDRESS), "0x10"),
+        (second_account_address, "0x11"),
+    ];
+    let executions: Vec<_> = accounts_and_keys
+        .into_iter()
+        .map(|(account_address, key)| {
+            let execute_calldata = calldata![
+                stark_felt!(TEST_CONTRACT_ADDRESS), // Contract address.
+                selector_from_name("test_storage_read_write").0, // EP selector.
+                stark_felt!(2_u8),                  // Calldata length.
+                stark_felt!(key),                   // Calldata: address.
+                stark_felt!(1_u8)                   // Calldata: value.
+            ];
+            let account_tx = account_invoke_tx(
+                execute_calldata,
+                account_address,
+                &mut nonce_manager,
+                Fee(MAX_FEE),
+            );
+            account_tx.execute_with_state_access(&mut state, &block_context, true, true).unwrap()
+        })
+        .collect();
+
+    // Both fee transfers change the sequencer balance.
+    let state_accesses: Vec<_> =
+        executions.iter().map(|(_, state_access)| state_access.clone()).collect();
+    assert_eq!(ConflictGraph::new(&state_accesses).levels(), vec![vec![0], vec![1]]);
+    let graph = ConflictGraph::from_executions(&block_context, &executions).unwrap();
+    assert!(graph.conflicts().is_empty());
+    assert_eq!(graph.levels(), vec![vec![0, 1]]);
+}
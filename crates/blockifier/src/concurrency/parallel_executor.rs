@@ -0,0 +1,424 @@
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread::{self, Scope};
+
+use crate::abi::abi_utils::get_erc20_balance_var_addresses;
+use crate::block_context::BlockContext;
+use crate::concurrency::versioned_state::{ReadSet, VersionedState};
+use crate::execution::entry_point::CallInfo;
+use crate::state::cached_state::{
+    CachedState, ContractClassMapping, ContractStorageKey, GlobalContractCache, StateMaps,
+};
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+use crate::transaction::account_transaction::AccountTransaction;
+use crate::transaction::objects::{TransactionExecutionInfo, TransactionExecutionResult};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
+
+#[cfg(test)]
+#[path = "parallel_executor_test.rs"]
+mod test;
+
+/// The outcome of the latest execution (incarnation) of a transaction.
+struct TxExecution {
+    result: TransactionExecutionResult<TransactionExecutionInfo>,
+    read_set: ReadSet,
+    writes: StateMaps,
+    declared_classes: ContractClassMapping,
+    deferred_fee: Option<DeferredFee>,
+}
+
+impl TxExecution {
+    /// Returns whether the values read by the execution are still the ones written by the
+    /// preceding transactions.
+    fn is_valid<S: SharedStateReader>(
+        &self,
+        versioned_state: &VersionedState<'_, S>,
+        tx_index: usize,
+    ) -> bool {
+        // On a failed read, the transaction is re-executed; thus, the failure is reported.
+        self.read_set.is_consistent_with(&mut versioned_state.proxy(tx_index)).unwrap_or(false)
+    }
+}
+
+fn lock_execution(execution: &Mutex<Option<TxExecution>>) -> MutexGuard<'_, Option<TxExecution>> {
+    execution.lock().expect("Transaction executions are poisoned.")
+}
+
+/// The storage cells of a fee transfer whose update of the sequencer balance is deferred to
+/// commit time.
+struct DeferredFee {
+    // Read again at commit, hence excluded from the read set.
+    sequencer_balance_cells: [ContractStorageKey; 2],
+    // All cells accessed by the fee transfer; written again at commit.
+    fee_transfer_cells: HashSet<ContractStorageKey>,
+}
+
+/// A transaction committed to the block state: its result, and its writes.
+struct CommittedTx {
+    result: TransactionExecutionResult<TransactionExecutionInfo>,
+    writes: StateMaps,
+    declared_classes: ContractClassMapping,
+}
+
+/// A task run by the worker pool, on the transaction at the given index.
+#[derive(Clone, Copy, Debug)]
+enum Task {
+    Execute(usize),
+    Validate(usize),
+}
+
+/// A pool of worker threads, running batches of tasks.
+struct WorkerPool {
+    task_sender: mpsc::Sender<Task>,
+    // Each task is sent back once run, along with whether the transaction is valid (always true
+    // for an execution), or the panic it caused.
+    done_receiver: mpsc::Receiver<(Task, thread::Result<bool>)>,
+}
+
+impl WorkerPool {
+    /// Spawns the workers in the given scope; they exit once the pool is dropped.
+    fn spawn<'scope, F>(
+        scope: &'scope Scope<'scope, '_>,
+        n_workers: usize,
+        run_task: &'scope F,
+    ) -> Self
+    where
+        F: Fn(Task) -> bool + Sync,
+    {
+        let (task_sender, task_receiver) = mpsc::channel();
+        let (done_sender, done_receiver) = mpsc::channel();
+        let task_receiver = Arc::new(Mutex::new(task_receiver));
+        for _ in 0..n_workers {
+            let task_receiver = Arc::clone(&task_receiver);
+            let done_sender = done_sender.clone();
+            scope.spawn(move || loop {
+                let Ok(task) = task_receiver.lock().expect("Task queue is poisoned.").recv() else {
+                    break;
+                };
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| run_task(task)));
+                if done_sender.send((task, outcome)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        Self { task_sender, done_receiver }
+    }
+
+    /// Runs the given tasks and waits for all of them; returns each task along with its outcome.
+    fn run(&self, tasks: impl IntoIterator<Item = Task>) -> Vec<(Task, bool)> {
+        let mut n_tasks = 0;
+        for task in tasks {
+            self.task_sender.send(task).expect("Worker pool is down.");
+            n_tasks += 1;
+        }
+
+        (0..n_tasks)
+            .map(|_| {
+                let (task, outcome) = self.done_receiver.recv().expect("Worker pool is down.");
+                match outcome {
+                    Ok(is_valid) => (task, is_valid),
+                    Err(panic_payload) => panic::resume_unwind(panic_payload),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Executes the transactions of a block optimistically, in parallel, producing the same results
+/// as executing them one after the other.
+///
+/// Execution proceeds in rounds, as in Block-STM. In each round, a pool of workers executes the
+/// pending transactions, each against a versioned view of the state (see `VersionedState`) that
+/// exposes the writes of the latest executions of its predecessors; then, it validates the read
+/// sets of all uncommitted transactions against that view. The longest valid prefix is then
+/// committed, in block order; invalid transactions are executed again (as a new incarnation) in
+/// the next round. The first uncommitted transaction is committed in every round, since by then
+/// its predecessors are final.
+///
+/// Since every fee transfer increases the sequencer's balance, the balance is excluded from the
+/// conflict detection of transactions that do not access it otherwise; their fee transfer is
+/// re-run at commit, against the committed state.
+pub struct ParallelTransactionExecutor {
+    pub block_context: BlockContext,
+    pub n_workers: usize,
+    pub charge_fee: bool,
+    pub validate: bool,
+}
+
+impl ParallelTransactionExecutor {
+    pub fn new(block_context: BlockContext, n_workers: usize) -> Self {
+        Self { block_context, n_workers: n_workers.max(1), charge_fee: true, validate: true }
+    }
+
+    /// Executes the given transactions on top of the given state, in order, and returns their
+    /// results. Failed transactions do not affect the state, as in
+    /// `ExecutableTransaction::execute`.
+    pub fn execute_txs<S: StateReader + SharedStateReader>(
+        &self,
+        state: &mut CachedState<S>,
+        txs: Vec<Transaction>,
+    ) -> Vec<TransactionExecutionResult<TransactionExecutionInfo>> {
+        let committed_txs = {
+            let versioned_state = VersionedState::new(state);
+            self.execute_in_rounds(&versioned_state, &txs)
+        };
+
+        committed_txs
+            .into_iter()
+            .map(|CommittedTx { result, writes, declared_classes }| {
+                state.apply_writes(writes, declared_classes);
+                result
+            })
+            .collect()
+    }
+
+    /// Executes and validates the given transactions on the worker pool, until all are committed;
+    /// returns them in order.
+    fn execute_in_rounds<S: SharedStateReader>(
+        &self,
+        versioned_state: &VersionedState<'_, S>,
+        txs: &[Transaction],
+    ) -> Vec<CommittedTx> {
+        let n_txs = txs.len();
+        let executions: Vec<Mutex<Option<TxExecution>>> =
+            (0..n_txs).map(|_| Mutex::default()).collect();
+        let run_task = |task: Task| match task {
+            Task::Execute(tx_index) => {
+                let execution =
+                    self.execute_tx_speculatively(versioned_state, tx_index, &txs[tx_index]);
+                *lock_execution(&executions[tx_index]) = Some(execution);
+                true
+            }
+            Task::Validate(tx_index) => lock_execution(&executions[tx_index])
+                .as_ref()
+                .is_some_and(|execution| execution.is_valid(versioned_state, tx_index)),
+        };
+
+        let mut committed_txs = Vec::with_capacity(n_txs);
+        thread::scope(|scope| {
+            let worker_pool = WorkerPool::spawn(scope, self.n_workers.min(n_txs), &run_task);
+            // Whether the latest execution of each transaction is known to be valid.
+            let mut is_valid = vec![false; n_txs];
+            while committed_txs.len() < n_txs {
+                let n_committed = committed_txs.len();
+                let pending_txs: Vec<_> =
+                    (n_committed..n_txs).filter(|&tx_index| !is_valid[tx_index]).collect();
+                worker_pool.run(pending_txs.iter().copied().map(Task::Execute));
+
+                // The first uncommitted transaction, if executed in this round, read only final
+                // values.
+                let first_to_validate = if pending_txs.first() == Some(&n_committed) {
+                    is_valid[n_committed] = true;
+                    n_committed + 1
+                } else {
+                    n_committed
+                };
+                for (task, is_task_valid) in
+                    worker_pool.run((first_to_validate..n_txs).map(Task::Validate))
+                {
+                    if let Task::Validate(tx_index) = task {
+                        is_valid[tx_index] = is_task_valid;
+                    }
+                }
+
+                // A fee transfer re-run at commit may write a sequencer balance other than the
+                // speculative one; the succeeding transactions are then validated again.
+                let mut is_validation_stale = false;
+                for tx_index in n_committed..n_txs {
+                    if is_validation_stale {
+                        is_valid[tx_index] = run_task(Task::Validate(tx_index));
+                    }
+                    if !is_valid[tx_index] {
+                        break;
+                    }
+
+                    let execution = lock_execution(&executions[tx_index])
+                        .take()
+                        .expect("A valid transaction was executed.");
+                    is_validation_stale |= execution.deferred_fee.is_some();
+                    committed_txs.push(self.commit(
+                        versioned_state,
+                        tx_index,
+                        &txs[tx_index],
+                        execution,
+                    ));
+                }
+            }
+        });
+
+        committed_txs
+    }
+
+    fn execute_tx_speculatively<S: SharedStateReader>(
+        &self,
+        versioned_state: &VersionedState<'_, S>,
+        tx_index: usize,
+        tx: &Transaction,
+    ) -> TxExecution {
+        // A private global cache, so that all class reads go through the versioned state.
+        let mut tx_state =
+            CachedState::new(versioned_state.proxy(tx_index), GlobalContractCache::default());
+        let result =
+            tx.clone().execute(&mut tx_state, &self.block_context, self.charge_fee, self.validate);
+
+        // A failed execution has no writes, but its failure may still depend on its reads.
+        let writes = tx_state.get_writes();
+        let mut read_set = std::mem::take(&mut tx_state.state.read_set);
+        let declared_classes: ContractClassMapping = tx_state
+            .class_hash_to_class()
+            .iter()
+            .filter(|(class_hash, _)| read_set.declared_classes.get(class_hash) != Some(&true))
+            .map(|(class_hash, contract_class)| (*class_hash, contract_class.clone()))
+            .collect();
+        versioned_state.apply_writes(tx_index, &writes, &declared_classes);
+
+        let deferred_fee = match &result {
+            Ok(execution_info) => self.defer_sequencer_fee(execution_info).unwrap_or_default(),
+            Err(_) => None,
+        };
+        if let Some(DeferredFee { sequencer_balance_cells, .. }) = &deferred_fee {
+            read_set.values.storage.retain(|contract_storage_key, _| {
+                !sequencer_balance_cells.contains(contract_storage_key)
+            });
+        }
+
+        TxExecution { result, read_set, writes, declared_classes, deferred_fee }
+    }
+
+    /// Commits the latest execution of the transaction at the given index, once all preceding
+    /// transactions are committed.
+    fn commit<S: SharedStateReader>(
+        &self,
+        versioned_state: &VersionedState<'_, S>,
+        tx_index: usize,
+        tx: &Transaction,
+        execution: TxExecution,
+    ) -> CommittedTx {
+        let TxExecution { result, mut writes, declared_classes, deferred_fee, .. } = execution;
+        let Some(DeferredFee { fee_transfer_cells, .. }) = deferred_fee else {
+            return CommittedTx { result, writes, declared_classes };
+        };
+        let (Ok(mut execution_info), Transaction::AccountTransaction(account_tx)) = (result, tx)
+        else {
+            unreachable!("Only successful account transaction executions have a deferred fee.");
+        };
+
+        // The fee transfer observed a speculative sequencer balance; re-run it on top of the
+        // committed state and the rest of the transaction's writes.
+        writes
+            .storage
+            .retain(|contract_storage_key, _| !fee_transfer_cells.contains(contract_storage_key));
+        let mut commit_state =
+            CachedState::new(versioned_state.proxy(tx_index), GlobalContractCache::default());
+        commit_state.apply_writes(writes, ContractClassMapping::default());
+        let fee_transfer_result = AccountTransaction::execute_fee_transfer(
+            &mut commit_state,
+            &self.block_context,
+            account_tx.get_account_transaction_context(),
+            execution_info.actual_fee,
+        );
+
+        let committed_tx = match fee_transfer_result {
+            Ok(fee_transfer_call_info) => {
+                execution_info.fee_transfer_call_info = Some(fee_transfer_call_info);
+                CommittedTx {
+                    result: Ok(execution_info),
+                    writes: commit_state.get_writes(),
+                    declared_classes,
+                }
+            }
+            // As in sequential execution, the failed transaction does not affect the state.
+            Err(error) => CommittedTx {
+                result: Err(error),
+                writes: StateMaps::default(),
+                declared_classes: ContractClassMapping::default(),
+            },
+        };
+        versioned_state.apply_writes(
+            tx_index,
+            &committed_tx.writes,
+            &committed_tx.declared_classes,
+        );
+
+        committed_tx
+    }
+
+    /// Returns the storage cells of the fee transfer of the given execution, if its update of the
+    /// sequencer balance can be deferred to commit time, where the transfer is re-run on top of
+    /// the rest of the transaction's writes. This requires that the cells accessed by the transfer
+    /// (the sequencer and sender balances) are not accessed by the rest of the transaction.
+    fn defer_sequencer_fee(
+        &self,
+        execution_info: &TransactionExecutionInfo,
+    ) -> StateResult<Option<DeferredFee>> {
+        let Some(sequencer_balance_cells) =
+            deferrable_sequencer_balance_cells(&self.block_context, execution_info)?
+        else {
+            return Ok(None);
+        };
+        let Some(fee_transfer_call_info) = &execution_info.fee_transfer_call_info else {
+            return Ok(None);
+        };
+
+        let fee_transfer_cells: HashSet<ContractStorageKey> = fee_transfer_call_info
+            .into_iter()
+            .flat_map(|call_info| {
+                let storage_address = call_info.call.storage_address;
+                call_info.accessed_storage_keys.iter().map(move |&key| (storage_address, key))
+            })
+            .collect();
+        let accesses_fee_transfer_cells = |call_info: &CallInfo| {
+            call_info
+                .accessed_storage_keys
+                .iter()
+                .any(|&key| fee_transfer_cells.contains(&(call_info.call.storage_address, key)))
+        };
+        let is_accessed = [&execution_info.validate_call_info, &execution_info.execute_call_info]
+            .into_iter()
+            .flatten()
+            .any(|call_info| call_info.into_iter().any(accesses_fee_transfer_cells));
+        if is_accessed {
+            return Ok(None);
+        }
+
+        Ok(Some(DeferredFee { sequencer_balance_cells, fee_transfer_cells }))
+    }
+}
+
+/// Returns the sequencer balance cells (low and high words) in the fee token of the given
+/// execution, if they are accessed only by its fee transfer; the transfer then merely increases
+/// the balance, which commutes with the fee transfers of other transactions.
+pub fn deferrable_sequencer_balance_cells(
+    block_context: &BlockContext,
+    execution_info: &TransactionExecutionInfo,
+) -> StateResult<Option<[ContractStorageKey; 2]>> {
+    let Some(fee_transfer_call_info) = &execution_info.fee_transfer_call_info else {
+        return Ok(None);
+    };
+    // The reverted execution's calls (and thus, accessed keys) are not reported.
+    if execution_info.revert_error.is_some()
+        || fee_transfer_call_info.call.caller_address == block_context.sequencer_address
+    {
+        return Ok(None);
+    }
+
+    let fee_token_address = block_context.fee_token_address(execution_info.fee_unit);
+    let (low_key, high_key) = get_erc20_balance_var_addresses(&block_context.sequencer_address)?;
+    let accesses_sequencer_balance = |call_info: &CallInfo| {
+        call_info.call.storage_address == fee_token_address
+            && (call_info.accessed_storage_keys.contains(&low_key)
+                || call_info.accessed_storage_keys.contains(&high_key))
+    };
+    let is_accessed = [&execution_info.validate_call_info, &execution_info.execute_call_info]
+        .into_iter()
+        .flatten()
+        .any(|call_info| call_info.into_iter().any(accesses_sequencer_balance));
+    if is_accessed {
+        return Ok(None);
+    }
+
+    Ok(Some([(fee_token_address, low_key), (fee_token_address, high_key)]))
+}
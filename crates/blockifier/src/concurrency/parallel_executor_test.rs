@@ -0,0 +1,125 @@
+use rstest::rstest;
+use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::transaction::{Calldata, Fee};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{get_erc20_balance_var_addresses, selector_from_name};
+use crate::block_context::BlockContext;
+use crate::concurrency::parallel_executor::ParallelTransactionExecutor;
+use crate::concurrency::versioned_state::VersionedState;
+use crate::state::cached_state::CachedState;
+use crate::state::state_api::State;
+use crate::test_utils::{
+    DictStateReader, NonceManager, BALANCE, MAX_FEE, TEST_ACCOUNT_CONTRACT_ADDRESS,
+    TEST_ACCOUNT_CONTRACT_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+};
+use crate::transaction::test_utils::{
+    account_invoke_tx, create_state_with_trivial_validation_account,
+};
+use crate::transaction::transaction_execution::Transaction;
+use crate::transaction::transactions::ExecutableTransaction;
+
+const SECOND_ACCOUNT_ADDRESS: &str = "0x777";
+
+fn create_state() -> CachedState<DictStateReader> {
+    let block_context = BlockContext::create_for_account_testing();
+    let mut state = create_state_with_trivial_validation_account();
+    let account_address = contract_address!(SECOND_ACCOUNT_ADDRESS);
+    let (balance_key, _) = get_erc20_balance_var_addresses(&account_address).unwrap();
+    state
+        .state
+        .address_to_class_hash
+        .insert(account_address, class_hash!(TEST_ACCOUNT_CONTRACT_CLASS_HASH));
+    state.state.storage_view.insert(
+        (block_context.fee_token_addresses.eth_fee_token_address, balance_key),
+        stark_felt!(BALANCE),
+    );
+
+    state
+}
+
+/// Interleaves invocations of the two accounts; some of them write to the same storage cell.
+fn create_txs() -> Vec<Transaction> {
+    let mut nonce_manager = NonceManager::default();
+    let accounts = [
+        contract_address!(TEST_ACCOUNT_CONTRACT_ADDRESS),
+        contract_address!(SECOND_ACCOUNT_ADDRESS),
+    ];
+
+    (0..8_u8)
+        .map(|i| {
+            let account_address = accounts[usize::from(i % 2)];
+            let execute_calldata = calldata![
+                stark_felt!(TEST_CONTRACT_ADDRESS), // Contract address.
+                selector_from_name("test_storage_read_write").0, // EP selector.
+                stark_felt!(2_u8),                  // Calldata length.
+                stark_felt!(i % 3),                 // Calldata: address.
+                stark_felt!(i)                      // Calldata: value.
+            ];
+            let account_tx = account_invoke_tx(
+                execute_calldata,
+                account_address,
+                &mut nonce_manager,
+                Fee(MAX_FEE),
+            );
+            Transaction::AccountTransaction(account_tx)
+        })
+        .collect()
+}
+
+#[rstest]
+fn test_parallel_execution_matches_sequential(#[values(1, 2, 4)] n_workers: usize) {
+    let block_context = BlockContext::create_for_account_testing();
+    let mut txs = create_txs();
+    // A transaction with an already used nonce; fails.
+    txs.insert(3, txs[0].clone());
+
+    let mut sequential_state = create_state();
+    let sequential_results: Vec<_> = txs
+        .clone()
+        .into_iter()
+        .map(|tx| tx.execute(&mut sequential_state, &block_context, true, true))
+        .collect();
+
+    let mut parallel_state = create_state();
+    let executor = ParallelTransactionExecutor::new(block_context, n_workers);
+    let parallel_results = executor.execute_txs(&mut parallel_state, txs);
+
+    assert_eq!(parallel_results.len(), sequential_results.len());
+    for (parallel_result, sequential_result) in parallel_results.iter().zip(&sequential_results) {
+        match (parallel_result, sequential_result) {
+            (Ok(parallel_info), Ok(sequential_info)) => assert_eq!(parallel_info, sequential_info),
+            (Err(parallel_error), Err(sequential_error)) => {
+                assert_eq!(parallel_error.to_string(), sequential_error.to_string())
+            }
+            _ => panic!("Parallel and sequential execution results differ."),
+        }
+    }
+    assert!(sequential_results[3].is_err());
+    assert_eq!(parallel_state.to_state_diff(), sequential_state.to_state_diff());
+}
+
+#[test]
+fn test_sequencer_fee_is_deferred() {
+    let block_context = BlockContext::create_for_account_testing();
+    let state = create_state();
+    let versioned_state = VersionedState::new(&state);
+    let executor = ParallelTransactionExecutor::new(block_context.clone(), 1);
+    let tx = create_txs().remove(0);
+
+    let execution = executor.execute_tx_speculatively(&versioned_state, 0, &tx);
+    let deferred_fee = execution.deferred_fee.unwrap();
+    let fee_token_address = block_context.fee_token_addresses.eth_fee_token_address;
+    let (low_key, high_key) =
+        get_erc20_balance_var_addresses(&block_context.sequencer_address).unwrap();
+    assert_eq!(
+        deferred_fee.sequencer_balance_cells,
+        [(fee_token_address, low_key), (fee_token_address, high_key)]
+    );
+    for contract_storage_key in deferred_fee.sequencer_balance_cells {
+        assert!(!execution.read_set.values.storage.contains_key(&contract_storage_key));
+        // Written again at commit.
+        assert!(deferred_fee.fee_transfer_cells.contains(&contract_storage_key));
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{MutexGuard, RwLock, RwLockReadGuard};
+
+use cached::Cached;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::{
+    CachedState, ContractClassMapping, GlobalContractCache, StateMaps,
+};
+use crate::state::contract_class_cache::ContractClassLRUCache;
+use crate::state::errors::StateError;
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "versioned_state_test.rs"]
+mod test;
+
+/// Holds, per cell, the values written by each transaction (indexed by its position in the
+/// block).
+#[derive(Debug)]
+struct VersionedMap<K, V> {
+    versions: HashMap<K, BTreeMap<usize, V>>,
+    // The keys written by each transaction, so that its writes can be replaced when it is
+    // re-executed.
+    keys_by_tx: HashMap<usize, Vec<K>>,
+}
+
+impl<K, V> Default for VersionedMap<K, V> {
+    fn default() -> Self {
+        Self { versions: HashMap::default(), keys_by_tx: HashMap::default() }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> VersionedMap<K, V> {
+    /// Returns the value written by the latest transaction preceding the given one, if any.
+    fn read(&self, tx_index: usize, key: &K) -> Option<V> {
+        let versions = self.versions.get(key)?;
+        versions.range(..tx_index).next_back().map(|(_, value)| value.clone())
+    }
+
+    /// Sets the writes of the given transaction, instead of its previous ones.
+    fn write_all(&mut self, tx_index: usize, writes: impl IntoIterator<Item = (K, V)>) {
+        for key in self.keys_by_tx.remove(&tx_index).unwrap_or_default() {
+            if let Some(versions) = self.versions.get_mut(&key) {
+                versions.remove(&tx_index);
+            }
+        }
+
+        let mut keys = vec![];
+        for (key, value) in writes {
+            self.versions.entry(key.clone()).or_default().insert(tx_index, value);
+            keys.push(key);
+        }
+        self.keys_by_tx.insert(tx_index, keys);
+    }
+}
+
+#[derive(Debug, Default)]
+struct VersionedWrites {
+    nonces: VersionedMap<ContractAddress, Nonce>,
+    class_hashes: VersionedMap<ContractAddress, ClassHash>,
+    storage: VersionedMap<(ContractAddress, StorageKey), StarkFelt>,
+    compiled_class_hashes: VersionedMap<ClassHash, CompiledClassHash>,
+    classes: VersionedMap<ClassHash, ContractClass>,
+}
+
+/// The values a transaction observed during its execution.
+#[derive(Debug, Default)]
+pub struct ReadSet {
+    pub values: StateMaps,
+    /// Whether each of the requested contract classes was declared.
+    pub declared_classes: HashMap<ClassHash, bool>,
+}
+
+impl ReadSet {
+    /// Returns whether reading the given state now yields the recorded values.
+    pub fn is_consistent_with<S: StateReader>(&self, state: &mut S) -> StateResult<bool> {
+        for (&(contract_address, key), &value) in &self.values.storage {
+            if state.get_storage_at(contract_address, key)? != value {
+                return Ok(false);
+            }
+        }
+        for (&contract_address, &nonce) in &self.values.nonces {
+            if state.get_nonce_at(contract_address)? != nonce {
+                return Ok(false);
+            }
+        }
+        for (&contract_address, &class_hash) in &self.values.class_hashes {
+            if state.get_class_hash_at(contract_address)? != class_hash {
+                return Ok(false);
+            }
+        }
+        for (&class_hash, &compiled_class_hash) in &self.values.compiled_class_hashes {
+            if state.get_compiled_class_hash(class_hash)? != compiled_class_hash {
+                return Ok(false);
+            }
+        }
+        for (class_hash, &declared) in &self.declared_classes {
+            let is_declared = match state.get_compiled_contract_class(class_hash) {
+                Ok(_) => true,
+                Err(StateError::UndeclaredClassHash(_)) => false,
+                Err(error) => return Err(error),
+            };
+            if is_declared != declared {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// A multi-version view of the state, used to execute the transactions of a block speculatively
+/// and concurrently.
+///
+/// Each transaction reads the values written by the latest preceding transaction that has already
+/// been executed, falling back to the block state. The block state is read through a
+/// `SharedStateReader`, without locking.
+pub struct VersionedState<'a, S: SharedStateReader> {
+    base: &'a S,
+    // The writes and contract classes of the block state on top of its reader, as of the creation
+    // of this view.
+    base_writes: StateMaps,
+    base_classes: ContractClassMapping,
+    global_class_cache: GlobalContractCache,
+    writes: RwLock<VersionedWrites>,
+}
+
+impl<'a, S: StateReader + SharedStateReader> VersionedState<'a, S> {
+    pub fn new(state: &'a CachedState<S>) -> Self {
+        Self {
+            base: &state.state,
+            base_writes: state.get_writes(),
+            base_classes: state.class_hash_to_class().clone(),
+            global_class_cache: state.global_class_cache().clone(),
+            writes: RwLock::default(),
+        }
+    }
+}
+
+impl<'a, S: SharedStateReader> VersionedState<'a, S> {
+    /// Returns a reader of the state as seen by the transaction at the given index.
+    pub fn proxy(&self, tx_index: usize) -> VersionedStateProxy<'_, 'a, S> {
+        VersionedStateProxy { tx_index, versioned_state: self, read_set: ReadSet::default() }
+    }
+
+    /// Publishes the values written by the transaction at the given index, to be read by the
+    /// succeeding transactions. Replaces the values written by its previous execution, if any.
+    pub fn apply_writes(
+        &self,
+        tx_index: usize,
+        writes: &StateMaps,
+        declared_classes: &ContractClassMapping,
+    ) {
+        let mut versioned_writes =
+            self.writes.write().expect("Versioned state writes are poisoned.");
+        versioned_writes.nonces.write_all(tx_index, writes.nonces.clone());
+        versioned_writes.class_hashes.write_all(tx_index, writes.class_hashes.clone());
+        versioned_writes.storage.write_all(tx_index, writes.storage.clone());
+        versioned_writes
+            .compiled_class_hashes
+            .write_all(tx_index, writes.compiled_class_hashes.clone());
+        versioned_writes.classes.write_all(tx_index, declared_classes.clone());
+    }
+
+    fn read_writes(&self) -> RwLockReadGuard<'_, VersionedWrites> {
+        self.writes.read().expect("Versioned state writes are poisoned.")
+    }
+
+    fn get_base_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        match self.base_writes.storage.get(&(contract_address, key)) {
+            Some(&value) => Ok(value),
+            None => self.base.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_base_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        match self.base_writes.nonces.get(&contract_address) {
+            Some(&nonce) => Ok(nonce),
+            None => self.base.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_base_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        match self.base_writes.class_hashes.get(&contract_address) {
+            Some(&class_hash) => Ok(class_hash),
+            None => self.base.get_class_hash_at(contract_address),
+        }
+    }
+
+    /// The global contract cache is locked only to look the class up and to store it; not while
+    /// reading it.
+    fn get_base_compiled_contract_class(
+        &self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        if let Some(contract_class) = self.base_classes.get(class_hash) {
+            return Ok(contract_class.clone());
+        }
+        let cached_class = self.lock_global_class_cache().cache_get(class_hash).cloned();
+        if let Some(contract_class) = cached_class {
+            return Ok(contract_class);
+        }
+
+        let contract_class = self.base.get_compiled_contract_class(class_hash)?;
+        self.lock_global_class_cache().cache_set(*class_hash, contract_class.clone());
+        Ok(contract_class)
+    }
+
+    fn get_base_compiled_class_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> StateResult<CompiledClassHash> {
+        match self.base_writes.compiled_class_hashes.get(&class_hash) {
+            Some(&compiled_class_hash) => Ok(compiled_class_hash),
+            None => self.base.get_compiled_class_hash(class_hash),
+        }
+    }
+
+    fn lock_global_class_cache(&self) -> MutexGuard<'_, ContractClassLRUCache> {
+        self.global_class_cache.lock().expect("Global contract cache is poisoned.")
+    }
+}
+
+/// Exposes the `StateReader` API of a `VersionedState` for a specific transaction, while recording
+/// the transaction's read set.
+pub struct VersionedStateProxy<'v, 'a, S: SharedStateReader> {
+    tx_index: usize,
+    versioned_state: &'v VersionedState<'a, S>,
+    pub read_set: ReadSet,
+}
+
+impl<'v, 'a, S: SharedStateReader> StateReader for VersionedStateProxy<'v, 'a, S> {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        let contract_storage_key = (contract_address, key);
+        let versioned_value =
+            self.versioned_state.read_writes().storage.read(self.tx_index, &contract_storage_key);
+        let value = match versioned_value {
+            Some(value) => value,
+            None => self.versioned_state.get_base_storage_at(contract_address, key)?,
+        };
+
+        self.read_set.values.storage.insert(contract_storage_key, value);
+        Ok(value)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        let versioned_nonce =
+            self.versioned_state.read_writes().nonces.read(self.tx_index, &contract_address);
+        let nonce = match versioned_nonce {
+            Some(nonce) => nonce,
+            None => self.versioned_state.get_base_nonce_at(contract_address)?,
+        };
+
+        self.read_set.values.nonces.insert(contract_address, nonce);
+        Ok(nonce)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        let versioned_class_hash =
+            self.versioned_state.read_writes().class_hashes.read(self.tx_index, &contract_address);
+        let class_hash = match versioned_class_hash {
+            Some(class_hash) => class_hash,
+            None => self.versioned_state.get_base_class_hash_at(contract_address)?,
+        };
+
+        self.read_set.values.class_hashes.insert(contract_address, class_hash);
+        Ok(class_hash)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        let versioned_class =
+            self.versioned_state.read_writes().classes.read(self.tx_index, class_hash);
+        let contract_class = match versioned_class {
+            Some(contract_class) => Ok(contract_class),
+            None => self.versioned_state.get_base_compiled_contract_class(class_hash),
+        };
+
+        match &contract_class {
+            Ok(_) => {
+                self.read_set.declared_classes.insert(*class_hash, true);
+            }
+            Err(StateError::UndeclaredClassHash(_)) => {
+                self.read_set.declared_classes.insert(*class_hash, false);
+            }
+            Err(_) => {}
+        }
+        contract_class
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        let versioned_compiled_class_hash = self
+            .versioned_state
+            .read_writes()
+            .compiled_class_hashes
+            .read(self.tx_index, &class_hash);
+        let compiled_class_hash = match versioned_compiled_class_hash {
+            Some(compiled_class_hash) => compiled_class_hash,
+            None => self.versioned_state.get_base_compiled_class_hash(class_hash)?,
+        };
+
+        self.read_set.values.compiled_class_hashes.insert(class_hash, compiled_class_hash);
+        Ok(compiled_class_hash)
+    }
+}
@@ -0,0 +1,34 @@
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{contract_address, patricia_key, stark_felt};
+
+use crate::concurrency::versioned_state::VersionedState;
+use crate::state::cached_state::{CachedState, ContractClassMapping, StateMaps};
+use crate::state::state_api::{State, StateReader};
+use crate::test_utils::DictStateReader;
+
+#[test]
+fn test_versioned_reads() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let mut state = CachedState::from(DictStateReader::default());
+    state.set_storage_at(contract_address, key, stark_felt!(1_u8));
+    let versioned_state = VersionedState::new(&state);
+
+    // Transactions read the writes of their predecessors, on top of the block state.
+    let writes = StateMaps {
+        storage: [((contract_address, key), stark_felt!(2_u8))].into(),
+        ..Default::default()
+    };
+    versioned_state.apply_writes(1, &writes, &ContractClassMapping::default());
+    for (tx_index, expected_value) in [(0, 1_u8), (1, 1), (2, 2), (3, 2)] {
+        let value = versioned_state.proxy(tx_index).get_storage_at(contract_address, key).unwrap();
+        assert_eq!(value, stark_felt!(expected_value));
+    }
+
+    // A re-execution replaces the writes of the previous one.
+    versioned_state.apply_writes(1, &StateMaps::default(), &ContractClassMapping::default());
+    let value = versioned_state.proxy(2).get_storage_at(contract_address, key).unwrap();
+    assert_eq!(value, stark_felt!(1_u8));
+}
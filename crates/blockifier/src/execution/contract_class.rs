@@ -25,7 +25,9 @@ use starknet_api::deprecated_contract_class::{
 use crate::abi::abi_utils::selector_from_name;
 use crate::abi::constants::{self, CONSTRUCTOR_ENTRY_POINT_NAME};
 use crate::execution::errors::PreExecutionError;
-use crate::execution::execution_utils::{felt_to_stark_felt, sn_api_to_cairo_vm_program};
+use crate::execution::execution_utils::{
+    felt_to_stark_felt, sn_api_to_cairo_vm_program, DeprecatedProgramArgs,
+};
 
 /// Represents a runnable StarkNet contract class (meaning, the program is runnable by the VM).
 /// We wrap the actual class in an Arc to avoid cloning the program when cloning the class.
@@ -51,6 +53,13 @@ impl ContractClass {
             ContractClass::V1(class) => class.estimate_casm_hash_computation_resources(),
         }
     }
+
+    pub fn bytecode_length(&self) -> usize {
+        match self {
+            ContractClass::V0(class) => class.bytecode_length(),
+            ContractClass::V1(class) => class.bytecode_length(),
+        }
+    }
 }
 
 // V0.
@@ -103,6 +112,17 @@ impl ContractClassV0 {
         let contract_class: ContractClassV0Inner = serde_json::from_str(raw_contract_class)?;
         Ok(ContractClassV0(Arc::new(contract_class)))
     }
+
+    /// Builds a class from its parsed program; see `DeprecatedProgramArgs`.
+    pub fn from_program_args(
+        program_args: DeprecatedProgramArgs,
+        entry_points_by_type: HashMap<EntryPointType, Vec<EntryPoint>>,
+    ) -> Result<ContractClassV0, ProgramError> {
+        Ok(Self(Arc::new(ContractClassV0Inner {
+            program: program_args.into_program()?,
+            entry_points_by_type,
+        })))
+    }
 }
 
 #[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize)]
@@ -116,10 +136,10 @@ impl TryFrom<DeprecatedContractClass> for ContractClassV0 {
     type Error = ProgramError;
 
     fn try_from(class: DeprecatedContractClass) -> Result<Self, Self::Error> {
-        Ok(Self(Arc::new(ContractClassV0Inner {
-            program: sn_api_to_cairo_vm_program(class.program)?,
-            entry_points_by_type: class.entry_points_by_type,
-        })))
+        Self::from_program_args(
+            DeprecatedProgramArgs::try_from(class.program)?,
+            class.entry_points_by_type,
+        )
     }
 }
 
@@ -193,6 +213,39 @@ impl ContractClassV1 {
 
         Ok(contract_class)
     }
+
+    /// Builds a class from its converted parts: the bytecode, the hints at each PC, the hints by
+    /// their code, and the entry points.
+    pub(crate) fn from_parts(
+        data: Vec<MaybeRelocatable>,
+        hints: HashMap<usize, Vec<HintParams>>,
+        string_to_hint: HashMap<String, Hint>,
+        entry_points_by_type: HashMap<EntryPointType, Vec<EntryPointV1>>,
+    ) -> Result<Self, ProgramError> {
+        let builtins = vec![]; // The builtins are initialize later.
+        let main = Some(0);
+        let reference_manager = ReferenceManager { references: Vec::new() };
+        let identifiers = HashMap::new();
+        let error_message_attributes = vec![];
+        let instruction_locations = None;
+
+        let program = Program::new(
+            builtins,
+            data,
+            main,
+            hints,
+            reference_manager,
+            identifiers,
+            error_message_attributes,
+            instruction_locations,
+        )?;
+
+        Ok(Self(Arc::new(ContractClassV1Inner {
+            program,
+            entry_points_by_type,
+            hints: string_to_hint,
+        })))
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -241,24 +294,6 @@ impl TryFrom<CasmContractClass> for ContractClassV1 {
             }
         }
 
-        let builtins = vec![]; // The builtins are initialize later.
-        let main = Some(0);
-        let reference_manager = ReferenceManager { references: Vec::new() };
-        let identifiers = HashMap::new();
-        let error_message_attributes = vec![];
-        let instruction_locations = None;
-
-        let program = Program::new(
-            builtins,
-            data,
-            main,
-            hints,
-            reference_manager,
-            identifiers,
-            error_message_attributes,
-            instruction_locations,
-        )?;
-
         let mut entry_points_by_type = HashMap::new();
         entry_points_by_type.insert(
             EntryPointType::Constructor,
@@ -273,11 +308,7 @@ impl TryFrom<CasmContractClass> for ContractClassV1 {
             convert_entry_points_v1(class.entry_points_by_type.l1_handler)?,
         );
 
-        Ok(Self(Arc::new(ContractClassV1Inner {
-            program,
-            entry_points_by_type,
-            hints: string_to_hint,
-        })))
+        Self::from_parts(data, hints, string_to_hint, entry_points_by_type)
     }
 }
 
@@ -296,17 +327,22 @@ pub fn deserialize_program<'de, D: Deserializer<'de>>(
 
 // TODO(spapini): Share with cairo-lang-runner.
 fn hint_to_hint_params(hint: &cairo_lang_casm::hints::Hint) -> Result<HintParams, ProgramError> {
-    Ok(HintParams {
-        code: serde_json::to_string(hint)?,
+    Ok(hint_code_to_hint_params(serde_json::to_string(hint)?))
+}
+
+/// Returns the params of a Cairo 1 hint, given its code; i.e., its JSON serialization.
+pub(crate) fn hint_code_to_hint_params(code: String) -> HintParams {
+    HintParams {
+        code,
         accessible_scopes: vec![],
         flow_tracking_data: FlowTrackingData {
             ap_tracking: ApTracking::new(),
             reference_ids: HashMap::new(),
         },
-    })
+    }
 }
 
-fn convert_entry_points_v1(
+pub(crate) fn convert_entry_points_v1(
     external: Vec<CasmContractEntryPoint>,
 ) -> Result<Vec<EntryPointV1>, ProgramError> {
     external
@@ -1,14 +1,15 @@
 use std::cmp::min;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use cairo_vm::vm::runners::cairo_runner::{
     ExecutionResources as VmExecutionResources, ResourceTracker, RunResources,
 };
+use serde::{Deserialize, Serialize};
 use starknet_api::core::{ClassHash, ContractAddress, EntryPointSelector, EthAddress};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
-use starknet_api::transaction::{Calldata, EventContent, Fee, L2ToL1Payload, TransactionVersion};
+use starknet_api::transaction::{Calldata, EventContent, L2ToL1Payload, TransactionVersion};
 
 use crate::abi::abi_utils::selector_from_name;
 use crate::abi::constants;
@@ -30,14 +31,17 @@ pub const FAULTY_CLASS_HASH: &str =
 pub type EntryPointExecutionResult<T> = Result<T, EntryPointExecutionError>;
 
 /// Represents a the type of the call (used for debugging).
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum CallType {
     #[default]
     Call = 0,
+    // A library call.
+    #[serde(rename = "LIBRARY_CALL")]
     Delegate = 1,
 }
 /// Represents a call to an entry point of a StarkNet contract.
-#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CallEntryPoint {
     // The class hash is not given if it can be deduced from the storage address.
     pub class_hash: Option<ClassHash>,
@@ -126,14 +130,28 @@ impl EntryPointExecutionContext {
         )
     }
 
-    /// Returns the maximum number of cairo steps allowed, given the max fee and gas price.
-    /// If fee is disabled, returns the global maximum.
+    /// Same as `new_invoke`, but the number of steps is not bounded by the max fee; used for fee
+    /// estimation.
+    pub fn new_fee_estimation(
+        block_context: &BlockContext,
+        account_tx_context: &AccountTransactionContext,
+    ) -> Self {
+        Self::new(block_context.clone(), account_tx_context.clone(), Self::max_steps(block_context))
+    }
+
+    /// Returns the global maximum number of cairo steps allowed for a transaction.
+    pub fn max_steps(block_context: &BlockContext) -> usize {
+        min(constants::MAX_STEPS_PER_TX, block_context.invoke_tx_max_n_steps as usize)
+    }
+
+    /// Returns the maximum number of cairo steps allowed, given the max fee and gas price (in
+    /// version 3, the max L1 gas amount). If fee is disabled, returns the global maximum.
     pub fn max_invoke_steps(
         block_context: &BlockContext,
         account_tx_context: &AccountTransactionContext,
     ) -> usize {
-        if account_tx_context.max_fee == Fee(0) {
-            min(constants::MAX_STEPS_PER_TX, block_context.invoke_tx_max_n_steps as usize)
+        if !account_tx_context.enforce_fee() {
+            Self::max_steps(block_context)
         } else {
             let gas_per_step = block_context
                 .vm_resource_fee_cost
@@ -141,10 +159,12 @@ impl EntryPointExecutionContext {
                 .unwrap_or_else(|| {
                     panic!("{} must appear in `vm_resource_fee_cost`.", constants::N_STEPS_RESOURCE)
                 });
-            let max_gas = account_tx_context.max_fee.0 / block_context.gas_price;
-            ((max_gas as f64 / gas_per_step).floor() as usize)
-                .min(constants::MAX_STEPS_PER_TX)
-                .min(block_context.invoke_tx_max_n_steps as usize)
+            let max_gas = if account_tx_context.is_v3() {
+                u128::from(account_tx_context.l1_resource_bounds().max_amount)
+            } else {
+                account_tx_context.max_fee.0 / block_context.gas_prices.eth_l1_gas_price
+            };
+            ((max_gas as f64 / gas_per_step).floor() as usize).min(Self::max_steps(block_context))
         }
     }
 
@@ -237,7 +257,7 @@ impl CallEntryPoint {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Retdata(pub Vec<StarkFelt>);
 
 #[macro_export]
@@ -247,24 +267,24 @@ macro_rules! retdata {
     };
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct OrderedEvent {
     pub order: usize,
     pub event: EventContent,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct MessageToL1 {
     pub to_address: EthAddress,
     pub payload: L2ToL1Payload,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct OrderedL2ToL1Message {
     pub order: usize,
     pub message: MessageToL1,
 }
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CallExecution {
     pub retdata: Retdata,
     pub events: Vec<OrderedEvent>,
@@ -273,10 +293,11 @@ pub struct CallExecution {
     pub gas_consumed: u64,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CallInfo {
     pub call: CallEntryPoint,
     pub execution: CallExecution,
+    #[serde(with = "VmExecutionResourcesDef")]
     pub vm_resources: VmExecutionResources,
     pub inner_calls: Vec<CallInfo>,
 
@@ -285,6 +306,15 @@ pub struct CallInfo {
     pub accessed_storage_keys: HashSet<StorageKey>,
 }
 
+/// The serde definition of `VmExecutionResources`, which does not implement `Serialize`.
+#[derive(Deserialize, Serialize)]
+#[serde(remote = "VmExecutionResources")]
+struct VmExecutionResourcesDef {
+    n_steps: usize,
+    n_memory_holes: usize,
+    builtin_instance_counter: HashMap<String, usize>,
+}
+
 impl CallInfo {
     /// Returns the set of class hashes that were executed during this call execution.
     // TODO: Add unit test for this method
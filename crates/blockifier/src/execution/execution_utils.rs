@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use cairo_felt::Felt252;
 use cairo_lang_runner::short_string::as_cairo_short_string;
 use cairo_vm::serde::deserialize_program::{
-    deserialize_array_of_bigint_hex, Attribute, HintParams, Identifier, ReferenceManager,
+    deserialize_array_of_bigint_hex, Attribute, BuiltinName, HintParams, Identifier,
+    ReferenceManager,
 };
 use cairo_vm::types::errors::program_errors::ProgramError;
 use cairo_vm::types::program::Program;
@@ -134,34 +135,60 @@ pub fn felt_range_from_ptr(
 
 // TODO(Elin,01/05/2023): aim to use LC's implementation once it's in a separate crate.
 pub fn sn_api_to_cairo_vm_program(program: DeprecatedProgram) -> Result<Program, ProgramError> {
-    let identifiers = serde_json::from_value::<HashMap<String, Identifier>>(program.identifiers)?;
-    let builtins = serde_json::from_value(program.builtins)?;
-    let data = deserialize_array_of_bigint_hex(program.data)?;
-    let hints = serde_json::from_value::<HashMap<usize, Vec<HintParams>>>(program.hints)?;
-    let main = None;
-    let error_message_attributes = match program.attributes {
-        serde_json::Value::Null => vec![],
-        attributes => serde_json::from_value::<Vec<Attribute>>(attributes)?
-            .into_iter()
-            .filter(|attr| attr.name == "error_message")
-            .collect(),
-    };
+    DeprecatedProgramArgs::try_from(program)?.into_program()
+}
 
-    let instruction_locations = None;
-    let reference_manager = serde_json::from_value::<ReferenceManager>(program.reference_manager)?;
-
-    let program = Program::new(
-        builtins,
-        data,
-        main,
-        hints,
-        reference_manager,
-        identifiers,
-        error_message_attributes,
-        instruction_locations,
-    )?;
+/// The arguments of `Program::new` for a deprecated (Cairo 0) program; parsing them from the SN
+/// API program is the expensive part of loading a Cairo 0 class.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeprecatedProgramArgs {
+    pub builtins: Vec<BuiltinName>,
+    pub data: Vec<MaybeRelocatable>,
+    pub hints: HashMap<usize, Vec<HintParams>>,
+    pub reference_manager: ReferenceManager,
+    pub identifiers: HashMap<String, Identifier>,
+    pub error_message_attributes: Vec<Attribute>,
+}
 
-    Ok(program)
+impl DeprecatedProgramArgs {
+    pub fn into_program(self) -> Result<Program, ProgramError> {
+        let main = None;
+        let instruction_locations = None;
+
+        Program::new(
+            self.builtins,
+            self.data,
+            main,
+            self.hints,
+            self.reference_manager,
+            self.identifiers,
+            self.error_message_attributes,
+            instruction_locations,
+        )
+    }
+}
+
+impl TryFrom<DeprecatedProgram> for DeprecatedProgramArgs {
+    type Error = ProgramError;
+
+    fn try_from(program: DeprecatedProgram) -> Result<Self, Self::Error> {
+        let identifiers =
+            serde_json::from_value::<HashMap<String, Identifier>>(program.identifiers)?;
+        let builtins = serde_json::from_value::<Vec<BuiltinName>>(program.builtins)?;
+        let data = deserialize_array_of_bigint_hex(program.data)?;
+        let hints = serde_json::from_value::<HashMap<usize, Vec<HintParams>>>(program.hints)?;
+        let error_message_attributes = match program.attributes {
+            serde_json::Value::Null => vec![],
+            attributes => serde_json::from_value::<Vec<Attribute>>(attributes)?
+                .into_iter()
+                .filter(|attr| attr.name == "error_message")
+                .collect(),
+        };
+        let reference_manager =
+            serde_json::from_value::<ReferenceManager>(program.reference_manager)?;
+
+        Ok(Self { builtins, data, hints, reference_manager, identifiers, error_message_attributes })
+    }
 }
 
 #[derive(Debug)]
@@ -375,23 +375,58 @@ impl<'a> SyscallHintProcessor<'a> {
         Ok(block_info_segment_start_ptr)
     }
 
-    fn allocate_tx_signature_segment(
+    /// Allocates a read-only segment with the given data; returns its start and end pointers.
+    fn allocate_data_segment(
         &mut self,
         vm: &mut VirtualMachine,
-    ) -> SyscallResult<Relocatable> {
-        let signature = &self.context.account_tx_context.signature.0;
-        let signature =
-            signature.iter().map(|&x| MaybeRelocatable::from(stark_felt_to_felt(x))).collect();
-        let signature_segment_start_ptr = self.read_only_segments.allocate(vm, &signature)?;
+        data: Vec<MaybeRelocatable>,
+    ) -> SyscallResult<(Relocatable, Relocatable)> {
+        let data_segment_start_ptr = self.read_only_segments.allocate(vm, &data)?;
+        let data_segment_end_ptr = (data_segment_start_ptr + data.len())?;
+
+        Ok((data_segment_start_ptr, data_segment_end_ptr))
+    }
 
-        Ok(signature_segment_start_ptr)
+    fn allocate_felts_segment(
+        &mut self,
+        vm: &mut VirtualMachine,
+        felts: &[StarkFelt],
+    ) -> SyscallResult<(Relocatable, Relocatable)> {
+        let data = felts.iter().map(|&x| MaybeRelocatable::from(stark_felt_to_felt(x))).collect();
+        self.allocate_data_segment(vm, data)
+    }
+
+    /// Allocates the resource bounds of the transaction, each as a (resource name, max amount,
+    /// max price per unit) triplet.
+    fn allocate_tx_resource_bounds_segment(
+        &mut self,
+        vm: &mut VirtualMachine,
+    ) -> SyscallResult<(Relocatable, Relocatable)> {
+        let resource_bounds = &self.context.account_tx_context.resource_bounds.0;
+        let resource_bounds = resource_bounds
+            .iter()
+            .flat_map(|(resource, bounds)| {
+                [
+                    Felt252::from_bytes_be(resource.name().as_bytes()),
+                    Felt252::from(bounds.max_amount),
+                    Felt252::from(bounds.max_price_per_unit),
+                ]
+            })
+            .map(MaybeRelocatable::from)
+            .collect();
+        self.allocate_data_segment(vm, resource_bounds)
     }
 
     fn allocate_tx_info_segment(&mut self, vm: &mut VirtualMachine) -> SyscallResult<Relocatable> {
-        let tx_signature_start_ptr = self.allocate_tx_signature_segment(vm)?;
-        let account_tx_context = &self.context.account_tx_context;
-        let tx_signature_length = account_tx_context.signature.0.len();
-        let tx_signature_end_ptr = (tx_signature_start_ptr + tx_signature_length)?;
+        let account_tx_context = self.context.account_tx_context.clone();
+        let (tx_signature_start_ptr, tx_signature_end_ptr) =
+            self.allocate_felts_segment(vm, &account_tx_context.signature.0)?;
+        let (tx_resource_bounds_start_ptr, tx_resource_bounds_end_ptr) =
+            self.allocate_tx_resource_bounds_segment(vm)?;
+        let (tx_paymaster_data_start_ptr, tx_paymaster_data_end_ptr) =
+            self.allocate_felts_segment(vm, &account_tx_context.paymaster_data.0)?;
+        let (tx_account_deployment_data_start_ptr, tx_account_deployment_data_end_ptr) =
+            self.allocate_felts_segment(vm, &account_tx_context.account_deployment_data.0)?;
         let tx_info: Vec<MaybeRelocatable> = vec![
             stark_felt_to_felt(account_tx_context.version.0).into(),
             stark_felt_to_felt(*account_tx_context.sender_address.0.key()).into(),
@@ -401,6 +436,16 @@ impl<'a> SyscallHintProcessor<'a> {
             stark_felt_to_felt(account_tx_context.transaction_hash.0).into(),
             Felt252::from_bytes_be(self.context.block_context.chain_id.0.as_bytes()).into(),
             stark_felt_to_felt(account_tx_context.nonce.0).into(),
+            // Fields added in version 3.
+            tx_resource_bounds_start_ptr.into(),
+            tx_resource_bounds_end_ptr.into(),
+            Felt252::from(account_tx_context.tip.0).into(),
+            tx_paymaster_data_start_ptr.into(),
+            tx_paymaster_data_end_ptr.into(),
+            Felt252::from(account_tx_context.nonce_data_availability_mode as u8).into(),
+            Felt252::from(account_tx_context.fee_data_availability_mode as u8).into(),
+            tx_account_deployment_data_start_ptr.into(),
+            tx_account_deployment_data_end_ptr.into(),
         ];
 
         let tx_info_start_ptr = self.read_only_segments.allocate(vm, &tx_info)?;
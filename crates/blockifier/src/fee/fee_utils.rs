@@ -5,7 +5,7 @@ use starknet_api::transaction::Fee;
 use crate::abi::constants;
 use crate::block_context::BlockContext;
 use crate::transaction::errors::TransactionExecutionError;
-use crate::transaction::objects::{ResourcesMapping, TransactionExecutionResult};
+use crate::transaction::objects::{FeeUnit, ResourcesMapping, TransactionExecutionResult};
 
 #[cfg(test)]
 #[path = "fee_test.rs"]
@@ -46,14 +46,24 @@ pub fn calculate_l1_gas_by_vm_usage(
 
 /// Calculates the fee that should be charged, given execution resources.
 /// We add the l1_gas_usage (which may include, for example, the direct cost of L2-to-L1 messages)
-/// to the gas consumed by Cairo VM resource and multiply by the L1 gas price.
+/// to the gas consumed by Cairo VM resource and multiply by the L1 gas price in the given unit.
 pub fn calculate_tx_fee(
     resources: &ResourcesMapping,
     block_context: &BlockContext,
+    fee_unit: FeeUnit,
 ) -> TransactionExecutionResult<Fee> {
+    let l1_gas_price = block_context.gas_prices.get_by_fee_unit(fee_unit);
+    Ok(Fee(calculate_tx_l1_gas_usage(resources, block_context)? * l1_gas_price))
+}
+
+/// Calculates the total L1 gas consumed by a transaction, given execution resources.
+pub fn calculate_tx_l1_gas_usage(
+    resources: &ResourcesMapping,
+    block_context: &BlockContext,
+) -> TransactionExecutionResult<u128> {
     let (l1_gas_usage, vm_resources) = extract_l1_gas_and_vm_usage(resources);
     let l1_gas_by_vm_usage = calculate_l1_gas_by_vm_usage(block_context, &vm_resources)?;
     let total_l1_gas_usage = l1_gas_usage as f64 + l1_gas_by_vm_usage;
 
-    Ok(Fee(total_l1_gas_usage.ceil() as u128 * block_context.gas_price))
+    Ok(total_l1_gas_usage.ceil() as u128)
 }
@@ -5,7 +5,7 @@ use starknet_api::transaction::Fee;
 use crate::abi::constants;
 use crate::block_context::BlockContext;
 use crate::fee::eth_gas_constants;
-use crate::fee::fee_utils::calculate_tx_fee;
+use crate::fee::fee_utils::calculate_tx_l1_gas_usage;
 use crate::fee::os_resources::OS_RESOURCES;
 use crate::state::cached_state::StateChangesCount;
 use crate::transaction::account_transaction::AccountTransaction;
@@ -57,7 +57,7 @@ pub fn calculate_tx_gas_usage(
 /// a transaction to a batch. Note that constant cells - such as the one that holds the number of
 /// modified contracts - are not counted.
 /// This segment consists of deployment info (of contracts deployed by the transaction) and
-/// storage updates.
+/// storage updates; see `state_diff_to_da_segment` for its encoding.
 pub fn get_onchain_data_segment_length(state_changes_count: StateChangesCount) -> usize {
     // For each newly modified contract:
     // contract address (1 word).
@@ -140,6 +140,16 @@ pub fn estimate_minimal_fee(
     block_context: &BlockContext,
     tx: &AccountTransaction,
 ) -> TransactionExecutionResult<Fee> {
+    let fee_unit = tx.get_account_transaction_context().fee_unit();
+    let l1_gas_price = block_context.gas_prices.get_by_fee_unit(fee_unit);
+    Ok(Fee(estimate_minimal_l1_gas(block_context, tx)? * l1_gas_price))
+}
+
+/// Return an estimated lower bound for the L1 gas used by an account transaction.
+pub fn estimate_minimal_l1_gas(
+    block_context: &BlockContext,
+    tx: &AccountTransaction,
+) -> TransactionExecutionResult<u128> {
     // TODO(Dori, 1/8/2023): Give names to the constant VM step estimates and regression-test them.
     let os_steps_for_type = OS_RESOURCES
         .execute_txs_inner()
@@ -180,5 +190,5 @@ pub fn estimate_minimal_fee(
         (constants::N_STEPS_RESOURCE.to_string(), os_steps_for_type),
     ]));
 
-    calculate_tx_fee(&resources, block_context)
+    calculate_tx_l1_gas_usage(&resources, block_context)
 }
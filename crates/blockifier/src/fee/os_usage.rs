@@ -18,8 +18,8 @@ pub struct OsResources {
     // Mapping from every syscall to its execution resources in the OS (e.g., amount of Cairo
     // steps).
     execute_syscalls: HashMap<DeprecatedSyscallSelector, VmExecutionResources>,
-    // Mapping from every transaction to its extra execution resources in the OS,
-    // i.e., resources that don't count during the execution itself.
+    // Mapping from every transaction type (except Deploy) to its extra execution resources in the
+    // OS, i.e., resources that don't count during the execution itself.
     execute_txs_inner: HashMap<TransactionType, VmExecutionResources>,
 }
 
@@ -44,6 +44,12 @@ pub fn get_additional_os_resources(
         os_additional_vm_resources += &(syscall_resources * *count);
     }
 
+    // Deprecated Deploy transactions have no known OS resources; only their syscalls are
+    // accounted for.
+    if tx_type == TransactionType::Deploy {
+        return Ok(os_additional_vm_resources);
+    }
+
     // Calculates the additional resources needed for the OS to run the given transaction;
     // i.e., the resources of the StarkNet OS function `execute_transactions_inner`.
     // Also adds the resources needed for the fee transfer execution, performed in the end·
@@ -51,6 +57,6 @@ pub fn get_additional_os_resources(
     let os_resources = OS_RESOURCES
         .execute_txs_inner
         .get(&tx_type)
-        .expect("`OS_RESOURCES` must contain all transaction types.");
+        .expect("`OS_RESOURCES` must contain all transaction types, except Deploy.");
     Ok(&os_additional_vm_resources + os_resources)
 }
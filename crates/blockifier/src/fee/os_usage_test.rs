@@ -10,7 +10,8 @@ use crate::transaction::transaction_types::TransactionType;
 #[test]
 fn test_resources_entries() {
     for tx_type in TransactionType::iter() {
-        assert!(OS_RESOURCES.execute_txs_inner.get(&tx_type).is_some());
+        let has_entry = OS_RESOURCES.execute_txs_inner.get(&tx_type).is_some();
+        assert_eq!(has_entry, tx_type != TransactionType::Deploy);
     }
     for syscall_selector in DeprecatedSyscallSelector::iter() {
         assert!(OS_RESOURCES.execute_syscalls.get(&syscall_selector).is_some());
@@ -1,6 +1,8 @@
 pub mod abi;
 pub mod block_context;
 pub mod block_execution;
+pub mod commitment;
+pub mod concurrency;
 pub mod execution;
 pub mod fee;
 pub mod state;
@@ -1,3 +1,13 @@
 pub mod cached_state;
+pub mod class_disk_cache;
+pub mod contract_class_cache;
+pub mod data_availability;
 pub mod errors;
+pub mod genesis_builder;
+pub mod in_memory_state;
+pub mod pending_state_reader;
+pub mod reversible_state_diff;
+pub mod rpc_state_reader;
+pub mod shared_state_reader;
 pub mod state_api;
+pub mod storage_value;
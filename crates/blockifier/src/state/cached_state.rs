@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use std::sync::{Arc, Mutex, MutexGuard};
 
-use cached::{Cached, SizedCache};
+use cached::Cached;
 use derive_more::IntoIterator;
 use indexmap::IndexMap;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
@@ -10,7 +11,9 @@ use starknet_api::state::StorageKey;
 
 use crate::abi::abi_utils::get_erc20_balance_var_addresses;
 use crate::execution::contract_class::ContractClass;
+use crate::state::contract_class_cache::{CacheCapacity, CacheStats, ContractClassLRUCache};
 use crate::state::errors::StateError;
+use crate::state::reversible_state_diff::ReversibleStateDiff;
 use crate::state::state_api::{State, StateReader, StateResult};
 use crate::utils::subtract_mappings;
 
@@ -32,6 +35,13 @@ pub struct CachedState<S: StateReader> {
     class_hash_to_class: ContractClassMapping,
     // Invariant: managed by CachedState.
     global_class_hash_to_class: GlobalContractCache,
+    // Undo log of the writes done while there are open checkpoints; see `checkpoint`.
+    journal: Vec<JournalEntry>,
+    // The journal length at each open checkpoint, from the outermost to the innermost.
+    checkpoints: Vec<usize>,
+    // The cells read while there are open checkpoints, with the value read and the journal length
+    // at the time of reading.
+    reads: Vec<(StateRead, usize)>,
 }
 
 impl<S: StateReader> CachedState<S> {
@@ -41,6 +51,9 @@ impl<S: StateReader> CachedState<S> {
             cache: StateCache::default(),
             class_hash_to_class: HashMap::default(),
             global_class_hash_to_class,
+            journal: Vec::default(),
+            checkpoints: Vec::default(),
+            reads: Vec::default(),
         }
     }
 
@@ -48,8 +61,248 @@ impl<S: StateReader> CachedState<S> {
     /// It allows performing buffered modifying actions on the given state, which
     /// will either all happen (will be committed) or none of them (will be discarded).
     pub fn create_transactional(state: &mut CachedState<S>) -> TransactionalState<'_, S> {
-        let global_class_hash_to_class = state.global_class_hash_to_class.clone();
-        CachedState::new(MutRefState::new(state), global_class_hash_to_class)
+        TransactionalState::new(state)
+    }
+
+    /// Opens a checkpoint; the writes done from now on can be undone using `revert_to`.
+    /// Checkpoints can be nested, and must be closed (by either `revert_to` or `commit`) in the
+    /// reverse order of their creation.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let checkpoint = Checkpoint {
+            depth: self.checkpoints.len(),
+            journal_len: self.journal.len(),
+            reads_len: self.reads.len(),
+        };
+        self.checkpoints.push(checkpoint.journal_len);
+        checkpoint
+    }
+
+    /// Undoes all writes done since the given checkpoint was opened, and closes it (along with
+    /// any checkpoint nested in it).
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        self.close_checkpoint(checkpoint);
+        self.forget_reads_of_reverted_writes(checkpoint);
+        while self.journal.len() > checkpoint.journal_len {
+            let entry = self.journal.pop().expect("The journal is not empty.");
+            self.undo(entry);
+        }
+        if self.checkpoints.is_empty() {
+            self.reads.clear();
+        }
+    }
+
+    /// Closes the given checkpoint (along with any checkpoint nested in it), keeping the writes
+    /// done since it was opened. These can still be undone by reverting to an enclosing checkpoint.
+    pub fn commit(&mut self, checkpoint: Checkpoint) {
+        self.close_checkpoint(checkpoint);
+        if self.checkpoints.is_empty() {
+            self.journal.clear();
+            self.reads.clear();
+        }
+    }
+
+    fn close_checkpoint(&mut self, checkpoint: Checkpoint) {
+        assert_eq!(
+            self.checkpoints.get(checkpoint.depth),
+            Some(&checkpoint.journal_len),
+            "Checkpoint {checkpoint:?} is not open."
+        );
+        self.checkpoints.truncate(checkpoint.depth);
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        fn restore<K: Eq + Hash, V>(map: &mut HashMap<K, V>, key: K, previous: Option<V>) {
+            match previous {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+
+        match entry {
+            JournalEntry::Storage(key, previous) => {
+                restore(&mut self.cache.storage_writes, key, previous)
+            }
+            JournalEntry::Nonce(address, previous) => {
+                restore(&mut self.cache.nonce_writes, address, previous)
+            }
+            JournalEntry::ClassHash(address, previous) => {
+                restore(&mut self.cache.class_hash_writes, address, previous)
+            }
+            JournalEntry::CompiledClassHash(class_hash, previous) => {
+                restore(&mut self.cache.compiled_class_hash_writes, class_hash, previous)
+            }
+            JournalEntry::ContractClass(class_hash, previous) => {
+                restore(&mut self.class_hash_to_class, class_hash, previous)
+            }
+        }
+    }
+
+    fn record(&mut self, entry: JournalEntry) {
+        if !self.checkpoints.is_empty() {
+            self.journal.push(entry);
+        }
+    }
+
+    fn record_read(&mut self, read: StateRead) {
+        if !self.checkpoints.is_empty() {
+            self.reads.push((read, self.journal.len()));
+        }
+    }
+
+    /// Returns the journal position of the first write to each cell written since the given
+    /// journal position.
+    fn get_first_writes_since(&self, journal_len: usize) -> HashMap<StateCell, usize> {
+        let mut first_writes = HashMap::new();
+        for (index, entry) in self.journal.iter().enumerate().skip(journal_len) {
+            if let Some(cell) = entry.cell() {
+                first_writes.entry(cell).or_insert(index);
+            }
+        }
+
+        first_writes
+    }
+
+    /// Drops the reads (done since the given checkpoint) of values written since it, as these
+    /// values are about to be undone; the remaining reads are moved to the checkpoint position.
+    fn forget_reads_of_reverted_writes(&mut self, checkpoint: Checkpoint) {
+        let first_writes = self.get_first_writes_since(checkpoint.journal_len);
+        let reverted_reads = self.reads.split_off(checkpoint.reads_len);
+        for (read, journal_len) in reverted_reads {
+            if !is_read_of_own_write(&first_writes, read.cell(), journal_len) {
+                self.reads.push((read, checkpoint.journal_len));
+            }
+        }
+    }
+
+    /// Returns the values read since the given checkpoint, as first read; cells read only after
+    /// being written since the checkpoint are excluded.
+    fn get_reads_since(&self, checkpoint: Checkpoint) -> StateMaps {
+        let first_writes = self.get_first_writes_since(checkpoint.journal_len);
+        let mut reads = StateMaps::default();
+        for &(read, journal_len) in &self.reads[checkpoint.reads_len..] {
+            if is_read_of_own_write(&first_writes, read.cell(), journal_len) {
+                continue;
+            }
+
+            match read {
+                StateRead::Storage(key, value) => {
+                    reads.storage.entry(key).or_insert(value);
+                }
+                StateRead::Nonce(address, nonce) => {
+                    reads.nonces.entry(address).or_insert(nonce);
+                }
+                StateRead::ClassHash(address, class_hash) => {
+                    reads.class_hashes.entry(address).or_insert(class_hash);
+                }
+                StateRead::CompiledClassHash(class_hash, compiled_class_hash) => {
+                    reads.compiled_class_hashes.entry(class_hash).or_insert(compiled_class_hash);
+                }
+            }
+        }
+
+        reads
+    }
+
+    /// Returns the cells written since the given journal position, mapped to the value they held
+    /// at that position; `None` stands for the cell's initial value.
+    fn get_previous_writes_since(&self, journal_len: usize) -> PreviousWrites {
+        let mut previous_writes = PreviousWrites::default();
+        // The earliest journal entry of each cell holds its value at the given position.
+        for entry in self.journal[journal_len..].iter().rev() {
+            match entry {
+                JournalEntry::Storage(key, previous) => {
+                    previous_writes.storage.insert(*key, *previous);
+                }
+                JournalEntry::Nonce(address, previous) => {
+                    previous_writes.nonces.insert(*address, *previous);
+                }
+                JournalEntry::ClassHash(address, previous) => {
+                    previous_writes.class_hashes.insert(*address, *previous);
+                }
+                JournalEntry::CompiledClassHash(class_hash, previous) => {
+                    previous_writes.compiled_class_hashes.insert(*class_hash, *previous);
+                }
+                JournalEntry::ContractClass(..) => {}
+            }
+        }
+
+        previous_writes
+    }
+
+    /// Returns the values changed since the given journal position, reading initial values of
+    /// write-only cells from the underlying state, if needed.
+    fn get_updates_since(&mut self, journal_len: usize) -> StateResult<StateMaps> {
+        let previous_writes = self.get_previous_writes_since(journal_len);
+        // Cells first written since the given position are compared to their initial values.
+        let write_only_storage_keys: Vec<_> =
+            previous_writes.storage.iter().filter(|(_, v)| v.is_none()).map(|(k, _)| *k).collect();
+        let write_only_nonces: Vec<_> =
+            previous_writes.nonces.iter().filter(|(_, v)| v.is_none()).map(|(k, _)| *k).collect();
+        let write_only_class_hashes: Vec<_> = previous_writes
+            .class_hashes
+            .iter()
+            .filter(|(_, v)| v.is_none())
+            .map(|(k, _)| *k)
+            .collect();
+        self.cache_initial_values(
+            &write_only_storage_keys,
+            &write_only_nonces,
+            &write_only_class_hashes,
+        )?;
+
+        let cache = &self.cache;
+        let mut updates = StateMaps::default();
+
+        for (key, previous) in previous_writes.storage {
+            let previous = match previous {
+                Some(value) => value,
+                None => cache.storage_initial_values[&key],
+            };
+            let current = cache.storage_writes[&key];
+            if current != previous {
+                updates.storage.insert(key, current);
+            }
+        }
+
+        for (address, previous) in previous_writes.nonces {
+            let previous = match previous {
+                Some(nonce) => nonce,
+                None => cache.nonce_initial_values[&address],
+            };
+            let current = cache.nonce_writes[&address];
+            if current != previous {
+                updates.nonces.insert(address, current);
+            }
+        }
+
+        for (address, previous) in previous_writes.class_hashes {
+            let previous = match previous {
+                Some(class_hash) => class_hash,
+                None => cache.class_hash_initial_values[&address],
+            };
+            let current = cache.class_hash_writes[&address];
+            if current != previous {
+                updates.class_hashes.insert(address, current);
+            }
+        }
+
+        for (class_hash, previous) in previous_writes.compiled_class_hashes {
+            // Initial values of write-only cells are not read, as in `to_state_diff`.
+            let previous = previous
+                .or_else(|| cache.compiled_class_hash_initial_values.get(&class_hash).copied());
+            let current = cache.compiled_class_hash_writes[&class_hash];
+            if previous != Some(current) {
+                updates.compiled_class_hashes.insert(class_hash, current);
+            }
+        }
+
+        Ok(updates)
+    }
+
+    /// Returns the cells read and changed since the given checkpoint.
+    fn get_state_access_since(&mut self, checkpoint: Checkpoint) -> StateResult<StateAccess> {
+        let writes = self.get_updates_since(checkpoint.journal_len)?;
+        Ok(StateAccess { reads: self.get_reads_since(checkpoint), writes })
     }
 
     /// Returns the storage changes done through this state.
@@ -62,79 +315,144 @@ impl<S: StateReader> CachedState<S> {
     ) -> StateResult<StateChanges> {
         self.update_initial_values_of_write_only_access()?;
 
-        // Storage Update.
-        let storage_updates = &mut self.cache.get_storage_updates();
-        let mut modified_contracts: HashSet<ContractAddress> =
-            storage_updates.keys().map(|address_key_pair| address_key_pair.0).collect();
-
-        // Class hash Update (deployed contracts + replace_class syscall).
-        let class_hash_updates = &self.cache.get_class_hash_updates();
-        modified_contracts.extend(class_hash_updates.keys());
-
-        // Nonce updates.
-        let nonce_updates = &self.cache.get_nonce_updates();
-        modified_contracts.extend(nonce_updates.keys());
-
-        // Compiled class hash updates (declare Cairo 1 contract).
-        let compiled_class_hash_updates = &self.cache.get_compiled_class_hash_updates();
+        let updates = StateMaps {
+            nonces: self.cache.get_nonce_updates(),
+            class_hashes: self.cache.get_class_hash_updates(),
+            storage: self.cache.get_storage_updates(),
+            compiled_class_hashes: self.cache.get_compiled_class_hash_updates(),
+        };
+        StateChanges::for_fee_charge(updates, fee_token_address, sender_address)
+    }
 
-        // Calculated before executing fee transfer and therefore we add manually the fee transfer
-        // changes. Exclude the fee token contract modification, since it’s charged once throughout
-        // the block.
-        if let Some(sender_address) = sender_address {
-            let (sender_low_key, _sender_high_key) =
-                get_erc20_balance_var_addresses(&sender_address)?;
-            storage_updates.insert((fee_token_address, sender_low_key), StarkFelt::default());
-        }
-        modified_contracts.remove(&fee_token_address);
+    /// Returns the changes done to the underlying state, with the previous value of each changed
+    /// cell. Compiled class hashes whose initial value is not cached are taken to be previously
+    /// undeclared.
+    pub fn to_reversible_state_diff(&mut self) -> StateResult<ReversibleStateDiff> {
+        self.update_initial_values_of_write_only_access()?;
 
-        Ok(StateChanges {
-            storage_updates: storage_updates.clone(),
-            modified_contracts,
-            class_hash_updates: class_hash_updates.clone(),
-            compiled_class_hash_updates: compiled_class_hash_updates.clone(),
-        })
+        let initial_values = StateMaps {
+            nonces: self.cache.nonce_initial_values.clone(),
+            class_hashes: self.cache.class_hash_initial_values.clone(),
+            storage: self.cache.storage_initial_values.clone(),
+            compiled_class_hashes: self.cache.compiled_class_hash_initial_values.clone(),
+        };
+        Ok(ReversibleStateDiff::new(&initial_values, &self.get_writes()))
     }
 
     /// Updates cache with initial cell values for write-only access.
     /// If written values match the original, the cell is unchanged and not counted as a
     /// storage-change for fee calculation.
     /// Same for class hash and nonce writes.
-    // TODO(Noa, 30/07/23): Consider adding DB getters in bulk (via a DB read transaction).
     fn update_initial_values_of_write_only_access(&mut self) -> StateResult<()> {
         // Eliminate storage writes that are identical to the initial value (no change). Assumes
         // that `set_storage_at` does not affect the state field.
-        for contract_storage_key in self.cache.storage_writes.keys() {
-            if !self.cache.storage_initial_values.contains_key(contract_storage_key) {
-                // First access to this cell was write; cache initial value.
-                self.cache.storage_initial_values.insert(
-                    *contract_storage_key,
-                    self.state.get_storage_at(contract_storage_key.0, contract_storage_key.1)?,
-                );
-            }
+        let storage_keys: Vec<_> = self.cache.storage_writes.keys().copied().collect();
+        let nonce_addresses: Vec<_> = self.cache.nonce_writes.keys().copied().collect();
+        let class_hash_addresses: Vec<_> = self.cache.class_hash_writes.keys().copied().collect();
+
+        self.cache_initial_values(&storage_keys, &nonce_addresses, &class_hash_addresses)
+    }
+
+    /// Reads the initial values of the given cells from the underlying state, in bulk, unless
+    /// already cached. Used to warm up the cache with cells that are known to be accessed.
+    pub fn prefetch(
+        &mut self,
+        storage_keys: &[ContractStorageKey],
+        nonce_addresses: &[ContractAddress],
+        class_hash_addresses: &[ContractAddress],
+    ) -> StateResult<()> {
+        self.cache_initial_values(storage_keys, nonce_addresses, class_hash_addresses)
+    }
+
+    fn cache_initial_values(
+        &mut self,
+        storage_keys: &[ContractStorageKey],
+        nonce_addresses: &[ContractAddress],
+        class_hash_addresses: &[ContractAddress],
+    ) -> StateResult<()> {
+        let cache = &mut self.cache;
+
+        let missing_storage_keys: Vec<_> = storage_keys
+            .iter()
+            .filter(|key| !cache.storage_initial_values.contains_key(key))
+            .copied()
+            .collect();
+        if !missing_storage_keys.is_empty() {
+            let values = self.state.get_storage_batch(&missing_storage_keys)?;
+            cache.storage_initial_values.extend(missing_storage_keys.into_iter().zip(values));
         }
 
-        for contract_address in self.cache.class_hash_writes.keys() {
-            if !self.cache.class_hash_initial_values.contains_key(contract_address) {
-                // First access to this cell was write; cache initial value.
-                self.cache
-                    .class_hash_initial_values
-                    .insert(*contract_address, self.state.get_class_hash_at(*contract_address)?);
-            }
+        let missing_nonce_addresses: Vec<_> = nonce_addresses
+            .iter()
+            .filter(|address| !cache.nonce_initial_values.contains_key(address))
+            .copied()
+            .collect();
+        if !missing_nonce_addresses.is_empty() {
+            let nonces = self.state.get_nonces_batch(&missing_nonce_addresses)?;
+            cache.nonce_initial_values.extend(missing_nonce_addresses.into_iter().zip(nonces));
         }
 
-        for contract_address in self.cache.nonce_writes.keys() {
-            if !self.cache.nonce_initial_values.contains_key(contract_address) {
-                // First access to this cell was write; cache initial value.
-                self.cache
-                    .nonce_initial_values
-                    .insert(*contract_address, self.state.get_nonce_at(*contract_address)?);
-            }
+        let missing_class_hash_addresses: Vec<_> = class_hash_addresses
+            .iter()
+            .filter(|address| !cache.class_hash_initial_values.contains_key(address))
+            .copied()
+            .collect();
+        if !missing_class_hash_addresses.is_empty() {
+            let class_hashes = self.state.get_class_hashes_batch(&missing_class_hash_addresses)?;
+            cache
+                .class_hash_initial_values
+                .extend(missing_class_hash_addresses.into_iter().zip(class_hashes));
         }
 
         Ok(())
     }
 
+    /// Returns the values written through this state (including writes identical to the initial
+    /// values), per cell type.
+    pub(crate) fn get_writes(&self) -> StateMaps {
+        StateMaps {
+            nonces: self.cache.nonce_writes.clone(),
+            class_hashes: self.cache.class_hash_writes.clone(),
+            storage: self.cache.storage_writes.clone(),
+            compiled_class_hashes: self.cache.compiled_class_hash_writes.clone(),
+        }
+    }
+
+    /// Returns the contract classes cached by this state; both read and set ones.
+    pub(crate) fn class_hash_to_class(&self) -> &ContractClassMapping {
+        &self.class_hash_to_class
+    }
+
+    /// Returns the global contract cache shared by this state.
+    pub(crate) fn global_class_cache(&self) -> &GlobalContractCache {
+        &self.global_class_hash_to_class
+    }
+
+    /// Writes the given values and contract classes into the cache, as if they were set through
+    /// the `State` API.
+    pub(crate) fn apply_writes(&mut self, writes: StateMaps, classes: ContractClassMapping) {
+        for (contract_address, nonce) in writes.nonces {
+            let previous = self.cache.set_nonce_value(contract_address, nonce);
+            self.record(JournalEntry::Nonce(contract_address, previous));
+        }
+        for (contract_address, class_hash) in writes.class_hashes {
+            let previous = self.cache.set_class_hash_write(contract_address, class_hash);
+            self.record(JournalEntry::ClassHash(contract_address, previous));
+        }
+        for ((contract_address, key), value) in writes.storage {
+            self.set_storage_at(contract_address, key, value);
+        }
+        for (class_hash, compiled_class_hash) in writes.compiled_class_hashes {
+            let previous =
+                self.cache.set_compiled_class_hash_write(class_hash, compiled_class_hash);
+            self.record(JournalEntry::CompiledClassHash(class_hash, previous));
+        }
+        for (class_hash, contract_class) in classes {
+            let previous = self.class_hash_to_class.insert(class_hash, contract_class);
+            self.record(JournalEntry::ContractClass(class_hash, previous));
+        }
+    }
+
     /// Drains contract-class cache collected during execution and updates the global cache.
     pub fn move_classes_to_global_cache(&mut self) {
         let contract_class_updates: Vec<_> = self.class_hash_to_class.drain().collect();
@@ -147,9 +465,7 @@ impl<S: StateReader> CachedState<S> {
     // store. The Guard will panic only if the Mutex panics during the lock operation, but
     // this shouldn't happen in our flow.
     // Note: `&mut` is used since the LRU cache updates internal counters on reads.
-    pub fn global_class_hash_to_class(
-        &mut self,
-    ) -> MutexGuard<'_, SizedCache<ClassHash, ContractClass>> {
+    pub fn global_class_hash_to_class(&mut self) -> MutexGuard<'_, ContractClassLRUCache> {
         self.global_class_hash_to_class.lock().expect("Global contract cache is poisoned.")
     }
 }
@@ -171,10 +487,11 @@ impl<S: StateReader> StateReader for CachedState<S> {
             self.cache.set_storage_initial_value(contract_address, key, storage_value);
         }
 
-        let value = self.cache.get_storage_at(contract_address, key).unwrap_or_else(|| {
+        let value = *self.cache.get_storage_at(contract_address, key).unwrap_or_else(|| {
             panic!("Cannot retrieve '{contract_address:?}' and '{key:?}' from the cache.")
         });
-        Ok(*value)
+        self.record_read(StateRead::Storage((contract_address, key), value));
+        Ok(value)
     }
 
     fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
@@ -183,11 +500,12 @@ impl<S: StateReader> StateReader for CachedState<S> {
             self.cache.set_nonce_initial_value(contract_address, nonce);
         }
 
-        let nonce = self
+        let nonce = *self
             .cache
             .get_nonce_at(contract_address)
             .unwrap_or_else(|| panic!("Cannot retrieve '{contract_address:?}' from the cache."));
-        Ok(*nonce)
+        self.record_read(StateRead::Nonce(contract_address, nonce));
+        Ok(nonce)
     }
 
     fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
@@ -196,11 +514,12 @@ impl<S: StateReader> StateReader for CachedState<S> {
             self.cache.set_class_hash_initial_value(contract_address, class_hash);
         }
 
-        let class_hash = self
+        let class_hash = *self
             .cache
             .get_class_hash_at(contract_address)
             .unwrap_or_else(|| panic!("Cannot retrieve '{contract_address:?}' from the cache."));
-        Ok(*class_hash)
+        self.record_read(StateRead::ClassHash(contract_address, class_hash));
+        Ok(class_hash)
     }
 
     fn get_compiled_contract_class(
@@ -237,11 +556,45 @@ impl<S: StateReader> StateReader for CachedState<S> {
             self.cache.set_compiled_class_hash_initial_value(class_hash, compiled_class_hash);
         }
 
-        let compiled_class_hash = self
+        let compiled_class_hash = *self
             .cache
             .get_compiled_class_hash(class_hash)
             .unwrap_or_else(|| panic!("Cannot retrieve '{class_hash:?}' from the cache."));
-        Ok(*compiled_class_hash)
+        self.record_read(StateRead::CompiledClassHash(class_hash, compiled_class_hash));
+        Ok(compiled_class_hash)
+    }
+
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        self.prefetch(contract_storage_keys, &[], &[])?;
+        contract_storage_keys
+            .iter()
+            .map(|&(contract_address, key)| self.get_storage_at(contract_address, key))
+            .collect()
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        self.prefetch(&[], contract_addresses, &[])?;
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_nonce_at(contract_address))
+            .collect()
+    }
+
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        self.prefetch(&[], &[], contract_addresses)?;
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_class_hash_at(contract_address))
+            .collect()
     }
 }
 
@@ -252,7 +605,8 @@ impl<S: StateReader> State for CachedState<S> {
         key: StorageKey,
         value: StarkFelt,
     ) {
-        self.cache.set_storage_value(contract_address, key, value);
+        let previous = self.cache.set_storage_value(contract_address, key, value);
+        self.record(JournalEntry::Storage((contract_address, key), previous));
     }
 
     fn increment_nonce(&mut self, contract_address: ContractAddress) -> StateResult<()> {
@@ -260,8 +614,15 @@ impl<S: StateReader> State for CachedState<S> {
         let current_nonce_as_u64 = usize::try_from(current_nonce.0)? as u64;
         let next_nonce_val = 1_u64 + current_nonce_as_u64;
         let next_nonce = Nonce(StarkFelt::from(next_nonce_val));
-        self.cache.set_nonce_value(contract_address, next_nonce);
+        let previous = self.cache.set_nonce_value(contract_address, next_nonce);
+        self.record(JournalEntry::Nonce(contract_address, previous));
+
+        Ok(())
+    }
 
+    fn set_nonce_at(&mut self, contract_address: ContractAddress, nonce: Nonce) -> StateResult<()> {
+        let previous = self.cache.set_nonce_value(contract_address, nonce);
+        self.record(JournalEntry::Nonce(contract_address, previous));
         Ok(())
     }
 
@@ -274,7 +635,8 @@ impl<S: StateReader> State for CachedState<S> {
             return Err(StateError::OutOfRangeContractAddress);
         }
 
-        self.cache.set_class_hash_write(contract_address, class_hash);
+        let previous = self.cache.set_class_hash_write(contract_address, class_hash);
+        self.record(JournalEntry::ClassHash(contract_address, previous));
         Ok(())
     }
 
@@ -283,7 +645,8 @@ impl<S: StateReader> State for CachedState<S> {
         class_hash: &ClassHash,
         contract_class: ContractClass,
     ) -> StateResult<()> {
-        self.class_hash_to_class.insert(*class_hash, contract_class);
+        let previous = self.class_hash_to_class.insert(*class_hash, contract_class);
+        self.record(JournalEntry::ContractClass(*class_hash, previous));
         Ok(())
     }
 
@@ -292,7 +655,8 @@ impl<S: StateReader> State for CachedState<S> {
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
     ) -> StateResult<()> {
-        self.cache.set_compiled_class_hash_write(class_hash, compiled_class_hash);
+        let previous = self.cache.set_compiled_class_hash_write(class_hash, compiled_class_hash);
+        self.record(JournalEntry::CompiledClassHash(class_hash, previous));
         Ok(())
     }
 
@@ -313,6 +677,7 @@ impl<S: StateReader> State for CachedState<S> {
             class_hash_to_compiled_class_hash: IndexMap::from_iter(declared_classes),
             address_to_nonce: IndexMap::from_iter(nonces),
         }
+        .sorted()
     }
 }
 
@@ -324,6 +689,9 @@ impl Default for CachedState<crate::test_utils::DictStateReader> {
             cache: Default::default(),
             class_hash_to_class: Default::default(),
             global_class_hash_to_class: Default::default(),
+            journal: Default::default(),
+            checkpoints: Default::default(),
+            reads: Default::default(),
         }
     }
 }
@@ -350,6 +718,48 @@ impl From<StorageView> for IndexMap<ContractAddress, IndexMap<StorageKey, StarkF
     }
 }
 
+/// Holds a value per state cell, for each of the cell types.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateMaps {
+    pub nonces: HashMap<ContractAddress, Nonce>,
+    pub class_hashes: HashMap<ContractAddress, ClassHash>,
+    pub storage: HashMap<ContractStorageKey, StarkFelt>,
+    pub compiled_class_hashes: HashMap<ClassHash, CompiledClassHash>,
+}
+
+impl StateMaps {
+    /// Returns the cells holding a value, in no particular order.
+    pub fn cells(&self) -> impl Iterator<Item = StateCell> + '_ {
+        let nonces = self.nonces.keys().map(|address| StateCell::Nonce(*address));
+        let class_hashes = self.class_hashes.keys().map(|address| StateCell::ClassHash(*address));
+        let storage = self.storage.keys().map(|key| StateCell::Storage(*key));
+        let compiled_class_hashes = self
+            .compiled_class_hashes
+            .keys()
+            .map(|class_hash| StateCell::CompiledClassHash(*class_hash));
+
+        nonces.chain(class_hashes).chain(storage).chain(compiled_class_hashes)
+    }
+}
+
+/// A single state cell.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum StateCell {
+    Nonce(ContractAddress),
+    ClassHash(ContractAddress),
+    Storage(ContractStorageKey),
+    CompiledClassHash(ClassHash),
+}
+
+/// The state cells accessed by a transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateAccess {
+    /// The values read from the state, unless first written by the transaction itself.
+    pub reads: StateMaps,
+    /// The values changed by the transaction.
+    pub writes: StateMaps,
+}
+
 /// Caches read and write requests.
 /// The tracked changes are needed for block state commitment.
 
@@ -397,22 +807,27 @@ impl StateCache {
         self.storage_initial_values.insert(contract_storage_key, value);
     }
 
+    /// Returns the previously written value, if any.
     fn set_storage_value(
         &mut self,
         contract_address: ContractAddress,
         key: StorageKey,
         value: StarkFelt,
-    ) {
+    ) -> Option<StarkFelt> {
         let contract_storage_key = (contract_address, key);
-        self.storage_writes.insert(contract_storage_key, value);
+        self.storage_writes.insert(contract_storage_key, value)
     }
 
     fn set_nonce_initial_value(&mut self, contract_address: ContractAddress, nonce: Nonce) {
         self.nonce_initial_values.insert(contract_address, nonce);
     }
 
-    fn set_nonce_value(&mut self, contract_address: ContractAddress, nonce: Nonce) {
-        self.nonce_writes.insert(contract_address, nonce);
+    fn set_nonce_value(
+        &mut self,
+        contract_address: ContractAddress,
+        nonce: Nonce,
+    ) -> Option<Nonce> {
+        self.nonce_writes.insert(contract_address, nonce)
     }
 
     fn get_class_hash_at(&self, contract_address: ContractAddress) -> Option<&ClassHash> {
@@ -429,8 +844,12 @@ impl StateCache {
         self.class_hash_initial_values.insert(contract_address, class_hash);
     }
 
-    fn set_class_hash_write(&mut self, contract_address: ContractAddress, class_hash: ClassHash) {
-        self.class_hash_writes.insert(contract_address, class_hash);
+    fn set_class_hash_write(
+        &mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> Option<ClassHash> {
+        self.class_hash_writes.insert(contract_address, class_hash)
     }
 
     fn get_compiled_class_hash(&self, class_hash: ClassHash) -> Option<&CompiledClassHash> {
@@ -451,8 +870,8 @@ impl StateCache {
         &mut self,
         class_hash: ClassHash,
         compiled_class_hash: CompiledClassHash,
-    ) {
-        self.compiled_class_hash_writes.insert(class_hash, compiled_class_hash);
+    ) -> Option<CompiledClassHash> {
+        self.compiled_class_hash_writes.insert(class_hash, compiled_class_hash)
     }
 
     fn get_storage_updates(&self) -> HashMap<ContractStorageKey, StarkFelt> {
@@ -513,6 +932,27 @@ impl<'a, S: State + ?Sized> StateReader for MutRefState<'a, S> {
     fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
         self.0.get_compiled_class_hash(class_hash)
     }
+
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        self.0.get_storage_batch(contract_storage_keys)
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        self.0.get_nonces_batch(contract_addresses)
+    }
+
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        self.0.get_class_hashes_batch(contract_addresses)
+    }
 }
 
 impl<'a, S: State + ?Sized> State for MutRefState<'a, S> {
@@ -529,6 +969,10 @@ impl<'a, S: State + ?Sized> State for MutRefState<'a, S> {
         self.0.increment_nonce(contract_address)
     }
 
+    fn set_nonce_at(&mut self, contract_address: ContractAddress, nonce: Nonce) -> StateResult<()> {
+        self.0.set_nonce_at(contract_address, nonce)
+    }
+
     fn set_class_hash_at(
         &mut self,
         contract_address: ContractAddress,
@@ -558,25 +1002,310 @@ impl<'a, S: State + ?Sized> State for MutRefState<'a, S> {
     }
 }
 
-pub type TransactionalState<'a, S> = CachedState<MutRefState<'a, CachedState<S>>>;
+/// An open checkpoint of a `CachedState`; see `CachedState::checkpoint`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    depth: usize,
+    journal_len: usize,
+    reads_len: usize,
+}
+
+/// An undo-log entry: a written cell and its previously written value (if any).
+#[derive(Debug)]
+enum JournalEntry {
+    Storage(ContractStorageKey, Option<StarkFelt>),
+    Nonce(ContractAddress, Option<Nonce>),
+    ClassHash(ContractAddress, Option<ClassHash>),
+    CompiledClassHash(ClassHash, Option<CompiledClassHash>),
+    ContractClass(ClassHash, Option<ContractClass>),
+}
+
+impl JournalEntry {
+    fn cell(&self) -> Option<StateCell> {
+        match *self {
+            JournalEntry::Storage(key, _) => Some(StateCell::Storage(key)),
+            JournalEntry::Nonce(address, _) => Some(StateCell::Nonce(address)),
+            JournalEntry::ClassHash(address, _) => Some(StateCell::ClassHash(address)),
+            JournalEntry::CompiledClassHash(class_hash, _) => {
+                Some(StateCell::CompiledClassHash(class_hash))
+            }
+            JournalEntry::ContractClass(..) => None,
+        }
+    }
+}
+
+/// A value read from the state.
+#[derive(Clone, Copy, Debug)]
+enum StateRead {
+    Storage(ContractStorageKey, StarkFelt),
+    Nonce(ContractAddress, Nonce),
+    ClassHash(ContractAddress, ClassHash),
+    CompiledClassHash(ClassHash, CompiledClassHash),
+}
+
+impl StateRead {
+    fn cell(&self) -> StateCell {
+        match *self {
+            StateRead::Storage(key, _) => StateCell::Storage(key),
+            StateRead::Nonce(address, _) => StateCell::Nonce(address),
+            StateRead::ClassHash(address, _) => StateCell::ClassHash(address),
+            StateRead::CompiledClassHash(class_hash, _) => StateCell::CompiledClassHash(class_hash),
+        }
+    }
+}
+
+/// Returns whether a read, done at the given journal position, is of a value written since the
+/// position the given first writes were collected from.
+fn is_read_of_own_write(
+    first_writes: &HashMap<StateCell, usize>,
+    cell: StateCell,
+    journal_len: usize,
+) -> bool {
+    first_writes.get(&cell).is_some_and(|&first_write| first_write < journal_len)
+}
+
+#[derive(Debug, Default)]
+struct PreviousWrites {
+    nonces: HashMap<ContractAddress, Option<Nonce>>,
+    class_hashes: HashMap<ContractAddress, Option<ClassHash>>,
+    storage: HashMap<ContractStorageKey, Option<StarkFelt>>,
+    compiled_class_hashes: HashMap<ClassHash, Option<CompiledClassHash>>,
+}
+
+/// A transactional view of a `CachedState`: the changes done through it will either all happen
+/// (will be committed) or none of them (will be discarded; also upon drop).
+/// Nested transactional states share the cache of the underlying state, each holding a checkpoint
+/// of it.
+#[derive(Debug)]
+pub struct TransactionalState<'a, S: StateReader> {
+    state: &'a mut CachedState<S>,
+    // Taken once the transactional state is committed or aborted.
+    checkpoint: Option<Checkpoint>,
+}
 
-/// Adds the ability to perform a transactional execution.
 impl<'a, S: StateReader> TransactionalState<'a, S> {
-    /// Commits changes in the child (wrapping) state to its parent.
-    pub fn commit(self) {
-        let child_cache = self.cache;
-        let parent_cache = &mut self.state.0.cache;
+    fn new(state: &'a mut CachedState<S>) -> Self {
+        let checkpoint = state.checkpoint();
+        Self { state, checkpoint: Some(checkpoint) }
+    }
+
+    /// Creates a transactional instance nested in this one; committing it keeps its changes as
+    /// part of this instance.
+    pub fn create_transactional(&mut self) -> TransactionalState<'_, S> {
+        TransactionalState::new(self.state)
+    }
+
+    /// Returns the storage changes done through this state; see
+    /// `CachedState::get_actual_state_changes_for_fee_charge`.
+    pub fn get_actual_state_changes_for_fee_charge(
+        &mut self,
+        fee_token_address: ContractAddress,
+        sender_address: Option<ContractAddress>,
+    ) -> StateResult<StateChanges> {
+        let updates = self.state.get_updates_since(self.checkpoint().journal_len)?;
+        StateChanges::for_fee_charge(updates, fee_token_address, sender_address)
+    }
+
+    /// Returns the cells read and changed through this state.
+    pub fn get_state_access(&mut self) -> StateResult<StateAccess> {
+        self.state.get_state_access_since(self.checkpoint())
+    }
+
+    /// Returns the class hashes, before the changes done through this state, of the contract
+    /// instances whose class hash was written through it.
+    pub fn get_previous_class_hashes(
+        &mut self,
+    ) -> StateResult<HashMap<ContractAddress, ClassHash>> {
+        let previous_writes = self.state.get_previous_writes_since(self.checkpoint().journal_len);
+        let mut previous_class_hashes = HashMap::new();
+        for (address, previous) in previous_writes.class_hashes {
+            let initial = self.state.cache.class_hash_initial_values.get(&address).copied();
+            let class_hash = match previous.or(initial) {
+                Some(class_hash) => class_hash,
+                None => self.state.state.get_class_hash_at(address)?,
+            };
+            previous_class_hashes.insert(address, class_hash);
+        }
+
+        Ok(previous_class_hashes)
+    }
+
+    /// Warms up the cache of the underlying state; see `CachedState::prefetch`.
+    pub fn prefetch(
+        &mut self,
+        storage_keys: &[ContractStorageKey],
+        nonce_addresses: &[ContractAddress],
+        class_hash_addresses: &[ContractAddress],
+    ) -> StateResult<()> {
+        self.state.prefetch(storage_keys, nonce_addresses, class_hash_addresses)
+    }
+
+    /// Commits the changes done through this state to the underlying state.
+    pub fn commit(mut self) {
+        let checkpoint = self.checkpoint.take().expect("Checkpoint is open until dropped.");
+        self.state.commit(checkpoint);
+    }
+
+    /// Discards the changes done through this state.
+    pub fn abort(mut self) {
+        let checkpoint = self.checkpoint.take().expect("Checkpoint is open until dropped.");
+        self.state.revert_to(checkpoint);
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint.expect("Checkpoint is open until dropped.")
+    }
+}
+
+impl<'a, S: StateReader> Drop for TransactionalState<'a, S> {
+    fn drop(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.take() {
+            self.state.revert_to(checkpoint);
+        }
+    }
+}
+
+/// Proxies the underlying state.
+impl<'a, S: StateReader> StateReader for TransactionalState<'a, S> {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.state.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.state.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.state.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        self.state.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        self.state.get_storage_batch(contract_storage_keys)
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        self.state.get_nonces_batch(contract_addresses)
+    }
+
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        self.state.get_class_hashes_batch(contract_addresses)
+    }
+}
+
+impl<'a, S: StateReader> State for TransactionalState<'a, S> {
+    fn set_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: StarkFelt,
+    ) {
+        self.state.set_storage_at(contract_address, key, value)
+    }
+
+    fn increment_nonce(&mut self, contract_address: ContractAddress) -> StateResult<()> {
+        self.state.increment_nonce(contract_address)
+    }
+
+    fn set_nonce_at(&mut self, contract_address: ContractAddress, nonce: Nonce) -> StateResult<()> {
+        self.state.set_nonce_at(contract_address, nonce)
+    }
+
+    fn set_class_hash_at(
+        &mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> StateResult<()> {
+        self.state.set_class_hash_at(contract_address, class_hash)
+    }
+
+    fn set_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+        contract_class: ContractClass,
+    ) -> StateResult<()> {
+        self.state.set_contract_class(class_hash, contract_class)
+    }
 
-        parent_cache.nonce_writes.extend(child_cache.nonce_writes);
-        parent_cache.class_hash_writes.extend(child_cache.class_hash_writes);
-        parent_cache.storage_writes.extend(child_cache.storage_writes);
-        parent_cache.compiled_class_hash_writes.extend(child_cache.compiled_class_hash_writes);
-        self.state.0.class_hash_to_class.extend(self.class_hash_to_class);
-        self.state.0.global_class_hash_to_class = self.global_class_hash_to_class;
+    fn set_compiled_class_hash(
+        &mut self,
+        class_hash: ClassHash,
+        compiled_class_hash: CompiledClassHash,
+    ) -> StateResult<()> {
+        self.state.set_compiled_class_hash(class_hash, compiled_class_hash)
     }
 
-    /// Drops `self`.
-    pub fn abort(self) {}
+    /// Returns the changes done through this state. Write-only cells whose initial value is not
+    /// cached are considered changed.
+    fn to_state_diff(&self) -> CommitmentStateDiff {
+        let previous_writes = self.state.get_previous_writes_since(self.checkpoint().journal_len);
+        let cache = &self.state.cache;
+        fn is_changed<T: PartialEq>(current: T, previous: Option<T>, initial: Option<T>) -> bool {
+            previous.or(initial) != Some(current)
+        }
+
+        let mut storage_updates = HashMap::new();
+        for (key, previous) in previous_writes.storage {
+            let current = cache.storage_writes[&key];
+            if is_changed(current, previous, cache.storage_initial_values.get(&key).copied()) {
+                storage_updates.insert(key, current);
+            }
+        }
+        let mut address_to_nonce = IndexMap::new();
+        for (address, previous) in previous_writes.nonces {
+            let current = cache.nonce_writes[&address];
+            if is_changed(current, previous, cache.nonce_initial_values.get(&address).copied()) {
+                address_to_nonce.insert(address, current);
+            }
+        }
+        let mut address_to_class_hash = IndexMap::new();
+        for (address, previous) in previous_writes.class_hashes {
+            let current = cache.class_hash_writes[&address];
+            let initial = cache.class_hash_initial_values.get(&address).copied();
+            if is_changed(current, previous, initial) {
+                address_to_class_hash.insert(address, current);
+            }
+        }
+        let mut class_hash_to_compiled_class_hash = IndexMap::new();
+        for (class_hash, previous) in previous_writes.compiled_class_hashes {
+            let current = cache.compiled_class_hash_writes[&class_hash];
+            let initial = cache.compiled_class_hash_initial_values.get(&class_hash).copied();
+            if is_changed(current, previous, initial) {
+                class_hash_to_compiled_class_hash.insert(class_hash, current);
+            }
+        }
+
+        CommitmentStateDiff {
+            address_to_class_hash,
+            address_to_nonce,
+            storage_updates: StorageView(storage_updates).into(),
+            class_hash_to_compiled_class_hash,
+        }
+        .sorted()
+    }
 }
 
 /// Holds uncommitted changes induced on StarkNet contracts.
@@ -591,6 +1320,21 @@ pub struct CommitmentStateDiff {
     pub class_hash_to_compiled_class_hash: IndexMap<ClassHash, CompiledClassHash>,
 }
 
+impl CommitmentStateDiff {
+    /// Sorts all mappings by key, so that the order of the diff does not depend on the order
+    /// in which the cells were written.
+    fn sorted(mut self) -> Self {
+        self.address_to_class_hash.sort_keys();
+        self.address_to_nonce.sort_keys();
+        self.storage_updates.sort_keys();
+        for contract_storage_updates in self.storage_updates.values_mut() {
+            contract_storage_updates.sort_keys();
+        }
+        self.class_hash_to_compiled_class_hash.sort_keys();
+        self
+    }
+}
+
 /// Holds the state changes.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct StateChanges {
@@ -601,6 +1345,43 @@ pub struct StateChanges {
 }
 
 impl StateChanges {
+    /// Returns the state changes counted for fee charge, given the updated values.
+    /// Calculated before executing fee transfer and therefore we add manually the fee transfer
+    /// changes. Exclude the fee token contract modification, since it’s charged once throughout
+    /// the block.
+    fn for_fee_charge(
+        updates: StateMaps,
+        fee_token_address: ContractAddress,
+        sender_address: Option<ContractAddress>,
+    ) -> StateResult<Self> {
+        let StateMaps {
+            nonces: nonce_updates,
+            class_hashes: class_hash_updates,
+            storage: mut storage_updates,
+            compiled_class_hashes: compiled_class_hash_updates,
+        } = updates;
+
+        // Storage, class hash (deployed contracts + replace_class syscall) and nonce updates.
+        let mut modified_contracts: HashSet<ContractAddress> =
+            storage_updates.keys().map(|address_key_pair| address_key_pair.0).collect();
+        modified_contracts.extend(class_hash_updates.keys());
+        modified_contracts.extend(nonce_updates.keys());
+
+        if let Some(sender_address) = sender_address {
+            let (sender_low_key, _sender_high_key) =
+                get_erc20_balance_var_addresses(&sender_address)?;
+            storage_updates.insert((fee_token_address, sender_low_key), StarkFelt::default());
+        }
+        modified_contracts.remove(&fee_token_address);
+
+        Ok(Self {
+            storage_updates,
+            modified_contracts,
+            class_hash_updates,
+            compiled_class_hash_updates,
+        })
+    }
+
     /// Merges the given state changes into a single one. Note that the order of the state changes
     /// is important. The state changes are merged in the order they appear in the given vector.
     pub fn merge(state_changes: Vec<Self>) -> Self {
@@ -627,6 +1408,24 @@ pub struct StateChangesCount {
     pub n_modified_contracts: usize,
 }
 
+impl From<&CommitmentStateDiff> for StateChangesCount {
+    fn from(state_diff: &CommitmentStateDiff) -> Self {
+        let modified_contracts: HashSet<&ContractAddress> = state_diff
+            .address_to_class_hash
+            .keys()
+            .chain(state_diff.address_to_nonce.keys())
+            .chain(state_diff.storage_updates.keys())
+            .collect();
+
+        Self {
+            n_storage_updates: state_diff.storage_updates.values().map(IndexMap::len).sum(),
+            n_class_hash_updates: state_diff.address_to_class_hash.len(),
+            n_compiled_class_hash_updates: state_diff.class_hash_to_compiled_class_hash.len(),
+            n_modified_contracts: modified_contracts.len(),
+        }
+    }
+}
+
 impl From<&StateChanges> for StateChangesCount {
     fn from(state_changes: &StateChanges) -> Self {
         Self {
@@ -638,20 +1437,29 @@ impl From<&StateChanges> for StateChangesCount {
     }
 }
 
-// Note: `ContractClassLRUCache` key-value types must align with `ContractClassMapping`.
-type ContractClassLRUCache = SizedCache<ClassHash, ContractClass>;
 #[derive(Debug, Clone, derive_more::Deref, derive_more::DerefMut)]
 // Thread-safe LRU cache for contract classes, optimized for inter-language sharing when
 // `blockifier` compiles as a shared library.
 pub struct GlobalContractCache(pub Arc<Mutex<ContractClassLRUCache>>);
 
 impl GlobalContractCache {
-    // TODO: make this configurable via a CachedState constructor argument.
-    const CACHE_SIZE: usize = 100;
+    pub const DEFAULT_CAPACITY: CacheCapacity = CacheCapacity::Classes(100);
+    /// The default capacity when classes are weighed by their bytecode length; about as many
+    /// classes as `DEFAULT_CAPACITY`, of 20K felts each.
+    pub const DEFAULT_BYTECODE_LENGTH_CAPACITY: CacheCapacity =
+        CacheCapacity::BytecodeLength(2_000_000);
+
+    pub fn new(capacity: CacheCapacity) -> Self {
+        Self(Arc::new(Mutex::new(ContractClassLRUCache::new(capacity))))
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().expect("Global contract cache is poisoned.").stats()
+    }
 }
 
 impl Default for GlobalContractCache {
     fn default() -> Self {
-        Self(Arc::new(Mutex::new(ContractClassLRUCache::with_size(Self::CACHE_SIZE))))
+        Self::new(Self::DEFAULT_CAPACITY)
     }
 }
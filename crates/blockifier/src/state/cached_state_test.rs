@@ -256,8 +256,30 @@ fn cached_state_state_diff_conversion() {
     assert_eq!(expected_state_diff, state.to_state_diff());
 }
 
-fn create_state_changes_for_test<S: StateReader>(state: &mut CachedState<S>) -> StateChanges {
-    let block_context = BlockContext::create_for_testing();
+#[test]
+fn state_diff_is_sorted() {
+    let mut state = CachedState::default();
+    for address in ["0x300", "0x100", "0x200"] {
+        for key in ["0x3", "0x1", "0x2"] {
+            state.set_storage_at(
+                contract_address!(address),
+                StorageKey(patricia_key!(key)),
+                stark_felt!(1_u8),
+            );
+        }
+    }
+
+    let state_diff = state.to_state_diff();
+    let addresses: Vec<_> = state_diff.storage_updates.keys().copied().collect();
+    assert_eq!(addresses, ["0x100", "0x200", "0x300"].map(|address| contract_address!(address)));
+    for storage_updates in state_diff.storage_updates.values() {
+        let keys: Vec<_> = storage_updates.keys().copied().collect();
+        assert_eq!(keys, ["0x1", "0x2", "0x3"].map(|key| StorageKey(patricia_key!(key))));
+    }
+}
+
+/// Applies the test state changes and returns the sender address.
+fn apply_state_changes_for_test(state: &mut dyn State) -> ContractAddress {
     let contract_address = contract_address!("0x100");
     let contract_address2 = contract_address!("0x101");
     let class_hash = class_hash!("0x10");
@@ -276,19 +298,20 @@ fn create_state_changes_for_test<S: StateReader>(state: &mut CachedState<S>) ->
     // As the second access:
     state.set_storage_at(contract_address, key, storage_val);
 
-    // Return the resulting state changes.
-    state
-        .get_actual_state_changes_for_fee_charge(
-            block_context.fee_token_address,
-            Some(contract_address),
-        )
-        .unwrap()
+    contract_address
 }
 
 #[test]
 fn count_actual_state_changes() {
+    let block_context = BlockContext::create_for_testing();
     let mut state: CachedState<DictStateReader> = CachedState::default();
-    let state_changes = create_state_changes_for_test(&mut state);
+    let sender_address = apply_state_changes_for_test(&mut state);
+    let state_changes = state
+        .get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_addresses.eth_fee_token_address,
+            Some(sender_address),
+        )
+        .unwrap();
     assert_eq!(
         StateChangesCount::from(&state_changes),
         StateChangesCount {
@@ -302,22 +325,31 @@ fn count_actual_state_changes() {
 
 #[test]
 fn test_state_changes_merge() {
-    // Create a transactional state containing the `create_state_changes_for_test` logic, get the
+    // Create a transactional state containing the `apply_state_changes_for_test` logic, get the
     // state changes and then commit.
+    let block_context = BlockContext::create_for_testing();
     let mut state: CachedState<DictStateReader> = CachedState::default();
     let mut transactional_state = CachedState::create_transactional(&mut state);
-    let state_changes1 = create_state_changes_for_test(&mut transactional_state);
+    let sender_address = apply_state_changes_for_test(&mut transactional_state);
+    let state_changes1 = transactional_state
+        .get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_addresses.eth_fee_token_address,
+            Some(sender_address),
+        )
+        .unwrap();
     transactional_state.commit();
 
     // After performing `commit`, the transactional state is moved (into state).  We need to create
     // a new transactional state that wraps `state` to continue.
-    let block_context = BlockContext::create_for_testing();
     let mut transactional_state = CachedState::create_transactional(&mut state);
     // Make sure that `get_actual_state_changes_for_fee_charge` on a newly created transactional
     // state returns null state changes and that merging null state changes with non-null state
     // changes results in the non-null state changes, no matter the order.
     let state_changes2 = transactional_state
-        .get_actual_state_changes_for_fee_charge(block_context.fee_token_address, None)
+        .get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_addresses.eth_fee_token_address,
+            None,
+        )
         .unwrap();
     assert_eq!(state_changes2, StateChanges::default());
     assert_eq!(
@@ -343,7 +375,10 @@ fn test_state_changes_merge() {
     transactional_state.increment_nonce(contract_address).unwrap();
     // Get the new state changes and then commit the transactional state.
     let state_changes3 = transactional_state
-        .get_actual_state_changes_for_fee_charge(block_context.fee_token_address, None)
+        .get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_addresses.eth_fee_token_address,
+            None,
+        )
         .unwrap();
     transactional_state.commit();
 
@@ -351,7 +386,10 @@ fn test_state_changes_merge() {
     // states. We expect the state_changes to match the merged state_changes of the transactional
     // states, but only when done in the right order.
     let state_changes_final = state
-        .get_actual_state_changes_for_fee_charge(block_context.fee_token_address, None)
+        .get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_addresses.eth_fee_token_address,
+            None,
+        )
         .unwrap();
     assert_eq!(
         StateChanges::merge(vec![
@@ -367,6 +405,175 @@ fn test_state_changes_merge() {
     );
 }
 
+#[test]
+fn batch_reads_and_prefetch() {
+    let contract_address = contract_address!("0x100");
+    let contract_address2 = contract_address!("0x101");
+    let key = StorageKey(patricia_key!("0x10"));
+    let key2 = StorageKey(patricia_key!("0x20"));
+    let nonce = Nonce(stark_felt!("0x3"));
+    let class_hash = class_hash!("0x40");
+    let mut state = CachedState::from(DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, key), stark_felt!("0x1")),
+            ((contract_address, key2), stark_felt!("0x2")),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, nonce)]),
+        address_to_class_hash: HashMap::from([(contract_address2, class_hash)]),
+        ..Default::default()
+    });
+
+    // Written values take precedence over the underlying state.
+    state.set_storage_at(contract_address, key2, stark_felt!("0x5"));
+    assert_eq!(
+        state.get_storage_batch(&[(contract_address, key), (contract_address, key2)]).unwrap(),
+        vec![stark_felt!("0x1"), stark_felt!("0x5")]
+    );
+    assert_eq!(
+        state.get_nonces_batch(&[contract_address, contract_address2]).unwrap(),
+        vec![nonce, Nonce::default()]
+    );
+
+    // Prefetching caches initial values, including the ones of written cells.
+    state.prefetch(&[(contract_address2, key)], &[], &[contract_address2]).unwrap();
+    assert_eq!(
+        state.cache.storage_initial_values,
+        HashMap::from([
+            ((contract_address, key), stark_felt!("0x1")),
+            ((contract_address, key2), stark_felt!("0x2")),
+            ((contract_address2, key), StarkFelt::default()),
+        ])
+    );
+    assert_eq!(
+        state.cache.class_hash_initial_values,
+        HashMap::from([(contract_address2, class_hash)])
+    );
+}
+
+#[test]
+fn nested_checkpoints() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let mut state: CachedState<DictStateReader> = CachedState::default();
+    state.set_storage_at(contract_address, key, stark_felt!("0x1"));
+
+    let outer_checkpoint = state.checkpoint();
+    state.set_storage_at(contract_address, key, stark_felt!("0x2"));
+    state.increment_nonce(contract_address).unwrap();
+
+    // Reverting an inner checkpoint keeps the outer checkpoint's changes.
+    let inner_checkpoint = state.checkpoint();
+    state.set_storage_at(contract_address, key, stark_felt!("0x3"));
+    state.increment_nonce(contract_address).unwrap();
+    state.set_class_hash_at(contract_address, class_hash!("0x20")).unwrap();
+    state.revert_to(inner_checkpoint);
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x2"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!("0x1")));
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), ClassHash::default());
+
+    // Committing an inner checkpoint makes its changes part of the outer one.
+    let inner_checkpoint = state.checkpoint();
+    state.set_storage_at(contract_address, key, stark_felt!("0x4"));
+    state.commit(inner_checkpoint);
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x4"));
+
+    state.revert_to(outer_checkpoint);
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x1"));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce::default());
+    assert!(state.journal.is_empty());
+}
+
+#[test]
+fn transactional_state_reverts_on_drop() {
+    let block_context = BlockContext::create_for_testing();
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let mut state: CachedState<DictStateReader> = CachedState::default();
+
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    transactional_state.set_storage_at(contract_address, key, stark_felt!("0x1"));
+    {
+        let mut nested_state = transactional_state.create_transactional();
+        nested_state.set_storage_at(contract_address, key, stark_felt!("0x2"));
+        let nested_changes = nested_state
+            .get_actual_state_changes_for_fee_charge(
+                block_context.fee_token_addresses.eth_fee_token_address,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            nested_changes.storage_updates,
+            HashMap::from([((contract_address, key), stark_felt!("0x2"))])
+        );
+    }
+    assert_eq!(
+        transactional_state.get_storage_at(contract_address, key).unwrap(),
+        stark_felt!("0x1")
+    );
+    drop(transactional_state);
+
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), StarkFelt::default());
+    assert!(state.to_state_diff().storage_updates.is_empty());
+}
+
+#[test]
+fn state_access_tracking() {
+    let contract_address = contract_address!("0x100");
+    let [key_a, key_b, key_c, key_d, key_e] =
+        ["0x10", "0x11", "0x12", "0x13", "0x14"].map(|key| StorageKey(patricia_key!(key)));
+    let mut state = CachedState::from(DictStateReader {
+        storage_view: HashMap::from([((contract_address, key_a), stark_felt!("0x5"))]),
+        ..Default::default()
+    });
+    state.set_storage_at(contract_address, key_b, stark_felt!("0x7"));
+
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    // Read, then written.
+    transactional_state.get_storage_at(contract_address, key_a).unwrap();
+    transactional_state.set_storage_at(contract_address, key_a, stark_felt!("0x6"));
+    // Written, then read.
+    transactional_state.set_storage_at(contract_address, key_c, stark_felt!("0x1"));
+    transactional_state.get_storage_at(contract_address, key_c).unwrap();
+    // Written before the transactional state was created.
+    transactional_state.get_storage_at(contract_address, key_b).unwrap();
+    transactional_state.increment_nonce(contract_address).unwrap();
+    {
+        // Reads of reverted writes are forgotten, other reads are kept.
+        let mut nested_state = transactional_state.create_transactional();
+        nested_state.set_storage_at(contract_address, key_d, stark_felt!("0x2"));
+        nested_state.get_storage_at(contract_address, key_d).unwrap();
+        nested_state.get_storage_at(contract_address, key_e).unwrap();
+    }
+
+    let state_access = transactional_state.get_state_access().unwrap();
+    assert_eq!(
+        state_access.reads,
+        StateMaps {
+            nonces: HashMap::from([(contract_address, Nonce::default())]),
+            storage: HashMap::from([
+                ((contract_address, key_a), stark_felt!("0x5")),
+                ((contract_address, key_b), stark_felt!("0x7")),
+                ((contract_address, key_e), StarkFelt::default()),
+            ]),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        state_access.writes,
+        StateMaps {
+            nonces: HashMap::from([(contract_address, Nonce(stark_felt!("0x1")))]),
+            storage: HashMap::from([
+                ((contract_address, key_a), stark_felt!("0x6")),
+                ((contract_address, key_c), stark_felt!("0x1")),
+            ]),
+            ..Default::default()
+        }
+    );
+
+    transactional_state.commit();
+    assert!(state.reads.is_empty());
+}
+
 #[test]
 fn global_contract_cache_is_used() {
     // Initialize the global cache with a single class, and initialize an empty state with this
@@ -0,0 +1,727 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use cached::Cached;
+use cairo_felt::Felt252;
+use cairo_lang_casm::hints::Hint;
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use cairo_vm::serde::deserialize_program::{
+    ApTracking, Attribute, BuiltinName, FlowTrackingData, HintParams, Identifier, Member,
+    OffsetValue, Reference, ReferenceManager, ValueAddress,
+};
+use cairo_vm::types::instruction::Register;
+use cairo_vm::types::relocatable::MaybeRelocatable;
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use starknet_api::core::{ClassHash, EntryPointSelector};
+use starknet_api::deprecated_contract_class::{EntryPoint, EntryPointOffset, EntryPointType};
+use starknet_api::hash::StarkFelt;
+
+use crate::execution::contract_class::{
+    convert_entry_points_v1, hint_code_to_hint_params, ContractClass, ContractClassV0,
+    ContractClassV1, EntryPointV1,
+};
+use crate::execution::execution_utils::DeprecatedProgramArgs;
+use crate::state::cached_state::GlobalContractCache;
+use crate::state::errors::{ClassDiskCacheError, StateError};
+use crate::state::state_api::StateReader;
+
+#[cfg(test)]
+#[path = "class_disk_cache_test.rs"]
+mod test;
+
+pub type ClassDiskCacheResult<T> = Result<T, ClassDiskCacheError>;
+
+/// The version of the on-disk format; files of other versions are discarded on read.
+/// Must be bumped whenever the stored layout, or the way classes are loaded from it, changes.
+pub const CLASS_DISK_CACHE_VERSION: u32 = 3;
+
+const MAGIC: &[u8; 4] = b"BKCC";
+const CHECKSUM_LENGTH: usize = 32;
+// Magic, format version, class hash, payload checksum; the payload is a bincode-encoded
+// `CachedClass`.
+const HEADER_LENGTH: usize = MAGIC.len() + 4 + 32 + CHECKSUM_LENGTH;
+const INDEX_FILE_NAME: &str = "index.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Index {
+    version: u32,
+    access_counts: Vec<(ClassHash, u64)>,
+}
+
+/// A persistent cache of contract classes, keyed by class hash; one file per class.
+/// Classes are stored pre-processed, as the parts their programs are built from (see
+/// `CachedClass`), guarded by a format version and a checksum; stale or corrupted files are
+/// treated as misses.
+///
+/// Also counts the requests of each class (see `record_access`), to preload the hottest ones on
+/// startup (see `warm_up`).
+#[derive(Debug)]
+pub struct ClassDiskCache {
+    dir: PathBuf,
+    access_counts: Mutex<HashMap<ClassHash, u64>>,
+    // Distinguishes the temporary files of concurrent writes.
+    n_writes: AtomicU64,
+}
+
+impl ClassDiskCache {
+    /// Opens the cache in the given directory, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> ClassDiskCacheResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let index = match fs::read(dir.join(INDEX_FILE_NAME)) {
+            Ok(bytes) => serde_json::from_slice::<Index>(&bytes).unwrap_or_else(|error| {
+                log::warn!("Discarding an unreadable class disk cache index: {error}.");
+                Index::default()
+            }),
+            Err(error) if error.kind() == ErrorKind::NotFound => Index::default(),
+            Err(error) => return Err(error.into()),
+        };
+        let access_counts = if index.version == CLASS_DISK_CACHE_VERSION {
+            index.access_counts.into_iter().collect()
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self { dir, access_counts: Mutex::new(access_counts), n_writes: AtomicU64::new(0) })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the class under the given hash, if it is cached.
+    /// Whether the class is declared is up to the caller.
+    pub fn get(&self, class_hash: ClassHash) -> ClassDiskCacheResult<Option<ContractClass>> {
+        let path = self.class_path(class_hash);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let payload = match parse_class_file(class_hash, &bytes) {
+            Ok(payload) => payload,
+            Err(reason) => {
+                log::warn!("Discarding cached class file {}: {reason}.", path.display());
+                // The file is rewritten on the next `set`; failing to remove it is harmless.
+                let _ = fs::remove_file(&path);
+                return Ok(None);
+            }
+        };
+
+        let (cached_class, _): (CachedClass, usize) =
+            bincode::serde::decode_from_slice(payload, bincode::config::standard())?;
+        Ok(Some(cached_class.try_into()?))
+    }
+
+    /// Stores a Cairo 0 class, given its parsed program (see `DeprecatedProgramArgs`).
+    pub fn set_v0(
+        &self,
+        class_hash: ClassHash,
+        program_args: &DeprecatedProgramArgs,
+        entry_points_by_type: &HashMap<EntryPointType, Vec<EntryPoint>>,
+    ) -> ClassDiskCacheResult<()> {
+        let cached_class = CachedClassV0::new(program_args, entry_points_by_type)?;
+        self.store(class_hash, &CachedClass::V0(cached_class))
+    }
+
+    pub fn set_v1(
+        &self,
+        class_hash: ClassHash,
+        contract_class: &CasmContractClass,
+    ) -> ClassDiskCacheResult<()> {
+        let cached_class = CachedClassV1::new(contract_class)?;
+        self.store(class_hash, &CachedClass::V1(cached_class))
+    }
+
+    /// Counts a request of the given class; e.g., its execution by a transaction.
+    pub fn record_access(&self, class_hash: ClassHash) {
+        *self
+            .access_counts
+            .lock()
+            .expect("Failed to lock class disk cache access counts.")
+            .entry(class_hash)
+            .or_default() += 1;
+    }
+
+    /// Loads the (at most) `n_classes` most requested classes into the given in-memory cache;
+    /// returns the number of loaded classes.
+    /// Classes are read through the given state reader (typically, backed by this cache), so that
+    /// only classes declared at its state are loaded; others are skipped.
+    pub fn warm_up(
+        &self,
+        state_reader: &mut impl StateReader,
+        global_contract_cache: &GlobalContractCache,
+        n_classes: usize,
+    ) -> ClassDiskCacheResult<usize> {
+        let mut access_counts: Vec<(ClassHash, u64)> = self
+            .access_counts
+            .lock()
+            .expect("Failed to lock class disk cache access counts.")
+            .iter()
+            .map(|(&class_hash, &count)| (class_hash, count))
+            .collect();
+        access_counts.sort_by(|(hash_a, count_a), (hash_b, count_b)| {
+            count_b.cmp(count_a).then(hash_a.cmp(hash_b))
+        });
+
+        let mut n_loaded_classes = 0;
+        for (class_hash, _) in access_counts {
+            if n_loaded_classes == n_classes {
+                break;
+            }
+
+            let contract_class = match state_reader.get_compiled_contract_class(&class_hash) {
+                Ok(contract_class) => contract_class,
+                Err(StateError::UndeclaredClassHash(_)) => continue,
+                Err(error) => return Err(error.into()),
+            };
+            global_contract_cache
+                .0
+                .lock()
+                .expect("Failed to lock global contract cache.")
+                .cache_set(class_hash, contract_class);
+            n_loaded_classes += 1;
+        }
+
+        Ok(n_loaded_classes)
+    }
+
+    /// Persists the access counts, used by `warm_up` in later runs.
+    pub fn save_index(&self) -> ClassDiskCacheResult<()> {
+        let access_counts = self
+            .access_counts
+            .lock()
+            .expect("Failed to lock class disk cache access counts.")
+            .iter()
+            .map(|(&class_hash, &count)| (class_hash, count))
+            .collect();
+        let index = Index { version: CLASS_DISK_CACHE_VERSION, access_counts };
+        self.write_atomically(&self.dir.join(INDEX_FILE_NAME), &serde_json::to_vec(&index)?)
+    }
+
+    fn class_path(&self, class_hash: ClassHash) -> PathBuf {
+        self.dir.join(format!("{}.class", class_hash.0))
+    }
+
+    fn store(&self, class_hash: ClassHash, cached_class: &CachedClass) -> ClassDiskCacheResult<()> {
+        let payload = bincode::serde::encode_to_vec(cached_class, bincode::config::standard())?;
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&CLASS_DISK_CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(class_hash.0.bytes());
+        bytes.extend(Sha3_256::digest(&payload));
+        bytes.extend_from_slice(&payload);
+
+        self.write_atomically(&self.class_path(class_hash), &bytes)
+    }
+
+    /// Writes to a temporary file first, so that readers never see a partially written file.
+    fn write_atomically(&self, path: &Path, bytes: &[u8]) -> ClassDiskCacheResult<()> {
+        let write_id = self.n_writes.fetch_add(1, Ordering::Relaxed);
+        let temp_path = path.with_extension(format!("tmp.{}.{write_id}", std::process::id()));
+        fs::write(&temp_path, bytes)?;
+        fs::rename(&temp_path, path).map_err(|error| {
+            let _ = fs::remove_file(&temp_path);
+            error.into()
+        })
+    }
+}
+
+impl Drop for ClassDiskCache {
+    fn drop(&mut self) {
+        if let Err(error) = self.save_index() {
+            log::warn!("Failed to save the class disk cache index: {error}.");
+        }
+    }
+}
+
+/// Validates the header of a cached class file; returns the payload.
+fn parse_class_file(class_hash: ClassHash, bytes: &[u8]) -> Result<&[u8], String> {
+    if bytes.len() < HEADER_LENGTH || !bytes.starts_with(MAGIC) {
+        return Err("not a class file".to_string());
+    }
+
+    let (header, payload) = bytes.split_at(HEADER_LENGTH);
+    let (version, header) = header[MAGIC.len()..].split_at(4);
+    let version = u32::from_le_bytes(version.try_into().expect("Version must be 4 bytes long."));
+    if version != CLASS_DISK_CACHE_VERSION {
+        return Err(format!("format version {version}, expected {CLASS_DISK_CACHE_VERSION}"));
+    }
+
+    let (stored_class_hash, checksum) = header.split_at(32);
+    if stored_class_hash != class_hash.0.bytes() {
+        return Err("class hash mismatch".to_string());
+    }
+    if checksum != Sha3_256::digest(payload).as_slice() {
+        return Err("checksum mismatch".to_string());
+    }
+
+    Ok(payload)
+}
+
+// The stored form of classes.
+// Everything that is parsed when loading a class from its JSON or SN API form is stored parsed:
+// felts as their big-endian bytes, Cairo 0 references with their resolved addresses, and Cairo 1
+// hints SCALE-encoded, along with their code. Loading a class only rebuilds its `Program`.
+// Mirrors the Cairo VM types whose serde implementations only support their JSON form.
+
+type FeltBytes = [u8; 32];
+
+#[derive(Deserialize, Serialize)]
+enum CachedClass {
+    V0(CachedClassV0),
+    V1(CachedClassV1),
+}
+
+impl TryFrom<CachedClass> for ContractClass {
+    type Error = ClassDiskCacheError;
+
+    fn try_from(cached_class: CachedClass) -> Result<Self, Self::Error> {
+        Ok(match cached_class {
+            CachedClass::V0(cached_class) => cached_class.into_contract_class()?.into(),
+            CachedClass::V1(cached_class) => cached_class.into_contract_class()?.into(),
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedClassV0 {
+    builtins: Vec<BuiltinName>,
+    data: Vec<FeltBytes>,
+    hints: Vec<(usize, Vec<CachedHintParams>)>,
+    references: Vec<CachedReference>,
+    identifiers: Vec<(String, CachedIdentifier)>,
+    error_message_attributes: Vec<CachedAttribute>,
+    // Selectors and offsets.
+    entry_points_by_type: Vec<(EntryPointType, Vec<(FeltBytes, usize)>)>,
+}
+
+impl CachedClassV0 {
+    fn new(
+        program_args: &DeprecatedProgramArgs,
+        entry_points_by_type: &HashMap<EntryPointType, Vec<EntryPoint>>,
+    ) -> ClassDiskCacheResult<Self> {
+        let data = program_args
+            .data
+            .iter()
+            .map(|value| match value {
+                MaybeRelocatable::Int(felt) => Ok(felt.to_be_bytes()),
+                MaybeRelocatable::RelocatableValue(_) => {
+                    Err(ClassDiskCacheError::RelocatableProgramData)
+                }
+            })
+            .collect::<ClassDiskCacheResult<_>>()?;
+
+        Ok(Self {
+            builtins: program_args.builtins.clone(),
+            data,
+            hints: program_args
+                .hints
+                .iter()
+                .map(|(&pc, hints)| (pc, hints.iter().map(CachedHintParams::from).collect()))
+                .collect(),
+            references: program_args
+                .reference_manager
+                .references
+                .iter()
+                .map(CachedReference::from)
+                .collect(),
+            identifiers: program_args
+                .identifiers
+                .iter()
+                .map(|(name, identifier)| (name.clone(), CachedIdentifier::from(identifier)))
+                .collect(),
+            error_message_attributes: program_args
+                .error_message_attributes
+                .iter()
+                .map(CachedAttribute::from)
+                .collect(),
+            entry_points_by_type: entry_points_by_type
+                .iter()
+                .map(|(&entry_point_type, entry_points)| {
+                    let entry_points = entry_points
+                        .iter()
+                        .map(|entry_point| {
+                            (stark_felt_to_bytes(entry_point.selector.0), entry_point.offset.0)
+                        })
+                        .collect();
+                    (entry_point_type, entry_points)
+                })
+                .collect(),
+        })
+    }
+
+    fn into_contract_class(self) -> ClassDiskCacheResult<ContractClassV0> {
+        let program_args = DeprecatedProgramArgs {
+            builtins: self.builtins,
+            data: self
+                .data
+                .iter()
+                .map(|felt| MaybeRelocatable::from(bytes_to_felt(felt)))
+                .collect(),
+            hints: self
+                .hints
+                .into_iter()
+                .map(|(pc, hints)| (pc, hints.into_iter().map(HintParams::from).collect()))
+                .collect(),
+            reference_manager: ReferenceManager {
+                references: self.references.into_iter().map(Reference::from).collect(),
+            },
+            identifiers: self
+                .identifiers
+                .into_iter()
+                .map(|(name, identifier)| (name, Identifier::from(identifier)))
+                .collect(),
+            error_message_attributes: self
+                .error_message_attributes
+                .into_iter()
+                .map(Attribute::from)
+                .collect(),
+        };
+        let entry_points_by_type = self
+            .entry_points_by_type
+            .into_iter()
+            .map(|(entry_point_type, entry_points)| -> ClassDiskCacheResult<_> {
+                let entry_points = entry_points
+                    .into_iter()
+                    .map(|(selector, offset)| -> ClassDiskCacheResult<_> {
+                        Ok(EntryPoint {
+                            selector: EntryPointSelector(StarkFelt::new(selector)?),
+                            offset: EntryPointOffset(offset),
+                        })
+                    })
+                    .collect::<ClassDiskCacheResult<_>>()?;
+                Ok((entry_point_type, entry_points))
+            })
+            .collect::<ClassDiskCacheResult<_>>()?;
+
+        Ok(ContractClassV0::from_program_args(program_args, entry_points_by_type)?)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedClassV1 {
+    bytecode: Vec<FeltBytes>,
+    // The distinct hints, by their code (see `hint_code_to_hint_params`), SCALE-encoded.
+    hints: Vec<(String, Vec<u8>)>,
+    // The indices in `hints` of the hints at each PC.
+    hint_indices_by_pc: Vec<(usize, Vec<usize>)>,
+    entry_points_by_type: Vec<(EntryPointType, Vec<CachedEntryPointV1>)>,
+}
+
+impl CachedClassV1 {
+    fn new(contract_class: &CasmContractClass) -> ClassDiskCacheResult<Self> {
+        let bytecode = contract_class
+            .bytecode
+            .iter()
+            .map(|value| Felt252::from(value.value.clone()).to_be_bytes())
+            .collect();
+
+        let mut hints = Vec::new();
+        let mut hint_indices: HashMap<String, usize> = HashMap::new();
+        let mut hint_indices_by_pc = Vec::with_capacity(contract_class.hints.len());
+        for (pc, pc_hints) in &contract_class.hints {
+            let mut pc_hint_indices = Vec::with_capacity(pc_hints.len());
+            for hint in pc_hints {
+                let code = serde_json::to_string(hint)?;
+                let index = *hint_indices.entry(code.clone()).or_insert_with(|| {
+                    hints.push((code, hint.encode()));
+                    hints.len() - 1
+                });
+                pc_hint_indices.push(index);
+            }
+            hint_indices_by_pc.push((*pc, pc_hint_indices));
+        }
+
+        let casm_entry_points = &contract_class.entry_points_by_type;
+        let entry_points_by_type = [
+            (EntryPointType::Constructor, &casm_entry_points.constructor),
+            (EntryPointType::External, &casm_entry_points.external),
+            (EntryPointType::L1Handler, &casm_entry_points.l1_handler),
+        ]
+        .into_iter()
+        .map(|(entry_point_type, entry_points)| -> ClassDiskCacheResult<_> {
+            let entry_points = convert_entry_points_v1(entry_points.clone())?
+                .into_iter()
+                .map(CachedEntryPointV1::from)
+                .collect();
+            Ok((entry_point_type, entry_points))
+        })
+        .collect::<ClassDiskCacheResult<_>>()?;
+
+        Ok(Self { bytecode, hints, hint_indices_by_pc, entry_points_by_type })
+    }
+
+    fn into_contract_class(self) -> ClassDiskCacheResult<ContractClassV1> {
+        let data =
+            self.bytecode.iter().map(|felt| MaybeRelocatable::from(bytes_to_felt(felt))).collect();
+
+        let mut hint_codes = Vec::with_capacity(self.hints.len());
+        let mut string_to_hint = HashMap::with_capacity(self.hints.len());
+        for (code, encoded_hint) in self.hints {
+            let hint = Hint::decode(&mut encoded_hint.as_slice())?;
+            hint_codes.push(code.clone());
+            string_to_hint.insert(code, hint);
+        }
+        let hints = self
+            .hint_indices_by_pc
+            .into_iter()
+            .map(|(pc, hint_indices)| {
+                let hint_params = hint_indices
+                    .into_iter()
+                    .map(|index| hint_code_to_hint_params(hint_codes[index].clone()))
+                    .collect();
+                (pc, hint_params)
+            })
+            .collect();
+
+        let entry_points_by_type = self
+            .entry_points_by_type
+            .into_iter()
+            .map(|(entry_point_type, entry_points)| -> ClassDiskCacheResult<_> {
+                let entry_points = entry_points
+                    .into_iter()
+                    .map(EntryPointV1::try_from)
+                    .collect::<ClassDiskCacheResult<_>>()?;
+                Ok((entry_point_type, entry_points))
+            })
+            .collect::<ClassDiskCacheResult<_>>()?;
+
+        Ok(ContractClassV1::from_parts(data, hints, string_to_hint, entry_points_by_type)?)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedEntryPointV1 {
+    selector: FeltBytes,
+    offset: usize,
+    builtins: Vec<String>,
+}
+
+impl From<EntryPointV1> for CachedEntryPointV1 {
+    fn from(entry_point: EntryPointV1) -> Self {
+        Self {
+            selector: stark_felt_to_bytes(entry_point.selector.0),
+            offset: entry_point.offset.0,
+            builtins: entry_point.builtins,
+        }
+    }
+}
+
+impl TryFrom<CachedEntryPointV1> for EntryPointV1 {
+    type Error = ClassDiskCacheError;
+
+    fn try_from(entry_point: CachedEntryPointV1) -> Result<Self, Self::Error> {
+        Ok(Self {
+            selector: EntryPointSelector(StarkFelt::new(entry_point.selector)?),
+            offset: EntryPointOffset(entry_point.offset),
+            builtins: entry_point.builtins,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedFlowTrackingData {
+    ap_tracking: ApTracking,
+    reference_ids: HashMap<String, usize>,
+}
+
+impl From<&FlowTrackingData> for CachedFlowTrackingData {
+    fn from(flow_tracking_data: &FlowTrackingData) -> Self {
+        Self {
+            ap_tracking: flow_tracking_data.ap_tracking.clone(),
+            reference_ids: flow_tracking_data.reference_ids.clone(),
+        }
+    }
+}
+
+impl From<CachedFlowTrackingData> for FlowTrackingData {
+    fn from(flow_tracking_data: CachedFlowTrackingData) -> Self {
+        Self {
+            ap_tracking: flow_tracking_data.ap_tracking,
+            reference_ids: flow_tracking_data.reference_ids,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedHintParams {
+    code: String,
+    accessible_scopes: Vec<String>,
+    flow_tracking_data: CachedFlowTrackingData,
+}
+
+impl From<&HintParams> for CachedHintParams {
+    fn from(hint_params: &HintParams) -> Self {
+        Self {
+            code: hint_params.code.clone(),
+            accessible_scopes: hint_params.accessible_scopes.clone(),
+            flow_tracking_data: (&hint_params.flow_tracking_data).into(),
+        }
+    }
+}
+
+impl From<CachedHintParams> for HintParams {
+    fn from(hint_params: CachedHintParams) -> Self {
+        Self {
+            code: hint_params.code,
+            accessible_scopes: hint_params.accessible_scopes,
+            flow_tracking_data: hint_params.flow_tracking_data.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+enum CachedOffsetValue {
+    Immediate(FeltBytes),
+    Value(i32),
+    Reference(Register, i32, bool),
+}
+
+impl From<&OffsetValue> for CachedOffsetValue {
+    fn from(offset_value: &OffsetValue) -> Self {
+        match offset_value {
+            OffsetValue::Immediate(felt) => Self::Immediate(felt.to_be_bytes()),
+            OffsetValue::Value(value) => Self::Value(*value),
+            OffsetValue::Reference(register, offset, dereference) => {
+                Self::Reference(*register, *offset, *dereference)
+            }
+        }
+    }
+}
+
+impl From<CachedOffsetValue> for OffsetValue {
+    fn from(offset_value: CachedOffsetValue) -> Self {
+        match offset_value {
+            CachedOffsetValue::Immediate(felt) => Self::Immediate(bytes_to_felt(&felt)),
+            CachedOffsetValue::Value(value) => Self::Value(value),
+            CachedOffsetValue::Reference(register, offset, dereference) => {
+                Self::Reference(register, offset, dereference)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedReference {
+    ap_tracking_data: ApTracking,
+    pc: Option<usize>,
+    offset1: CachedOffsetValue,
+    offset2: CachedOffsetValue,
+    dereference: bool,
+    value_type: String,
+}
+
+impl From<&Reference> for CachedReference {
+    fn from(reference: &Reference) -> Self {
+        let value_address = &reference.value_address;
+        Self {
+            ap_tracking_data: reference.ap_tracking_data.clone(),
+            pc: reference.pc,
+            offset1: (&value_address.offset1).into(),
+            offset2: (&value_address.offset2).into(),
+            dereference: value_address.dereference,
+            value_type: value_address.value_type.clone(),
+        }
+    }
+}
+
+impl From<CachedReference> for Reference {
+    fn from(reference: CachedReference) -> Self {
+        Self {
+            ap_tracking_data: reference.ap_tracking_data,
+            pc: reference.pc,
+            value_address: ValueAddress {
+                offset1: reference.offset1.into(),
+                offset2: reference.offset2.into(),
+                dereference: reference.dereference,
+                value_type: reference.value_type,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedIdentifier {
+    pc: Option<usize>,
+    type_: Option<String>,
+    value: Option<FeltBytes>,
+    full_name: Option<String>,
+    members: Option<HashMap<String, Member>>,
+    cairo_type: Option<String>,
+}
+
+impl From<&Identifier> for CachedIdentifier {
+    fn from(identifier: &Identifier) -> Self {
+        Self {
+            pc: identifier.pc,
+            type_: identifier.type_.clone(),
+            value: identifier.value.as_ref().map(Felt252::to_be_bytes),
+            full_name: identifier.full_name.clone(),
+            members: identifier.members.clone(),
+            cairo_type: identifier.cairo_type.clone(),
+        }
+    }
+}
+
+impl From<CachedIdentifier> for Identifier {
+    fn from(identifier: CachedIdentifier) -> Self {
+        Self {
+            pc: identifier.pc,
+            type_: identifier.type_,
+            value: identifier.value.as_ref().map(bytes_to_felt),
+            full_name: identifier.full_name,
+            members: identifier.members,
+            cairo_type: identifier.cairo_type,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedAttribute {
+    name: String,
+    start_pc: usize,
+    end_pc: usize,
+    value: String,
+    flow_tracking_data: Option<CachedFlowTrackingData>,
+}
+
+impl From<&Attribute> for CachedAttribute {
+    fn from(attribute: &Attribute) -> Self {
+        Self {
+            name: attribute.name.clone(),
+            start_pc: attribute.start_pc,
+            end_pc: attribute.end_pc,
+            value: attribute.value.clone(),
+            flow_tracking_data: attribute.flow_tracking_data.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl From<CachedAttribute> for Attribute {
+    fn from(attribute: CachedAttribute) -> Self {
+        Self {
+            name: attribute.name,
+            start_pc: attribute.start_pc,
+            end_pc: attribute.end_pc,
+            value: attribute.value,
+            flow_tracking_data: attribute.flow_tracking_data.map(Into::into),
+        }
+    }
+}
+
+fn stark_felt_to_bytes(stark_felt: StarkFelt) -> FeltBytes {
+    stark_felt.bytes().try_into().expect("A StarkFelt must be 32 bytes long.")
+}
+
+fn bytes_to_felt(bytes: &FeltBytes) -> Felt252 {
+    Felt252::from_bytes_be(bytes)
+}
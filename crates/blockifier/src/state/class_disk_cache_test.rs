@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use cached::Cached;
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use pretty_assertions::assert_eq;
+use starknet_api::class_hash;
+use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkHash;
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::execution::execution_utils::DeprecatedProgramArgs;
+use crate::state::cached_state::GlobalContractCache;
+use crate::state::class_disk_cache::{ClassDiskCache, CLASS_DISK_CACHE_VERSION};
+use crate::test_utils::{
+    get_deprecated_contract_class, get_raw_contract_class, DictStateReader,
+    TEST_CONTRACT_CAIRO0_PATH, TEST_CONTRACT_CAIRO1_PATH, TEST_EMPTY_CONTRACT_CAIRO1_PATH,
+};
+
+/// A fresh directory under the system's temporary directory, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir()
+            .join(format!("blockifier_class_disk_cache_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn casm_contract_class(contract_path: &str) -> CasmContractClass {
+    serde_json::from_str(&get_raw_contract_class(contract_path)).unwrap()
+}
+
+#[test]
+fn stored_classes_load_as_converted() {
+    let dir = TempDir::new("round_trip");
+    let cache = ClassDiskCache::open(&dir.0).unwrap();
+    let (class_hash, deprecated_class_hash) = (class_hash!("0x1"), class_hash!("0x2"));
+    assert_eq!(cache.get(class_hash).unwrap(), None);
+
+    let casm_class = casm_contract_class(TEST_CONTRACT_CAIRO1_PATH);
+    cache.set_v1(class_hash, &casm_class).unwrap();
+    let expected_class: ContractClass = ContractClassV1::try_from(casm_class).unwrap().into();
+    assert_eq!(cache.get(class_hash).unwrap(), Some(expected_class.clone()));
+
+    let deprecated_class = get_deprecated_contract_class(TEST_CONTRACT_CAIRO0_PATH);
+    let program_args = DeprecatedProgramArgs::try_from(deprecated_class.program.clone()).unwrap();
+    cache
+        .set_v0(deprecated_class_hash, &program_args, &deprecated_class.entry_points_by_type)
+        .unwrap();
+    let expected_deprecated_class: ContractClass =
+        ContractClassV0::try_from(deprecated_class).unwrap().into();
+    assert_eq!(cache.get(deprecated_class_hash).unwrap(), Some(expected_deprecated_class.clone()));
+
+    // Classes survive reopening the cache.
+    drop(cache);
+    let cache = ClassDiskCache::open(&dir.0).unwrap();
+    assert_eq!(cache.get(class_hash).unwrap(), Some(expected_class));
+    assert_eq!(cache.get(deprecated_class_hash).unwrap(), Some(expected_deprecated_class));
+}
+
+#[test]
+fn invalid_files_are_discarded() {
+    let dir = TempDir::new("invalid_files");
+    let cache = ClassDiskCache::open(&dir.0).unwrap();
+    let class_hash = class_hash!("0x1");
+    let class_path = dir.0.join(format!("{}.class", class_hash.0));
+    let casm_class = casm_contract_class(TEST_CONTRACT_CAIRO1_PATH);
+
+    // Corrupted payload.
+    cache.set_v1(class_hash, &casm_class).unwrap();
+    let mut bytes = fs::read(&class_path).unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+    fs::write(&class_path, &bytes).unwrap();
+    assert_eq!(cache.get(class_hash).unwrap(), None);
+    assert!(!class_path.exists());
+
+    // Stale format version.
+    cache.set_v1(class_hash, &casm_class).unwrap();
+    let mut bytes = fs::read(&class_path).unwrap();
+    bytes[4..8].copy_from_slice(&(CLASS_DISK_CACHE_VERSION + 1).to_le_bytes());
+    fs::write(&class_path, &bytes).unwrap();
+    assert_eq!(cache.get(class_hash).unwrap(), None);
+
+    // A file stored under another class hash.
+    cache.set_v1(class_hash!("0x2"), &casm_class).unwrap();
+    fs::copy(dir.0.join(format!("{}.class", class_hash!("0x2").0)), &class_path).unwrap();
+    assert_eq!(cache.get(class_hash).unwrap(), None);
+
+    // Truncated file.
+    fs::write(&class_path, b"BKCC").unwrap();
+    assert_eq!(cache.get(class_hash).unwrap(), None);
+}
+
+#[test]
+fn warm_up_loads_hottest_declared_classes() {
+    let dir = TempDir::new("warm_up");
+    let [class_hash_a, class_hash_b, class_hash_c] =
+        ["0x1", "0x2", "0x3"].map(|class_hash| class_hash!(class_hash));
+    {
+        let cache = ClassDiskCache::open(&dir.0).unwrap();
+        for (class_hash, n_accesses) in [(class_hash_a, 1), (class_hash_b, 2), (class_hash_c, 3)] {
+            for _ in 0..n_accesses {
+                cache.record_access(class_hash);
+            }
+        }
+    }
+
+    // Access counts are persisted across runs; the hottest class is not declared at the state
+    // being warmed up.
+    let cache = ClassDiskCache::open(&dir.0).unwrap();
+    let class = |contract_path| -> ContractClass {
+        ContractClassV1::try_from(casm_contract_class(contract_path)).unwrap().into()
+    };
+    let mut state_reader = DictStateReader {
+        class_hash_to_class: HashMap::from([
+            (class_hash_a, class(TEST_CONTRACT_CAIRO1_PATH)),
+            (class_hash_b, class(TEST_EMPTY_CONTRACT_CAIRO1_PATH)),
+        ]),
+        ..Default::default()
+    };
+    let global_contract_cache = GlobalContractCache::default();
+    assert_eq!(cache.warm_up(&mut state_reader, &global_contract_cache, 2).unwrap(), 2);
+
+    let mut in_memory_cache = global_contract_cache.0.lock().unwrap();
+    assert_eq!(in_memory_cache.cache_size(), 2);
+    assert!(in_memory_cache.cache_get(&class_hash_a).is_some());
+    assert!(in_memory_cache.cache_get(&class_hash_b).is_some());
+    assert!(in_memory_cache.cache_get(&class_hash_c).is_none());
+}
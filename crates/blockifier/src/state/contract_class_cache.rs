@@ -0,0 +1,244 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use cached::Cached;
+use starknet_api::core::ClassHash;
+
+use crate::execution::contract_class::ContractClass;
+
+#[cfg(test)]
+#[path = "contract_class_cache_test.rs"]
+mod test;
+
+/// The capacity of a `ContractClassLRUCache`, and how it is measured.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CacheCapacity {
+    /// The maximal number of cached classes; all classes weigh the same.
+    Classes(usize),
+    /// The maximal total bytecode length (in felts) of the cached classes.
+    BytecodeLength(usize),
+}
+
+impl CacheCapacity {
+    fn limit(&self) -> usize {
+        match *self {
+            CacheCapacity::Classes(limit) | CacheCapacity::BytecodeLength(limit) => limit,
+        }
+    }
+
+    fn weigh(&self, contract_class: &ContractClass) -> usize {
+        match self {
+            CacheCapacity::Classes(_) => 1,
+            CacheCapacity::BytecodeLength(_) => contract_class.bytecode_length(),
+        }
+    }
+}
+
+/// Counters of a `ContractClassLRUCache`, accumulated since its creation (or since the metrics
+/// were last reset), along with its current occupancy.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub n_classes: usize,
+    /// The total weight of the cached classes; see `CacheCapacity`.
+    pub total_weight: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    contract_class: ContractClass,
+    weight: usize,
+    // The last time the entry was used; a key of `ContractClassLRUCache::lru_order`.
+    last_used: u64,
+}
+
+/// An LRU cache of contract classes, whose capacity is either a number of classes or a total
+/// bytecode length; once exceeded, the least recently used classes are evicted.
+/// A class weighing more than the whole capacity is not cached; `cache_get_or_set_with` still
+/// returns it, by holding it outside the cache until the next such class.
+#[derive(Debug)]
+pub struct ContractClassLRUCache {
+    capacity: CacheCapacity,
+    entries: HashMap<ClassHash, CacheEntry>,
+    // Maps the last time each entry was used to its key, from the least recently used.
+    lru_order: BTreeMap<u64, ClassHash>,
+    clock: u64,
+    total_weight: usize,
+    // The last class too heavy to be cached, returned by `cache_get_or_set_with`.
+    uncached: Option<(ClassHash, ContractClass)>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl ContractClassLRUCache {
+    pub fn new(capacity: CacheCapacity) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            lru_order: BTreeMap::new(),
+            clock: 0,
+            total_weight: 0,
+            uncached: None,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> CacheCapacity {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            n_classes: self.entries.len(),
+            total_weight: self.total_weight,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Marks the entry under the given key as the most recently used; returns it, if it exists.
+    fn touch<Q>(&mut self, class_hash: &Q) -> Option<&mut CacheEntry>
+    where
+        ClassHash: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.tick();
+        let entry = self.entries.get_mut(class_hash)?;
+        let key = self.lru_order.remove(&entry.last_used).expect("Entry must have an LRU key.");
+        self.lru_order.insert(now, key);
+        entry.last_used = now;
+        Some(entry)
+    }
+
+    fn record_lookup(&mut self, is_hit: bool) {
+        if is_hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+
+    /// Evicts the least recently used entries until the given additional weight fits.
+    fn make_room(&mut self, weight: usize) {
+        while self.total_weight + weight > self.capacity.limit() {
+            let Some((_, class_hash)) = self.lru_order.pop_first() else { return };
+            let entry = self.entries.remove(&class_hash).expect("LRU key must have an entry.");
+            self.total_weight -= entry.weight;
+            self.evictions += 1;
+        }
+    }
+}
+
+impl Cached<ClassHash, ContractClass> for ContractClassLRUCache {
+    fn cache_get<Q>(&mut self, class_hash: &Q) -> Option<&ContractClass>
+    where
+        ClassHash: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache_get_mut(class_hash).map(|contract_class| &*contract_class)
+    }
+
+    fn cache_get_mut<Q>(&mut self, class_hash: &Q) -> Option<&mut ContractClass>
+    where
+        ClassHash: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let is_hit = self.entries.contains_key(class_hash);
+        self.record_lookup(is_hit);
+        self.touch(class_hash).map(|entry| &mut entry.contract_class)
+    }
+
+    fn cache_set(
+        &mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+    ) -> Option<ContractClass> {
+        let previous = self.cache_remove(&class_hash);
+        let weight = self.capacity.weigh(&contract_class);
+        if weight > self.capacity.limit() {
+            return previous;
+        }
+
+        self.make_room(weight);
+        let last_used = self.tick();
+        self.lru_order.insert(last_used, class_hash);
+        self.entries.insert(class_hash, CacheEntry { contract_class, weight, last_used });
+        self.total_weight += weight;
+        previous
+    }
+
+    fn cache_get_or_set_with<F: FnOnce() -> ContractClass>(
+        &mut self,
+        class_hash: ClassHash,
+        f: F,
+    ) -> &mut ContractClass {
+        let is_hit = self.entries.contains_key(&class_hash);
+        self.record_lookup(is_hit);
+        if !is_hit {
+            let contract_class = f();
+            if self.capacity.weigh(&contract_class) > self.capacity.limit() {
+                let (_, contract_class) = self.uncached.insert((class_hash, contract_class));
+                return contract_class;
+            }
+            self.cache_set(class_hash, contract_class);
+        }
+
+        &mut self.touch(&class_hash).expect("Cached class must have an entry.").contract_class
+    }
+
+    fn cache_remove<Q>(&mut self, class_hash: &Q) -> Option<ContractClass>
+    where
+        ClassHash: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(class_hash)?;
+        self.lru_order.remove(&entry.last_used);
+        self.total_weight -= entry.weight;
+        Some(entry.contract_class)
+    }
+
+    fn cache_clear(&mut self) {
+        self.entries.clear();
+        self.lru_order.clear();
+        self.total_weight = 0;
+        self.uncached = None;
+    }
+
+    fn cache_reset(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+
+    fn cache_reset_metrics(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.evictions = 0;
+    }
+
+    fn cache_size(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn cache_hits(&self) -> Option<u64> {
+        Some(self.hits)
+    }
+
+    fn cache_misses(&self) -> Option<u64> {
+        Some(self.misses)
+    }
+
+    fn cache_capacity(&self) -> Option<usize> {
+        Some(self.capacity.limit())
+    }
+}
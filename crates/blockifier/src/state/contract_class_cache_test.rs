@@ -0,0 +1,86 @@
+use cached::Cached;
+use pretty_assertions::assert_eq;
+use starknet_api::class_hash;
+use starknet_api::core::ClassHash;
+use starknet_api::hash::StarkHash;
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0};
+use crate::state::contract_class_cache::{CacheCapacity, CacheStats, ContractClassLRUCache};
+use crate::test_utils::{
+    get_test_contract_class, ACCOUNT_CONTRACT_CAIRO0_PATH, ERC20_CONTRACT_PATH,
+};
+
+#[test]
+fn evicts_least_recently_used_class() {
+    let mut cache = ContractClassLRUCache::new(CacheCapacity::Classes(2));
+    let contract_class = get_test_contract_class();
+    let [class_hash_a, class_hash_b, class_hash_c] =
+        ["0x1", "0x2", "0x3"].map(|class_hash| class_hash!(class_hash));
+
+    cache.cache_set(class_hash_a, contract_class.clone());
+    cache.cache_set(class_hash_b, contract_class.clone());
+    // Using the first class makes the second one the least recently used.
+    assert_eq!(cache.cache_get(&class_hash_a), Some(&contract_class));
+    cache.cache_set(class_hash_c, contract_class.clone());
+
+    assert!(cache.cache_get(&class_hash_b).is_none());
+    assert!(cache.cache_get(&class_hash_a).is_some());
+    assert!(cache.cache_get(&class_hash_c).is_some());
+    assert_eq!(
+        cache.stats(),
+        CacheStats { hits: 3, misses: 1, evictions: 1, n_classes: 2, total_weight: 2 }
+    );
+}
+
+#[test]
+fn evicts_by_bytecode_length() {
+    let small_class: ContractClass = get_test_contract_class();
+    let large_class: ContractClass = ContractClassV0::from_file(ERC20_CONTRACT_PATH).into();
+    let other_class: ContractClass =
+        ContractClassV0::from_file(ACCOUNT_CONTRACT_CAIRO0_PATH).into();
+    let (small_weight, large_weight, other_weight) = (
+        small_class.bytecode_length(),
+        large_class.bytecode_length(),
+        other_class.bytecode_length(),
+    );
+    assert!(small_weight + other_weight < large_weight);
+
+    // Room for the large class, or for both smaller ones.
+    let mut cache = ContractClassLRUCache::new(CacheCapacity::BytecodeLength(large_weight));
+    cache.cache_set(class_hash!("0x1"), small_class);
+    cache.cache_set(class_hash!("0x2"), other_class);
+    assert_eq!(cache.stats().total_weight, small_weight + other_weight);
+
+    // Caching the large class evicts both.
+    cache.cache_set(class_hash!("0x3"), large_class);
+    assert_eq!(
+        cache.stats(),
+        CacheStats { hits: 0, misses: 0, evictions: 2, n_classes: 1, total_weight: large_weight }
+    );
+
+    // A class heavier than the whole capacity is not cached, yet is returned when requested.
+    let large_class: ContractClass = ContractClassV0::from_file(ERC20_CONTRACT_PATH).into();
+    let mut cache = ContractClassLRUCache::new(CacheCapacity::BytecodeLength(small_weight));
+    cache.cache_set(class_hash!("0x3"), large_class.clone());
+    assert_eq!(cache.cache_size(), 0);
+    assert_eq!(
+        *cache.cache_get_or_set_with(class_hash!("0x3"), || large_class.clone()),
+        large_class
+    );
+    assert_eq!(cache.cache_size(), 0);
+    assert!(cache.cache_get(&class_hash!("0x3")).is_none());
+}
+
+#[test]
+fn replacing_class_updates_weight() {
+    let mut cache = ContractClassLRUCache::new(CacheCapacity::BytecodeLength(usize::MAX));
+    let class_hash = class_hash!("0x1");
+    let small_class: ContractClass = get_test_contract_class();
+    let large_class: ContractClass = ContractClassV0::from_file(ERC20_CONTRACT_PATH).into();
+
+    assert!(cache.cache_set(class_hash, small_class.clone()).is_none());
+    assert_eq!(cache.cache_set(class_hash, large_class.clone()), Some(small_class));
+    assert_eq!(cache.stats().total_weight, large_class.bytecode_length());
+    assert_eq!(cache.cache_remove(&class_hash), Some(large_class));
+    assert_eq!(cache.stats().total_weight, 0);
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::state::errors::DataAvailabilityError;
+use crate::state::state_api::StateReader;
+
+#[cfg(test)]
+#[path = "data_availability_test.rs"]
+mod test;
+
+pub type DataAvailabilityResult<T> = Result<T, DataAvailabilityError>;
+
+// The packed word of each contract holds, from the least significant bit: the number of storage
+// updates (64 bits), the new nonce (64 bits) and a flag indicating whether the class was updated.
+const N_UPDATES_BYTES: usize = 8;
+const NONCE_BYTES: usize = 8;
+const CLASS_FLAG_BYTE: usize = 32 - N_UPDATES_BYTES - NONCE_BYTES - 1;
+
+#[derive(Default)]
+struct ContractDiff {
+    class_hash: Option<ClassHash>,
+    nonce: Nonce,
+    storage_updates: BTreeMap<StorageKey, StarkFelt>,
+}
+
+/// Encodes the given state diff as the data-availability segment of the OS output:
+/// the number of modified contracts, and for each contract (by ascending address): its address,
+/// a packed word (see below), its new class hash (if updated) and its storage updates as
+/// key-value pairs (by ascending key); then the number of declared classes, and for each class
+/// (by ascending class hash): its class hash and compiled class hash.
+///
+/// The packed word is `class_flag * 2**128 + nonce * 2**64 + n_storage_updates`, where `nonce` is
+/// the current nonce of the contract, as in the OS. Nonces missing from the diff (i.e., unchanged)
+/// are read from the given state.
+/// The encoding does not depend on the order of the diff.
+pub fn state_diff_to_da_segment(
+    state_diff: &CommitmentStateDiff,
+    state: &mut impl StateReader,
+) -> DataAvailabilityResult<Vec<StarkFelt>> {
+    let mut contract_diffs = BTreeMap::<ContractAddress, ContractDiff>::new();
+    for (&address, &class_hash) in &state_diff.address_to_class_hash {
+        contract_diffs.entry(address).or_default().class_hash = Some(class_hash);
+    }
+    for (&address, &nonce) in &state_diff.address_to_nonce {
+        contract_diffs.entry(address).or_default().nonce = nonce;
+    }
+    for (&address, storage_updates) in &state_diff.storage_updates {
+        contract_diffs.entry(address).or_default().storage_updates.extend(storage_updates);
+    }
+    for (&address, contract_diff) in &mut contract_diffs {
+        if !state_diff.address_to_nonce.contains_key(&address) {
+            contract_diff.nonce = state.get_nonce_at(address)?;
+        }
+    }
+    let declared_classes: BTreeMap<_, _> =
+        state_diff.class_hash_to_compiled_class_hash.iter().collect();
+
+    let mut segment = vec![count_to_felt(contract_diffs.len())];
+    for (address, contract_diff) in contract_diffs {
+        segment.push(*address.0.key());
+        segment.push(pack_contract_word(address, &contract_diff)?);
+        segment.extend(contract_diff.class_hash.map(|class_hash| class_hash.0));
+        for (key, value) in contract_diff.storage_updates {
+            segment.extend([*key.0.key(), value]);
+        }
+    }
+
+    segment.push(count_to_felt(declared_classes.len()));
+    for (class_hash, compiled_class_hash) in declared_classes {
+        segment.extend([class_hash.0, compiled_class_hash.0]);
+    }
+
+    Ok(segment)
+}
+
+/// Decodes a data-availability segment produced by `state_diff_to_da_segment`.
+/// The decoded diff holds the nonce of every modified contract, including unchanged ones.
+pub fn da_segment_to_state_diff(
+    segment: &[StarkFelt],
+) -> DataAvailabilityResult<CommitmentStateDiff> {
+    let mut felts = segment.iter().copied();
+    let mut next_felt = || felts.next().ok_or(DataAvailabilityError::UnexpectedEndOfSegment);
+
+    let mut state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: IndexMap::new(),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+
+    let n_contracts = felt_to_count(next_felt()?)?;
+    for _ in 0..n_contracts {
+        let address = ContractAddress(PatriciaKey::try_from(next_felt()?)?);
+        let (is_class_updated, nonce, n_storage_updates) = unpack_contract_word(next_felt()?)?;
+        if is_class_updated {
+            state_diff.address_to_class_hash.insert(address, ClassHash(next_felt()?));
+        }
+        state_diff.address_to_nonce.insert(address, nonce);
+
+        let mut storage_updates = IndexMap::new();
+        for _ in 0..n_storage_updates {
+            let key = StorageKey(PatriciaKey::try_from(next_felt()?)?);
+            storage_updates.insert(key, next_felt()?);
+        }
+        if !storage_updates.is_empty() {
+            state_diff.storage_updates.insert(address, storage_updates);
+        }
+    }
+
+    let n_declared_classes = felt_to_count(next_felt()?)?;
+    for _ in 0..n_declared_classes {
+        let class_hash = ClassHash(next_felt()?);
+        state_diff
+            .class_hash_to_compiled_class_hash
+            .insert(class_hash, CompiledClassHash(next_felt()?));
+    }
+
+    let n_trailing_felts = felts.len();
+    if n_trailing_felts > 0 {
+        return Err(DataAvailabilityError::TrailingData { n_felts: n_trailing_felts });
+    }
+
+    Ok(state_diff)
+}
+
+fn pack_contract_word(
+    address: ContractAddress,
+    contract_diff: &ContractDiff,
+) -> DataAvailabilityResult<StarkFelt> {
+    let nonce = contract_diff.nonce;
+    if nonce.0.bytes()[..32 - NONCE_BYTES].iter().any(|&byte| byte != 0) {
+        return Err(DataAvailabilityError::NonceOutOfRange { address, nonce });
+    }
+
+    let mut packed_word = [0_u8; 32];
+    packed_word[CLASS_FLAG_BYTE] = u8::from(contract_diff.class_hash.is_some());
+    packed_word[32 - N_UPDATES_BYTES - NONCE_BYTES..32 - N_UPDATES_BYTES]
+        .copy_from_slice(&nonce.0.bytes()[32 - NONCE_BYTES..]);
+    packed_word[32 - N_UPDATES_BYTES..]
+        .copy_from_slice(&(contract_diff.storage_updates.len() as u64).to_be_bytes());
+    Ok(StarkFelt::new(packed_word).expect("A packed contract word fits in a felt."))
+}
+
+/// Returns the class-updated flag, the nonce and the number of storage updates.
+fn unpack_contract_word(packed_word: StarkFelt) -> DataAvailabilityResult<(bool, Nonce, u64)> {
+    let bytes = packed_word.bytes();
+    let is_class_updated = match bytes[CLASS_FLAG_BYTE] {
+        0 => false,
+        1 => true,
+        _ => return Err(DataAvailabilityError::InvalidPackedContractWord(packed_word)),
+    };
+    if bytes[..CLASS_FLAG_BYTE].iter().any(|&byte| byte != 0) {
+        return Err(DataAvailabilityError::InvalidPackedContractWord(packed_word));
+    }
+
+    let mut nonce = [0_u8; 32];
+    nonce[32 - NONCE_BYTES..]
+        .copy_from_slice(&bytes[32 - N_UPDATES_BYTES - NONCE_BYTES..32 - N_UPDATES_BYTES]);
+    let n_storage_updates = u64::from_be_bytes(
+        bytes[32 - N_UPDATES_BYTES..].try_into().expect("The number of updates is 8 bytes long."),
+    );
+
+    Ok((
+        is_class_updated,
+        Nonce(StarkFelt::new(nonce).expect("A 64-bit nonce fits in a felt.")),
+        n_storage_updates,
+    ))
+}
+
+fn count_to_felt(count: usize) -> StarkFelt {
+    StarkFelt::from(count as u64)
+}
+
+fn felt_to_count(felt: StarkFelt) -> DataAvailabilityResult<usize> {
+    usize::try_from(felt).map_err(|_| DataAvailabilityError::InvalidCount(felt))
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::fee::gas_usage::get_onchain_data_segment_length;
+use crate::state::cached_state::{CommitmentStateDiff, StateChangesCount};
+use crate::state::data_availability::{da_segment_to_state_diff, state_diff_to_da_segment};
+use crate::state::errors::DataAvailabilityError;
+use crate::test_utils::DictStateReader;
+
+fn storage_updates(updates: &[(&str, u8)]) -> IndexMap<StorageKey, StarkFelt> {
+    updates
+        .iter()
+        .map(|(key, value)| (StorageKey(patricia_key!(*key)), stark_felt!(*value)))
+        .collect()
+}
+
+/// The state under the diff; holds the (unchanged) nonce of contract 0x200.
+fn state() -> DictStateReader {
+    DictStateReader {
+        address_to_nonce: HashMap::from([(contract_address!("0x200"), Nonce(stark_felt!(3_u8)))]),
+        ..Default::default()
+    }
+}
+
+/// A diff whose mappings are not sorted.
+fn state_diff() -> CommitmentStateDiff {
+    CommitmentStateDiff {
+        address_to_class_hash: IndexMap::from([(contract_address!("0x200"), class_hash!("0x20"))]),
+        address_to_nonce: IndexMap::from([
+            (contract_address!("0x300"), Nonce(stark_felt!(5_u8))),
+            (contract_address!("0x100"), Nonce(stark_felt!(1_u8))),
+        ]),
+        storage_updates: IndexMap::from([
+            (contract_address!("0x200"), storage_updates(&[("0x2", 7), ("0x1", 8)])),
+            (contract_address!("0x100"), storage_updates(&[("0x3", 9)])),
+        ]),
+        class_hash_to_compiled_class_hash: IndexMap::from([
+            (class_hash!("0x31"), CompiledClassHash(stark_felt!("0x41"))),
+            (class_hash!("0x30"), CompiledClassHash(stark_felt!("0x40"))),
+        ]),
+    }
+}
+
+#[test]
+fn encoding() {
+    let segment = state_diff_to_da_segment(&state_diff(), &mut state()).unwrap();
+    assert_eq!(
+        segment,
+        [
+            // Number of modified contracts.
+            "0x3",
+            // Contract 0x100: nonce 1, one storage update.
+            "0x100",
+            "0x10000000000000001",
+            "0x3",
+            "0x9",
+            // Contract 0x200: class updated, unchanged nonce 3, two storage updates.
+            "0x200",
+            "0x100000000000000030000000000000002",
+            "0x20",
+            "0x1",
+            "0x8",
+            "0x2",
+            "0x7",
+            // Contract 0x300: nonce 5.
+            "0x300",
+            "0x50000000000000000",
+            // Declared classes.
+            "0x2",
+            "0x30",
+            "0x40",
+            "0x31",
+            "0x41",
+        ]
+        .map(|felt| stark_felt!(felt))
+    );
+
+    // The segment length is the one charged for, plus the two counts.
+    let state_changes_count = StateChangesCount::from(&state_diff());
+    assert_eq!(segment.len(), get_onchain_data_segment_length(state_changes_count) + 2);
+}
+
+#[test]
+fn encoding_is_order_independent() {
+    let mut reordered_state_diff = state_diff();
+    reordered_state_diff.address_to_nonce.reverse();
+    reordered_state_diff.storage_updates.reverse();
+    reordered_state_diff.class_hash_to_compiled_class_hash.reverse();
+    assert_eq!(
+        state_diff_to_da_segment(&reordered_state_diff, &mut state()).unwrap(),
+        state_diff_to_da_segment(&state_diff(), &mut state()).unwrap()
+    );
+}
+
+#[test]
+fn encoding_nonce_out_of_range() {
+    let mut state_diff = state_diff();
+    let large_nonce = Nonce(stark_felt!("0x10000000000000000"));
+    state_diff.address_to_nonce.insert(contract_address!("0x100"), large_nonce);
+    assert_matches!(
+        state_diff_to_da_segment(&state_diff, &mut state()),
+        Err(DataAvailabilityError::NonceOutOfRange { address, nonce })
+        if address == contract_address!("0x100") && nonce == large_nonce
+    );
+}
+
+#[test]
+fn decoding() {
+    let segment = state_diff_to_da_segment(&state_diff(), &mut state()).unwrap();
+    let decoded_state_diff = da_segment_to_state_diff(&segment).unwrap();
+    // The decoded diff also holds the unchanged nonces.
+    let mut expected_state_diff = state_diff();
+    expected_state_diff
+        .address_to_nonce
+        .insert(contract_address!("0x200"), Nonce(stark_felt!(3_u8)));
+    assert_eq!(decoded_state_diff, expected_state_diff);
+    // Decoded nonces are taken from the segment, not from the state.
+    assert_eq!(
+        state_diff_to_da_segment(&decoded_state_diff, &mut DictStateReader::default()).unwrap(),
+        segment
+    );
+
+    // A nonce of 0 is decoded as is.
+    let contract_address = contract_address!("0x400");
+    let zero_nonce_state_diff = CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: IndexMap::from([(contract_address, storage_updates(&[("0x1", 1)]))]),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    };
+    let segment = state_diff_to_da_segment(&zero_nonce_state_diff, &mut state()).unwrap();
+    assert_eq!(
+        da_segment_to_state_diff(&segment).unwrap().address_to_nonce,
+        IndexMap::from([(contract_address, Nonce::default())])
+    );
+
+    let empty_segment = [stark_felt!(0_u8), stark_felt!(0_u8)];
+    let empty_state_diff = da_segment_to_state_diff(&empty_segment).unwrap();
+    assert_eq!(state_diff_to_da_segment(&empty_state_diff, &mut state()).unwrap(), empty_segment);
+}
+
+#[test]
+fn decoding_invalid_segments() {
+    let segment = state_diff_to_da_segment(&state_diff(), &mut state()).unwrap();
+    assert_matches!(
+        da_segment_to_state_diff(&segment[..segment.len() - 1]),
+        Err(DataAvailabilityError::UnexpectedEndOfSegment)
+    );
+    assert_matches!(
+        da_segment_to_state_diff(&[segment.as_slice(), &[stark_felt!(0_u8)]].concat()),
+        Err(DataAvailabilityError::TrailingData { n_felts: 1 })
+    );
+
+    let mut invalid_segment = segment;
+    invalid_segment[2] = stark_felt!("0x200000000000000000000000000000001");
+    assert_matches!(
+        da_segment_to_state_diff(&invalid_segment),
+        Err(DataAvailabilityError::InvalidPackedContractWord(_))
+    );
+}
@@ -1,8 +1,14 @@
+use cairo_lang_starknet::casm_contract_class::StarknetSierraCompilationError;
 use cairo_vm::types::errors::program_errors::ProgramError;
-use starknet_api::core::{ClassHash, ContractAddress};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
 use starknet_api::StarknetApiError;
 use thiserror::Error;
 
+use crate::execution::errors::EntryPointExecutionError;
+use crate::state::cached_state::StateCell;
+
 #[derive(Debug, Error)]
 pub enum StateError {
     #[error("Cannot deploy contract at address 0.")]
@@ -15,7 +21,131 @@ pub enum StateError {
     UndeclaredClassHash(ClassHash),
     #[error(transparent)]
     StarknetApiError(#[from] StarknetApiError),
+    #[error(
+        "Storage value {value} under {key:?} of {contract_address:?} is not a valid {value_type}."
+    )]
+    InvalidStorageValue {
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: StarkFelt,
+        value_type: &'static str,
+    },
     /// Represents all unexpected errors that may occur while reading from state.
     #[error("Failed to read from state: {0}.")]
     StateReadError(String),
 }
+
+#[derive(Debug, Error)]
+pub enum ClassDiskCacheError {
+    #[error(transparent)]
+    DecodeError(#[from] bincode::error::DecodeError),
+    #[error(transparent)]
+    EncodeError(#[from] bincode::error::EncodeError),
+    #[error(transparent)]
+    HintCodecError(#[from] parity_scale_codec::Error),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+    #[error("Cannot cache a program with relocatable data.")]
+    RelocatableProgramData,
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error(transparent)]
+    StateError(#[from] StateError),
+}
+
+#[derive(Debug, Error)]
+pub enum GenesisBuilderError {
+    #[error("Funding {0:?} exceeds the u256 range of the fee token.")]
+    BalanceOverflow(ContractAddress),
+    #[error(transparent)]
+    EntryPointExecutionError(#[from] EntryPointExecutionError),
+    #[error("A compiled class hash is required to declare the Cairo 1 class {0:?}.")]
+    MissingCompiledClassHash(ClassHash),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error("Funding exceeds the u256 range of the total supply of the fee token {0:?}.")]
+    TotalSupplyOverflow(ContractAddress),
+    #[error("The Cairo 0 class {0:?} has no compiled class hash.")]
+    UnexpectedCompiledClassHash(ClassHash),
+}
+
+#[derive(Debug, Error)]
+pub enum InMemoryStateError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum RpcStateReaderError {
+    #[error(transparent)]
+    Base64Error(#[from] base64::DecodeError),
+    #[error(
+        "Compiled class hash of {0:?} is unavailable: the node does not serve compiled classes."
+    )]
+    CompiledClassHashUnavailable(ClassHash),
+    #[error(transparent)]
+    HttpClientError(#[from] reqwest::Error),
+    #[error("HTTP request failed with status {status}: {body}")]
+    HttpError { status: u16, body: String },
+    #[error("Invalid node URL: {0}; expected an http:// or https:// URL.")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error("JSON-RPC error {code}: {message}.")]
+    JsonRpcError { code: i64, message: String },
+    #[error("Malformed response: {0}.")]
+    MalformedResponse(String),
+    #[error(transparent)]
+    ProgramError(#[from] ProgramError),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+    #[error(transparent)]
+    SierraCompilationError(#[from] StarknetSierraCompilationError),
+}
+
+impl From<RpcStateReaderError> for StateError {
+    fn from(error: RpcStateReaderError) -> Self {
+        StateError::StateReadError(error.to_string())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DataAvailabilityError {
+    #[error("Invalid count {0} in data-availability segment.")]
+    InvalidCount(StarkFelt),
+    #[error("Invalid packed contract word {0} in data-availability segment.")]
+    InvalidPackedContractWord(StarkFelt),
+    #[error("Nonce {nonce:?} of contract {address:?} exceeds 64 bits.")]
+    NonceOutOfRange { address: ContractAddress, nonce: Nonce },
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error("Data-availability segment has {n_felts} unexpected trailing felts.")]
+    TrailingData { n_felts: usize },
+    #[error("Data-availability segment ended unexpectedly.")]
+    UnexpectedEndOfSegment,
+}
+
+#[derive(Debug, Error)]
+pub enum StateDiffError {
+    #[error(
+        "Cannot compose state diffs: {0:?} is changed from a value other than the one it was set \
+         to."
+    )]
+    InconsistentComposition(StateCell),
+    #[error(transparent)]
+    StateError(#[from] StateError),
+    #[error("Cannot apply state diff: the current value of {0:?} is not its previous value.")]
+    UnexpectedValue(StateCell),
+}
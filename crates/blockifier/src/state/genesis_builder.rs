@@ -0,0 +1,201 @@
+use cairo_felt::Felt252;
+use num_bigint::BigUint;
+use starknet_api::core::{
+    calculate_contract_address, ClassHash, CompiledClassHash, ContractAddress,
+};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, ContractAddressSalt};
+
+use crate::abi::abi_utils::{get_erc20_balance_var_addresses, get_uint256_storage_var_addresses};
+use crate::abi::constants;
+use crate::block_context::BlockContext;
+use crate::execution::contract_class::ContractClass;
+use crate::execution::entry_point::{
+    ConstructorContext, EntryPointExecutionContext, ExecutionResources,
+};
+use crate::execution::execution_utils::{
+    execute_deployment, felt_to_stark_felt, stark_felt_to_felt,
+};
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::state::errors::GenesisBuilderError;
+use crate::state::state_api::{State, StateReader};
+use crate::transaction::objects::{AccountTransactionContext, FeeUnit};
+
+#[cfg(test)]
+#[path = "genesis_builder_test.rs"]
+mod test;
+
+pub type GenesisBuilderResult<T> = Result<T, GenesisBuilderError>;
+
+/// Sets up the initial state of a chain: declares classes, deploys contracts (running their
+/// constructors) and funds accounts with the fee token, on top of the given state.
+pub struct GenesisBuilder<S: StateReader> {
+    block_context: BlockContext,
+    state: CachedState<S>,
+}
+
+impl<S: StateReader> GenesisBuilder<S> {
+    pub fn new(block_context: BlockContext, state: S) -> Self {
+        Self { block_context, state: CachedState::from(state) }
+    }
+
+    /// Declares the given class; a compiled class hash must be given for Cairo 1 classes, and only
+    /// for them.
+    pub fn declare_class(
+        &mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+        compiled_class_hash: Option<CompiledClassHash>,
+    ) -> GenesisBuilderResult<()> {
+        match (&contract_class, compiled_class_hash) {
+            (ContractClass::V0(_), None) => {
+                self.state.set_contract_class(&class_hash, contract_class)?;
+            }
+            (ContractClass::V1(_), Some(compiled_class_hash)) => {
+                self.state.set_contract_class(&class_hash, contract_class)?;
+                self.state.set_compiled_class_hash(class_hash, compiled_class_hash)?;
+            }
+            (ContractClass::V0(_), Some(_)) => {
+                return Err(GenesisBuilderError::UnexpectedCompiledClassHash(class_hash));
+            }
+            (ContractClass::V1(_), None) => {
+                return Err(GenesisBuilderError::MissingCompiledClassHash(class_hash));
+            }
+        }
+        Ok(())
+    }
+
+    /// Deploys an instance of the given (declared) class at the address computed from the given
+    /// salt and constructor calldata, as done by the deploy syscall with a zero deployer address;
+    /// returns the address.
+    pub fn deploy_contract(
+        &mut self,
+        class_hash: ClassHash,
+        salt: ContractAddressSalt,
+        constructor_calldata: Calldata,
+    ) -> GenesisBuilderResult<ContractAddress> {
+        let contract_address = calculate_contract_address(
+            salt,
+            class_hash,
+            &constructor_calldata,
+            ContractAddress::default(),
+        )?;
+        self.deploy_contract_at(contract_address, class_hash, constructor_calldata)?;
+        Ok(contract_address)
+    }
+
+    /// Deploys an instance of the given (declared) class at the given address.
+    pub fn deploy_contract_at(
+        &mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+        constructor_calldata: Calldata,
+    ) -> GenesisBuilderResult<()> {
+        let ctor_context = ConstructorContext {
+            class_hash,
+            code_address: None,
+            storage_address: contract_address,
+            caller_address: ContractAddress::default(),
+        };
+        let mut context = EntryPointExecutionContext::new(
+            self.block_context.clone(),
+            AccountTransactionContext::default(),
+            self.block_context.invoke_tx_max_n_steps as usize,
+        );
+        // A failed deployment must not leave the address allocated.
+        let mut transactional_state = CachedState::create_transactional(&mut self.state);
+        execute_deployment(
+            &mut transactional_state,
+            &mut ExecutionResources::default(),
+            &mut context,
+            ctor_context,
+            constructor_calldata,
+            constants::INITIAL_GAS_COST,
+        )?;
+        transactional_state.commit();
+
+        Ok(())
+    }
+
+    /// Deploys the contract of the fee token of the given unit at its address in the block
+    /// context.
+    pub fn deploy_fee_token(
+        &mut self,
+        fee_unit: FeeUnit,
+        class_hash: ClassHash,
+        constructor_calldata: Calldata,
+    ) -> GenesisBuilderResult<()> {
+        self.deploy_contract_at(
+            self.block_context.fee_token_address(fee_unit),
+            class_hash,
+            constructor_calldata,
+        )
+    }
+
+    /// Adds the given amount to the balance of the given account in the fee token of the given
+    /// unit, and to its total supply; both are u256 values, stored as `ERC20_balances` and
+    /// `ERC20_total_supply`.
+    pub fn fund_account(
+        &mut self,
+        fee_unit: FeeUnit,
+        account_address: ContractAddress,
+        amount: &BigUint,
+    ) -> GenesisBuilderResult<()> {
+        let fee_token_address = self.block_context.fee_token_address(fee_unit);
+        let (total_supply_low, total_supply_high) =
+            get_uint256_storage_var_addresses("ERC20_total_supply", &[])?;
+        let (balance_low, balance_high) = get_erc20_balance_var_addresses(&account_address)?;
+        // Either both values are updated, or neither is.
+        let mut transactional_state = CachedState::create_transactional(&mut self.state);
+        if !add_to_uint256(
+            &mut transactional_state,
+            fee_token_address,
+            total_supply_low,
+            total_supply_high,
+            amount,
+        )? {
+            return Err(GenesisBuilderError::TotalSupplyOverflow(fee_token_address));
+        }
+        if !add_to_uint256(
+            &mut transactional_state,
+            fee_token_address,
+            balance_low,
+            balance_high,
+            amount,
+        )? {
+            return Err(GenesisBuilderError::BalanceOverflow(account_address));
+        }
+        transactional_state.commit();
+
+        Ok(())
+    }
+
+    /// Returns the built state, and the changes applied to the initial state.
+    pub fn build(mut self) -> GenesisBuilderResult<(CachedState<S>, CommitmentStateDiff)> {
+        let state_diff = self.state.to_reversible_state_diff()?.to_commitment_state_diff();
+        Ok((self.state, state_diff))
+    }
+}
+
+/// Adds the given amount to the u256 value stored under the given keys of the given fee token;
+/// returns false, leaving the value unchanged, if the sum exceeds 256 bits.
+fn add_to_uint256(
+    state: &mut dyn State,
+    fee_token_address: ContractAddress,
+    low_key: StorageKey,
+    high_key: StorageKey,
+    amount: &BigUint,
+) -> GenesisBuilderResult<bool> {
+    let low = stark_felt_to_felt(state.get_storage_at(fee_token_address, low_key)?);
+    let high = stark_felt_to_felt(state.get_storage_at(fee_token_address, high_key)?);
+    let value = (high.to_biguint() << 128_usize) + low.to_biguint() + amount;
+    if value.bits() > 256 {
+        return Ok(false);
+    }
+
+    let new_low = Felt252::from(&value & BigUint::from(u128::MAX));
+    let new_high = Felt252::from(value >> 128_usize);
+    state.set_storage_at(fee_token_address, low_key, felt_to_stark_felt(&new_low));
+    state.set_storage_at(fee_token_address, high_key, felt_to_stark_felt(&new_high));
+    Ok(true)
+}
@@ -0,0 +1,302 @@
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{
+    calculate_contract_address, ClassHash, CompiledClassHash, ContractAddress, PatriciaKey,
+};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, ContractAddressSalt, Fee};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{
+    get_erc20_balance_var_addresses, get_storage_var_address, get_uint256_storage_var_addresses,
+    selector_from_name,
+};
+use crate::block_context::BlockContext;
+use crate::execution::contract_class::{ContractClassV0, ContractClassV1};
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::state::errors::GenesisBuilderError;
+use crate::state::genesis_builder::GenesisBuilder;
+use crate::state::in_memory_state::InMemoryState;
+use crate::state::state_api::StateReader;
+use crate::test_utils::{
+    NonceManager, ACCOUNT_CONTRACT_CAIRO0_PATH, ERC20_CONTRACT_PATH, MAX_FEE,
+    TEST_ACCOUNT_CONTRACT_CLASS_HASH, TEST_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+    TEST_CONTRACT_CAIRO0_PATH, TEST_CONTRACT_CAIRO1_PATH, TEST_ERC20_CONTRACT_CLASS_HASH,
+};
+use crate::transaction::objects::FeeUnit;
+use crate::transaction::test_utils::account_invoke_tx;
+use crate::transaction::transactions::ExecutableTransaction;
+
+fn genesis_builder() -> GenesisBuilder<InMemoryState> {
+    let mut builder =
+        GenesisBuilder::new(BlockContext::create_for_account_testing(), InMemoryState::default());
+    builder
+        .declare_class(
+            class_hash!(TEST_ERC20_CONTRACT_CLASS_HASH),
+            ContractClassV0::from_file(ERC20_CONTRACT_PATH).into(),
+            None,
+        )
+        .unwrap();
+    builder
+        .deploy_fee_token(FeeUnit::Wei, class_hash!(TEST_ERC20_CONTRACT_CLASS_HASH), calldata![])
+        .unwrap();
+    builder
+}
+
+#[test]
+fn build_usable_state() {
+    let block_context = BlockContext::create_for_account_testing();
+    let mut builder = genesis_builder();
+    let (account_class_hash, test_class_hash, cairo1_class_hash) = (
+        class_hash!(TEST_ACCOUNT_CONTRACT_CLASS_HASH),
+        class_hash!(TEST_CLASS_HASH),
+        class_hash!("0x30"),
+    );
+    builder
+        .declare_class(
+            account_class_hash,
+            ContractClassV0::from_file(ACCOUNT_CONTRACT_CAIRO0_PATH).into(),
+            None,
+        )
+        .unwrap();
+    builder
+        .declare_class(
+            test_class_hash,
+            ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into(),
+            None,
+        )
+        .unwrap();
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x31"));
+    builder
+        .declare_class(
+            cairo1_class_hash,
+            ContractClassV1::from_file(TEST_CONTRACT_CAIRO1_PATH).into(),
+            Some(compiled_class_hash),
+        )
+        .unwrap();
+
+    // Deploy at chosen and at computed addresses, running the constructors.
+    let test_contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    builder
+        .deploy_contract_at(
+            test_contract_address,
+            test_class_hash,
+            calldata![stark_felt!("0x10"), stark_felt!("0x11")],
+        )
+        .unwrap();
+    let salt = ContractAddressSalt(stark_felt!(7_u8));
+    let cairo1_calldata = calldata![stark_felt!(1_u8), stark_felt!(2_u8)];
+    let cairo1_contract_address =
+        builder.deploy_contract(cairo1_class_hash, salt, cairo1_calldata.clone()).unwrap();
+    assert_eq!(
+        cairo1_contract_address,
+        calculate_contract_address(
+            salt,
+            cairo1_class_hash,
+            &cairo1_calldata,
+            ContractAddress::default()
+        )
+        .unwrap()
+    );
+    let account_address = builder
+        .deploy_contract(account_class_hash, ContractAddressSalt::default(), calldata![])
+        .unwrap();
+
+    // Fund the account with a balance above 128 bits, in two parts.
+    builder
+        .fund_account(FeeUnit::Wei, account_address, &(BigUint::from(1_u8) << 128_usize))
+        .unwrap();
+    builder.fund_account(FeeUnit::Wei, account_address, &BigUint::from(MAX_FEE)).unwrap();
+
+    let (mut state, state_diff) = builder.build().unwrap();
+    assert_eq!(
+        state
+            .get_fee_token_balance(
+                &account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
+        (stark_felt!(MAX_FEE), stark_felt!(1_u8))
+    );
+    let (total_supply_low, total_supply_high) =
+        get_uint256_storage_var_addresses("ERC20_total_supply", &[]).unwrap();
+    let fee_token_storage =
+        &state_diff.storage_updates[&block_context.fee_token_addresses.eth_fee_token_address];
+    assert_eq!(fee_token_storage[&total_supply_low], stark_felt!(MAX_FEE));
+    assert_eq!(fee_token_storage[&total_supply_high], stark_felt!(1_u8));
+    assert_eq!(
+        state_diff.storage_updates[&test_contract_address][&StorageKey(patricia_key!("0x10"))],
+        stark_felt!("0x11")
+    );
+    let my_storage_var = get_storage_var_address("my_storage_var", &[]).unwrap();
+    assert_eq!(
+        state_diff.storage_updates[&cairo1_contract_address][&my_storage_var],
+        stark_felt!(3_u8)
+    );
+    assert_eq!(state_diff.address_to_class_hash[&account_address], account_class_hash);
+    assert_eq!(state_diff.address_to_class_hash.len(), 4);
+    assert_eq!(
+        state_diff.class_hash_to_compiled_class_hash[&cairo1_class_hash],
+        compiled_class_hash
+    );
+
+    // The account can transact right away.
+    let execute_calldata = calldata![
+        *test_contract_address.0.key(),
+        selector_from_name("return_result").0,
+        stark_felt!(1_u8),
+        stark_felt!(2_u8)
+    ];
+    let tx = account_invoke_tx(
+        execute_calldata,
+        account_address,
+        &mut NonceManager::default(),
+        Fee(MAX_FEE),
+    );
+    let execution_info = tx.execute(&mut state, &block_context, true, true).unwrap();
+    assert!(!execution_info.is_reverted());
+    assert_eq!(
+        state
+            .get_fee_token_balance(
+                &account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
+        (stark_felt!(MAX_FEE - execution_info.actual_fee.0), stark_felt!(1_u8))
+    );
+}
+
+#[test]
+fn total_supply_overflow() {
+    let block_context = BlockContext::create_for_account_testing();
+    let mut builder = genesis_builder();
+    let account_address = contract_address!("0x100");
+    let max_uint256 = (BigUint::from(1_u8) << 256_usize) - 1_u8;
+    builder.fund_account(FeeUnit::Wei, account_address, &max_uint256).unwrap();
+    let error = builder
+        .fund_account(FeeUnit::Wei, contract_address!("0x200"), &BigUint::from(1_u8))
+        .unwrap_err();
+    assert_matches!(
+        error,
+        GenesisBuilderError::TotalSupplyOverflow(address)
+            if address == block_context.fee_token_addresses.eth_fee_token_address
+    );
+
+    let (mut state, _) = builder.build().unwrap();
+    let u128_max = stark_felt!(u128::MAX);
+    assert_eq!(
+        state
+            .get_fee_token_balance(
+                &account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
+        (u128_max, u128_max)
+    );
+    assert_eq!(
+        state
+            .get_fee_token_balance(
+                &contract_address!("0x200"),
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
+        (StarkFelt::default(), StarkFelt::default())
+    );
+}
+
+#[test]
+fn balance_overflow_leaves_total_supply() {
+    let block_context = BlockContext::create_for_account_testing();
+    let fee_token_address = block_context.fee_token_addresses.eth_fee_token_address;
+    let account_address = contract_address!("0x100");
+    let (balance_low, balance_high) = get_erc20_balance_var_addresses(&account_address).unwrap();
+    let u128_max = stark_felt!(u128::MAX);
+    let mut state = InMemoryState::default();
+    state.apply_state_diff(&CommitmentStateDiff {
+        address_to_class_hash: IndexMap::new(),
+        address_to_nonce: IndexMap::new(),
+        storage_updates: IndexMap::from([(
+            fee_token_address,
+            IndexMap::from([(balance_low, u128_max), (balance_high, u128_max)]),
+        )]),
+        class_hash_to_compiled_class_hash: IndexMap::new(),
+    });
+
+    // The total supply does not overflow, but the balance does.
+    let mut builder = GenesisBuilder::new(block_context, state);
+    assert_matches!(
+        builder.fund_account(FeeUnit::Wei, account_address, &BigUint::from(1_u8)).unwrap_err(),
+        GenesisBuilderError::BalanceOverflow(address) if address == account_address
+    );
+    let (_, state_diff) = builder.build().unwrap();
+    assert!(state_diff.storage_updates.is_empty());
+}
+
+#[test]
+fn compiled_class_hash_mismatch() {
+    let mut builder = genesis_builder();
+    let (cairo0_class_hash, cairo1_class_hash) =
+        (class_hash!(TEST_CLASS_HASH), class_hash!("0x30"));
+    assert_matches!(
+        builder
+            .declare_class(
+                cairo0_class_hash,
+                ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into(),
+                Some(CompiledClassHash(stark_felt!("0x31"))),
+            )
+            .unwrap_err(),
+        GenesisBuilderError::UnexpectedCompiledClassHash(class_hash)
+            if class_hash == cairo0_class_hash
+    );
+    assert_matches!(
+        builder
+            .declare_class(
+                cairo1_class_hash,
+                ContractClassV1::from_file(TEST_CONTRACT_CAIRO1_PATH).into(),
+                None,
+            )
+            .unwrap_err(),
+        GenesisBuilderError::MissingCompiledClassHash(class_hash)
+            if class_hash == cairo1_class_hash
+    );
+
+    // Neither class was declared.
+    let (mut state, _) = builder.build().unwrap();
+    for class_hash in [cairo0_class_hash, cairo1_class_hash] {
+        assert!(state.get_compiled_contract_class(&class_hash).is_err());
+    }
+}
+
+#[test]
+fn failed_deployment() {
+    let mut builder = genesis_builder();
+    let test_class_hash = class_hash!(TEST_CLASS_HASH);
+    builder
+        .declare_class(
+            test_class_hash,
+            ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into(),
+            None,
+        )
+        .unwrap();
+
+    // The constructor expects two arguments.
+    let test_contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    assert_matches!(
+        builder
+            .deploy_contract_at(
+                test_contract_address,
+                test_class_hash,
+                calldata![stark_felt!(1_u8)]
+            )
+            .unwrap_err(),
+        GenesisBuilderError::EntryPointExecutionError(_)
+    );
+
+    // The address was not allocated.
+    let (mut state, state_diff) = builder.build().unwrap();
+    assert!(!state_diff.address_to_class_hash.contains_key(&test_contract_address));
+    assert_eq!(state.get_class_hash_at(test_contract_address).unwrap(), ClassHash::default());
+}
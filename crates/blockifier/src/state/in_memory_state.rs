@@ -0,0 +1,262 @@
+//! An in-memory state, which can be loaded from and saved to a JSON genesis file of the
+//! following form (all fields are optional; felts are hex strings):
+//!
+//! ```json
+//! {
+//!     "contracts": {
+//!         "<address>": {
+//!             "class_hash": "<class hash>",
+//!             "nonce": "<nonce>",
+//!             "storage": { "<key>": "<value>" }
+//!         }
+//!     },
+//!     "classes": {
+//!         "<class hash>": { "version": "cairo0", "contract_class": <compiled contract> },
+//!         "<class hash>": { "version": "cairo1", "contract_class": <CASM contract class> }
+//!     },
+//!     "compiled_class_hashes": { "<class hash>": "<compiled class hash>" }
+//! }
+//! ```
+//!
+//! A Cairo 0 contract class is given in its compiled form (program, entry points and ABI), as
+//! output by `starknet-compile-deprecated`; a Cairo 1 contract class is given in its CASM form.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::state::cached_state::{CommitmentStateDiff, ContractStorageKey};
+use crate::state::errors::{InMemoryStateError, StateError};
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "in_memory_state_test.rs"]
+mod test;
+
+pub type InMemoryStateResult<T> = Result<T, InMemoryStateError>;
+
+/// A contract class in its raw (JSON) form; see the module documentation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "version", content = "contract_class")]
+pub enum RawContractClass {
+    Cairo0(serde_json::Value),
+    Cairo1(serde_json::Value),
+}
+
+impl RawContractClass {
+    pub fn compile(&self) -> InMemoryStateResult<ContractClass> {
+        Ok(match self {
+            RawContractClass::Cairo0(raw_contract_class) => {
+                ContractClassV0::try_from_json_string(&raw_contract_class.to_string())?.into()
+            }
+            RawContractClass::Cairo1(raw_contract_class) => {
+                ContractClassV1::try_from_json_string(&raw_contract_class.to_string())?.into()
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GenesisContract {
+    #[serde(default)]
+    pub class_hash: ClassHash,
+    #[serde(default)]
+    pub nonce: Nonce,
+    #[serde(default)]
+    pub storage: BTreeMap<StorageKey, StarkFelt>,
+}
+
+/// The contents of a genesis file; see the module documentation.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GenesisState {
+    #[serde(default)]
+    pub contracts: BTreeMap<ContractAddress, GenesisContract>,
+    #[serde(default)]
+    pub classes: BTreeMap<ClassHash, RawContractClass>,
+    #[serde(default)]
+    pub compiled_class_hashes: BTreeMap<ClassHash, CompiledClassHash>,
+}
+
+/// A state held entirely in memory, advanced by committing state diffs.
+/// Uninitialized cells hold their default values (see `StateReader`).
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryState {
+    storage: HashMap<ContractStorageKey, StarkFelt>,
+    address_to_nonce: HashMap<ContractAddress, Nonce>,
+    address_to_class_hash: HashMap<ContractAddress, ClassHash>,
+    class_hash_to_compiled_class_hash: HashMap<ClassHash, CompiledClassHash>,
+    raw_classes: HashMap<ClassHash, RawContractClass>,
+    // The compiled form of `raw_classes`.
+    classes: HashMap<ClassHash, ContractClass>,
+}
+
+impl InMemoryState {
+    pub fn from_genesis(genesis: GenesisState) -> InMemoryStateResult<Self> {
+        let mut state = Self::default();
+        for (address, contract) in genesis.contracts {
+            for (key, value) in contract.storage {
+                state.set_storage_at(address, key, value);
+            }
+            state.set_nonce_at(address, contract.nonce);
+            state.set_class_hash_at(address, contract.class_hash);
+        }
+        for (class_hash, raw_contract_class) in genesis.classes {
+            state.declare_class(class_hash, raw_contract_class)?;
+        }
+        state.class_hash_to_compiled_class_hash.extend(genesis.compiled_class_hashes);
+
+        Ok(state)
+    }
+
+    pub fn to_genesis(&self) -> GenesisState {
+        let mut contracts = BTreeMap::<_, GenesisContract>::new();
+        for (&(address, key), &value) in &self.storage {
+            contracts.entry(address).or_default().storage.insert(key, value);
+        }
+        for (&address, &nonce) in &self.address_to_nonce {
+            contracts.entry(address).or_default().nonce = nonce;
+        }
+        for (&address, &class_hash) in &self.address_to_class_hash {
+            contracts.entry(address).or_default().class_hash = class_hash;
+        }
+
+        GenesisState {
+            contracts,
+            classes: self.raw_classes.clone().into_iter().collect(),
+            compiled_class_hashes: self
+                .class_hash_to_compiled_class_hash
+                .clone()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Loads the state from the given genesis file.
+    pub fn load(path: impl AsRef<Path>) -> InMemoryStateResult<Self> {
+        let genesis: GenesisState = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        Self::from_genesis(genesis)
+    }
+
+    /// Saves the full state to the given genesis file.
+    pub fn save(&self, path: impl AsRef<Path>) -> InMemoryStateResult<()> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), &self.to_genesis())?;
+        Ok(())
+    }
+
+    /// Adds the given contract class; its compiled class hash (for Cairo 1 classes) is set by the
+    /// state diff that declares it.
+    pub fn declare_class(
+        &mut self,
+        class_hash: ClassHash,
+        raw_contract_class: RawContractClass,
+    ) -> InMemoryStateResult<()> {
+        self.classes.insert(class_hash, raw_contract_class.compile()?);
+        self.raw_classes.insert(class_hash, raw_contract_class);
+        Ok(())
+    }
+
+    /// Commits the given state diff (e.g., of a block) on top of the current state.
+    pub fn apply_state_diff(&mut self, state_diff: &CommitmentStateDiff) {
+        for (&address, storage_updates) in &state_diff.storage_updates {
+            for (&key, &value) in storage_updates {
+                self.set_storage_at(address, key, value);
+            }
+        }
+        for (&address, &nonce) in &state_diff.address_to_nonce {
+            self.set_nonce_at(address, nonce);
+        }
+        for (&address, &class_hash) in &state_diff.address_to_class_hash {
+            self.set_class_hash_at(address, class_hash);
+        }
+        self.class_hash_to_compiled_class_hash
+            .extend(&state_diff.class_hash_to_compiled_class_hash);
+    }
+
+    // Default values are not kept, so that saved states only hold initialized cells.
+
+    fn set_storage_at(&mut self, address: ContractAddress, key: StorageKey, value: StarkFelt) {
+        if value == StarkFelt::default() {
+            self.storage.remove(&(address, key));
+        } else {
+            self.storage.insert((address, key), value);
+        }
+    }
+
+    fn set_nonce_at(&mut self, address: ContractAddress, nonce: Nonce) {
+        if nonce == Nonce::default() {
+            self.address_to_nonce.remove(&address);
+        } else {
+            self.address_to_nonce.insert(address, nonce);
+        }
+    }
+
+    fn set_class_hash_at(&mut self, address: ContractAddress, class_hash: ClassHash) {
+        if class_hash == ClassHash::default() {
+            self.address_to_class_hash.remove(&address);
+        } else {
+            self.address_to_class_hash.insert(address, class_hash);
+        }
+    }
+}
+
+impl SharedStateReader for InMemoryState {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        Ok(self.storage.get(&(contract_address, key)).copied().unwrap_or_default())
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        Ok(self.address_to_nonce.get(&contract_address).copied().unwrap_or_default())
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        Ok(self.address_to_class_hash.get(&contract_address).copied().unwrap_or_default())
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
+        self.classes.get(class_hash).cloned().ok_or(StateError::UndeclaredClassHash(*class_hash))
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        Ok(self.class_hash_to_compiled_class_hash.get(&class_hash).copied().unwrap_or_default())
+    }
+}
+
+impl StateReader for InMemoryState {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        SharedStateReader::get_storage_at(self, contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        SharedStateReader::get_nonce_at(self, contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        SharedStateReader::get_class_hash_at(self, contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        SharedStateReader::get_compiled_contract_class(self, class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        SharedStateReader::get_compiled_class_hash(self, class_hash)
+    }
+}
@@ -0,0 +1,157 @@
+use std::fs;
+
+use assert_matches::assert_matches;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Calldata;
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::execution::entry_point::{CallEntryPoint, CallExecution, Retdata};
+use crate::retdata;
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::state::errors::{InMemoryStateError, StateError};
+use crate::state::in_memory_state::{GenesisState, InMemoryState, RawContractClass};
+use crate::state::state_api::{State, StateReader};
+use crate::test_utils::{
+    get_raw_contract_class, trivial_external_entry_point, TEST_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+    TEST_CONTRACT_CAIRO0_PATH, TEST_CONTRACT_CAIRO1_PATH,
+};
+
+fn raw_contract_class(contract_path: &str) -> Value {
+    serde_json::from_str(&get_raw_contract_class(contract_path)).unwrap()
+}
+
+fn genesis() -> Value {
+    json!({
+        "contracts": {
+            TEST_CONTRACT_ADDRESS: {
+                "class_hash": TEST_CLASS_HASH,
+                "nonce": "0x1",
+                "storage": { "0x10": "0x11", "0x20": "0x21" },
+            },
+            "0x200": { "class_hash": "0x20" },
+        },
+        "classes": {
+            TEST_CLASS_HASH: {
+                "version": "cairo0",
+                "contract_class": raw_contract_class(TEST_CONTRACT_CAIRO0_PATH),
+            },
+            "0x20": {
+                "version": "cairo1",
+                "contract_class": raw_contract_class(TEST_CONTRACT_CAIRO1_PATH),
+            },
+        },
+        "compiled_class_hashes": { "0x20": "0x21" },
+    })
+}
+
+#[test]
+fn load_and_save_genesis() {
+    let path = std::env::temp_dir().join(format!("blockifier_genesis_{}.json", std::process::id()));
+    fs::write(&path, genesis().to_string()).unwrap();
+    let mut state = InMemoryState::load(&path).unwrap();
+
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    assert_eq!(
+        state.get_storage_at(contract_address, StorageKey(patricia_key!("0x20"))).unwrap(),
+        stark_felt!("0x21")
+    );
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(1_u8)));
+    assert_eq!(state.get_class_hash_at(contract_address!("0x200")).unwrap(), class_hash!("0x20"));
+    assert_eq!(
+        state.get_compiled_class_hash(class_hash!("0x20")).unwrap(),
+        CompiledClassHash(stark_felt!("0x21"))
+    );
+    let expected_contract_class: ContractClass =
+        ContractClassV1::from_file(TEST_CONTRACT_CAIRO1_PATH).into();
+    assert_eq!(
+        state.get_compiled_contract_class(&class_hash!("0x20")).unwrap(),
+        expected_contract_class
+    );
+    assert_matches!(
+        state.get_compiled_contract_class(&class_hash!("0x30")).unwrap_err(),
+        StateError::UndeclaredClassHash(_)
+    );
+
+    // Saving and reloading preserves the full state.
+    state.save(&path).unwrap();
+    let saved_genesis: GenesisState =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved_genesis, serde_json::from_value(genesis()).unwrap());
+    assert_eq!(InMemoryState::load(&path).unwrap().to_genesis(), state.to_genesis());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_classes() {
+    let mut state = InMemoryState::default();
+    let raw_contract_class =
+        RawContractClass::Cairo1(raw_contract_class(TEST_CONTRACT_CAIRO0_PATH));
+    assert_matches!(
+        state.declare_class(class_hash!("0x1"), raw_contract_class).unwrap_err(),
+        InMemoryStateError::ProgramError(_)
+    );
+}
+
+#[test]
+fn apply_state_diffs() {
+    let mut state = InMemoryState::default();
+    state
+        .declare_class(
+            class_hash!(TEST_CLASS_HASH),
+            RawContractClass::Cairo0(raw_contract_class(TEST_CONTRACT_CAIRO0_PATH)),
+        )
+        .unwrap();
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    state.apply_state_diff(&CommitmentStateDiff {
+        address_to_class_hash: indexmap! { contract_address => class_hash!(TEST_CLASS_HASH) },
+        address_to_nonce: indexmap! {},
+        storage_updates: indexmap! {},
+        class_hash_to_compiled_class_hash: indexmap! {},
+    });
+    let expected_contract_class: ContractClass =
+        ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into();
+    assert_eq!(
+        state.get_compiled_contract_class(&class_hash!(TEST_CLASS_HASH)).unwrap(),
+        expected_contract_class
+    );
+
+    // Execute a block on top of the state, and commit it.
+    let mut block_state = CachedState::from(state.clone());
+    let (key, value) = (stark_felt!(1234_u16), stark_felt!(18_u8));
+    let entry_point_call = CallEntryPoint {
+        calldata: calldata![key, value],
+        entry_point_selector: selector_from_name("test_storage_read_write"),
+        ..trivial_external_entry_point()
+    };
+    assert_eq!(
+        entry_point_call.execute_directly(&mut block_state).unwrap().execution,
+        CallExecution::from_retdata(retdata![value])
+    );
+    block_state.increment_nonce(contract_address).unwrap();
+    state.apply_state_diff(&block_state.to_state_diff());
+
+    let storage_key = StorageKey(patricia_key!(1234_u16));
+    assert_eq!(state.get_storage_at(contract_address, storage_key).unwrap(), value);
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(1_u8)));
+
+    // Cells reset to their default values are not saved.
+    state.apply_state_diff(&CommitmentStateDiff {
+        address_to_class_hash: indexmap! {},
+        address_to_nonce: indexmap! { contract_address => Nonce::default() },
+        storage_updates: indexmap! {
+            contract_address => indexmap! { storage_key => StarkFelt::default() },
+        },
+        class_hash_to_compiled_class_hash: indexmap! {},
+    });
+    let genesis = state.to_genesis();
+    assert_eq!(genesis.contracts.len(), 1);
+    assert_eq!(genesis.contracts[&contract_address].storage, Default::default());
+    assert_eq!(genesis.contracts[&contract_address].nonce, Nonce::default());
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::{CommitmentStateDiff, ContractStorageKey};
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "pending_state_reader_test.rs"]
+mod test;
+
+/// Reads the state of a pending (uncommitted) block: the values set by its state diff, and the
+/// classes it declares, shadow those of the state it extends.
+pub struct PendingStateReader<R> {
+    base_reader: R,
+    pending_state_diff: CommitmentStateDiff,
+    pending_classes: HashMap<ClassHash, ContractClass>,
+}
+
+impl<R: SharedStateReader> PendingStateReader<R> {
+    pub fn new(
+        base_reader: R,
+        pending_state_diff: CommitmentStateDiff,
+        pending_classes: HashMap<ClassHash, ContractClass>,
+    ) -> Self {
+        Self { base_reader, pending_state_diff, pending_classes }
+    }
+
+    pub fn base_reader(&self) -> &R {
+        &self.base_reader
+    }
+
+    fn get_pending_storage_at(
+        &self,
+        (contract_address, key): &ContractStorageKey,
+    ) -> Option<StarkFelt> {
+        self.pending_state_diff.storage_updates.get(contract_address)?.get(key).copied()
+    }
+
+    fn get_pending_nonce_at(&self, contract_address: &ContractAddress) -> Option<Nonce> {
+        self.pending_state_diff.address_to_nonce.get(contract_address).copied()
+    }
+
+    fn get_pending_class_hash_at(&self, contract_address: &ContractAddress) -> Option<ClassHash> {
+        self.pending_state_diff.address_to_class_hash.get(contract_address).copied()
+    }
+}
+
+impl<R: SharedStateReader> SharedStateReader for PendingStateReader<R> {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        match self.get_pending_storage_at(&(contract_address, key)) {
+            Some(value) => Ok(value),
+            None => self.base_reader.get_storage_at(contract_address, key),
+        }
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        match self.get_pending_nonce_at(&contract_address) {
+            Some(nonce) => Ok(nonce),
+            None => self.base_reader.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        match self.get_pending_class_hash_at(&contract_address) {
+            Some(class_hash) => Ok(class_hash),
+            None => self.base_reader.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
+        match self.pending_classes.get(class_hash) {
+            Some(contract_class) => Ok(contract_class.clone()),
+            None => self.base_reader.get_compiled_contract_class(class_hash),
+        }
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        match self.pending_state_diff.class_hash_to_compiled_class_hash.get(&class_hash) {
+            Some(compiled_class_hash) => Ok(*compiled_class_hash),
+            None => self.base_reader.get_compiled_class_hash(class_hash),
+        }
+    }
+
+    /// Reads the values not set by the pending block in a single batch from the base reader.
+    fn get_storage_batch(
+        &self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        overlay_batch(
+            contract_storage_keys,
+            |contract_storage_key| self.get_pending_storage_at(contract_storage_key),
+            |base_keys| self.base_reader.get_storage_batch(base_keys),
+        )
+    }
+
+    /// Reads the nonces not set by the pending block in a single batch from the base reader.
+    fn get_nonces_batch(&self, contract_addresses: &[ContractAddress]) -> StateResult<Vec<Nonce>> {
+        overlay_batch(
+            contract_addresses,
+            |contract_address| self.get_pending_nonce_at(contract_address),
+            |base_addresses| self.base_reader.get_nonces_batch(base_addresses),
+        )
+    }
+
+    /// Reads the class hashes not set by the pending block in a single batch from the base reader.
+    fn get_class_hashes_batch(
+        &self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        overlay_batch(
+            contract_addresses,
+            |contract_address| self.get_pending_class_hash_at(contract_address),
+            |base_addresses| self.base_reader.get_class_hashes_batch(base_addresses),
+        )
+    }
+}
+
+impl<R: SharedStateReader> StateReader for PendingStateReader<R> {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        SharedStateReader::get_storage_at(self, contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        SharedStateReader::get_nonce_at(self, contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        SharedStateReader::get_class_hash_at(self, contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        SharedStateReader::get_compiled_contract_class(self, class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        SharedStateReader::get_compiled_class_hash(self, class_hash)
+    }
+
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        SharedStateReader::get_storage_batch(self, contract_storage_keys)
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        SharedStateReader::get_nonces_batch(self, contract_addresses)
+    }
+
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        SharedStateReader::get_class_hashes_batch(self, contract_addresses)
+    }
+}
+
+/// Returns the values of the given keys, taking the pending ones when set, and reading the rest
+/// in a single batch.
+fn overlay_batch<K: Copy, V>(
+    keys: &[K],
+    get_pending: impl Fn(&K) -> Option<V>,
+    get_base_batch: impl FnOnce(&[K]) -> StateResult<Vec<V>>,
+) -> StateResult<Vec<V>> {
+    let base_keys: Vec<K> = keys.iter().filter(|key| get_pending(key).is_none()).copied().collect();
+    let mut base_values = get_base_batch(&base_keys)?.into_iter();
+
+    Ok(keys
+        .iter()
+        .map(|key| {
+            get_pending(key).unwrap_or_else(|| {
+                base_values.next().expect("A value should be read for each non-pending key.")
+            })
+        })
+        .collect())
+}
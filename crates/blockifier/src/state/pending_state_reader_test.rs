@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0};
+use crate::state::cached_state::{CachedState, CommitmentStateDiff};
+use crate::state::pending_state_reader::PendingStateReader;
+use crate::state::state_api::StateReader;
+use crate::test_utils::{DictStateReader, TEST_CLASS_HASH, TEST_CONTRACT_CAIRO0_PATH};
+
+#[test]
+fn pending_values_shadow_base_values() {
+    let contract_address = contract_address!("0x100");
+    let other_contract_address = contract_address!("0x200");
+    let (key, other_key) = (StorageKey(patricia_key!("0x10")), StorageKey(patricia_key!("0x20")));
+    let base_reader = DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, key), stark_felt!(1_u8)),
+            ((contract_address, other_key), stark_felt!(2_u8)),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!(1_u8)))]),
+        address_to_class_hash: HashMap::from([(contract_address, class_hash!("0x1"))]),
+        ..Default::default()
+    };
+
+    let pending_class_hash = class_hash!(TEST_CLASS_HASH);
+    let pending_contract_class: ContractClass =
+        ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into();
+    let pending_state_diff = CommitmentStateDiff {
+        address_to_class_hash: indexmap! { other_contract_address => pending_class_hash },
+        address_to_nonce: indexmap! { contract_address => Nonce(stark_felt!(2_u8)) },
+        storage_updates: indexmap! {
+            contract_address => indexmap! { key => stark_felt!(7_u8) },
+        },
+        class_hash_to_compiled_class_hash: indexmap! {
+            class_hash!("0x2") => CompiledClassHash(stark_felt!("0x3")),
+        },
+    };
+    let mut state = CachedState::from(PendingStateReader::new(
+        base_reader,
+        pending_state_diff,
+        HashMap::from([(pending_class_hash, pending_contract_class.clone())]),
+    ));
+
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!(7_u8));
+    assert_eq!(state.get_storage_at(contract_address, other_key).unwrap(), stark_felt!(2_u8));
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(2_u8)));
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), class_hash!("0x1"));
+    assert_eq!(state.get_class_hash_at(other_contract_address).unwrap(), pending_class_hash);
+    assert_eq!(
+        state.get_compiled_contract_class(&pending_class_hash).unwrap(),
+        pending_contract_class
+    );
+    assert_eq!(
+        state.get_compiled_class_hash(class_hash!("0x2")).unwrap(),
+        CompiledClassHash(stark_felt!("0x3"))
+    );
+
+    // Batches mix pending and base values, in order.
+    assert_eq!(
+        state
+            .get_storage_batch(&[
+                (contract_address, other_key),
+                (contract_address, key),
+                (other_contract_address, key)
+            ])
+            .unwrap(),
+        vec![stark_felt!(2_u8), stark_felt!(7_u8), StarkFelt::default()]
+    );
+    assert_eq!(
+        state.get_class_hashes_batch(&[other_contract_address, contract_address]).unwrap(),
+        vec![pending_class_hash, class_hash!("0x1")]
+    );
+    assert_eq!(
+        state.get_nonces_batch(&[other_contract_address, contract_address]).unwrap(),
+        vec![Nonce::default(), Nonce(stark_felt!(2_u8))]
+    );
+}
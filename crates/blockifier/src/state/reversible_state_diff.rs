@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::state::cached_state::{CommitmentStateDiff, ContractStorageKey, StateCell, StateMaps};
+use crate::state::errors::{StateDiffError, StateError};
+use crate::state::state_api::State;
+
+#[cfg(test)]
+#[path = "reversible_state_diff_test.rs"]
+mod test;
+
+pub type StateDiffResult<T> = Result<T, StateDiffError>;
+
+/// The value of a state cell before and after a change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ValueChange<T> {
+    pub previous: T,
+    pub new: T,
+}
+
+impl<T: Copy> ValueChange<T> {
+    pub fn inverse(&self) -> Self {
+        Self { previous: self.new, new: self.previous }
+    }
+}
+
+/// A state diff that holds the previous value of every changed cell, next to its new value; unlike
+/// `CommitmentStateDiff`, it can be inverted and composed with consecutive diffs.
+/// Only cells whose value is actually changed are included.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReversibleStateDiff {
+    // Contract instance attributes (per address).
+    pub address_to_class_hash: IndexMap<ContractAddress, ValueChange<ClassHash>>,
+    pub address_to_nonce: IndexMap<ContractAddress, ValueChange<Nonce>>,
+    pub storage_updates: IndexMap<ContractAddress, IndexMap<StorageKey, ValueChange<StarkFelt>>>,
+
+    // Global attributes.
+    pub class_hash_to_compiled_class_hash: IndexMap<ClassHash, ValueChange<CompiledClassHash>>,
+}
+
+impl ReversibleStateDiff {
+    /// Creates the diff from the given previous and new values of the changed cells; cells missing
+    /// from `previous_values` are taken to have held the default value.
+    pub fn new(previous_values: &StateMaps, new_values: &StateMaps) -> Self {
+        let storage = value_changes(&previous_values.storage, &new_values.storage);
+        Self {
+            address_to_class_hash: value_changes(
+                &previous_values.class_hashes,
+                &new_values.class_hashes,
+            ),
+            address_to_nonce: value_changes(&previous_values.nonces, &new_values.nonces),
+            storage_updates: group_storage(storage),
+            class_hash_to_compiled_class_hash: value_changes(
+                &previous_values.compiled_class_hashes,
+                &new_values.compiled_class_hashes,
+            ),
+        }
+        .sorted()
+    }
+
+    /// Returns the diff that undoes this one.
+    /// Class declarations are not part of the diff: the inverse of a declaration resets the
+    /// compiled class hash, but the class itself stays declared in the state it is applied to.
+    pub fn inverse(&self) -> Self {
+        fn invert<K: Copy + Eq + Hash, V: Copy>(
+            changes: &IndexMap<K, ValueChange<V>>,
+        ) -> IndexMap<K, ValueChange<V>> {
+            changes.iter().map(|(key, change)| (*key, change.inverse())).collect()
+        }
+
+        Self {
+            address_to_class_hash: invert(&self.address_to_class_hash),
+            address_to_nonce: invert(&self.address_to_nonce),
+            storage_updates: self
+                .storage_updates
+                .iter()
+                .map(|(address, storage_updates)| (*address, invert(storage_updates)))
+                .collect(),
+            class_hash_to_compiled_class_hash: invert(&self.class_hash_to_compiled_class_hash),
+        }
+    }
+
+    /// Returns a single diff equivalent to applying this diff and then the given one; cells
+    /// restored to their previous value are dropped.
+    /// Fails if the given diff changes a cell from a value other than the one set by this diff.
+    pub fn compose(&self, next: &Self) -> StateDiffResult<Self> {
+        let storage = compose_changes(
+            &flatten_storage(&self.storage_updates),
+            &flatten_storage(&next.storage_updates),
+            StateCell::Storage,
+        )?;
+
+        Ok(Self {
+            address_to_class_hash: compose_changes(
+                &self.address_to_class_hash,
+                &next.address_to_class_hash,
+                StateCell::ClassHash,
+            )?,
+            address_to_nonce: compose_changes(
+                &self.address_to_nonce,
+                &next.address_to_nonce,
+                StateCell::Nonce,
+            )?,
+            storage_updates: group_storage(storage),
+            class_hash_to_compiled_class_hash: compose_changes(
+                &self.class_hash_to_compiled_class_hash,
+                &next.class_hash_to_compiled_class_hash,
+                StateCell::CompiledClassHash,
+            )?,
+        }
+        .sorted())
+    }
+
+    /// Writes the new values of the diff to the given state, after checking that it holds their
+    /// previous values. Compiled class hashes are not checked, as not all readers can read them.
+    /// The diff is applied atomically: everything is checked before anything is written, so on
+    /// failure, the state is left unchanged.
+    pub fn apply(&self, state: &mut impl State) -> StateDiffResult<()> {
+        for (&(address, key), change) in &flatten_storage(&self.storage_updates) {
+            if state.get_storage_at(address, key)? != change.previous {
+                return Err(StateDiffError::UnexpectedValue(StateCell::Storage((address, key))));
+            }
+        }
+        for (&address, change) in &self.address_to_nonce {
+            if state.get_nonce_at(address)? != change.previous {
+                return Err(StateDiffError::UnexpectedValue(StateCell::Nonce(address)));
+            }
+        }
+        for (&address, change) in &self.address_to_class_hash {
+            // Rejected by the state on write.
+            if address == ContractAddress::default() {
+                return Err(StateError::OutOfRangeContractAddress.into());
+            }
+            if state.get_class_hash_at(address)? != change.previous {
+                return Err(StateDiffError::UnexpectedValue(StateCell::ClassHash(address)));
+            }
+        }
+
+        for (&address, storage_updates) in &self.storage_updates {
+            for (&key, change) in storage_updates {
+                state.set_storage_at(address, key, change.new);
+            }
+        }
+        for (&address, change) in &self.address_to_nonce {
+            state.set_nonce_at(address, change.new)?;
+        }
+        for (&address, change) in &self.address_to_class_hash {
+            state.set_class_hash_at(address, change.new)?;
+        }
+        for (&class_hash, change) in &self.class_hash_to_compiled_class_hash {
+            state.set_compiled_class_hash(class_hash, change.new)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the new values of the diff.
+    pub fn to_commitment_state_diff(&self) -> CommitmentStateDiff {
+        fn new_values<K: Copy + Eq + Hash, V: Copy>(
+            changes: &IndexMap<K, ValueChange<V>>,
+        ) -> IndexMap<K, V> {
+            changes.iter().map(|(key, change)| (*key, change.new)).collect()
+        }
+
+        CommitmentStateDiff {
+            address_to_class_hash: new_values(&self.address_to_class_hash),
+            address_to_nonce: new_values(&self.address_to_nonce),
+            storage_updates: self
+                .storage_updates
+                .iter()
+                .map(|(address, storage_updates)| (*address, new_values(storage_updates)))
+                .collect(),
+            class_hash_to_compiled_class_hash: new_values(&self.class_hash_to_compiled_class_hash),
+        }
+    }
+
+    /// Sorts all mappings by key; see `CommitmentStateDiff`.
+    fn sorted(mut self) -> Self {
+        self.address_to_class_hash.sort_keys();
+        self.address_to_nonce.sort_keys();
+        self.storage_updates.sort_keys();
+        for contract_storage_updates in self.storage_updates.values_mut() {
+            contract_storage_updates.sort_keys();
+        }
+        self.class_hash_to_compiled_class_hash.sort_keys();
+        self
+    }
+}
+
+fn value_changes<K: Copy + Eq + Hash, V: Copy + Default + Eq>(
+    previous_values: &HashMap<K, V>,
+    new_values: &HashMap<K, V>,
+) -> IndexMap<K, ValueChange<V>> {
+    new_values
+        .iter()
+        .filter_map(|(key, &new)| {
+            let previous = previous_values.get(key).copied().unwrap_or_default();
+            (previous != new).then_some((*key, ValueChange { previous, new }))
+        })
+        .collect()
+}
+
+fn compose_changes<K: Copy + Eq + Hash, V: Copy + Eq>(
+    first: &IndexMap<K, ValueChange<V>>,
+    second: &IndexMap<K, ValueChange<V>>,
+    to_cell: impl Fn(K) -> StateCell,
+) -> StateDiffResult<IndexMap<K, ValueChange<V>>> {
+    let mut composed = first.clone();
+    for (&key, second_change) in second {
+        let Some(first_change) = first.get(&key) else {
+            composed.insert(key, *second_change);
+            continue;
+        };
+        if first_change.new != second_change.previous {
+            return Err(StateDiffError::InconsistentComposition(to_cell(key)));
+        }
+
+        if first_change.previous == second_change.new {
+            composed.shift_remove(&key);
+        } else {
+            composed.insert(
+                key,
+                ValueChange { previous: first_change.previous, new: second_change.new },
+            );
+        }
+    }
+
+    Ok(composed)
+}
+
+fn flatten_storage(
+    storage_updates: &IndexMap<ContractAddress, IndexMap<StorageKey, ValueChange<StarkFelt>>>,
+) -> IndexMap<ContractStorageKey, ValueChange<StarkFelt>> {
+    storage_updates
+        .iter()
+        .flat_map(|(&address, storage_updates)| {
+            storage_updates.iter().map(move |(&key, &change)| ((address, key), change))
+        })
+        .collect()
+}
+
+fn group_storage(
+    storage: IndexMap<ContractStorageKey, ValueChange<StarkFelt>>,
+) -> IndexMap<ContractAddress, IndexMap<StorageKey, ValueChange<StarkFelt>>> {
+    let mut storage_updates = IndexMap::<_, IndexMap<_, _>>::new();
+    for ((address, key), change) in storage {
+        storage_updates.entry(address).or_default().insert(key, change);
+    }
+    storage_updates
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use indexmap::{indexmap, IndexMap};
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::state::cached_state::{CachedState, StateCell};
+use crate::state::errors::{StateDiffError, StateError};
+use crate::state::reversible_state_diff::{ReversibleStateDiff, ValueChange};
+use crate::state::state_api::{State, StateReader};
+use crate::test_utils::{get_test_contract_class, DictStateReader, TEST_CLASS_HASH};
+
+fn change<T>(previous: T, new: T) -> ValueChange<T> {
+    ValueChange { previous, new }
+}
+
+fn felt_change(previous: u8, new: u8) -> ValueChange<StarkFelt> {
+    change(stark_felt!(previous), stark_felt!(new))
+}
+
+fn storage_diff(
+    changes: &[(ContractAddress, &str, ValueChange<StarkFelt>)],
+) -> ReversibleStateDiff {
+    let mut storage_updates = IndexMap::<_, IndexMap<_, _>>::new();
+    for (address, key, change) in changes {
+        storage_updates
+            .entry(*address)
+            .or_default()
+            .insert(StorageKey(patricia_key!(*key)), *change);
+    }
+    ReversibleStateDiff { storage_updates, ..Default::default() }
+}
+
+fn initial_state() -> CachedState<DictStateReader> {
+    let contract_address = contract_address!("0x100");
+    CachedState::from(DictStateReader {
+        storage_view: HashMap::from([
+            ((contract_address, StorageKey(patricia_key!("0x1"))), stark_felt!(1_u8)),
+            ((contract_address, StorageKey(patricia_key!("0x2"))), stark_felt!(2_u8)),
+        ]),
+        address_to_nonce: HashMap::from([(contract_address, Nonce(stark_felt!(5_u8)))]),
+        address_to_class_hash: HashMap::from([(contract_address, class_hash!("0x10"))]),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn from_cached_state() {
+    let mut state = initial_state();
+    let (contract_address, new_contract_address) =
+        (contract_address!("0x100"), contract_address!("0x200"));
+    state.set_storage_at(contract_address, StorageKey(patricia_key!("0x1")), stark_felt!(7_u8));
+    // Unchanged values are not part of the diff.
+    state.set_storage_at(contract_address, StorageKey(patricia_key!("0x2")), stark_felt!(2_u8));
+    state.set_storage_at(new_contract_address, StorageKey(patricia_key!("0x3")), stark_felt!(3_u8));
+    state.increment_nonce(contract_address).unwrap();
+    state.set_class_hash_at(new_contract_address, class_hash!("0x20")).unwrap();
+    state
+        .set_compiled_class_hash(class_hash!("0x20"), CompiledClassHash(stark_felt!("0x21")))
+        .unwrap();
+
+    let state_diff = state.to_reversible_state_diff().unwrap();
+    assert_eq!(
+        state_diff,
+        ReversibleStateDiff {
+            address_to_class_hash: indexmap! {
+                new_contract_address => change(ClassHash::default(), class_hash!("0x20")),
+            },
+            address_to_nonce: indexmap! {
+                contract_address => change(Nonce(stark_felt!(5_u8)), Nonce(stark_felt!(6_u8))),
+            },
+            class_hash_to_compiled_class_hash: indexmap! {
+                class_hash!("0x20") => change(
+                    CompiledClassHash::default(),
+                    CompiledClassHash(stark_felt!("0x21"))
+                ),
+            },
+            ..storage_diff(&[
+                (contract_address, "0x1", felt_change(1, 7)),
+                (new_contract_address, "0x3", felt_change(0, 3)),
+            ])
+        }
+    );
+    assert_eq!(state_diff.to_commitment_state_diff(), state.to_state_diff());
+}
+
+#[test]
+fn apply_and_revert() {
+    let contract_address = contract_address!("0x100");
+    let mut state_diff = storage_diff(&[(contract_address, "0x1", felt_change(1, 7))]);
+    let nonce_change = change(Nonce(stark_felt!(5_u8)), Nonce(stark_felt!(9_u8)));
+    state_diff.address_to_nonce = indexmap! { contract_address => nonce_change };
+    state_diff.address_to_class_hash =
+        indexmap! { contract_address => change(class_hash!("0x10"), class_hash!("0x11")) };
+
+    let mut state = initial_state();
+    state_diff.apply(&mut state).unwrap();
+    assert_eq!(
+        state.get_storage_at(contract_address, StorageKey(patricia_key!("0x1"))).unwrap(),
+        stark_felt!(7_u8)
+    );
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(9_u8)));
+    assert_eq!(state.to_reversible_state_diff().unwrap(), state_diff);
+
+    // Applying the inverse diff restores the initial state.
+    state_diff.inverse().apply(&mut state).unwrap();
+    assert_eq!(state.to_reversible_state_diff().unwrap(), ReversibleStateDiff::default());
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), class_hash!("0x10"));
+
+    // A diff only applies on top of its previous values.
+    let mut state = initial_state();
+    assert_matches!(
+        state_diff.inverse().apply(&mut state).unwrap_err(),
+        StateDiffError::UnexpectedValue(StateCell::Storage((address, _)))
+        if address == contract_address
+    );
+    assert_eq!(state.to_reversible_state_diff().unwrap(), ReversibleStateDiff::default());
+}
+
+#[test]
+fn composition() {
+    let contract_address = contract_address!("0x100");
+    let first_diff = storage_diff(&[
+        (contract_address, "0x1", felt_change(1, 7)),
+        (contract_address, "0x2", felt_change(2, 8)),
+    ]);
+    let second_diff = storage_diff(&[
+        (contract_address, "0x1", felt_change(7, 1)),
+        (contract_address, "0x2", felt_change(8, 9)),
+        (contract_address, "0x3", felt_change(0, 3)),
+    ]);
+
+    // Cells restored to their previous values are dropped.
+    let composed_diff = first_diff.compose(&second_diff).unwrap();
+    assert_eq!(
+        composed_diff,
+        storage_diff(&[
+            (contract_address, "0x2", felt_change(2, 9)),
+            (contract_address, "0x3", felt_change(0, 3)),
+        ])
+    );
+
+    let mut state = initial_state();
+    first_diff.apply(&mut state).unwrap();
+    second_diff.apply(&mut state).unwrap();
+    assert_eq!(state.to_reversible_state_diff().unwrap(), composed_diff);
+
+    assert_eq!(first_diff.compose(&first_diff.inverse()).unwrap(), ReversibleStateDiff::default());
+    assert_matches!(
+        first_diff.compose(&first_diff).unwrap_err(),
+        StateDiffError::InconsistentComposition(StateCell::Storage(_))
+    );
+}
+
+#[test]
+fn failed_apply_leaves_state_unchanged() {
+    let contract_address = contract_address!("0x100");
+    let mut state_diff = storage_diff(&[(contract_address, "0x1", felt_change(1, 7))]);
+    let nonce_change = change(Nonce(stark_felt!(5_u8)), Nonce(stark_felt!(6_u8)));
+    state_diff.address_to_nonce = indexmap! { contract_address => nonce_change };
+    // Rejected by the state.
+    let class_hash_change = change(ClassHash::default(), class_hash!("0x11"));
+    state_diff.address_to_class_hash =
+        indexmap! { ContractAddress::default() => class_hash_change };
+
+    let mut state = initial_state();
+    assert_matches!(
+        state_diff.apply(&mut state).unwrap_err(),
+        StateDiffError::StateError(StateError::OutOfRangeContractAddress)
+    );
+    assert_eq!(
+        state.get_storage_at(contract_address, StorageKey(patricia_key!("0x1"))).unwrap(),
+        stark_felt!(1_u8)
+    );
+    assert_eq!(state.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(5_u8)));
+    assert_eq!(state.to_reversible_state_diff().unwrap(), ReversibleStateDiff::default());
+
+    // A mismatching previous value is detected before anything is written, on any state.
+    let class_hash_change = change(class_hash!("0x12"), class_hash!("0x11"));
+    state_diff.address_to_class_hash = indexmap! { contract_address => class_hash_change };
+    let mut transactional_state = CachedState::create_transactional(&mut state);
+    assert_matches!(
+        state_diff.apply(&mut transactional_state).unwrap_err(),
+        StateDiffError::UnexpectedValue(StateCell::ClassHash(address))
+        if address == contract_address
+    );
+    assert_eq!(
+        transactional_state.get_nonce_at(contract_address).unwrap(),
+        Nonce(stark_felt!(5_u8))
+    );
+    transactional_state.commit();
+    assert_eq!(state.to_reversible_state_diff().unwrap(), ReversibleStateDiff::default());
+}
+
+#[test]
+fn inverse_keeps_declared_classes() {
+    let class_hash = class_hash!(TEST_CLASS_HASH);
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x21"));
+    let mut state = initial_state();
+    state.set_contract_class(&class_hash, get_test_contract_class()).unwrap();
+    state.set_compiled_class_hash(class_hash, compiled_class_hash).unwrap();
+    let state_diff = state.to_reversible_state_diff().unwrap();
+
+    // The compiled class hash is reset, but the class is not undeclared.
+    state_diff.inverse().apply(&mut state).unwrap();
+    assert_eq!(state.get_compiled_class_hash(class_hash).unwrap(), CompiledClassHash::default());
+    assert_eq!(state.get_compiled_contract_class(&class_hash).unwrap(), get_test_contract_class());
+}
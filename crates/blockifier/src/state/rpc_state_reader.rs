@@ -0,0 +1,529 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::Engine;
+use cairo_lang_starknet::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
+use cairo_lang_starknet::contract_class::ContractClass as SierraContractClass;
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use crate::state::cached_state::ContractStorageKey;
+use crate::state::errors::{RpcStateReaderError, StateError};
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "rpc_state_reader_test.rs"]
+mod test;
+
+pub type RpcStateReaderResult<T> = Result<T, RpcStateReaderError>;
+
+// JSON-RPC error codes.
+const METHOD_NOT_FOUND: i64 = -32601;
+const CONTRACT_NOT_FOUND: i64 = 20;
+const CLASS_HASH_NOT_FOUND: i64 = 28;
+
+/// The version prefix of compiled class hashes.
+const COMPILED_CLASS_V1: &[u8] = b"COMPILED_CLASS_V1";
+
+/// The block whose state is read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RpcBlockId {
+    Number(BlockNumber),
+    Hash(BlockHash),
+}
+
+impl RpcBlockId {
+    fn to_json(self) -> Value {
+        match self {
+            RpcBlockId::Number(block_number) => json!({ "block_number": block_number.0 }),
+            RpcBlockId::Hash(block_hash) => json!({ "block_hash": block_hash }),
+        }
+    }
+}
+
+/// Delivers JSON-RPC requests to a node.
+pub trait RpcTransport: Send + Sync {
+    /// Sends the given request body; returns the response body.
+    fn send(&self, request: &str) -> RpcStateReaderResult<String>;
+}
+
+/// Sends requests over HTTP(S).
+#[derive(Clone, Debug)]
+pub struct HttpTransport {
+    client: reqwest::blocking::Client,
+    url: reqwest::Url,
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Expects an `http://` or `https://` URL.
+    pub fn new(url: &str) -> RpcStateReaderResult<Self> {
+        let parsed_url = reqwest::Url::parse(url)
+            .map_err(|_| RpcStateReaderError::InvalidUrl(url.to_string()))?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(RpcStateReaderError::InvalidUrl(url.to_string()));
+        }
+
+        Ok(Self {
+            client: reqwest::blocking::Client::builder().build()?,
+            url: parsed_url,
+            timeout: Self::DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl RpcTransport for HttpTransport {
+    fn send(&self, request: &str) -> RpcStateReaderResult<String> {
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request.to_string())
+            .timeout(self.timeout)
+            .send()?;
+        let status = response.status();
+        let body = response.text()?;
+        if !status.is_success() {
+            return Err(RpcStateReaderError::HttpError { status: status.as_u16(), body });
+        }
+
+        Ok(body)
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    result: Option<Value>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Default)]
+struct RpcCache {
+    storage: HashMap<ContractStorageKey, StarkFelt>,
+    nonces: HashMap<ContractAddress, Nonce>,
+    class_hashes: HashMap<ContractAddress, ClassHash>,
+    contract_classes: HashMap<ClassHash, ContractClass>,
+    // `None` for classes compiled locally.
+    compiled_class_hashes: HashMap<ClassHash, Option<CompiledClassHash>>,
+}
+
+/// Reads the state of a remote node at a fixed block, over Starknet JSON-RPC; e.g., to execute
+/// transactions on top of a live network ("fork mode").
+/// Read values are cached locally; the block must therefore not be reorganized away.
+///
+/// Cairo 1 classes are run as compiled by the network, fetched with `starknet_getCompiledCasm`.
+/// Nodes that do not serve it only provide the Sierra program, which is then compiled locally;
+/// the bundled compiler may differ from the one the network used, and so may the resulting
+/// resources and fees. The compiled class hash of such classes is unavailable.
+pub struct RpcStateReader {
+    transport: Box<dyn RpcTransport>,
+    block_id: RpcBlockId,
+    next_request_id: AtomicU64,
+    cache: Mutex<RpcCache>,
+}
+
+impl RpcStateReader {
+    /// Reads from the node at the given URL, over plain HTTP; see `HttpTransport`.
+    pub fn new(url: &str, block_id: RpcBlockId) -> RpcStateReaderResult<Self> {
+        Ok(Self::with_transport(HttpTransport::new(url)?, block_id))
+    }
+
+    pub fn with_transport(transport: impl RpcTransport + 'static, block_id: RpcBlockId) -> Self {
+        Self {
+            transport: Box::new(transport),
+            block_id,
+            next_request_id: AtomicU64::new(0),
+            cache: Mutex::new(RpcCache::default()),
+        }
+    }
+
+    pub fn block_id(&self) -> RpcBlockId {
+        self.block_id
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> RpcStateReaderResult<T> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_request_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: JsonRpcResponse =
+            serde_json::from_str(&self.transport.send(&request.to_string())?)?;
+
+        match response {
+            JsonRpcResponse { error: Some(JsonRpcError { code, message }), .. } => {
+                Err(RpcStateReaderError::JsonRpcError { code, message })
+            }
+            JsonRpcResponse { result: Some(result), .. } => Ok(serde_json::from_value(result)?),
+            JsonRpcResponse { result: None, error: None } => {
+                Err(RpcStateReaderError::MalformedResponse("missing JSON-RPC result".to_string()))
+            }
+        }
+    }
+
+    /// Like `call`, but returns the given default if the contract is not deployed.
+    fn call_on_contract<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+        default: T,
+    ) -> StateResult<T> {
+        match self.call(method, params) {
+            Err(RpcStateReaderError::JsonRpcError { code: CONTRACT_NOT_FOUND, .. }) => Ok(default),
+            result => Ok(result?),
+        }
+    }
+
+    fn get_cached_or_fetch<K, V>(
+        &self,
+        cached_values: impl Fn(&mut RpcCache) -> &mut HashMap<K, V>,
+        key: K,
+        fetch: impl FnOnce() -> StateResult<V>,
+    ) -> StateResult<V>
+    where
+        K: Eq + Hash,
+        V: Clone,
+    {
+        if let Some(value) = cached_values(&mut self.lock_cache()).get(&key) {
+            return Ok(value.clone());
+        }
+
+        // The cache is not locked while fetching, to allow concurrent requests.
+        let value = fetch()?;
+        cached_values(&mut self.lock_cache()).insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Fetches the given class, along with its compiled class hash (if known); returns `None` if
+    /// the class is not declared. Both are cached.
+    fn fetch_contract_class(
+        &self,
+        class_hash: ClassHash,
+    ) -> StateResult<Option<(ContractClass, Option<CompiledClassHash>)>> {
+        let params = json!({ "block_id": self.block_id.to_json(), "class_hash": class_hash });
+        let raw_contract_class: Value = match self.call("starknet_getClass", params) {
+            Ok(raw_contract_class) => raw_contract_class,
+            Err(RpcStateReaderError::JsonRpcError { code: CLASS_HASH_NOT_FOUND, .. }) => {
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let (contract_class, compiled_class_hash) =
+            if raw_contract_class.get("sierra_program").is_some() {
+                self.fetch_casm_contract_class(class_hash, raw_contract_class)?
+            } else {
+                let contract_class = parse_deprecated_contract_class(raw_contract_class)?;
+                (contract_class, Some(CompiledClassHash::default()))
+            };
+
+        let mut cache = self.lock_cache();
+        cache.contract_classes.insert(class_hash, contract_class.clone());
+        cache.compiled_class_hashes.insert(class_hash, compiled_class_hash);
+        Ok(Some((contract_class, compiled_class_hash)))
+    }
+
+    /// Fetches the CASM of the given Cairo 1 class, as compiled by the network, along with its
+    /// compiled class hash; compiles the given Sierra class locally if the node does not serve it.
+    fn fetch_casm_contract_class(
+        &self,
+        class_hash: ClassHash,
+        raw_contract_class: Value,
+    ) -> RpcStateReaderResult<(ContractClass, Option<CompiledClassHash>)> {
+        match self.call("starknet_getCompiledCasm", json!({ "class_hash": class_hash })) {
+            Ok(raw_casm_contract_class) => {
+                let (contract_class, compiled_class_hash) =
+                    parse_casm_contract_class(raw_casm_contract_class)?;
+                Ok((contract_class, Some(compiled_class_hash)))
+            }
+            Err(RpcStateReaderError::JsonRpcError { code: METHOD_NOT_FOUND, .. }) => {
+                log::warn!(
+                    "The node does not serve compiled classes; compiling {class_hash:?} locally."
+                );
+                Ok((compile_sierra_contract_class(raw_contract_class)?, None))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, RpcCache> {
+        self.cache.lock().expect("Failed to lock RPC state reader cache.")
+    }
+}
+
+impl SharedStateReader for RpcStateReader {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.get_cached_or_fetch(
+            |cache| &mut cache.storage,
+            (contract_address, key),
+            || {
+                let params = json!({
+                    "contract_address": contract_address,
+                    "key": key,
+                    "block_id": self.block_id.to_json(),
+                });
+                self.call_on_contract("starknet_getStorageAt", params, StarkFelt::default())
+            },
+        )
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.get_cached_or_fetch(
+            |cache| &mut cache.nonces,
+            contract_address,
+            || {
+                let params = json!({
+                    "block_id": self.block_id.to_json(),
+                    "contract_address": contract_address,
+                });
+                self.call_on_contract("starknet_getNonce", params, Nonce::default())
+            },
+        )
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.get_cached_or_fetch(
+            |cache| &mut cache.class_hashes,
+            contract_address,
+            || {
+                let params = json!({
+                    "block_id": self.block_id.to_json(),
+                    "contract_address": contract_address,
+                });
+                self.call_on_contract("starknet_getClassHashAt", params, ClassHash::default())
+            },
+        )
+    }
+
+    /// Cairo 1 classes are compiled locally if the node does not serve their CASM.
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
+        self.get_cached_or_fetch(
+            |cache| &mut cache.contract_classes,
+            *class_hash,
+            || match self.fetch_contract_class(*class_hash)? {
+                Some((contract_class, _)) => Ok(contract_class),
+                None => Err(StateError::UndeclaredClassHash(*class_hash)),
+            },
+        )
+    }
+
+    /// Not served over JSON-RPC; computed from the class, as compiled by the network. Deprecated
+    /// and undeclared classes have the default value.
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        let compiled_class_hash = self.get_cached_or_fetch(
+            |cache| &mut cache.compiled_class_hashes,
+            class_hash,
+            || match self.fetch_contract_class(class_hash)? {
+                Some((_, compiled_class_hash)) => Ok(compiled_class_hash),
+                None => Ok(Some(CompiledClassHash::default())),
+            },
+        )?;
+        Ok(compiled_class_hash
+            .ok_or(RpcStateReaderError::CompiledClassHashUnavailable(class_hash))?)
+    }
+}
+
+impl StateReader for RpcStateReader {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        SharedStateReader::get_storage_at(self, contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        SharedStateReader::get_nonce_at(self, contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        SharedStateReader::get_class_hash_at(self, contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        SharedStateReader::get_compiled_contract_class(self, class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        SharedStateReader::get_compiled_class_hash(self, class_hash)
+    }
+}
+
+/// Converts a deprecated class, as returned by `starknet_getClass`, to a runnable class.
+fn parse_deprecated_contract_class(
+    mut raw_contract_class: Value,
+) -> RpcStateReaderResult<ContractClass> {
+    // The program of a Cairo 0 class is gzipped, then base64-encoded.
+    let encoded_program = raw_contract_class["program"].as_str().ok_or_else(|| {
+        RpcStateReaderError::MalformedResponse("missing Cairo 0 program".to_string())
+    })?;
+    let compressed_program = base64::engine::general_purpose::STANDARD.decode(encoded_program)?;
+    let mut program = String::new();
+    GzDecoder::new(compressed_program.as_slice()).read_to_string(&mut program)?;
+
+    let contract_class = DeprecatedContractClass {
+        abi: None,
+        program: serde_json::from_str(&program)?,
+        entry_points_by_type: serde_json::from_value(
+            raw_contract_class["entry_points_by_type"].take(),
+        )?,
+    };
+    Ok(ContractClassV0::try_from(contract_class)?.into())
+}
+
+/// Converts a compiled class, as returned by `starknet_getCompiledCasm`, to a runnable class,
+/// along with its compiled class hash.
+fn parse_casm_contract_class(
+    mut raw_casm_contract_class: Value,
+) -> RpcStateReaderResult<(ContractClass, CompiledClassHash)> {
+    // Not part of the compiled class type of this compiler version.
+    let bytecode_segment_lengths: Option<BytecodeSegmentLengths> =
+        serde_json::from_value(raw_casm_contract_class["bytecode_segment_lengths"].take())?;
+    let casm_contract_class: CasmContractClass = serde_json::from_value(raw_casm_contract_class)?;
+    let compiled_class_hash =
+        compiled_class_hash(&casm_contract_class, bytecode_segment_lengths.as_ref())?;
+    Ok((ContractClassV1::try_from(casm_contract_class)?.into(), compiled_class_hash))
+}
+
+/// Compiles a Cairo 1 class, as returned by `starknet_getClass`, to a runnable class.
+fn compile_sierra_contract_class(
+    mut raw_contract_class: Value,
+) -> RpcStateReaderResult<ContractClass> {
+    // Served as a string, and not needed for execution.
+    raw_contract_class["abi"] = Value::Null;
+    let sierra_contract_class: SierraContractClass = serde_json::from_value(raw_contract_class)?;
+    let casm_contract_class = CasmContractClass::from_contract_class(sierra_contract_class, false)?;
+    Ok(ContractClassV1::try_from(casm_contract_class)?.into())
+}
+
+/// The lengths of the bytecode segments of a compiled class, which determine how its bytecode is
+/// hashed.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BytecodeSegmentLengths {
+    Leaf(usize),
+    Node(Vec<BytecodeSegmentLengths>),
+}
+
+/// Returns the hash of a compiled class: `poseidon("COMPILED_CLASS_V1", external, l1_handler,
+/// constructor, poseidon(bytecode))`, where each list of entry points is hashed as
+/// `poseidon(selector_0, offset_0, poseidon(builtins_0), selector_1, ...)`.
+/// Segmented bytecode is hashed as `1 + poseidon(length_0, hash_0, length_1, ...)`, recursively.
+fn compiled_class_hash(
+    casm_contract_class: &CasmContractClass,
+    bytecode_segment_lengths: Option<&BytecodeSegmentLengths>,
+) -> RpcStateReaderResult<CompiledClassHash> {
+    let to_field_element = |bytes: &[u8]| {
+        FieldElement::from_byte_slice_be(bytes).map_err(|_| {
+            RpcStateReaderError::MalformedResponse("compiled class value out of range".to_string())
+        })
+    };
+    let hash_entry_points = |entry_points: &[CasmContractEntryPoint]| {
+        let mut hashed_data = Vec::with_capacity(3 * entry_points.len());
+        for entry_point in entry_points {
+            let builtins = entry_point
+                .builtins
+                .iter()
+                .map(|builtin| to_field_element(builtin.as_bytes()))
+                .collect::<RpcStateReaderResult<Vec<_>>>()?;
+            hashed_data.push(to_field_element(&entry_point.selector.to_bytes_be())?);
+            hashed_data.push(FieldElement::from(entry_point.offset));
+            hashed_data.push(poseidon_hash_many(&builtins));
+        }
+        Ok::<_, RpcStateReaderError>(poseidon_hash_many(&hashed_data))
+    };
+
+    let bytecode = casm_contract_class
+        .bytecode
+        .iter()
+        .map(|felt| to_field_element(&felt.value.to_bytes_be()))
+        .collect::<RpcStateReaderResult<Vec<_>>>()?;
+    let entry_points = &casm_contract_class.entry_points_by_type;
+    let hash = poseidon_hash_many(&[
+        to_field_element(COMPILED_CLASS_V1)?,
+        hash_entry_points(&entry_points.external)?,
+        hash_entry_points(&entry_points.l1_handler)?,
+        hash_entry_points(&entry_points.constructor)?,
+        bytecode_hash(&bytecode, bytecode_segment_lengths)?,
+    ]);
+    Ok(CompiledClassHash(hash.into()))
+}
+
+fn bytecode_hash(
+    bytecode: &[FieldElement],
+    bytecode_segment_lengths: Option<&BytecodeSegmentLengths>,
+) -> RpcStateReaderResult<FieldElement> {
+    let Some(bytecode_segment_lengths) = bytecode_segment_lengths else {
+        return Ok(poseidon_hash_many(bytecode));
+    };
+
+    let (hash, hashed_length) = segment_hash(bytecode, bytecode_segment_lengths)?;
+    if hashed_length != bytecode.len() {
+        return Err(RpcStateReaderError::MalformedResponse(
+            "bytecode segment lengths do not match the bytecode".to_string(),
+        ));
+    }
+    Ok(hash)
+}
+
+/// Returns the hash of the segment at the start of the given bytecode, and its length.
+fn segment_hash(
+    bytecode: &[FieldElement],
+    segment_lengths: &BytecodeSegmentLengths,
+) -> RpcStateReaderResult<(FieldElement, usize)> {
+    match segment_lengths {
+        BytecodeSegmentLengths::Leaf(length) => {
+            let segment = bytecode.get(..*length).ok_or_else(|| {
+                RpcStateReaderError::MalformedResponse(
+                    "bytecode segment lengths exceed the bytecode".to_string(),
+                )
+            })?;
+            Ok((poseidon_hash_many(segment), *length))
+        }
+        BytecodeSegmentLengths::Node(inner_segment_lengths) => {
+            let mut hashed_data = Vec::with_capacity(2 * inner_segment_lengths.len());
+            let mut offset = 0;
+            for segment_lengths in inner_segment_lengths {
+                let (hash, length) = segment_hash(&bytecode[offset..], segment_lengths)?;
+                hashed_data.push(FieldElement::from(length));
+                hashed_data.push(hash);
+                offset += length;
+            }
+            Ok((poseidon_hash_many(&hashed_data) + FieldElement::ONE, offset))
+        }
+    }
+}
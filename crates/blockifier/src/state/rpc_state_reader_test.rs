@@ -0,0 +1,343 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use assert_matches::assert_matches;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Calldata;
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::execution::contract_class::{ContractClass, ContractClassV0};
+use crate::execution::entry_point::{CallEntryPoint, CallExecution, Retdata};
+use crate::retdata;
+use crate::state::cached_state::CachedState;
+use crate::state::errors::{RpcStateReaderError, StateError};
+use crate::state::rpc_state_reader::{
+    compiled_class_hash, BytecodeSegmentLengths, HttpTransport, RpcBlockId, RpcStateReader,
+    RpcTransport,
+};
+use crate::state::state_api::StateReader;
+use crate::test_utils::{
+    get_raw_contract_class, trivial_external_entry_point, TEST_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+    TEST_CONTRACT_CAIRO0_PATH,
+};
+
+type RpcResponse = Result<Value, (i64, &'static str)>;
+
+/// A JSON-RPC node on a local port, answering each request with the given handler.
+/// Records the received requests.
+struct MockNode {
+    url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockNode {
+    fn start(handler: impl Fn(&str, &Value) -> RpcResponse + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/rpc/v0.4", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded_requests = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let request = read_http_request(&mut stream);
+                let response =
+                    match handler(request["method"].as_str().unwrap(), &request["params"]) {
+                        Ok(result) => {
+                            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                        }
+                        Err((code, message)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": { "code": code, "message": message },
+                        }),
+                    }
+                    .to_string();
+                recorded_requests.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: \
+                     {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                )
+                .unwrap();
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    fn methods(&self) -> Vec<String> {
+        let requests = self.requests.lock().unwrap();
+        requests.iter().map(|request| request["method"].as_str().unwrap().to_string()).collect()
+    }
+}
+
+fn read_http_request(stream: &mut impl Read) -> Value {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            break;
+        }
+        if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = length.trim().parse().unwrap();
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// A compiled class, as served by `starknet_getCompiledCasm`.
+fn rpc_casm_contract_class() -> Value {
+    json!({
+        "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+        "compiler_version": "2.1.0",
+        "bytecode": ["0x7", "0x8"],
+        "hints": [],
+        "entry_points_by_type": {
+            "EXTERNAL": [{ "selector": "0x10", "offset": 0, "builtins": ["range_check"] }],
+            "L1_HANDLER": [],
+            "CONSTRUCTOR": [],
+        },
+    })
+}
+
+/// The test contract, as served by `starknet_getClass`.
+fn rpc_test_contract_class() -> Value {
+    let mut contract_class: Value =
+        serde_json::from_str(&get_raw_contract_class(TEST_CONTRACT_CAIRO0_PATH)).unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contract_class["program"].to_string().as_bytes()).unwrap();
+    let program = base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap());
+
+    json!({
+        "program": program,
+        "entry_points_by_type": contract_class["entry_points_by_type"].take(),
+        "abi": contract_class["abi"].take(),
+    })
+}
+
+#[test]
+fn reads_are_fetched_once() {
+    let node = MockNode::start(|method, params| {
+        assert_eq!(params["block_id"], json!({ "block_number": 7 }));
+        match method {
+            "starknet_getStorageAt" => Ok(json!("0x12")),
+            "starknet_getNonce" => Ok(json!("0x3")),
+            "starknet_getClassHashAt" => Ok(json!(TEST_CLASS_HASH)),
+            _ => panic!("Unexpected method {method}."),
+        }
+    });
+    let mut reader = RpcStateReader::new(&node.url, RpcBlockId::Number(BlockNumber(7))).unwrap();
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+
+    for _ in 0..2 {
+        assert_eq!(reader.get_storage_at(contract_address, key).unwrap(), stark_felt!("0x12"));
+        assert_eq!(reader.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(3_u8)));
+        assert_eq!(
+            reader.get_class_hash_at(contract_address).unwrap(),
+            class_hash!(TEST_CLASS_HASH)
+        );
+    }
+    assert_eq!(
+        node.methods(),
+        ["starknet_getStorageAt", "starknet_getNonce", "starknet_getClassHashAt"]
+    );
+    assert_eq!(
+        node.requests.lock().unwrap()[0]["params"]["key"],
+        serde_json::to_value(key).unwrap()
+    );
+}
+
+#[test]
+fn missing_state() {
+    let node = MockNode::start(|method, _params| match method {
+        "starknet_getClass" => Err((28, "Class hash not found")),
+        "starknet_getStorageAt" => Err((24, "Block not found")),
+        _ => Err((20, "Contract not found")),
+    });
+    let mut reader = RpcStateReader::new(&node.url, RpcBlockId::Number(BlockNumber(7))).unwrap();
+    let contract_address = contract_address!("0x100");
+
+    // Undeployed contracts have default values.
+    assert_eq!(reader.get_nonce_at(contract_address).unwrap(), Nonce::default());
+    assert_eq!(reader.get_class_hash_at(contract_address).unwrap(), ClassHash::default());
+
+    let class_hash = class_hash!("0x1");
+    assert_matches!(
+        reader.get_compiled_contract_class(&class_hash).unwrap_err(),
+        StateError::UndeclaredClassHash(undeclared_class_hash)
+        if undeclared_class_hash == class_hash
+    );
+    assert_eq!(reader.get_compiled_class_hash(class_hash).unwrap(), CompiledClassHash::default());
+    assert_matches!(
+        reader.get_storage_at(contract_address, StorageKey::default()).unwrap_err(),
+        StateError::StateReadError(message) if message.contains("Block not found")
+    );
+}
+
+#[test]
+fn executes_on_remote_state() {
+    let node = MockNode::start(|method, params| match method {
+        "starknet_getClassHashAt" => Ok(json!(TEST_CLASS_HASH)),
+        "starknet_getClass" => {
+            assert_eq!(params["class_hash"], json!(TEST_CLASS_HASH));
+            Ok(rpc_test_contract_class())
+        }
+        _ => Ok(json!("0x0")),
+    });
+    let reader = RpcStateReader::new(&node.url, RpcBlockId::Number(BlockNumber(7))).unwrap();
+    let mut state = CachedState::from(reader);
+
+    let expected_contract_class: ContractClass =
+        ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into();
+    assert_eq!(
+        state.get_compiled_contract_class(&class_hash!(TEST_CLASS_HASH)).unwrap(),
+        expected_contract_class
+    );
+    // Deprecated classes have no compiled class hash.
+    assert_eq!(
+        state.get_compiled_class_hash(class_hash!(TEST_CLASS_HASH)).unwrap(),
+        CompiledClassHash::default()
+    );
+
+    let (key, value) = (stark_felt!(1234_u16), stark_felt!(18_u8));
+    let entry_point_call = CallEntryPoint {
+        calldata: calldata![key, value],
+        entry_point_selector: selector_from_name("test_storage_read_write"),
+        ..trivial_external_entry_point()
+    };
+    assert_eq!(
+        entry_point_call.execute_directly(&mut state).unwrap().execution,
+        CallExecution::from_retdata(retdata![value])
+    );
+}
+
+#[test]
+fn fetches_compiled_classes() {
+    let node = MockNode::start(|method, _params| match method {
+        "starknet_getClass" => Ok(json!({
+            "sierra_program": [],
+            "contract_class_version": "0.1.0",
+            "entry_points_by_type": { "EXTERNAL": [], "L1_HANDLER": [], "CONSTRUCTOR": [] },
+            "abi": "[]",
+        })),
+        "starknet_getCompiledCasm" => Ok(rpc_casm_contract_class()),
+        _ => panic!("Unexpected method {method}."),
+    });
+    let mut reader = RpcStateReader::new(&node.url, RpcBlockId::Number(BlockNumber(7))).unwrap();
+    let class_hash = class_hash!("0x1");
+
+    let casm_contract_class = serde_json::from_value(rpc_casm_contract_class()).unwrap();
+    assert_eq!(
+        reader.get_compiled_class_hash(class_hash).unwrap(),
+        compiled_class_hash(&casm_contract_class, None).unwrap()
+    );
+    assert_matches!(reader.get_compiled_contract_class(&class_hash).unwrap(), ContractClass::V1(_));
+    assert_eq!(node.methods(), ["starknet_getClass", "starknet_getCompiledCasm"]);
+}
+
+#[test]
+fn http_errors() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let mut stream = listener.incoming().next().unwrap().unwrap();
+        read_http_request(&mut stream);
+        write!(
+            stream,
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\nConnection: \
+             close\r\n\r\nbusy"
+        )
+        .unwrap();
+    });
+    let transport = HttpTransport::new(&url).unwrap();
+    assert_matches!(
+        transport.send("{}").unwrap_err(),
+        RpcStateReaderError::HttpError { status: 503, body } if body == "busy"
+    );
+
+    assert_matches!(
+        HttpTransport::new("ftp://example.com").unwrap_err(),
+        RpcStateReaderError::InvalidUrl(_)
+    );
+}
+
+#[test]
+fn compiled_class_hash_of_casm() {
+    let casm_contract_class = serde_json::from_value(json!({
+        "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+        "compiler_version": "2.1.0",
+        "bytecode": ["0x7", "0x8"],
+        "hints": [],
+        "entry_points_by_type": {
+            "EXTERNAL": [{ "selector": "0x10", "offset": 3, "builtins": ["range_check"] }],
+            "L1_HANDLER": [],
+            "CONSTRUCTOR": [],
+        },
+    }))
+    .unwrap();
+
+    let short_string = |string: &str| FieldElement::from_byte_slice_be(string.as_bytes()).unwrap();
+    let no_entry_points_hash = poseidon_hash_many(&[]);
+    let expected_hash = poseidon_hash_many(&[
+        short_string("COMPILED_CLASS_V1"),
+        poseidon_hash_many(&[
+            FieldElement::from(0x10_u8),
+            FieldElement::from(3_u8),
+            poseidon_hash_many(&[short_string("range_check")]),
+        ]),
+        no_entry_points_hash,
+        no_entry_points_hash,
+        poseidon_hash_many(&[FieldElement::from(7_u8), FieldElement::from(8_u8)]),
+    ]);
+    assert_eq!(
+        compiled_class_hash(&casm_contract_class, None).unwrap(),
+        CompiledClassHash(expected_hash.into())
+    );
+
+    // Segmented bytecode.
+    let bytecode_segment_lengths = BytecodeSegmentLengths::Node(vec![
+        BytecodeSegmentLengths::Leaf(1),
+        BytecodeSegmentLengths::Node(vec![BytecodeSegmentLengths::Leaf(1)]),
+    ]);
+    let (first_segment, second_segment) = (FieldElement::from(7_u8), FieldElement::from(8_u8));
+    let one = FieldElement::ONE;
+    let second_segment_hash =
+        poseidon_hash_many(&[one, poseidon_hash_many(&[second_segment])]) + one;
+    let expected_bytecode_hash =
+        poseidon_hash_many(&[one, poseidon_hash_many(&[first_segment]), one, second_segment_hash])
+            + one;
+    let expected_hash = poseidon_hash_many(&[
+        short_string("COMPILED_CLASS_V1"),
+        poseidon_hash_many(&[
+            FieldElement::from(0x10_u8),
+            FieldElement::from(3_u8),
+            poseidon_hash_many(&[short_string("range_check")]),
+        ]),
+        no_entry_points_hash,
+        no_entry_points_hash,
+        expected_bytecode_hash,
+    ]);
+    assert_eq!(
+        compiled_class_hash(&casm_contract_class, Some(&bytecode_segment_lengths)).unwrap(),
+        CompiledClassHash(expected_hash.into())
+    );
+}
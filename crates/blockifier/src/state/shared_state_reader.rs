@@ -0,0 +1,130 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::execution::contract_class::ContractClass;
+use crate::state::cached_state::ContractStorageKey;
+use crate::state::state_api::{SharedStateReader, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "shared_state_reader_test.rs"]
+mod test;
+
+/// Adapts a shared reader, held by reference or by a (smart) pointer, into a `StateReader`.
+#[derive(Clone, Debug)]
+pub struct SharedStateReaderAdapter<P>(pub P);
+
+/// A state snapshot that can be read from many threads at once; e.g., by a `CachedState` per
+/// thread. Cloning it is cheap.
+pub type StateSnapshot<R> = SharedStateReaderAdapter<Arc<R>>;
+
+impl<R: SharedStateReader> StateSnapshot<R> {
+    pub fn new(reader: R) -> Self {
+        Self(Arc::new(reader))
+    }
+}
+
+impl<P> StateReader for SharedStateReaderAdapter<P>
+where
+    P: Deref,
+    P::Target: SharedStateReader,
+{
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.0.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.0.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.0.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        self.0.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.0.get_compiled_class_hash(class_hash)
+    }
+
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        self.0.get_storage_batch(contract_storage_keys)
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        self.0.get_nonces_batch(contract_addresses)
+    }
+
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        self.0.get_class_hashes_batch(contract_addresses)
+    }
+}
+
+impl<P> SharedStateReader for SharedStateReaderAdapter<P>
+where
+    P: Deref + Send + Sync,
+    P::Target: SharedStateReader,
+{
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        self.0.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        self.0.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        self.0.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
+        self.0.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.0.get_compiled_class_hash(class_hash)
+    }
+
+    fn get_storage_batch(
+        &self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        self.0.get_storage_batch(contract_storage_keys)
+    }
+
+    fn get_nonces_batch(&self, contract_addresses: &[ContractAddress]) -> StateResult<Vec<Nonce>> {
+        self.0.get_nonces_batch(contract_addresses)
+    }
+
+    fn get_class_hashes_batch(
+        &self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        self.0.get_class_hashes_batch(contract_addresses)
+    }
+}
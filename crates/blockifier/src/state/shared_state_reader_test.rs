@@ -0,0 +1,80 @@
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, Fee};
+use starknet_api::{calldata, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::selector_from_name;
+use crate::block_context::BlockContext;
+use crate::state::cached_state::CachedState;
+use crate::state::shared_state_reader::{SharedStateReaderAdapter, StateSnapshot};
+use crate::state::state_api::{State, StateReader};
+use crate::test_utils::{
+    DictStateReader, NonceManager, MAX_FEE, TEST_ACCOUNT_CONTRACT_ADDRESS, TEST_CONTRACT_ADDRESS,
+};
+use crate::transaction::test_utils::{
+    account_invoke_tx, create_state_with_trivial_validation_account,
+};
+use crate::transaction::transactions::ExecutableTransaction;
+
+#[test]
+fn adapter_reads_through() {
+    let contract_address = contract_address!("0x100");
+    let key = StorageKey(patricia_key!("0x10"));
+    let dict_reader = DictStateReader {
+        storage_view: [((contract_address, key), stark_felt!(7_u8))].into(),
+        ..Default::default()
+    };
+
+    let mut state = CachedState::from(SharedStateReaderAdapter(&dict_reader));
+    state.set_storage_at(contract_address, key, stark_felt!(8_u8));
+    assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!(8_u8));
+    // The shared reader is left untouched.
+    assert_eq!(dict_reader.storage_view[&(contract_address, key)], stark_felt!(7_u8));
+}
+
+#[test]
+fn concurrent_executions_on_snapshot() {
+    let block_context = BlockContext::create_for_account_testing();
+    let snapshot = StateSnapshot::new(create_state_with_trivial_validation_account().state);
+    let account_address = contract_address!(TEST_ACCOUNT_CONTRACT_ADDRESS);
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+
+    // Each thread executes its own transaction, using the same nonce, against the snapshot.
+    let results: Vec<(Fee, _)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4_u8)
+            .map(|i| {
+                let mut state = CachedState::from(snapshot.clone());
+                let block_context = &block_context;
+                scope.spawn(move || {
+                    let execute_calldata = calldata![
+                        *contract_address.0.key(),                       // Contract address.
+                        selector_from_name("test_storage_read_write").0, // EP selector.
+                        stark_felt!(2_u8),                               // Calldata length.
+                        stark_felt!(i),                                  // Calldata: address.
+                        stark_felt!(i + 1)                               // Calldata: value.
+                    ];
+                    let account_tx = account_invoke_tx(
+                        execute_calldata,
+                        account_address,
+                        &mut NonceManager::default(),
+                        Fee(MAX_FEE),
+                    );
+                    let execution_info =
+                        account_tx.execute(&mut state, block_context, true, true).unwrap();
+                    (execution_info.actual_fee, state)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let expected_fee = results[0].0;
+    for (i, (actual_fee, mut state)) in (0..4_u8).zip(results) {
+        assert_eq!(actual_fee, expected_fee);
+        let key = StorageKey(patricia_key!(i));
+        assert_eq!(state.get_storage_at(contract_address, key).unwrap(), stark_felt!(i + 1));
+        assert!(!snapshot.0.storage_view.contains_key(&(contract_address, key)));
+    }
+}
@@ -3,10 +3,10 @@ use starknet_api::hash::StarkFelt;
 use starknet_api::state::StorageKey;
 
 use crate::abi::abi_utils::get_erc20_balance_var_addresses;
-use crate::block_context::BlockContext;
 use crate::execution::contract_class::ContractClass;
-use crate::state::cached_state::CommitmentStateDiff;
+use crate::state::cached_state::{CommitmentStateDiff, ContractStorageKey};
 use crate::state::errors::StateError;
+use crate::state::storage_value::StorageValue;
 
 pub type StateResult<T> = Result<T, StateError>;
 
@@ -39,20 +39,118 @@ pub trait StateReader {
     /// Returns the compiled class hash of the given class hash.
     fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash>;
 
-    /// Returns the storage value representing the balance (in fee token) at the given address.
+    /// Returns the storage values under the given keys (see `get_storage_at`), in the same
+    /// order. Readers backed by a database should override it to read in bulk.
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        contract_storage_keys
+            .iter()
+            .map(|&(contract_address, key)| self.get_storage_at(contract_address, key))
+            .collect()
+    }
+
+    /// Returns the nonces of the given contract instances (see `get_nonce_at`), in the same
+    /// order.
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_nonce_at(contract_address))
+            .collect()
+    }
+
+    /// Returns the class hashes of the given contract instances (see `get_class_hash_at`), in the
+    /// same order.
+    fn get_class_hashes_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_class_hash_at(contract_address))
+            .collect()
+    }
+
+    /// Returns the storage value representing the balance (in the given fee token) at the given
+    /// address.
     // TODO(Dori, 1/7/2023): When a standard representation for large integers is set, change the
     //    return type to that.
     fn get_fee_token_balance(
         &mut self,
-        block_context: &BlockContext,
         contract_address: &ContractAddress,
+        fee_token_address: &ContractAddress,
     ) -> Result<(StarkFelt, StarkFelt), StateError> {
         let (low_key, high_key) = get_erc20_balance_var_addresses(contract_address)?;
-        let low = self.get_storage_at(block_context.fee_token_address, low_key)?;
-        let high = self.get_storage_at(block_context.fee_token_address, high_key)?;
+        let low = self.get_storage_at(*fee_token_address, low_key)?;
+        let high = self.get_storage_at(*fee_token_address, high_key)?;
 
         Ok((low, high))
     }
+
+    /// Returns the value of the given type laid out from the given address in the storage of the
+    /// given (Cairo 1) contract instance.
+    fn read_storage_value<T: StorageValue>(
+        &mut self,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<T>
+    where
+        Self: Sized,
+    {
+        T::read(self, contract_address, address)
+    }
+}
+
+/// A read-only API for accessing StarkNet global state, which can be shared between threads; see
+/// `StateReader` for the semantics of each method.
+///
+/// Implementations read through `&self`, so that a single reader can serve many concurrent
+/// executions (e.g., through a `StateSnapshot`).
+pub trait SharedStateReader: Send + Sync {
+    fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt>;
+
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce>;
+
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash>;
+
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass>;
+
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash>;
+
+    fn get_storage_batch(
+        &self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        contract_storage_keys
+            .iter()
+            .map(|&(contract_address, key)| self.get_storage_at(contract_address, key))
+            .collect()
+    }
+
+    fn get_nonces_batch(&self, contract_addresses: &[ContractAddress]) -> StateResult<Vec<Nonce>> {
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_nonce_at(contract_address))
+            .collect()
+    }
+
+    fn get_class_hashes_batch(
+        &self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        contract_addresses
+            .iter()
+            .map(|&contract_address| self.get_class_hash_at(contract_address))
+            .collect()
+    }
 }
 
 /// A class defining the API for writing to StarkNet global state.
@@ -71,6 +169,9 @@ pub trait State: StateReader {
     /// Increments the nonce of the given contract instance.
     fn increment_nonce(&mut self, contract_address: ContractAddress) -> StateResult<()>;
 
+    /// Sets the nonce of the given contract instance (e.g., when reverting changes).
+    fn set_nonce_at(&mut self, contract_address: ContractAddress, nonce: Nonce) -> StateResult<()>;
+
     /// Allocates the given address to the given class hash.
     /// Raises an exception if the address is already assigned;
     /// meaning: this is a write once action.
@@ -95,4 +196,18 @@ pub trait State: StateReader {
     ) -> StateResult<()>;
 
     fn to_state_diff(&self) -> CommitmentStateDiff;
+
+    /// Lays out the given value from the given address in the storage of the given (Cairo 1)
+    /// contract instance.
+    fn write_storage_value<T: StorageValue>(
+        &mut self,
+        contract_address: ContractAddress,
+        address: StorageKey,
+        value: &T,
+    ) -> StateResult<()>
+    where
+        Self: Sized,
+    {
+        value.write(self, contract_address, address)
+    }
 }
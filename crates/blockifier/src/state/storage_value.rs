@@ -0,0 +1,280 @@
+use std::cmp::min;
+
+use num_bigint::BigUint;
+use starknet_api::core::{ClassHash, ContractAddress};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::StarknetApiError;
+
+use crate::abi::abi_utils::{get_byte_array_chunk_address, get_storage_address_at_offset};
+use crate::abi::constants::{BYTES_IN_BYTES31, BYTE_ARRAY_CHUNK_SIZE};
+use crate::state::errors::StateError;
+use crate::state::state_api::{State, StateReader, StateResult};
+
+#[cfg(test)]
+#[path = "storage_value_test.rs"]
+mod test;
+
+/// A value in the storage of a Cairo 1 contract, laid out from its address as by the `Store`
+/// implementations of the Cairo core library. See `abi_utils` for deriving the addresses of
+/// storage variables, map entries and struct members.
+pub trait StorageValue: Sized {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self>;
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()>;
+}
+
+/// A `u256`, stored as its low and high 128-bit words at consecutive addresses.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct U256 {
+    pub low: u128,
+    pub high: u128,
+}
+
+impl From<u128> for U256 {
+    fn from(low: u128) -> Self {
+        Self { low, high: 0 }
+    }
+}
+
+impl From<U256> for BigUint {
+    fn from(value: U256) -> Self {
+        (BigUint::from(value.high) << 128_usize) + value.low
+    }
+}
+
+/// A Cairo `ByteArray`; stored as its length in bytes, followed by its 31-byte words, in chunks of
+/// 256 words at addresses derived from the address of the byte array.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ByteArray(pub Vec<u8>);
+
+impl From<&str> for ByteArray {
+    fn from(value: &str) -> Self {
+        Self(value.as_bytes().to_vec())
+    }
+}
+
+impl StorageValue for StarkFelt {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        state.get_storage_at(contract_address, address)
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        state.set_storage_at(contract_address, address, *self);
+        Ok(())
+    }
+}
+
+impl StorageValue for bool {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        read_converted(state, contract_address, address, "bool", |value| {
+            match felt_to_u128(value)? {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None,
+            }
+        })
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        StarkFelt::from(u8::from(*self)).write(state, contract_address, address)
+    }
+}
+
+macro_rules! impl_storage_value_for_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl StorageValue for $uint {
+                fn read<S: StateReader + ?Sized>(
+                    state: &mut S,
+                    contract_address: ContractAddress,
+                    address: StorageKey,
+                ) -> StateResult<Self> {
+                    read_converted(state, contract_address, address, stringify!($uint), |value| {
+                        <$uint>::try_from(felt_to_u128(value)?).ok()
+                    })
+                }
+
+                fn write<S: State + ?Sized>(
+                    &self,
+                    state: &mut S,
+                    contract_address: ContractAddress,
+                    address: StorageKey,
+                ) -> StateResult<()> {
+                    StarkFelt::from(*self).write(state, contract_address, address)
+                }
+            }
+        )*
+    };
+}
+
+impl_storage_value_for_uint!(u8, u16, u32, u64, u128);
+
+impl StorageValue for ContractAddress {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        read_converted(state, contract_address, address, "ContractAddress", |value| {
+            ContractAddress::try_from(value).ok()
+        })
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        self.0.key().write(state, contract_address, address)
+    }
+}
+
+impl StorageValue for ClassHash {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        Ok(ClassHash(StarkFelt::read(state, contract_address, address)?))
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        self.0.write(state, contract_address, address)
+    }
+}
+
+impl StorageValue for U256 {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        let high_address = get_storage_address_at_offset(address, 1)?;
+        Ok(Self {
+            low: u128::read(state, contract_address, address)?,
+            high: u128::read(state, contract_address, high_address)?,
+        })
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        let high_address = get_storage_address_at_offset(address, 1)?;
+        self.low.write(state, contract_address, address)?;
+        self.high.write(state, contract_address, high_address)
+    }
+}
+
+impl StorageValue for ByteArray {
+    fn read<S: StateReader + ?Sized>(
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<Self> {
+        let len = usize::try_from(u32::read(state, contract_address, address)?)
+            .expect("Conversion to usize should not fail.");
+        let mut bytes = Vec::with_capacity(len);
+        for word_index in 0..(len + BYTES_IN_BYTES31 - 1) / BYTES_IN_BYTES31 {
+            let word_len = min(BYTES_IN_BYTES31, len - word_index * BYTES_IN_BYTES31);
+            let word_address = get_byte_array_word_address(address, word_index)?;
+            let word = read_converted(state, contract_address, word_address, "bytes31", |value| {
+                let (padding, word) = value.bytes().split_at(value.bytes().len() - word_len);
+                padding.iter().all(|&byte| byte == 0).then(|| word.to_vec())
+            })?;
+            bytes.extend(word);
+        }
+
+        Ok(Self(bytes))
+    }
+
+    fn write<S: State + ?Sized>(
+        &self,
+        state: &mut S,
+        contract_address: ContractAddress,
+        address: StorageKey,
+    ) -> StateResult<()> {
+        let len = u32::try_from(self.0.len()).map_err(|_| StarknetApiError::OutOfRange {
+            string: format!("byte array length {}", self.0.len()),
+        })?;
+        len.write(state, contract_address, address)?;
+        for (word_index, word) in self.0.chunks(BYTES_IN_BYTES31).enumerate() {
+            let mut word_bytes = [0_u8; 32];
+            word_bytes[32 - word.len()..].copy_from_slice(word);
+            let word_address = get_byte_array_word_address(address, word_index)?;
+            StarkFelt::new(word_bytes)?.write(state, contract_address, word_address)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the address of the given word of a `ByteArray` stored at the given address.
+fn get_byte_array_word_address(address: StorageKey, word_index: usize) -> StateResult<StorageKey> {
+    let chunk = u64::try_from(word_index / BYTE_ARRAY_CHUNK_SIZE)
+        .expect("Conversion to u64 should not fail.");
+    let offset =
+        u8::try_from(word_index % BYTE_ARRAY_CHUNK_SIZE).expect("A chunk offset should fit in u8.");
+    Ok(get_storage_address_at_offset(get_byte_array_chunk_address(address, chunk)?, offset)?)
+}
+
+fn felt_to_u128(felt: StarkFelt) -> Option<u128> {
+    let (high_bytes, low_bytes) = felt.bytes().split_at(16);
+    if high_bytes.iter().any(|&byte| byte != 0) {
+        return None;
+    }
+
+    Some(u128::from_be_bytes(low_bytes.try_into().expect("Slice should be of length 16.")))
+}
+
+fn read_converted<S: StateReader + ?Sized, T>(
+    state: &mut S,
+    contract_address: ContractAddress,
+    address: StorageKey,
+    value_type: &'static str,
+    convert: impl FnOnce(StarkFelt) -> Option<T>,
+) -> StateResult<T> {
+    let value = state.get_storage_at(contract_address, address)?;
+    convert(value).ok_or(StateError::InvalidStorageValue {
+        contract_address,
+        key: address,
+        value,
+        value_type,
+    })
+}
@@ -0,0 +1,139 @@
+use assert_matches::assert_matches;
+use num_bigint::BigUint;
+use pretty_assertions::assert_eq;
+use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::{class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::abi::abi_utils::{
+    get_byte_array_chunk_address, get_map_entry_address, get_storage_address_at_offset,
+    get_storage_var_address,
+};
+use crate::state::cached_state::CachedState;
+use crate::state::errors::StateError;
+use crate::state::state_api::{State, StateReader};
+use crate::state::storage_value::{ByteArray, U256};
+use crate::test_utils::DictStateReader;
+
+#[test]
+fn read_and_write_values() {
+    let mut state = CachedState::from(DictStateReader::default());
+    let contract_address = contract_address!("0x100");
+
+    // A struct `{ owner: ContractAddress, balance: u256, frozen: bool }` in a map entry.
+    let struct_address = get_map_entry_address(
+        get_storage_var_address("accounts", &[]).unwrap(),
+        &[stark_felt!(3_u8)],
+    )
+    .unwrap();
+    let member_address = |offset| get_storage_address_at_offset(struct_address, offset).unwrap();
+    let balance = U256 { low: 5, high: 1 };
+    state.write_storage_value(contract_address, member_address(0), &contract_address).unwrap();
+    state.write_storage_value(contract_address, member_address(1), &balance).unwrap();
+    state.write_storage_value(contract_address, member_address(3), &true).unwrap();
+
+    assert_eq!(
+        state.get_storage_at(contract_address, member_address(2)).unwrap(),
+        stark_felt!(1_u8)
+    );
+    assert_eq!(
+        state.read_storage_value::<ContractAddress>(contract_address, member_address(0)).unwrap(),
+        contract_address
+    );
+    let read_balance = state.read_storage_value::<U256>(contract_address, member_address(1));
+    assert_eq!(read_balance.unwrap(), balance);
+    assert_eq!(BigUint::from(balance), (BigUint::from(1_u8) << 128_usize) + 5_u8);
+    assert!(state.read_storage_value::<bool>(contract_address, member_address(3)).unwrap());
+    assert_eq!(state.read_storage_value::<u8>(contract_address, member_address(1)).unwrap(), 5);
+
+    let class_hash_address = StorageKey(patricia_key!("0x20"));
+    state.write_storage_value(contract_address, class_hash_address, &class_hash!("0x30")).unwrap();
+    assert_eq!(
+        state.read_storage_value::<ClassHash>(contract_address, class_hash_address).unwrap(),
+        class_hash!("0x30")
+    );
+}
+
+#[test]
+fn invalid_values() {
+    let mut state = CachedState::from(DictStateReader::default());
+    let contract_address = contract_address!("0x100");
+    let address = StorageKey(patricia_key!("0x10"));
+    state.set_storage_at(contract_address, address, stark_felt!(u128::MAX));
+
+    assert_matches!(
+        state.read_storage_value::<bool>(contract_address, address).unwrap_err(),
+        StateError::InvalidStorageValue { value_type: "bool", .. }
+    );
+    assert_matches!(
+        state.read_storage_value::<u64>(contract_address, address).unwrap_err(),
+        StateError::InvalidStorageValue { key, .. } if key == address
+    );
+    assert_eq!(state.read_storage_value::<u128>(contract_address, address).unwrap(), u128::MAX);
+    // An oversized length.
+    assert_matches!(
+        state.read_storage_value::<ByteArray>(contract_address, address).unwrap_err(),
+        StateError::InvalidStorageValue { value_type: "u32", .. }
+    );
+}
+
+#[test]
+fn byte_arrays() {
+    let mut state = CachedState::from(DictStateReader::default());
+    let contract_address = contract_address!("0x100");
+    let address = get_storage_var_address("name", &[]).unwrap();
+
+    let byte_array = ByteArray::from("A string longer than a single word of 31 bytes.");
+    state.write_storage_value(contract_address, address, &byte_array).unwrap();
+    let chunk_address = get_byte_array_chunk_address(address, 0).unwrap();
+    assert_eq!(state.get_storage_at(contract_address, address).unwrap(), stark_felt!(47_u8));
+    assert_eq!(
+        state.get_storage_at(contract_address, chunk_address).unwrap(),
+        StarkFelt::new({
+            let mut word = [0; 32];
+            word[1..].copy_from_slice(b"A string longer than a single w");
+            word
+        })
+        .unwrap()
+    );
+    assert_eq!(
+        state
+            .get_storage_at(
+                contract_address,
+                get_storage_address_at_offset(chunk_address, 1).unwrap()
+            )
+            .unwrap(),
+        StarkFelt::new({
+            let mut word = [0; 32];
+            word[16..].copy_from_slice(b"ord of 31 bytes.");
+            word
+        })
+        .unwrap()
+    );
+    assert_eq!(
+        state.read_storage_value::<ByteArray>(contract_address, address).unwrap(),
+        byte_array
+    );
+
+    // Words beyond the first 256 are stored in the next chunk.
+    let byte_array = ByteArray((0..31 * 256 + 1).map(|i| (i % 251) as u8).collect());
+    state.write_storage_value(contract_address, address, &byte_array).unwrap();
+    assert_eq!(
+        state
+            .get_storage_at(contract_address, get_byte_array_chunk_address(address, 1).unwrap())
+            .unwrap(),
+        stark_felt!(byte_array.0[31 * 256])
+    );
+    assert_eq!(
+        state.read_storage_value::<ByteArray>(contract_address, address).unwrap(),
+        byte_array
+    );
+
+    let empty_byte_array = ByteArray::default();
+    state.write_storage_value(contract_address, address, &empty_byte_array).unwrap();
+    assert_eq!(
+        state.read_storage_value::<ByteArray>(contract_address, address).unwrap(),
+        empty_byte_array
+    );
+}
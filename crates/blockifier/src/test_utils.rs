@@ -27,7 +27,7 @@ use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_f
 
 use crate::abi::abi_utils::get_storage_var_address;
 use crate::abi::constants;
-use crate::block_context::BlockContext;
+use crate::block_context::{BlockContext, FeeTokenAddresses, GasPrices};
 use crate::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
 use crate::execution::entry_point::{
     CallEntryPoint, CallExecution, CallInfo, CallType, EntryPointExecutionContext,
@@ -36,7 +36,7 @@ use crate::execution::entry_point::{
 use crate::execution::execution_utils::felt_to_stark_felt;
 use crate::state::cached_state::{CachedState, ContractClassMapping, ContractStorageKey};
 use crate::state::errors::StateError;
-use crate::state::state_api::{State, StateReader, StateResult};
+use crate::state::state_api::{SharedStateReader, State, StateReader, StateResult};
 use crate::transaction::objects::AccountTransactionContext;
 use crate::transaction::transactions::DeployAccountTransaction;
 
@@ -48,6 +48,7 @@ pub const TEST_ACCOUNT_CONTRACT_ADDRESS: &str = "0x101";
 pub const TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS: &str = "0x102";
 pub const TEST_SEQUENCER_ADDRESS: &str = "0x1000";
 pub const TEST_ERC20_CONTRACT_ADDRESS: &str = "0x1001";
+pub const TEST_STRK_ERC20_CONTRACT_ADDRESS: &str = "0x1002";
 
 // Class hashes.
 pub const TEST_CLASS_HASH: &str = "0x110";
@@ -99,6 +100,7 @@ pub const MAX_FEE: u128 = 1000000 * 100000000000; // 1000000 * min_gas_price.
 pub const BALANCE: u128 = 10 * MAX_FEE;
 
 pub const DEFAULT_GAS_PRICE: u128 = 100 * u128::pow(10, 9); // Given in units of wei.
+pub const DEFAULT_STRK_GAS_PRICE: u128 = 50 * u128::pow(10, 9); // Given in units of fri.
 
 // The block number of the BlockContext being used for testing.
 pub const CURRENT_BLOCK_NUMBER: u64 = 2000;
@@ -113,9 +115,9 @@ pub struct DictStateReader {
     pub class_hash_to_compiled_class_hash: HashMap<ClassHash, CompiledClassHash>,
 }
 
-impl StateReader for DictStateReader {
+impl SharedStateReader for DictStateReader {
     fn get_storage_at(
-        &mut self,
+        &self,
         contract_address: ContractAddress,
         key: StorageKey,
     ) -> StateResult<StarkFelt> {
@@ -124,15 +126,12 @@ impl StateReader for DictStateReader {
         Ok(value)
     }
 
-    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
         let nonce = self.address_to_nonce.get(&contract_address).copied().unwrap_or_default();
         Ok(nonce)
     }
 
-    fn get_compiled_contract_class(
-        &mut self,
-        class_hash: &ClassHash,
-    ) -> StateResult<ContractClass> {
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
         let contract_class = self.class_hash_to_class.get(class_hash).cloned();
         match contract_class {
             Some(contract_class) => Ok(contract_class),
@@ -140,14 +139,14 @@ impl StateReader for DictStateReader {
         }
     }
 
-    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
         let class_hash =
             self.address_to_class_hash.get(&contract_address).copied().unwrap_or_default();
         Ok(class_hash)
     }
 
     fn get_compiled_class_hash(
-        &mut self,
+        &self,
         class_hash: ClassHash,
     ) -> StateResult<starknet_api::core::CompiledClassHash> {
         let compiled_class_hash =
@@ -156,6 +155,38 @@ impl StateReader for DictStateReader {
     }
 }
 
+impl StateReader for DictStateReader {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        SharedStateReader::get_storage_at(self, contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        SharedStateReader::get_nonce_at(self, contract_address)
+    }
+
+    fn get_compiled_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+    ) -> StateResult<ContractClass> {
+        SharedStateReader::get_compiled_contract_class(self, class_hash)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        SharedStateReader::get_class_hash_at(self, contract_address)
+    }
+
+    fn get_compiled_class_hash(
+        &mut self,
+        class_hash: ClassHash,
+    ) -> StateResult<starknet_api::core::CompiledClassHash> {
+        SharedStateReader::get_compiled_class_hash(self, class_hash)
+    }
+}
+
 #[derive(Default)]
 pub struct NonceManager {
     next_nonce: HashMap<ContractAddress, Felt252>,
@@ -338,9 +369,15 @@ impl BlockContext {
             block_number: BlockNumber(CURRENT_BLOCK_NUMBER),
             block_timestamp: BlockTimestamp::default(),
             sequencer_address: contract_address!(TEST_SEQUENCER_ADDRESS),
-            fee_token_address: contract_address!(TEST_ERC20_CONTRACT_ADDRESS),
+            fee_token_addresses: FeeTokenAddresses {
+                eth_fee_token_address: contract_address!(TEST_ERC20_CONTRACT_ADDRESS),
+                strk_fee_token_address: contract_address!(TEST_STRK_ERC20_CONTRACT_ADDRESS),
+            },
             vm_resource_fee_cost: Default::default(),
-            gas_price: DEFAULT_GAS_PRICE,
+            gas_prices: GasPrices {
+                eth_l1_gas_price: DEFAULT_GAS_PRICE,
+                strk_l1_gas_price: DEFAULT_STRK_GAS_PRICE,
+            },
             invoke_tx_max_n_steps: 1_000_000,
             validate_max_n_steps: 1_000_000,
             max_recursion_depth: 50,
@@ -398,7 +435,11 @@ pub fn deploy_account_tx(
         nonce: nonce_manager.next(contract_address),
     };
 
-    DeployAccountTransaction { tx, tx_hash: TransactionHash::default(), contract_address }
+    DeployAccountTransaction {
+        tx: tx.into(),
+        tx_hash: TransactionHash::default(),
+        contract_address,
+    }
 }
 
 pub fn invoke_tx(
@@ -2,9 +2,11 @@ pub mod account_transaction;
 pub mod constants;
 pub mod errors;
 pub mod objects;
+pub mod rpc_trace;
 #[cfg(any(feature = "testing", test))]
 pub mod test_utils;
 pub mod transaction_execution;
 pub mod transaction_types;
 pub mod transaction_utils;
 pub mod transactions;
+pub mod versioned_transactions;
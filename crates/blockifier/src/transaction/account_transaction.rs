@@ -3,12 +3,12 @@ use std::cmp::min;
 use cairo_vm::vm::runners::cairo_runner::ResourceTracker;
 use itertools::concat;
 use starknet_api::calldata;
-use starknet_api::core::{ContractAddress, EntryPointSelector, Nonce};
+use starknet_api::core::{ContractAddress, EntryPointSelector};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::StarkFelt;
 use starknet_api::transaction::{Calldata, Fee, TransactionVersion};
 
-use crate::abi::abi_utils::selector_from_name;
+use crate::abi::abi_utils::{get_erc20_balance_var_addresses, selector_from_name};
 use crate::abi::constants as abi_constants;
 use crate::block_context::BlockContext;
 use crate::execution::contract_class::ContractClass;
@@ -16,17 +16,15 @@ use crate::execution::entry_point::{
     CallEntryPoint, CallInfo, CallType, EntryPointExecutionContext, ExecutionResources, Retdata,
 };
 use crate::fee::fee_utils::calculate_tx_fee;
-use crate::fee::gas_usage::estimate_minimal_fee;
+use crate::fee::gas_usage::{estimate_minimal_fee, estimate_minimal_l1_gas};
 use crate::fee::os_resources::OS_RESOURCES;
 use crate::retdata;
-use crate::state::cached_state::{
-    CachedState, StateChanges, StateChangesCount, TransactionalState,
-};
+use crate::state::cached_state::{StateChanges, StateChangesCount, TransactionalState};
 use crate::state::state_api::{State, StateReader};
 use crate::transaction::constants;
 use crate::transaction::errors::TransactionExecutionError;
 use crate::transaction::objects::{
-    AccountTransactionContext, ResourcesMapping, TransactionExecutionInfo,
+    AccountTransactionContext, ResourcesMapping, SimulationFlags, TransactionExecutionInfo,
     TransactionExecutionResult,
 };
 use crate::transaction::transaction_execution::Transaction;
@@ -39,13 +37,14 @@ use crate::transaction::transactions::{
     DeclareTransaction, DeployAccountTransaction, Executable, ExecutableTransaction,
     InvokeTransaction,
 };
+use crate::transaction::versioned_transactions::{AccountDeploymentData, ResourceBounds};
 
 #[cfg(test)]
 #[path = "account_transactions_test.rs"]
 mod test;
 
 /// Represents a paid StarkNet transaction.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AccountTransaction {
     Declare(DeclareTransaction),
     DeployAccount(DeployAccountTransaction),
@@ -142,7 +141,7 @@ impl AccountTransaction {
         }
     }
 
-    fn get_account_transaction_context(&self) -> AccountTransactionContext {
+    pub fn get_account_transaction_context(&self) -> AccountTransactionContext {
         match self {
             Self::Declare(tx) => {
                 let sn_api_tx = &tx.tx();
@@ -153,6 +152,12 @@ impl AccountTransaction {
                     signature: sn_api_tx.signature(),
                     nonce: sn_api_tx.nonce(),
                     sender_address: sn_api_tx.sender_address(),
+                    resource_bounds: sn_api_tx.resource_bounds(),
+                    tip: sn_api_tx.tip(),
+                    nonce_data_availability_mode: sn_api_tx.nonce_data_availability_mode(),
+                    fee_data_availability_mode: sn_api_tx.fee_data_availability_mode(),
+                    paymaster_data: sn_api_tx.paymaster_data(),
+                    account_deployment_data: sn_api_tx.account_deployment_data(),
                 }
             }
             Self::DeployAccount(tx) => AccountTransactionContext {
@@ -162,33 +167,29 @@ impl AccountTransaction {
                 signature: tx.signature(),
                 nonce: tx.nonce(),
                 sender_address: tx.contract_address,
+                resource_bounds: tx.tx.resource_bounds(),
+                tip: tx.tx.tip(),
+                nonce_data_availability_mode: tx.tx.nonce_data_availability_mode(),
+                fee_data_availability_mode: tx.tx.fee_data_availability_mode(),
+                paymaster_data: tx.tx.paymaster_data(),
+                // The account is deployed by the transaction itself.
+                account_deployment_data: AccountDeploymentData::default(),
             },
             Self::Invoke(tx) => {
                 let sn_api_tx = &tx.tx;
                 AccountTransactionContext {
                     transaction_hash: tx.tx_hash,
                     max_fee: sn_api_tx.max_fee(),
-                    version: match sn_api_tx {
-                        starknet_api::transaction::InvokeTransaction::V0(_) => {
-                            TransactionVersion(StarkFelt::from(0_u8))
-                        }
-                        starknet_api::transaction::InvokeTransaction::V1(_) => {
-                            TransactionVersion(StarkFelt::from(1_u8))
-                        }
-                    },
+                    version: sn_api_tx.version(),
                     signature: sn_api_tx.signature(),
-                    nonce: match sn_api_tx {
-                        starknet_api::transaction::InvokeTransaction::V0(_) => Nonce::default(),
-                        starknet_api::transaction::InvokeTransaction::V1(tx_v1) => tx_v1.nonce,
-                    },
-                    sender_address: match sn_api_tx {
-                        starknet_api::transaction::InvokeTransaction::V0(tx_v0) => {
-                            tx_v0.contract_address
-                        }
-                        starknet_api::transaction::InvokeTransaction::V1(tx_v1) => {
-                            tx_v1.sender_address
-                        }
-                    },
+                    nonce: sn_api_tx.nonce(),
+                    sender_address: sn_api_tx.sender_address(),
+                    resource_bounds: sn_api_tx.resource_bounds(),
+                    tip: sn_api_tx.tip(),
+                    nonce_data_availability_mode: sn_api_tx.nonce_data_availability_mode(),
+                    fee_data_availability_mode: sn_api_tx.fee_data_availability_mode(),
+                    paymaster_data: sn_api_tx.paymaster_data(),
+                    account_deployment_data: sn_api_tx.account_deployment_data(),
                 }
             }
         }
@@ -202,15 +203,20 @@ impl AccountTransaction {
                     TransactionVersion(StarkFelt::from(0_u8)),
                     TransactionVersion(StarkFelt::from(1_u8)),
                     TransactionVersion(StarkFelt::from(2_u8)),
+                    TransactionVersion(StarkFelt::from(3_u8)),
                 ]
             }
             Self::Invoke(_) => {
                 vec![
                     TransactionVersion(StarkFelt::from(0_u8)),
                     TransactionVersion(StarkFelt::from(1_u8)),
+                    TransactionVersion(StarkFelt::from(3_u8)),
                 ]
             }
-            _ => vec![TransactionVersion(StarkFelt::from(1_u8))],
+            _ => vec![
+                TransactionVersion(StarkFelt::from(1_u8)),
+                TransactionVersion(StarkFelt::from(3_u8)),
+            ],
         };
         if allowed_versions.contains(&version) {
             Ok(())
@@ -222,6 +228,7 @@ impl AccountTransaction {
     fn handle_nonce(
         account_tx_context: &AccountTransactionContext,
         state: &mut dyn State,
+        check_nonce: bool,
     ) -> TransactionExecutionResult<()> {
         if account_tx_context.version == TransactionVersion(StarkFelt::from(0_u8)) {
             return Ok(());
@@ -229,7 +236,7 @@ impl AccountTransaction {
 
         let address = account_tx_context.sender_address;
         let current_nonce = state.get_nonce_at(address)?;
-        if current_nonce != account_tx_context.nonce {
+        if check_nonce && current_nonce != account_tx_context.nonce {
             return Err(TransactionExecutionError::InvalidNonce {
                 address,
                 expected_nonce: current_nonce,
@@ -311,7 +318,7 @@ impl AccountTransaction {
     }
 
     fn enforce_fee(&self) -> bool {
-        self.max_fee() != Fee(0)
+        self.get_account_transaction_context().enforce_fee()
     }
 
     // TODO(Dori,1/10/2023): If/when Fees can be more than 128 bit integers, this should be updated.
@@ -324,28 +331,50 @@ impl AccountTransaction {
         balance_high > StarkFelt::from(0_u8) || balance_low >= StarkFelt::from(fee.0)
     }
 
-    /// Checks that the account's balance covers max fee.
+    /// Checks that the account's balance covers max fee (in version 3, the L1 gas bounds).
     fn check_fee_balance<S: StateReader>(
         &self,
         state: &mut TransactionalState<'_, S>,
         block_context: &BlockContext,
     ) -> TransactionExecutionResult<()> {
         let account_tx_context = self.get_account_transaction_context();
-        let max_fee = account_tx_context.max_fee;
+        let max_fee = account_tx_context.fee_upper_bound();
 
         // Check fee balance.
         if self.enforce_fee() {
-            // Check max fee is at least the estimated constant overhead.
-            let minimal_fee = estimate_minimal_fee(block_context, self)?;
-            if minimal_fee > max_fee {
-                return Err(TransactionExecutionError::MaxFeeTooLow {
-                    min_fee: minimal_fee,
-                    max_fee,
-                });
+            // Check the fee bounds cover at least the estimated constant overhead.
+            if account_tx_context.is_v3() {
+                let ResourceBounds { max_amount, max_price_per_unit } =
+                    account_tx_context.l1_resource_bounds();
+                let minimal_l1_gas_amount = estimate_minimal_l1_gas(block_context, self)?;
+                if u128::from(max_amount) < minimal_l1_gas_amount {
+                    return Err(TransactionExecutionError::MaxL1GasAmountTooLow {
+                        max_l1_gas_amount: max_amount,
+                        minimal_l1_gas_amount,
+                    });
+                }
+                let actual_l1_gas_price =
+                    block_context.gas_prices.get_by_fee_unit(account_tx_context.fee_unit());
+                if max_price_per_unit < actual_l1_gas_price {
+                    return Err(TransactionExecutionError::MaxL1GasPriceTooLow {
+                        max_l1_gas_price: max_price_per_unit,
+                        actual_l1_gas_price,
+                    });
+                }
+            } else {
+                let minimal_fee = estimate_minimal_fee(block_context, self)?;
+                if minimal_fee > max_fee {
+                    return Err(TransactionExecutionError::MaxFeeTooLow {
+                        min_fee: minimal_fee,
+                        max_fee,
+                    });
+                }
             }
 
-            let (balance_low, balance_high) =
-                state.get_fee_token_balance(block_context, &account_tx_context.sender_address)?;
+            let (balance_low, balance_high) = state.get_fee_token_balance(
+                &account_tx_context.sender_address,
+                &block_context.fee_token_address(account_tx_context.fee_unit()),
+            )?;
             if !Self::is_sufficient_fee_balance(balance_low, balance_high, max_fee) {
                 return Err(TransactionExecutionError::MaxFeeExceedsBalance {
                     max_fee,
@@ -358,6 +387,28 @@ impl AccountTransaction {
         Ok(())
     }
 
+    /// Reads, in bulk, the cells accessed by every account transaction: the sender's nonce and
+    /// class hash, and (if fee is charged) the sender's and sequencer's fee token balances.
+    fn prefetch_hot_cells<S: StateReader>(
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        account_tx_context: &AccountTransactionContext,
+        charge_fee: bool,
+    ) -> TransactionExecutionResult<()> {
+        let sender_address = account_tx_context.sender_address;
+        let fee_token_address = block_context.fee_token_address(account_tx_context.fee_unit());
+        let mut storage_keys = vec![];
+        if charge_fee {
+            for address in [sender_address, block_context.sequencer_address] {
+                let (low_key, high_key) = get_erc20_balance_var_addresses(&address)?;
+                storage_keys.push((fee_token_address, low_key));
+                storage_keys.push((fee_token_address, high_key));
+            }
+        }
+
+        Ok(state.prefetch(&storage_keys, &[sender_address], &[sender_address])?)
+    }
+
     fn handle_fee(
         &self,
         state: &mut dyn State,
@@ -378,13 +429,13 @@ impl AccountTransaction {
         Ok(Some(fee_transfer_call_info))
     }
 
-    fn execute_fee_transfer(
+    pub(crate) fn execute_fee_transfer(
         state: &mut dyn State,
         block_context: &BlockContext,
         account_tx_context: AccountTransactionContext,
         actual_fee: Fee,
     ) -> TransactionExecutionResult<CallInfo> {
-        let max_fee = account_tx_context.max_fee;
+        let max_fee = account_tx_context.fee_upper_bound();
         if actual_fee > max_fee {
             return Err(TransactionExecutionError::FeeTransferError { max_fee, actual_fee });
         }
@@ -394,7 +445,7 @@ impl AccountTransaction {
         // The most significant 128 bits of the amount transferred.
         let msb_amount = StarkFelt::from(0_u8);
 
-        let storage_address = block_context.fee_token_address;
+        let storage_address = block_context.fee_token_address(account_tx_context.fee_unit());
         let fee_transfer_call = CallEntryPoint {
             class_hash: None,
             code_address: None,
@@ -432,6 +483,7 @@ impl AccountTransaction {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn run_non_revertible<S: StateReader>(
         &self,
         state: &mut TransactionalState<'_, S>,
@@ -440,6 +492,7 @@ impl AccountTransaction {
         block_context: &BlockContext,
         mut execution_context: EntryPointExecutionContext,
         validate: bool,
+        is_fee_estimation: bool,
     ) -> TransactionExecutionResult<ValidateExecuteCallInfo> {
         let validate_call_info: Option<CallInfo>;
         let execute_call_info: Option<CallInfo>;
@@ -455,9 +508,10 @@ impl AccountTransaction {
             execute_call_info =
                 self.run_execute(state, resources, &mut execution_context, remaining_gas)?;
         }
+        let account_tx_context = self.get_account_transaction_context();
         let state_changes = state.get_actual_state_changes_for_fee_charge(
-            block_context.fee_token_address,
-            Some(self.get_account_transaction_context().sender_address),
+            block_context.fee_token_address(account_tx_context.fee_unit()),
+            Some(account_tx_context.sender_address),
         )?;
         let (actual_fee, actual_resources) = self.calculate_actual_fee_and_resources(
             StateChangesCount::from(&state_changes),
@@ -467,6 +521,7 @@ impl AccountTransaction {
             block_context,
             false,
             0,
+            is_fee_estimation,
         )?;
         Ok(ValidateExecuteCallInfo::new_accepted(
             validate_call_info,
@@ -476,6 +531,7 @@ impl AccountTransaction {
         ))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn run_revertible<S: StateReader>(
         &self,
         state: &mut TransactionalState<'_, S>,
@@ -484,8 +540,10 @@ impl AccountTransaction {
         block_context: &BlockContext,
         mut execution_context: EntryPointExecutionContext,
         validate: bool,
+        is_fee_estimation: bool,
     ) -> TransactionExecutionResult<ValidateExecuteCallInfo> {
         let account_tx_context = self.get_account_transaction_context();
+        let fee_token_address = block_context.fee_token_address(account_tx_context.fee_unit());
         // Run the validation, and if execution later fails, only keep the validation diff.
         let validate_call_info =
             self.handle_validate_tx(state, resources, remaining_gas, block_context, validate)?;
@@ -515,14 +573,14 @@ impl AccountTransaction {
         // Save the state changes resulting from running `validate_tx`, to be used later for
         // resource and fee calculation.
         let validate_state_changes = state.get_actual_state_changes_for_fee_charge(
-            block_context.fee_token_address,
+            fee_token_address,
             Some(account_tx_context.sender_address),
         )?;
 
         // Create copies of state and resources for the execution.
         // Both will be rolled back if the execution is reverted or committed upon success.
         let mut execution_resources = resources.clone();
-        let mut execution_state = CachedState::create_transactional(state);
+        let mut execution_state = state.create_transactional();
 
         let execution_result = self.run_execute(
             &mut execution_state,
@@ -537,7 +595,7 @@ impl AccountTransaction {
                 // transactional state. If max_fee is insufficient, revert the `run_execute` part.
                 let execute_state_changes = execution_state
                     .get_actual_state_changes_for_fee_charge(
-                        block_context.fee_token_address,
+                        fee_token_address,
                         Some(account_tx_context.sender_address),
                     )?;
                 // Fee is determined by the sum of `validate` and `execute` state changes.
@@ -556,17 +614,21 @@ impl AccountTransaction {
                     block_context,
                     false,
                     0,
+                    is_fee_estimation,
                 )?;
 
                 // Check if as a result of tx execution the sender's fee token balance is maxed out,
                 // so that they can't pay fee. If so, the transaction must be reverted.
-                let (balance_low, balance_high) = execution_state
-                    .get_fee_token_balance(block_context, &account_tx_context.sender_address)?;
+                let (balance_low, balance_high) = execution_state.get_fee_token_balance(
+                    &account_tx_context.sender_address,
+                    &fee_token_address,
+                )?;
                 let is_maxed_out =
                     !Self::is_sufficient_fee_balance(balance_low, balance_high, actual_fee);
-                let max_fee = account_tx_context.max_fee;
+                let max_fee = account_tx_context.fee_upper_bound();
 
-                if actual_fee > max_fee || is_maxed_out {
+                // The fee is not bounded when estimating it.
+                if !is_fee_estimation && (actual_fee > max_fee || is_maxed_out) {
                     // Insufficient fee. Revert the execution and charge what is available.
                     let (final_fee, revert_error) = if actual_fee > max_fee {
                         (
@@ -597,6 +659,7 @@ impl AccountTransaction {
                         block_context,
                         true,
                         n_reverted_steps,
+                        is_fee_estimation,
                     )?;
 
                     return Ok(ValidateExecuteCallInfo::new_reverted(
@@ -635,6 +698,7 @@ impl AccountTransaction {
                     block_context,
                     true,
                     n_reverted_steps,
+                    is_fee_estimation,
                 )?;
 
                 Ok(ValidateExecuteCallInfo::new_reverted(
@@ -668,10 +732,14 @@ impl AccountTransaction {
         remaining_gas: &mut u64,
         block_context: &BlockContext,
         validate: bool,
+        is_fee_estimation: bool,
     ) -> TransactionExecutionResult<ValidateExecuteCallInfo> {
         let account_tx_context = self.get_account_transaction_context();
-        let execution_context =
-            EntryPointExecutionContext::new_invoke(block_context, &account_tx_context);
+        let execution_context = if is_fee_estimation {
+            EntryPointExecutionContext::new_fee_estimation(block_context, &account_tx_context)
+        } else {
+            EntryPointExecutionContext::new_invoke(block_context, &account_tx_context)
+        };
 
         if self.is_non_revertible() {
             return self.run_non_revertible(
@@ -681,6 +749,7 @@ impl AccountTransaction {
                 block_context,
                 execution_context,
                 validate,
+                is_fee_estimation,
             );
         }
 
@@ -691,9 +760,66 @@ impl AccountTransaction {
             block_context,
             execution_context,
             validate,
+            is_fee_estimation,
         )
     }
 
+    /// Runs the transaction; when estimating its fee, the fee is neither charged nor bounded by
+    /// the max fee. When the nonce is not checked, it is still incremented.
+    fn run<S: StateReader>(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        charge_fee: bool,
+        validate: bool,
+        check_nonce: bool,
+        is_fee_estimation: bool,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        let account_tx_context = self.get_account_transaction_context();
+        self.verify_tx_version(account_tx_context.version)?;
+        Self::prefetch_hot_cells(state, block_context, &account_tx_context, charge_fee)?;
+
+        let mut resources = ExecutionResources::default();
+        let mut remaining_gas = Transaction::initial_gas();
+
+        // Nonce and fee check should be done before running user code.
+        if charge_fee {
+            self.check_fee_balance(state, block_context)?;
+        }
+        // Handle nonce.
+        Self::handle_nonce(&account_tx_context, state, check_nonce)?;
+
+        // Run validation and execution.
+        let ValidateExecuteCallInfo {
+            validate_call_info,
+            execute_call_info,
+            revert_error,
+            final_fee,
+            final_resources,
+        } = self.run_or_revert(
+            state,
+            &mut resources,
+            &mut remaining_gas,
+            block_context,
+            validate,
+            is_fee_estimation,
+        )?;
+
+        let fee_transfer_call_info =
+            self.handle_fee(state, block_context, final_fee, charge_fee)?;
+
+        let tx_execution_info = TransactionExecutionInfo {
+            validate_call_info,
+            execute_call_info,
+            fee_transfer_call_info,
+            actual_fee: final_fee,
+            fee_unit: account_tx_context.fee_unit(),
+            actual_resources: final_resources,
+            revert_error,
+        };
+        Ok(tx_execution_info)
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn calculate_actual_fee_and_resources(
         &self,
@@ -704,6 +830,7 @@ impl AccountTransaction {
         block_context: &BlockContext,
         is_reverted: bool,
         n_reverted_steps: usize,
+        is_fee_estimation: bool,
     ) -> TransactionExecutionResult<(Fee, ResourcesMapping)> {
         let account_tx_context = self.get_account_transaction_context();
 
@@ -720,11 +847,12 @@ impl AccountTransaction {
         *actual_resources.0.get_mut(&abi_constants::N_STEPS_RESOURCE.to_string()).unwrap() +=
             n_reverted_steps;
 
-        let mut actual_fee = calculate_tx_fee(&actual_resources, block_context)?;
+        let mut actual_fee =
+            calculate_tx_fee(&actual_resources, block_context, account_tx_context.fee_unit())?;
 
-        if is_reverted || account_tx_context.max_fee == Fee(0) {
+        if !is_fee_estimation && (is_reverted || !account_tx_context.enforce_fee()) {
             // We cannot charge more than max_fee for reverted txs.
-            actual_fee = min(actual_fee, account_tx_context.max_fee);
+            actual_fee = min(actual_fee, account_tx_context.fee_upper_bound());
         }
 
         Ok((actual_fee, actual_resources))
@@ -739,40 +867,22 @@ impl<S: StateReader> ExecutableTransaction<S> for AccountTransaction {
         charge_fee: bool,
         validate: bool,
     ) -> TransactionExecutionResult<TransactionExecutionInfo> {
-        let account_tx_context = self.get_account_transaction_context();
-        self.verify_tx_version(account_tx_context.version)?;
-
-        let mut resources = ExecutionResources::default();
-        let mut remaining_gas = Transaction::initial_gas();
-
-        // Nonce and fee check should be done before running user code.
-        if charge_fee {
-            self.check_fee_balance(state, block_context)?;
-        }
-        // Handle nonce.
-        Self::handle_nonce(&account_tx_context, state)?;
-
-        // Run validation and execution.
-        let ValidateExecuteCallInfo {
-            validate_call_info,
-            execute_call_info,
-            revert_error,
-            final_fee,
-            final_resources,
-        } =
-            self.run_or_revert(state, &mut resources, &mut remaining_gas, block_context, validate)?;
-
-        let fee_transfer_call_info =
-            self.handle_fee(state, block_context, final_fee, charge_fee)?;
+        self.run(state, block_context, charge_fee, validate, true, false)
+    }
 
-        let tx_execution_info = TransactionExecutionInfo {
-            validate_call_info,
-            execute_call_info,
-            fee_transfer_call_info,
-            actual_fee: final_fee,
-            actual_resources: final_resources,
-            revert_error,
-        };
-        Ok(tx_execution_info)
+    fn execute_raw_with_flags(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        flags: SimulationFlags,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        self.run(
+            state,
+            block_context,
+            !flags.skip_fee_charge,
+            !flags.skip_validate,
+            !flags.skip_nonce_check,
+            flags.skip_fee_charge,
+        )
     }
 }
@@ -1,6 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::iter::zip;
 
+use assert_matches::assert_matches;
 use cairo_vm::vm::runners::cairo_runner::ResourceTracker;
+use indexmap::indexmap;
 use rstest::{fixture, rstest};
 use starknet_api::core::{
     calculate_contract_address, ClassHash, ContractAddress, Nonce, PatriciaKey,
@@ -9,7 +12,7 @@ use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::StorageKey;
 use starknet_api::transaction::{
     Calldata, ContractAddressSalt, DeclareTransactionV0V1, DeclareTransactionV2, Fee,
-    TransactionHash,
+    InvokeTransactionV1, TransactionHash, TransactionVersion,
 };
 use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
 use starknet_crypto::FieldElement;
@@ -21,19 +24,29 @@ use crate::execution::entry_point::EntryPointExecutionContext;
 use crate::state::cached_state::CachedState;
 use crate::state::state_api::{State, StateReader};
 use crate::test_utils::{
-    declare_tx, deploy_account_tx, DictStateReader, NonceManager, ACCOUNT_CONTRACT_CAIRO0_PATH,
-    BALANCE, ERC20_CONTRACT_PATH, MAX_FEE, TEST_ACCOUNT_CONTRACT_CLASS_HASH, TEST_CLASS_HASH,
-    TEST_CONTRACT_ADDRESS, TEST_CONTRACT_CAIRO0_PATH, TEST_ERC20_CONTRACT_CLASS_HASH,
-    TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS,
+    declare_tx, deploy_account_tx, invoke_tx, DictStateReader, NonceManager,
+    ACCOUNT_CONTRACT_CAIRO0_PATH, BALANCE, ERC20_CONTRACT_PATH, MAX_FEE,
+    TEST_ACCOUNT_CONTRACT_CLASS_HASH, TEST_CLASS_HASH, TEST_CONTRACT_ADDRESS,
+    TEST_CONTRACT_CAIRO0_PATH, TEST_EMPTY_CONTRACT_CAIRO0_PATH, TEST_EMPTY_CONTRACT_CLASS_HASH,
+    TEST_ERC20_CONTRACT_CLASS_HASH, TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS,
 };
 use crate::transaction::account_transaction::AccountTransaction;
-use crate::transaction::objects::TransactionExecutionInfo;
+use crate::transaction::errors::TransactionExecutionError;
+use crate::transaction::objects::{
+    AccountTransactionContext, FeeEstimate, FeeUnit, SimulationFlags, TransactionExecutionInfo,
+};
 use crate::transaction::test_utils::{
     account_invoke_tx, create_account_tx_for_validate_test,
     create_state_with_falliable_validation_account, run_invoke_tx, INVALID,
 };
+use crate::transaction::transaction_execution::{simulate, Transaction};
 use crate::transaction::transaction_types::TransactionType;
-use crate::transaction::transactions::{DeclareTransaction, ExecutableTransaction};
+use crate::transaction::transactions::{
+    estimate_fees, DeclareTransaction, ExecutableTransaction, InvokeTransaction,
+};
+use crate::transaction::versioned_transactions::{
+    self, InvokeTransactionV3, Resource, ResourceBounds, ResourceBoundsMapping,
+};
 
 struct TestInitData {
     pub state: CachedState<DictStateReader>,
@@ -62,9 +75,11 @@ fn create_state(block_context: BlockContext) -> CachedState<DictStateReader> {
         (test_account_class_hash, ContractClassV0::from_file(ACCOUNT_CONTRACT_CAIRO0_PATH).into()),
         (test_erc20_class_hash, ContractClassV0::from_file(ERC20_CONTRACT_PATH).into()),
     ]);
-    // Deploy the erc20 contract.
-    let test_erc20_address = block_context.fee_token_address;
-    let address_to_class_hash = HashMap::from([(test_erc20_address, test_erc20_class_hash)]);
+    // Deploy the erc20 contracts of both fee tokens.
+    let address_to_class_hash = HashMap::from(
+        [FeeUnit::Wei, FeeUnit::Fri]
+            .map(|fee_unit| (block_context.fee_token_address(fee_unit), test_erc20_class_hash)),
+    );
 
     CachedState::from(DictStateReader {
         address_to_class_hash,
@@ -91,15 +106,17 @@ fn create_test_init_data(
     );
     let account_address = deploy_account_tx.contract_address;
 
-    // Update the balance of the about-to-be deployed account contract in the erc20 contract, so it
-    // can pay for the transaction execution.
+    // Update the balance of the about-to-be deployed account contract in the erc20 contracts, so
+    // it can pay for the transaction execution in either fee token.
     let deployed_account_balance_key =
         get_storage_var_address("ERC20_balances", &[*account_address.0.key()]).unwrap();
-    state.set_storage_at(
-        block_context.fee_token_address,
-        deployed_account_balance_key,
-        stark_felt!(BALANCE),
-    );
+    for fee_unit in [FeeUnit::Wei, FeeUnit::Fri] {
+        state.set_storage_at(
+            block_context.fee_token_address(fee_unit),
+            deployed_account_balance_key,
+            stark_felt!(BALANCE),
+        );
+    }
 
     let account_tx = AccountTransaction::DeployAccount(deploy_account_tx);
     account_tx.execute(&mut state, &block_context, true, true).unwrap();
@@ -109,7 +126,7 @@ fn create_test_init_data(
     let declare_tx = declare_tx(TEST_CLASS_HASH, account_address, max_fee, None);
     let account_tx = AccountTransaction::Declare(
         DeclareTransaction::new(
-            starknet_api::transaction::DeclareTransaction::V1(DeclareTransactionV0V1 {
+            versioned_transactions::DeclareTransaction::V1(DeclareTransactionV0V1 {
                 nonce: nonce_manager.next(account_address),
                 ..declare_tx
             }),
@@ -302,7 +319,7 @@ fn test_revert_invoke(
     let deployed_account_balance_key =
         get_storage_var_address("ERC20_balances", &[*deployed_account_address.0.key()]).unwrap();
     state.set_storage_at(
-        block_context.fee_token_address,
+        block_context.fee_token_addresses.eth_fee_token_address,
         deployed_account_balance_key,
         stark_felt!(BALANCE),
     );
@@ -338,7 +355,12 @@ fn test_revert_invoke(
     // Check that the nonce was increased and the fee was deducted.
     let total_deducted_fee = deploy_execution_info.actual_fee.0 + tx_execution_info.actual_fee.0;
     assert_eq!(
-        state.get_fee_token_balance(&block_context, &deployed_account_address).unwrap(),
+        state
+            .get_fee_token_balance(
+                &deployed_account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
         (stark_felt!(BALANCE - total_deducted_fee), stark_felt!(0_u8))
     );
     assert_eq!(
@@ -365,8 +387,12 @@ fn test_fail_deploy_account(block_context: BlockContext) {
 
     let deployed_account_address =
         ContractAddress::try_from(stark_felt!(TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS)).unwrap();
-    let initial_balance =
-        state.get_fee_token_balance(&block_context, &deployed_account_address).unwrap();
+    let initial_balance = state
+        .get_fee_token_balance(
+            &deployed_account_address,
+            &block_context.fee_token_addresses.eth_fee_token_address,
+        )
+        .unwrap();
 
     // Create and execute (failing) deploy account transaction.
     let deploy_account_tx = create_account_tx_for_validate_test(
@@ -381,7 +407,12 @@ fn test_fail_deploy_account(block_context: BlockContext) {
     // Assert nonce and balance are unchanged, and that no contract was deployed at the address.
     assert_eq!(state.get_nonce_at(deployed_account_address).unwrap(), Nonce(stark_felt!(0_u8)));
     assert_eq!(
-        state.get_fee_token_balance(&block_context, &deployed_account_address).unwrap(),
+        state
+            .get_fee_token_balance(
+                &deployed_account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
         initial_balance
     );
     assert_eq!(state.get_class_hash_at(deploy_address).unwrap(), ClassHash::default());
@@ -394,7 +425,12 @@ fn test_fail_declare(max_fee: Fee, #[from(create_test_init_data)] init_data: Tes
         init_data;
     let class_hash = class_hash!(0xdeadeadeaf72_u128);
     let contract_class = ContractClass::V1(ContractClassV1::default());
-    let initial_balance = state.get_fee_token_balance(&block_context, &account_address).unwrap();
+    let initial_balance = state
+        .get_fee_token_balance(
+            &account_address,
+            &block_context.fee_token_addresses.eth_fee_token_address,
+        )
+        .unwrap();
     let next_nonce = nonce_manager.next(account_address);
 
     // Cannot fail executing a declare tx unless it's V2 or above, and already declared.
@@ -408,7 +444,7 @@ fn test_fail_declare(max_fee: Fee, #[from(create_test_init_data)] init_data: Tes
     state.set_compiled_class_hash(class_hash, declare_tx.compiled_class_hash).unwrap();
     let declare_account_tx = AccountTransaction::Declare(
         DeclareTransaction::new(
-            starknet_api::transaction::DeclareTransaction::V2(DeclareTransactionV2 {
+            versioned_transactions::DeclareTransaction::V2(DeclareTransactionV2 {
                 nonce: next_nonce,
                 ..declare_tx
             }),
@@ -422,7 +458,12 @@ fn test_fail_declare(max_fee: Fee, #[from(create_test_init_data)] init_data: Tes
     declare_account_tx.execute(&mut state, &block_context, true, true).unwrap_err();
     assert_eq!(state.get_nonce_at(account_address).unwrap(), next_nonce);
     assert_eq!(
-        state.get_fee_token_balance(&block_context, &account_address).unwrap(),
+        state
+            .get_fee_token_balance(
+                &account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
         initial_balance
     );
 }
@@ -777,6 +818,239 @@ fn test_insufficient_max_fee_reverts(
     );
 }
 
+#[rstest]
+/// Tests that fee estimation is not bounded by the max fee, supports dependent transactions and
+/// does not modify the state.
+fn test_estimate_fee(
+    block_context: BlockContext,
+    #[from(create_state)] state: CachedState<DictStateReader>,
+) {
+    let TestInitData {
+        mut state,
+        account_address,
+        contract_address,
+        mut nonce_manager,
+        block_context,
+    } = create_test_init_data(Fee(MAX_FEE), block_context, state);
+    let recurse_tx = |depth: u32, nonce: Nonce, max_fee: Fee| {
+        let execute_calldata = calldata![
+            *contract_address.0.key(),       // Contract address.
+            selector_from_name("recurse").0, // EP selector.
+            stark_felt!(1_u8),               // Calldata length.
+            stark_felt!(depth)               // Calldata: recursion depth.
+        ];
+        let tx = invoke_tx(execute_calldata, account_address, max_fee, None);
+        AccountTransaction::Invoke(InvokeTransactionV1 { nonce, ..tx }.into())
+    };
+    let depths = [2, 800];
+    let nonces = depths.map(|_| nonce_manager.next(account_address));
+
+    // The max fee of the transactions is too low to execute them.
+    let txs = zip(depths, nonces).map(|(depth, nonce)| recurse_tx(depth, nonce, Fee(1)));
+    let fee_estimates = estimate_fees(txs, &mut state, &block_context, true).unwrap();
+    assert_eq!(state.get_nonce_at(account_address).unwrap(), nonces[0]);
+    assert_eq!(
+        recurse_tx(depths[0], nonces[0], Fee(1))
+            .estimate_fee(&mut state, &block_context, true)
+            .unwrap(),
+        fee_estimates[0]
+    );
+    // The second transaction can only be estimated after the first.
+    assert_matches!(
+        recurse_tx(depths[1], nonces[1], Fee(1))
+            .estimate_fee(&mut state, &block_context, true)
+            .unwrap_err(),
+        TransactionExecutionError::InvalidNonce { .. }
+    );
+    // A failure is reported with the index of the failing transaction.
+    let txs = [recurse_tx(depths[0], nonces[0], Fee(1)), recurse_tx(depths[1], nonces[0], Fee(1))];
+    assert_matches!(
+        estimate_fees(txs, &mut state, &block_context, true).unwrap_err(),
+        TransactionExecutionError::TransactionFailed { tx_index: 1, error }
+        if matches!(*error, TransactionExecutionError::InvalidNonce { .. })
+    );
+
+    // The estimates match the fees charged when executing the transactions with enough max fee.
+    for ((depth, nonce), fee_estimate) in zip(zip(depths, nonces), fee_estimates) {
+        let tx_execution_info = recurse_tx(depth, nonce, Fee(MAX_FEE))
+            .execute(&mut state, &block_context, true, true)
+            .unwrap();
+        assert!(!tx_execution_info.is_reverted());
+        let actual_fee = tx_execution_info.actual_fee;
+        assert_eq!(
+            fee_estimate,
+            FeeEstimate {
+                overall_fee: actual_fee,
+                gas_consumed: actual_fee.0 / block_context.gas_prices.eth_l1_gas_price,
+                gas_price: block_context.gas_prices.eth_l1_gas_price,
+                unit: FeeUnit::Wei,
+            }
+        );
+    }
+}
+
+#[rstest]
+/// Tests that simulation skips the checks given by the flags, returns the trace and state diff of
+/// each transaction, and does not modify the state.
+fn test_simulate(
+    block_context: BlockContext,
+    #[from(create_state)] state: CachedState<DictStateReader>,
+) {
+    let TestInitData {
+        mut state,
+        account_address,
+        contract_address,
+        mut nonce_manager,
+        block_context,
+    } = create_test_init_data(Fee(MAX_FEE), block_context, state);
+    let write_tx = |value: u8, nonce: Nonce, max_fee: Fee| {
+        let execute_calldata = calldata![
+            *contract_address.0.key(),                       // Contract address.
+            selector_from_name("test_storage_read_write").0, // EP selector.
+            stark_felt!(2_u8),                               // Calldata length.
+            stark_felt!(15_u8),                              // Calldata: address.
+            stark_felt!(value)                               // Calldata: value.
+        ];
+        let tx = invoke_tx(execute_calldata, account_address, max_fee, None);
+        Transaction::AccountTransaction(AccountTransaction::Invoke(
+            InvokeTransactionV1 { nonce, ..tx }.into(),
+        ))
+    };
+    let nonce = nonce_manager.next(account_address);
+    let storage_key = StorageKey(patricia_key!(15_u8));
+
+    // Skip all checks; the transactions have an invalid nonce and too low a max fee.
+    let flags =
+        SimulationFlags { skip_validate: true, skip_fee_charge: true, skip_nonce_check: true };
+    let invalid_nonce = Nonce(stark_felt!(100_u8));
+    let txs = [1, 2].map(|value| write_tx(value, invalid_nonce, Fee(1)));
+    let simulated_txs = simulate(txs, &mut state, &block_context, flags).unwrap();
+    assert_eq!(state.get_nonce_at(account_address).unwrap(), nonce);
+    assert_eq!(state.get_storage_at(contract_address, storage_key).unwrap(), StarkFelt::default());
+    for (value, simulated_tx) in zip([1_u8, 2], &simulated_txs) {
+        let trace = &simulated_tx.trace;
+        assert_eq!(trace.tx_type, TransactionType::InvokeFunction);
+        assert!(trace.validate_invocation.is_none() && trace.fee_transfer_invocation.is_none());
+        assert!(trace.execute_invocation.is_some() && trace.revert_error.is_none());
+        assert_eq!(
+            trace.state_diff.storage_updates,
+            indexmap! { contract_address => indexmap! { storage_key => stark_felt!(value) } }
+        );
+    }
+    // The nonce is incremented by each transaction, regardless of its own.
+    let nonce_diffs =
+        simulated_txs.iter().map(|simulated_tx| &simulated_tx.trace.state_diff.address_to_nonce);
+    let expected_nonces = [(); 2].map(|_| {
+        indexmap! { account_address => nonce_manager.next(account_address) }
+    });
+    assert!(nonce_diffs.eq(expected_nonces.iter()));
+
+    // Without flags, the transaction is validated and charged.
+    let simulated_tx = simulate(
+        [write_tx(1, nonce, Fee(MAX_FEE))],
+        &mut state,
+        &block_context,
+        SimulationFlags::default(),
+    )
+    .unwrap()
+    .remove(0);
+    assert!(simulated_tx.trace.validate_invocation.is_some());
+    assert!(simulated_tx.trace.fee_transfer_invocation.is_some());
+    assert!(simulated_tx
+        .trace
+        .state_diff
+        .storage_updates
+        .contains_key(&block_context.fee_token_addresses.eth_fee_token_address));
+    let tx_execution_info =
+        write_tx(1, nonce, Fee(MAX_FEE)).execute(&mut state, &block_context, true, true).unwrap();
+    assert_eq!(simulated_tx.fee_estimate.overall_fee, tx_execution_info.actual_fee);
+    // A failure is reported with the index of the failing transaction.
+    let next_nonce = state.get_nonce_at(account_address).unwrap();
+    assert_matches!(
+        simulate(
+            [write_tx(2, next_nonce, Fee(MAX_FEE)), write_tx(3, nonce, Fee(MAX_FEE))],
+            &mut state,
+            &block_context,
+            SimulationFlags::default()
+        )
+        .unwrap_err(),
+        TransactionExecutionError::TransactionFailed { tx_index: 1, error }
+        if matches!(*error, TransactionExecutionError::InvalidNonce { .. })
+    );
+}
+
+#[rstest]
+/// Tests that the trace of a simulated transaction tells deployed contracts from replaced classes,
+/// and reports declared Cairo 0 classes.
+fn test_simulate_class_changes(
+    block_context: BlockContext,
+    #[from(create_state)] state: CachedState<DictStateReader>,
+) {
+    let TestInitData { mut state, account_address, contract_address, block_context, .. } =
+        create_test_init_data(Fee(MAX_FEE), block_context, state);
+    let invoke = |execute_calldata: Calldata| {
+        let tx = invoke_tx(execute_calldata, account_address, Fee(MAX_FEE), None);
+        Transaction::AccountTransaction(AccountTransaction::Invoke(tx.into()))
+    };
+
+    let empty_class_hash = class_hash!(TEST_EMPTY_CONTRACT_CLASS_HASH);
+    let declare_tx =
+        declare_tx(TEST_EMPTY_CONTRACT_CLASS_HASH, account_address, Fee(MAX_FEE), None);
+    let declare_tx = Transaction::AccountTransaction(AccountTransaction::Declare(
+        DeclareTransaction::new(
+            versioned_transactions::DeclareTransaction::V1(declare_tx),
+            TransactionHash::default(),
+            ContractClassV0::from_file(TEST_EMPTY_CONTRACT_CAIRO0_PATH).into(),
+        )
+        .unwrap(),
+    ));
+    let class_hash = class_hash!(TEST_CLASS_HASH);
+    let salt = ContractAddressSalt(stark_felt!(7_u8));
+    let deploy_tx = invoke(calldata![
+        *contract_address.0.key(),           // Contract address.
+        selector_from_name("test_deploy").0, // EP selector.
+        stark_felt!(6_u8),                   // Calldata length.
+        class_hash.0,                        // Calldata: class_hash.
+        salt.0,                              // Contract_address_salt.
+        stark_felt!(2_u8),                   // Constructor calldata length.
+        stark_felt!(1_u8),                   // Constructor calldata: address.
+        stark_felt!(1_u8),                   // Constructor calldata: value.
+        stark_felt!(0_u8)                    // Deploy from zero.
+    ]);
+    let replace_class_tx = invoke(calldata![
+        *contract_address.0.key(),                  // Contract address.
+        selector_from_name("test_replace_class").0, // EP selector.
+        stark_felt!(1_u8),                          // Calldata length.
+        empty_class_hash.0                          // Calldata: class_hash.
+    ]);
+
+    let flags =
+        SimulationFlags { skip_validate: true, skip_fee_charge: true, skip_nonce_check: true };
+    let traces: Vec<_> =
+        simulate([declare_tx, deploy_tx, replace_class_tx], &mut state, &block_context, flags)
+            .unwrap()
+            .into_iter()
+            .map(|simulated_tx| simulated_tx.trace)
+            .collect();
+    assert_eq!(traces[0].deprecated_declared_classes, vec![empty_class_hash]);
+    assert!(traces[1].deprecated_declared_classes.is_empty());
+
+    let deployed_contract_address = calculate_contract_address(
+        salt,
+        class_hash,
+        &calldata![stark_felt!(1_u8), stark_felt!(1_u8)],
+        contract_address,
+    )
+    .unwrap();
+    assert_eq!(traces[1].state_diff.address_to_class_hash[&deployed_contract_address], class_hash);
+    assert_eq!(
+        traces[1].previous_class_hashes,
+        HashMap::from([(deployed_contract_address, ClassHash::default())])
+    );
+    assert_eq!(traces[2].state_diff.address_to_class_hash[&contract_address], empty_class_hash);
+    assert_eq!(traces[2].previous_class_hashes, HashMap::from([(contract_address, class_hash)]));
+}
+
 #[allow(clippy::too_many_arguments)]
 /// Calls `test_write_and_transfer` with the given parameters.
 fn write_and_transfer(
@@ -799,7 +1073,7 @@ fn write_and_transfer(
         storage_value,                                   // Calldata: storage value.
         recipient,                                       // Calldata: to.
         transfer_amount,                                 // Calldata: amount.
-        *block_context.fee_token_address.0.key()
+        *block_context.fee_token_addresses.eth_fee_token_address.0.key()
     ];
     let account_tx = account_invoke_tx(execute_calldata, account_address, nonce_manager, max_fee);
     account_tx.execute(state, block_context, true, true).unwrap()
@@ -837,10 +1111,10 @@ fn test_revert_on_overdraft(
 
     // Approve the test contract to transfer funds.
     let approve_calldata = calldata![
-        *block_context.fee_token_address.0.key(), // Contract address.
-        selector_from_name("approve").0,          // EP selector.
-        stark_felt!(3_u8),                        // Calldata length.
-        *contract_address.0.key(),                // Calldata: to.
+        *block_context.fee_token_addresses.eth_fee_token_address.0.key(), // Contract address.
+        selector_from_name("approve").0,                                  // EP selector.
+        stark_felt!(3_u8),                                                // Calldata length.
+        *contract_address.0.key(),                                        // Calldata: to.
         stark_felt!(BALANCE),
         stark_felt!(0_u8)
     ];
@@ -869,7 +1143,12 @@ fn test_revert_on_overdraft(
     let transfer_tx_fee = execution_info.actual_fee;
 
     // Check the current balance, before next transaction.
-    let (balance, _) = state.get_fee_token_balance(&block_context, &account_address).unwrap();
+    let (balance, _) = state
+        .get_fee_token_balance(
+            &account_address,
+            &block_context.fee_token_addresses.eth_fee_token_address,
+        )
+        .unwrap();
 
     // Attempt to transfer the entire balance, such that no funds remain to pay transaction fee.
     // This operation should revert.
@@ -900,11 +1179,176 @@ fn test_revert_on_overdraft(
 
     // Verify balances of both sender and recipient are as expected.
     assert_eq!(
-        state.get_fee_token_balance(&block_context, &account_address).unwrap(),
+        state
+            .get_fee_token_balance(
+                &account_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
         (expected_new_balance, stark_felt!(0_u8))
     );
     assert_eq!(
-        state.get_fee_token_balance(&block_context, &recipient_address).unwrap(),
+        state
+            .get_fee_token_balance(
+                &recipient_address,
+                &block_context.fee_token_addresses.eth_fee_token_address
+            )
+            .unwrap(),
         (final_received_amount, stark_felt!(0_u8))
     );
 }
+
+#[rstest]
+fn test_state_access(
+    max_fee: Fee,
+    block_context: BlockContext,
+    #[from(create_state)] state: CachedState<DictStateReader>,
+) {
+    let TestInitData {
+        mut state,
+        account_address,
+        contract_address,
+        mut nonce_manager,
+        block_context,
+    } = create_test_init_data(max_fee, block_context, state);
+    let storage_key = StorageKey(patricia_key!("0x10"));
+    let nonce = state.get_nonce_at(account_address).unwrap();
+    let (balance, _) = state
+        .get_fee_token_balance(
+            &account_address,
+            &block_context.fee_token_addresses.eth_fee_token_address,
+        )
+        .unwrap();
+
+    let execute_calldata = calldata![
+        *contract_address.0.key(),                       // Contract address.
+        selector_from_name("test_storage_read_write").0, // EP selector.
+        stark_felt!(2_u8),                               // Calldata length.
+        *storage_key.0.key(),                            // Calldata: address.
+        stark_felt!(7_u8)                                // Calldata: value.
+    ];
+    let account_tx =
+        account_invoke_tx(execute_calldata, account_address, &mut nonce_manager, max_fee);
+    let (execution_info, state_access) =
+        account_tx.execute_with_state_access(&mut state, &block_context, true, true).unwrap();
+
+    // Cairo 0 storage writes read the previous value first.
+    assert_eq!(state_access.reads.storage[&(contract_address, storage_key)], stark_felt!(0_u8));
+    assert_eq!(state_access.writes.storage[&(contract_address, storage_key)], stark_felt!(7_u8));
+    assert_eq!(state_access.reads.nonces[&account_address], nonce);
+    assert_eq!(
+        state_access.writes.nonces[&account_address],
+        state.get_nonce_at(account_address).unwrap()
+    );
+    assert!(state_access.reads.class_hashes.contains_key(&account_address));
+
+    // The sender balance is read and changed by the fee transfer.
+    let sender_balance_key =
+        get_storage_var_address("ERC20_balances", &[*account_address.0.key()]).unwrap();
+    let sender_balance_cell =
+        (block_context.fee_token_addresses.eth_fee_token_address, sender_balance_key);
+    let expected_new_balance: StarkFelt = StarkFelt::from(
+        FieldElement::from(balance) - FieldElement::from(execution_info.actual_fee.0),
+    );
+    assert_eq!(state_access.reads.storage[&sender_balance_cell], balance);
+    assert_eq!(state_access.writes.storage[&sender_balance_cell], expected_new_balance);
+}
+
+#[rstest]
+/// Tests that version 3 transactions pay by their L1 gas bounds, instead of by max fee.
+fn test_invoke_v3_resource_bounds(#[from(create_test_init_data)] init_data: TestInitData) {
+    let TestInitData { mut state, account_address, contract_address, block_context, .. } =
+        init_data;
+    // Version 3 transactions pay in STRK, at the STRK gas price.
+    let gas_price = block_context.gas_prices.strk_l1_gas_price;
+    let max_l1_gas_amount =
+        u64::try_from(MAX_FEE / block_context.gas_prices.eth_l1_gas_price).unwrap();
+    let l1_resource_bounds = |max_amount, max_price_per_unit| {
+        ResourceBoundsMapping(BTreeMap::from([(
+            Resource::L1Gas,
+            ResourceBounds { max_amount, max_price_per_unit },
+        )]))
+    };
+
+    // The L1 gas bounds bound the number of steps as a max fee for the same amount of gas.
+    let v1_context = AccountTransactionContext {
+        max_fee: Fee(MAX_FEE),
+        version: TransactionVersion(stark_felt!(1_u8)),
+        ..Default::default()
+    };
+    let v3_context = AccountTransactionContext {
+        version: TransactionVersion(stark_felt!(3_u8)),
+        resource_bounds: l1_resource_bounds(max_l1_gas_amount, gas_price),
+        ..Default::default()
+    };
+    assert_eq!(v3_context.fee_unit(), FeeUnit::Fri);
+    assert_eq!(
+        EntryPointExecutionContext::max_invoke_steps(&block_context, &v3_context),
+        EntryPointExecutionContext::max_invoke_steps(&block_context, &v1_context)
+    );
+
+    let strk_fee_token_address = block_context.fee_token_addresses.strk_fee_token_address;
+    let eth_fee_token_address = block_context.fee_token_addresses.eth_fee_token_address;
+    let (strk_balance, _) =
+        state.get_fee_token_balance(&account_address, &strk_fee_token_address).unwrap();
+    let eth_balance =
+        state.get_fee_token_balance(&account_address, &eth_fee_token_address).unwrap();
+
+    let mut run_invoke_tx_v3 = |resource_bounds: ResourceBoundsMapping| {
+        let tx = InvokeTransactionV3 {
+            resource_bounds,
+            nonce: state.get_nonce_at(account_address).unwrap(),
+            sender_address: account_address,
+            calldata: calldata![
+                *contract_address.0.key(),             // Contract address.
+                selector_from_name("return_result").0, // EP selector.
+                stark_felt!(1_u8),                     // Calldata length.
+                stark_felt!(2_u8)                      // Calldata: num.
+            ],
+            ..Default::default()
+        };
+        let account_tx = AccountTransaction::Invoke(InvokeTransaction {
+            tx: tx.into(),
+            tx_hash: TransactionHash::default(),
+        });
+        account_tx.execute(&mut state, &block_context, true, true)
+    };
+
+    assert_matches!(
+        run_invoke_tx_v3(l1_resource_bounds(1, gas_price)).unwrap_err(),
+        TransactionExecutionError::MaxL1GasAmountTooLow { max_l1_gas_amount: 1, .. }
+    );
+    assert_matches!(
+        run_invoke_tx_v3(l1_resource_bounds(max_l1_gas_amount, gas_price - 1)).unwrap_err(),
+        TransactionExecutionError::MaxL1GasPriceTooLow { actual_l1_gas_price, .. }
+        if actual_l1_gas_price == gas_price
+    );
+    assert_matches!(
+        run_invoke_tx_v3(l1_resource_bounds(u64::MAX, gas_price)).unwrap_err(),
+        TransactionExecutionError::MaxFeeExceedsBalance { .. }
+    );
+
+    // The fee is charged in STRK; the ETH balance is untouched.
+    let tx_execution_info =
+        run_invoke_tx_v3(l1_resource_bounds(max_l1_gas_amount, gas_price)).unwrap();
+    assert!(!tx_execution_info.is_reverted());
+    assert_eq!(tx_execution_info.fee_unit, FeeUnit::Fri);
+    assert_eq!(
+        tx_execution_info.fee_transfer_call_info.unwrap().call.storage_address,
+        strk_fee_token_address
+    );
+    let actual_fee = tx_execution_info.actual_fee;
+    assert!(actual_fee.0 > 0);
+    assert!(actual_fee <= v3_context.fee_upper_bound());
+    assert_eq!(
+        state.get_fee_token_balance(&account_address, &strk_fee_token_address).unwrap(),
+        (
+            StarkFelt::from(FieldElement::from(strk_balance) - FieldElement::from(actual_fee.0)),
+            stark_felt!(0_u8)
+        )
+    );
+    assert_eq!(
+        state.get_fee_token_balance(&account_address, &eth_fee_token_address).unwrap(),
+        eth_balance
+    );
+}
@@ -25,6 +25,8 @@ pub enum TransactionExecutionError {
     EntryPointExecutionError(#[from] EntryPointExecutionError),
     #[error("Transaction execution has failed.")]
     ExecutionError(#[source] EntryPointExecutionError),
+    #[error("Transaction reverted during fee estimation: {revert_error}")]
+    FeeEstimationReverted { revert_error: String },
     #[error("Actual fee ({actual_fee:?}) exceeded max fee ({max_fee:?}).")]
     FeeTransferError { max_fee: Fee, actual_fee: Fee },
     #[error("Actual fee ({actual_fee:?}) exceeded paid fee on L1 ({paid_fee:?}).")]
@@ -50,12 +52,24 @@ pub enum TransactionExecutionError {
     MaxFeeExceedsBalance { max_fee: Fee, balance_low: StarkFelt, balance_high: StarkFelt },
     #[error("Max fee ({max_fee:?}) is too low. Minimum fee: {min_fee:?}.")]
     MaxFeeTooLow { min_fee: Fee, max_fee: Fee },
+    #[error(
+        "Max L1 gas amount ({max_l1_gas_amount}) is lower than the minimal gas amount: \
+         {minimal_l1_gas_amount}."
+    )]
+    MaxL1GasAmountTooLow { max_l1_gas_amount: u64, minimal_l1_gas_amount: u128 },
+    #[error(
+        "Max L1 gas price ({max_l1_gas_price}) is lower than the actual gas price: \
+         {actual_l1_gas_price}."
+    )]
+    MaxL1GasPriceTooLow { max_l1_gas_price: u128, actual_l1_gas_price: u128 },
     #[error(transparent)]
     StarknetApiError(#[from] StarknetApiError),
     #[error(transparent)]
     StateError(#[from] StateError),
     #[error("Calling other contracts during '{entry_point_kind}' execution is forbidden.")]
     UnauthorizedInnerCall { entry_point_kind: String },
+    #[error("Transaction at index {tx_index} has failed: {error}")]
+    TransactionFailed { tx_index: usize, error: Box<TransactionExecutionError> },
     #[error("Unexpected holes in the {object} order. No object with the order: {order}.")]
     UnexpectedHoles { object: String, order: usize },
     #[error("Transaction validation has failed.")]
@@ -1,13 +1,22 @@
 use std::collections::{HashMap, HashSet};
 
 use itertools::concat;
+use serde::{Deserialize, Serialize};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::hash::StarkFelt;
 use starknet_api::stark_felt;
 use starknet_api::transaction::{Fee, TransactionHash, TransactionSignature, TransactionVersion};
 
+use crate::block_context::BlockContext;
 use crate::execution::entry_point::CallInfo;
+use crate::fee::fee_utils::calculate_tx_l1_gas_usage;
+use crate::state::cached_state::CommitmentStateDiff;
 use crate::transaction::errors::TransactionExecutionError;
+use crate::transaction::transaction_types::TransactionType;
+use crate::transaction::versioned_transactions::{
+    AccountDeploymentData, DataAvailabilityMode, PaymasterData, Resource, ResourceBounds,
+    ResourceBoundsMapping, Tip,
+};
 
 pub type TransactionExecutionResult<T> = Result<T, TransactionExecutionError>;
 
@@ -20,16 +29,57 @@ pub struct AccountTransactionContext {
     pub signature: TransactionSignature,
     pub nonce: Nonce,
     pub sender_address: ContractAddress,
+    // Fields of version 3 transactions; have their default values in older versions.
+    pub resource_bounds: ResourceBoundsMapping,
+    pub tip: Tip,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+    pub paymaster_data: PaymasterData,
+    pub account_deployment_data: AccountDeploymentData,
 }
 
 impl AccountTransactionContext {
     pub fn is_v0(&self) -> bool {
         self.version == TransactionVersion(stark_felt!(0_u8))
     }
+
+    pub fn is_v3(&self) -> bool {
+        self.version == TransactionVersion(stark_felt!(3_u8))
+    }
+
+    /// Returns the L1 gas bounds of a version 3 transaction; zero if not given.
+    pub fn l1_resource_bounds(&self) -> ResourceBounds {
+        self.resource_bounds.0.get(&Resource::L1Gas).copied().unwrap_or_default()
+    }
+
+    /// Returns the maximal fee the sender agrees to pay: the max fee, or, in version 3, the
+    /// maximal amount of L1 gas times its maximal price.
+    pub fn fee_upper_bound(&self) -> Fee {
+        if self.is_v3() {
+            let ResourceBounds { max_amount, max_price_per_unit } = self.l1_resource_bounds();
+            Fee(u128::from(max_amount).saturating_mul(max_price_per_unit))
+        } else {
+            self.max_fee
+        }
+    }
+
+    pub fn enforce_fee(&self) -> bool {
+        self.fee_upper_bound() != Fee(0)
+    }
+
+    /// Returns the unit of the fee: version 3 transactions pay in STRK (fri), older ones in ETH
+    /// (wei).
+    pub fn fee_unit(&self) -> FeeUnit {
+        if self.is_v3() {
+            FeeUnit::Fri
+        } else {
+            FeeUnit::Wei
+        }
+    }
 }
 
 /// Contains the information gathered by the execution of a transaction.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TransactionExecutionInfo {
     /// Transaction validation call info; [None] for `L1Handler`.
     pub validate_call_info: Option<CallInfo>,
@@ -37,8 +87,10 @@ pub struct TransactionExecutionInfo {
     pub execute_call_info: Option<CallInfo>,
     /// Fee transfer call info; [None] for `L1Handler`.
     pub fee_transfer_call_info: Option<CallInfo>,
-    /// The actual fee that was charged (in Wei).
+    /// The actual fee that was charged, in `fee_unit`.
     pub actual_fee: Fee,
+    /// The unit of the actual fee; determines the fee token that was charged.
+    pub fee_unit: FeeUnit,
     /// Actual execution resources the transaction is charged for,
     /// including L1 gas and additional OS resources estimation.
     pub actual_resources: ResourcesMapping,
@@ -73,6 +125,96 @@ impl TransactionExecutionInfo {
     }
 }
 
+/// The unit in which a fee is denominated.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeeUnit {
+    /// ETH.
+    #[default]
+    Wei,
+    /// STRK.
+    Fri,
+}
+
+/// The estimated fee of a transaction: the L1 gas it consumes, priced at the block's gas price in
+/// the transaction's fee unit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FeeEstimate {
+    pub overall_fee: Fee,
+    pub gas_consumed: u128,
+    pub gas_price: u128,
+    pub unit: FeeUnit,
+}
+
+impl FeeEstimate {
+    /// Estimates the fee of an executed transaction from the resources it used.
+    pub fn from_execution_info(
+        execution_info: &TransactionExecutionInfo,
+        block_context: &BlockContext,
+    ) -> TransactionExecutionResult<Self> {
+        let gas_consumed =
+            calculate_tx_l1_gas_usage(&execution_info.actual_resources, block_context)?;
+        let unit = execution_info.fee_unit;
+        let gas_price = block_context.gas_prices.get_by_fee_unit(unit);
+
+        Ok(Self { overall_fee: Fee(gas_consumed * gas_price), gas_consumed, gas_price, unit })
+    }
+}
+
+/// The checks to skip when simulating transactions.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SimulationFlags {
+    pub skip_validate: bool,
+    /// Also lifts the bounds the max fee puts on the fee and on the number of steps.
+    pub skip_fee_charge: bool,
+    /// The nonce is still incremented.
+    pub skip_nonce_check: bool,
+}
+
+/// The trace of a transaction execution: its call trees and the state diff it caused.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TransactionTrace {
+    pub tx_type: TransactionType,
+    pub validate_invocation: Option<CallInfo>,
+    pub execute_invocation: Option<CallInfo>,
+    pub fee_transfer_invocation: Option<CallInfo>,
+    pub revert_error: Option<String>,
+    pub state_diff: CommitmentStateDiff,
+    // The class hashes of the contract instances in `state_diff.address_to_class_hash` before the
+    // transaction; 0 for the instances it deployed.
+    pub previous_class_hashes: HashMap<ContractAddress, ClassHash>,
+    // Cairo 0 classes are declared without a compiled class hash, hence not in `state_diff`.
+    pub deprecated_declared_classes: Vec<ClassHash>,
+}
+
+impl TransactionTrace {
+    pub fn new(
+        tx_type: TransactionType,
+        execution_info: TransactionExecutionInfo,
+        state_diff: CommitmentStateDiff,
+        previous_class_hashes: HashMap<ContractAddress, ClassHash>,
+        deprecated_declared_classes: Vec<ClassHash>,
+    ) -> Self {
+        Self {
+            tx_type,
+            validate_invocation: execution_info.validate_call_info,
+            execute_invocation: execution_info.execute_call_info,
+            fee_transfer_invocation: execution_info.fee_transfer_call_info,
+            revert_error: execution_info.revert_error,
+            state_diff,
+            previous_class_hashes,
+            deprecated_declared_classes,
+        }
+    }
+}
+
+/// The result of simulating a transaction.
+#[derive(Debug, Eq, PartialEq)]
+pub struct SimulatedTransaction {
+    pub trace: TransactionTrace,
+    pub fee_estimate: FeeEstimate,
+}
+
 /// A mapping from a transaction execution resource to its actual usage.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ResourcesMapping(pub HashMap<String, usize>);
@@ -0,0 +1,361 @@
+//! The Starknet JSON-RPC format of transaction traces, as returned by `starknet_traceTransaction`
+//! and `starknet_simulateTransactions`.
+
+use std::collections::HashMap;
+
+use cairo_vm::vm::runners::builtin_runner::{
+    BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, KECCAK_BUILTIN_NAME,
+    POSEIDON_BUILTIN_NAME, RANGE_CHECK_BUILTIN_NAME, SEGMENT_ARENA_BUILTIN_NAME,
+    SIGNATURE_BUILTIN_NAME,
+};
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
+use serde::{Deserialize, Serialize, Serializer};
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector, EthAddress, Nonce,
+};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::Calldata;
+
+use crate::execution::entry_point::{CallInfo, CallType};
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::transaction::objects::TransactionTrace;
+use crate::transaction::transaction_types::TransactionType;
+
+#[cfg(test)]
+#[path = "rpc_trace_test.rs"]
+mod test;
+
+/// A call and its inner calls (`FUNCTION_INVOCATION`).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FunctionInvocation {
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: EntryPointSelector,
+    pub calldata: Calldata,
+    pub caller_address: ContractAddress,
+    pub class_hash: ClassHash,
+    pub entry_point_type: EntryPointType,
+    pub call_type: CallType,
+    pub result: Vec<StarkFelt>,
+    pub calls: Vec<FunctionInvocation>,
+    pub events: Vec<InvocationEvent>,
+    pub messages: Vec<InvocationMessage>,
+    pub execution_resources: ComputationResources,
+}
+
+impl From<&CallInfo> for FunctionInvocation {
+    fn from(call_info: &CallInfo) -> Self {
+        let call = &call_info.call;
+        let execution = &call_info.execution;
+        Self {
+            contract_address: call.storage_address,
+            entry_point_selector: call.entry_point_selector,
+            calldata: call.calldata.clone(),
+            caller_address: call.caller_address,
+            class_hash: call.class_hash.unwrap_or_default(),
+            entry_point_type: call.entry_point_type,
+            call_type: call.call_type,
+            result: execution.retdata.0.clone(),
+            calls: call_info.inner_calls.iter().map(Self::from).collect(),
+            events: execution
+                .events
+                .iter()
+                .map(|ordered_event| InvocationEvent {
+                    order: ordered_event.order,
+                    keys: ordered_event.event.keys.iter().map(|key| key.0).collect(),
+                    data: ordered_event.event.data.0.clone(),
+                })
+                .collect(),
+            messages: execution
+                .l2_to_l1_messages
+                .iter()
+                .map(|ordered_message| InvocationMessage {
+                    order: ordered_message.order,
+                    from_address: call.storage_address,
+                    to_address: ordered_message.message.to_address,
+                    payload: ordered_message.message.payload.0.clone(),
+                })
+                .collect(),
+            execution_resources: ComputationResources::from(&call_info.vm_resources),
+        }
+    }
+}
+
+/// An event emitted by a call (`ORDERED_EVENT`).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InvocationEvent {
+    pub order: usize,
+    pub keys: Vec<StarkFelt>,
+    pub data: Vec<StarkFelt>,
+}
+
+/// An L2-to-L1 message sent by a call (`ORDERED_MESSAGE`).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InvocationMessage {
+    pub order: usize,
+    pub from_address: ContractAddress,
+    pub to_address: EthAddress,
+    pub payload: Vec<StarkFelt>,
+}
+
+/// The Cairo resources used by a call (`COMPUTATION_RESOURCES`); unused resources are omitted.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ComputationResources {
+    pub steps: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_holes: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_check_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pedersen_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poseidon_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ec_op_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecdsa_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitwise_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keccak_builtin_applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_arena_builtin: Option<usize>,
+}
+
+impl From<&VmExecutionResources> for ComputationResources {
+    fn from(vm_resources: &VmExecutionResources) -> Self {
+        let used = |n: usize| Some(n).filter(|&n| n > 0);
+        let builtin = |name: &str| {
+            used(vm_resources.builtin_instance_counter.get(name).copied().unwrap_or_default())
+        };
+        Self {
+            steps: vm_resources.n_steps,
+            memory_holes: used(vm_resources.n_memory_holes),
+            range_check_builtin_applications: builtin(RANGE_CHECK_BUILTIN_NAME),
+            pedersen_builtin_applications: builtin(HASH_BUILTIN_NAME),
+            poseidon_builtin_applications: builtin(POSEIDON_BUILTIN_NAME),
+            ec_op_builtin_applications: builtin(EC_OP_BUILTIN_NAME),
+            ecdsa_builtin_applications: builtin(SIGNATURE_BUILTIN_NAME),
+            bitwise_builtin_applications: builtin(BITWISE_BUILTIN_NAME),
+            keccak_builtin_applications: builtin(KECCAK_BUILTIN_NAME),
+            segment_arena_builtin: builtin(SEGMENT_ARENA_BUILTIN_NAME),
+        }
+    }
+}
+
+/// The execution of an invoke transaction: its call, or the reason it was reverted.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ExecuteInvocation {
+    Invocation(Box<FunctionInvocation>),
+    Reverted { revert_reason: String },
+}
+
+/// The trace of a transaction (`TRANSACTION_TRACE`).
+// Only used for (de)serialization, hence the variants are not boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum RpcTransactionTrace {
+    #[serde(rename = "INVOKE")]
+    Invoke {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        execute_invocation: Option<ExecuteInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_diff: Option<RpcStateDiff>,
+    },
+    #[serde(rename = "DECLARE")]
+    Declare {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_diff: Option<RpcStateDiff>,
+    },
+    /// Not part of the specification, which postdates deprecated Deploy transactions.
+    #[serde(rename = "DEPLOY")]
+    Deploy {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        constructor_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_diff: Option<RpcStateDiff>,
+    },
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        validate_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        constructor_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fee_transfer_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_diff: Option<RpcStateDiff>,
+    },
+    #[serde(rename = "L1_HANDLER")]
+    L1Handler {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        function_invocation: Option<FunctionInvocation>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state_diff: Option<RpcStateDiff>,
+    },
+}
+
+impl From<&TransactionTrace> for RpcTransactionTrace {
+    fn from(trace: &TransactionTrace) -> Self {
+        let validate_invocation = trace.validate_invocation.as_ref().map(FunctionInvocation::from);
+        let execute_invocation = trace.execute_invocation.as_ref().map(FunctionInvocation::from);
+        let fee_transfer_invocation =
+            trace.fee_transfer_invocation.as_ref().map(FunctionInvocation::from);
+        let state_diff = Some(RpcStateDiff::new(
+            &trace.state_diff,
+            &trace.previous_class_hashes,
+            &trace.deprecated_declared_classes,
+        ));
+
+        match trace.tx_type {
+            TransactionType::InvokeFunction => Self::Invoke {
+                validate_invocation,
+                execute_invocation: match &trace.revert_error {
+                    Some(revert_error) => {
+                        Some(ExecuteInvocation::Reverted { revert_reason: revert_error.clone() })
+                    }
+                    None => execute_invocation
+                        .map(|invocation| ExecuteInvocation::Invocation(Box::new(invocation))),
+                },
+                fee_transfer_invocation,
+                state_diff,
+            },
+            TransactionType::Declare => {
+                Self::Declare { validate_invocation, fee_transfer_invocation, state_diff }
+            }
+            TransactionType::Deploy => {
+                Self::Deploy { constructor_invocation: execute_invocation, state_diff }
+            }
+            TransactionType::DeployAccount => Self::DeployAccount {
+                validate_invocation,
+                constructor_invocation: execute_invocation,
+                fee_transfer_invocation,
+                state_diff,
+            },
+            TransactionType::L1Handler => {
+                Self::L1Handler { function_invocation: execute_invocation, state_diff }
+            }
+        }
+    }
+}
+
+/// Serializes the trace in the JSON-RPC format; see `RpcTransactionTrace`.
+impl Serialize for TransactionTrace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RpcTransactionTrace::from(self).serialize(serializer)
+    }
+}
+
+/// The state diff of a transaction (`STATE_DIFF`).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RpcStateDiff {
+    pub storage_diffs: Vec<StorageDiff>,
+    pub deprecated_declared_classes: Vec<ClassHash>,
+    pub declared_classes: Vec<DeclaredClass>,
+    pub deployed_contracts: Vec<DeployedContract>,
+    pub replaced_classes: Vec<ReplacedClass>,
+    pub nonces: Vec<ContractNonce>,
+}
+
+impl RpcStateDiff {
+    /// Class hash changes of contract instances whose previous class hash (see
+    /// `TransactionTrace::previous_class_hashes`) is 0 are reported as deployed contracts, and
+    /// the others as replaced classes.
+    pub fn new(
+        state_diff: &CommitmentStateDiff,
+        previous_class_hashes: &HashMap<ContractAddress, ClassHash>,
+        deprecated_declared_classes: &[ClassHash],
+    ) -> Self {
+        let (deployed_contracts, replaced_classes): (Vec<_>, Vec<_>) =
+            state_diff.address_to_class_hash.iter().partition(|(address, _)| {
+                previous_class_hashes.get(address).copied().unwrap_or_default()
+                    == ClassHash::default()
+            });
+
+        Self {
+            storage_diffs: state_diff
+                .storage_updates
+                .iter()
+                .map(|(&address, storage_updates)| StorageDiff {
+                    address,
+                    storage_entries: storage_updates
+                        .iter()
+                        .map(|(&key, &value)| StorageEntry { key, value })
+                        .collect(),
+                })
+                .collect(),
+            deprecated_declared_classes: deprecated_declared_classes.to_vec(),
+            declared_classes: state_diff
+                .class_hash_to_compiled_class_hash
+                .iter()
+                .map(|(&class_hash, &compiled_class_hash)| DeclaredClass {
+                    class_hash,
+                    compiled_class_hash,
+                })
+                .collect(),
+            deployed_contracts: deployed_contracts
+                .into_iter()
+                .map(|(&address, &class_hash)| DeployedContract { address, class_hash })
+                .collect(),
+            replaced_classes: replaced_classes
+                .into_iter()
+                .map(|(&contract_address, &class_hash)| ReplacedClass {
+                    contract_address,
+                    class_hash,
+                })
+                .collect(),
+            nonces: state_diff
+                .address_to_nonce
+                .iter()
+                .map(|(&contract_address, &nonce)| ContractNonce { contract_address, nonce })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StorageDiff {
+    pub address: ContractAddress,
+    pub storage_entries: Vec<StorageEntry>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StorageEntry {
+    pub key: StorageKey,
+    pub value: StarkFelt,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DeclaredClass {
+    pub class_hash: ClassHash,
+    pub compiled_class_hash: CompiledClassHash,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DeployedContract {
+    pub address: ContractAddress,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReplacedClass {
+    pub contract_address: ContractAddress,
+    pub class_hash: ClassHash,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContractNonce {
+    pub contract_address: ContractAddress,
+    pub nonce: Nonce,
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use cairo_vm::vm::runners::builtin_runner::RANGE_CHECK_BUILTIN_NAME;
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
+use indexmap::indexmap;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use starknet_api::core::{
+    ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector, EthAddress, Nonce,
+    PatriciaKey,
+};
+use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_api::hash::{StarkFelt, StarkHash};
+use starknet_api::state::StorageKey;
+use starknet_api::transaction::{Calldata, EventContent, EventData, EventKey, L2ToL1Payload};
+use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
+
+use crate::execution::entry_point::{
+    CallEntryPoint, CallExecution, CallInfo, CallType, MessageToL1, OrderedEvent,
+    OrderedL2ToL1Message, Retdata,
+};
+use crate::retdata;
+use crate::state::cached_state::CommitmentStateDiff;
+use crate::transaction::objects::{ResourcesMapping, TransactionExecutionInfo, TransactionTrace};
+use crate::transaction::rpc_trace::RpcTransactionTrace;
+use crate::transaction::transaction_types::TransactionType;
+
+fn call_info() -> CallInfo {
+    CallInfo {
+        call: CallEntryPoint {
+            class_hash: Some(class_hash!("0x10")),
+            entry_point_type: EntryPointType::External,
+            entry_point_selector: EntryPointSelector(stark_felt!("0x20")),
+            calldata: calldata![stark_felt!(1_u8), stark_felt!(2_u8)],
+            storage_address: contract_address!("0x30"),
+            caller_address: contract_address!("0x40"),
+            ..Default::default()
+        },
+        execution: CallExecution {
+            retdata: retdata![stark_felt!(3_u8)],
+            events: vec![OrderedEvent {
+                order: 1,
+                event: EventContent {
+                    keys: vec![EventKey(stark_felt!(4_u8))],
+                    data: EventData(vec![stark_felt!(5_u8)]),
+                },
+            }],
+            l2_to_l1_messages: vec![OrderedL2ToL1Message {
+                order: 0,
+                message: MessageToL1 {
+                    to_address: EthAddress::try_from(stark_felt!(6_u8)).unwrap(),
+                    payload: L2ToL1Payload(vec![stark_felt!(7_u8)]),
+                },
+            }],
+            ..Default::default()
+        },
+        vm_resources: VmExecutionResources {
+            n_steps: 100,
+            n_memory_holes: 0,
+            builtin_instance_counter: HashMap::from([(RANGE_CHECK_BUILTIN_NAME.to_string(), 2)]),
+        },
+        inner_calls: vec![CallInfo {
+            call: CallEntryPoint {
+                class_hash: Some(class_hash!("0x11")),
+                storage_address: contract_address!("0x30"),
+                caller_address: contract_address!("0x30"),
+                call_type: CallType::Delegate,
+                ..Default::default()
+            },
+            vm_resources: VmExecutionResources { n_steps: 10, ..Default::default() },
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn function_invocation_json() -> serde_json::Value {
+    json!({
+        "contract_address": "0x30",
+        "entry_point_selector": "0x20",
+        "calldata": ["0x1", "0x2"],
+        "caller_address": "0x40",
+        "class_hash": "0x10",
+        "entry_point_type": "EXTERNAL",
+        "call_type": "CALL",
+        "result": ["0x3"],
+        "calls": [{
+            "contract_address": "0x30",
+            "entry_point_selector": "0x0",
+            "calldata": [],
+            "caller_address": "0x30",
+            "class_hash": "0x11",
+            "entry_point_type": "EXTERNAL",
+            "call_type": "LIBRARY_CALL",
+            "result": [],
+            "calls": [],
+            "events": [],
+            "messages": [],
+            "execution_resources": { "steps": 10 },
+        }],
+        "events": [{ "order": 1, "keys": ["0x4"], "data": ["0x5"] }],
+        "messages": [
+            { "order": 0, "from_address": "0x30", "to_address": "0x6", "payload": ["0x7"] },
+        ],
+        "execution_resources": { "steps": 100, "range_check_builtin_applications": 2 },
+    })
+}
+
+#[test]
+fn invoke_trace() {
+    let trace = TransactionTrace {
+        tx_type: TransactionType::InvokeFunction,
+        validate_invocation: None,
+        execute_invocation: Some(call_info()),
+        fee_transfer_invocation: None,
+        revert_error: None,
+        state_diff: CommitmentStateDiff {
+            address_to_class_hash: indexmap! {
+                contract_address!("0x30") => class_hash!("0x10"),
+                contract_address!("0x31") => class_hash!("0x13"),
+            },
+            address_to_nonce: indexmap! { contract_address!("0x40") => Nonce(stark_felt!(1_u8)) },
+            storage_updates: indexmap! {
+                contract_address!("0x30") => indexmap! {
+                    StorageKey(patricia_key!("0x50")) => stark_felt!(8_u8),
+                },
+            },
+            class_hash_to_compiled_class_hash: indexmap! {
+                class_hash!("0x11") => CompiledClassHash(stark_felt!("0x12")),
+            },
+        },
+        previous_class_hashes: HashMap::from([
+            (contract_address!("0x30"), ClassHash::default()),
+            (contract_address!("0x31"), class_hash!("0x14")),
+        ]),
+        deprecated_declared_classes: vec![],
+    };
+
+    let trace_json = serde_json::to_value(&trace).unwrap();
+    assert_eq!(
+        trace_json,
+        json!({
+            "type": "INVOKE",
+            "execute_invocation": function_invocation_json(),
+            "state_diff": {
+                "storage_diffs": [{
+                    "address": "0x30",
+                    "storage_entries": [{ "key": "0x50", "value": "0x8" }],
+                }],
+                "deprecated_declared_classes": [],
+                "declared_classes": [{ "class_hash": "0x11", "compiled_class_hash": "0x12" }],
+                "deployed_contracts": [{ "address": "0x30", "class_hash": "0x10" }],
+                "replaced_classes": [{ "contract_address": "0x31", "class_hash": "0x13" }],
+                "nonces": [{ "contract_address": "0x40", "nonce": "0x1" }],
+            },
+        })
+    );
+    assert_eq!(
+        serde_json::from_value::<RpcTransactionTrace>(trace_json).unwrap(),
+        RpcTransactionTrace::from(&trace)
+    );
+}
+
+#[test]
+fn reverted_invoke_and_l1_handler_traces() {
+    let reverted_trace = TransactionTrace {
+        tx_type: TransactionType::InvokeFunction,
+        validate_invocation: Some(call_info()),
+        execute_invocation: None,
+        fee_transfer_invocation: Some(call_info()),
+        revert_error: Some(String::from("Execution failed.")),
+        state_diff: CommitmentStateDiff {
+            address_to_class_hash: indexmap! {},
+            address_to_nonce: indexmap! {},
+            storage_updates: indexmap! {},
+            class_hash_to_compiled_class_hash: indexmap! {},
+        },
+        previous_class_hashes: HashMap::new(),
+        deprecated_declared_classes: vec![],
+    };
+    let trace_json = serde_json::to_value(&reverted_trace).unwrap();
+    assert_eq!(trace_json["validate_invocation"], function_invocation_json());
+    assert_eq!(trace_json["execute_invocation"], json!({ "revert_reason": "Execution failed." }));
+    assert_eq!(trace_json["fee_transfer_invocation"], function_invocation_json());
+    assert_eq!(
+        serde_json::from_value::<RpcTransactionTrace>(trace_json).unwrap(),
+        RpcTransactionTrace::from(&reverted_trace)
+    );
+
+    let l1_handler_trace = TransactionTrace {
+        tx_type: TransactionType::L1Handler,
+        execute_invocation: Some(call_info()),
+        revert_error: None,
+        ..reverted_trace
+    };
+    let trace_json = serde_json::to_value(&l1_handler_trace).unwrap();
+    assert_eq!(trace_json["type"], "L1_HANDLER");
+    assert_eq!(trace_json["function_invocation"], function_invocation_json());
+    assert!(trace_json.get("validate_invocation").is_none());
+
+    let declare_trace = TransactionTrace {
+        tx_type: TransactionType::Declare,
+        deprecated_declared_classes: vec![class_hash!("0x15")],
+        ..l1_handler_trace
+    };
+    let trace_json = serde_json::to_value(&declare_trace).unwrap();
+    assert_eq!(trace_json["type"], "DECLARE");
+    assert_eq!(trace_json["state_diff"]["deprecated_declared_classes"], json!(["0x15"]));
+}
+
+#[test]
+fn execution_info_serde_round_trip() {
+    let execution_info = TransactionExecutionInfo {
+        execute_call_info: Some(call_info()),
+        actual_resources: ResourcesMapping(HashMap::from([(String::from("n_steps"), 100)])),
+        revert_error: Some(String::from("Execution failed.")),
+        ..Default::default()
+    };
+
+    let serialized = serde_json::to_string(&execution_info).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TransactionExecutionInfo>(&serialized).unwrap(),
+        execution_info
+    );
+}
@@ -21,12 +21,12 @@ use crate::test_utils::{
     TEST_FAULTY_ACCOUNT_CONTRACT_CAIRO0_PATH, TEST_FAULTY_ACCOUNT_CONTRACT_CLASS_HASH,
 };
 use crate::transaction::account_transaction::AccountTransaction;
-use crate::transaction::constants;
-use crate::transaction::objects::{TransactionExecutionInfo, TransactionExecutionResult};
+use crate::transaction::objects::{FeeUnit, TransactionExecutionInfo, TransactionExecutionResult};
 use crate::transaction::transaction_types::TransactionType;
 use crate::transaction::transactions::{
     DeclareTransaction, ExecutableTransaction, InvokeTransaction,
 };
+use crate::transaction::{constants, versioned_transactions};
 
 // Corresponding constants to the ones in faulty_account.
 pub const VALID: u64 = 0;
@@ -35,10 +35,7 @@ pub const CALL_CONTRACT: u64 = 2;
 
 impl From<InvokeTransactionV1> for InvokeTransaction {
     fn from(tx: InvokeTransactionV1) -> Self {
-        InvokeTransaction {
-            tx: starknet_api::transaction::InvokeTransaction::V1(tx),
-            tx_hash: TransactionHash::default(),
-        }
+        InvokeTransaction { tx: tx.into(), tx_hash: TransactionHash::default() }
     }
 }
 
@@ -65,19 +62,23 @@ pub fn create_account_tx_test_state(
     // A random address that is unlikely to equal the result of the calculation of a contract
     // address.
     let test_account_address = contract_address!(account_address);
-    let test_erc20_address = block_context.fee_token_address;
-    let address_to_class_hash = HashMap::from([
+    let mut address_to_class_hash = HashMap::from([
         (test_contract_address, test_contract_class_hash),
         (test_account_address, test_account_class_hash),
-        (test_erc20_address, test_erc20_class_hash),
     ]);
     let minter_var_address = get_storage_var_address("permitted_minter", &[])
         .expect("Failed to get permitted_minter storage address.");
-    let storage_view = HashMap::from([
-        ((test_erc20_address, erc20_account_balance_key), stark_felt!(initial_account_balance)),
-        // Give the account mint permission.
-        ((test_erc20_address, minter_var_address), *test_account_address.0.key()),
-    ]);
+    let mut storage_view = HashMap::new();
+    // Both fee tokens are deployed, and the account is funded in each of them.
+    for fee_unit in [FeeUnit::Wei, FeeUnit::Fri] {
+        let test_erc20_address = block_context.fee_token_address(fee_unit);
+        address_to_class_hash.insert(test_erc20_address, test_erc20_class_hash);
+        storage_view.extend([
+            ((test_erc20_address, erc20_account_balance_key), stark_felt!(initial_account_balance)),
+            // Give the account mint permission.
+            ((test_erc20_address, minter_var_address), *test_account_address.0.key()),
+        ]);
+    }
     CachedState::from(DictStateReader {
         address_to_class_hash,
         class_hash_to_class,
@@ -146,7 +147,7 @@ pub fn create_account_tx_for_validate_test(
 
             AccountTransaction::Declare(
                 DeclareTransaction::new(
-                    starknet_api::transaction::DeclareTransaction::V1(declare_tx),
+                    versioned_transactions::DeclareTransaction::V1(declare_tx),
                     TransactionHash::default(),
                     contract_class,
                 )
@@ -178,7 +179,7 @@ pub fn create_account_tx_for_validate_test(
             );
             AccountTransaction::Invoke(invoke_tx.into())
         }
-        TransactionType::L1Handler => unimplemented!(),
+        TransactionType::Deploy | TransactionType::L1Handler => unimplemented!(),
     }
 }
 
@@ -8,23 +8,25 @@ use crate::block_context::BlockContext;
 use crate::execution::contract_class::ContractClass;
 use crate::execution::entry_point::{EntryPointExecutionContext, ExecutionResources};
 use crate::fee::fee_utils::calculate_tx_fee;
-use crate::state::cached_state::{StateChangesCount, TransactionalState};
-use crate::state::state_api::StateReader;
+use crate::state::cached_state::{CachedState, StateChangesCount, TransactionalState};
+use crate::state::state_api::{State, StateReader};
 use crate::transaction::account_transaction::AccountTransaction;
 use crate::transaction::errors::TransactionExecutionError;
 use crate::transaction::objects::{
-    AccountTransactionContext, TransactionExecutionInfo, TransactionExecutionResult,
+    AccountTransactionContext, FeeEstimate, FeeUnit, SimulatedTransaction, SimulationFlags,
+    TransactionExecutionInfo, TransactionExecutionResult, TransactionTrace,
 };
 use crate::transaction::transaction_types::TransactionType;
 use crate::transaction::transaction_utils::{calculate_l1_gas_usage, calculate_tx_resources};
 use crate::transaction::transactions::{
-    DeclareTransaction, DeployAccountTransaction, Executable, ExecutableTransaction,
-    InvokeTransaction, L1HandlerTransaction,
+    DeclareTransaction, DeployAccountTransaction, DeployTransaction, Executable,
+    ExecutableTransaction, InvokeTransaction, L1HandlerTransaction,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Transaction {
     AccountTransaction(AccountTransaction),
+    DeployTransaction(DeployTransaction),
     L1HandlerTransaction(L1HandlerTransaction),
 }
 
@@ -33,6 +35,73 @@ impl Transaction {
     pub fn initial_gas() -> u64 {
         abi_constants::INITIAL_GAS_COST - abi_constants::TRANSACTION_GAS_COST
     }
+
+    pub fn tx_type(&self) -> TransactionType {
+        match self {
+            Self::AccountTransaction(account_tx) => account_tx.tx_type(),
+            Self::DeployTransaction(_) => TransactionType::Deploy,
+            Self::L1HandlerTransaction(_) => TransactionType::L1Handler,
+        }
+    }
+
+    /// Simulates the transaction on top of the given state, to which its changes are applied.
+    pub fn simulate_raw<S: StateReader>(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        flags: SimulationFlags,
+    ) -> TransactionExecutionResult<SimulatedTransaction> {
+        let tx_type = self.tx_type();
+        let deprecated_declared_class_hash = match &self {
+            Self::AccountTransaction(AccountTransaction::Declare(declare_tx))
+                if declare_tx.tx().compiled_class_hash().is_none() =>
+            {
+                Some(declare_tx.class_hash())
+            }
+            _ => None,
+        };
+        let mut tx_state = state.create_transactional();
+        let execution_info = self.execute_raw_with_flags(&mut tx_state, block_context, flags)?;
+        let state_diff = tx_state.to_state_diff();
+        let previous_class_hashes = tx_state
+            .get_previous_class_hashes()?
+            .into_iter()
+            .filter(|(address, _)| state_diff.address_to_class_hash.contains_key(address))
+            .collect();
+        tx_state.commit();
+
+        let fee_estimate = FeeEstimate::from_execution_info(&execution_info, block_context)?;
+        let trace = TransactionTrace::new(
+            tx_type,
+            execution_info,
+            state_diff,
+            previous_class_hashes,
+            deprecated_declared_class_hash.into_iter().collect(),
+        );
+        Ok(SimulatedTransaction { trace, fee_estimate })
+    }
+}
+
+/// Simulates a sequence of transactions, each executed on top of the ones before it, without
+/// modifying the given state. On failure, returns the index of the failing transaction.
+pub fn simulate<S: StateReader>(
+    txs: impl IntoIterator<Item = Transaction>,
+    state: &mut CachedState<S>,
+    block_context: &BlockContext,
+    flags: SimulationFlags,
+) -> TransactionExecutionResult<Vec<SimulatedTransaction>> {
+    let mut transactional_state = CachedState::create_transactional(state);
+    let simulated_txs = txs
+        .into_iter()
+        .enumerate()
+        .map(|(tx_index, tx)| {
+            tx.simulate_raw(&mut transactional_state, block_context, flags).map_err(|error| {
+                TransactionExecutionError::TransactionFailed { tx_index, error: Box::new(error) }
+            })
+        })
+        .collect();
+    transactional_state.abort();
+    simulated_txs
 }
 
 impl Transaction {
@@ -54,11 +123,28 @@ impl Transaction {
             }
             StarknetApiTransaction::Declare(declare) => {
                 Ok(Self::AccountTransaction(AccountTransaction::Declare(DeclareTransaction::new(
-                    declare,
+                    declare.into(),
                     tx_hash,
                     contract_class.expect("Declare should be created with a ContractClass"),
                 )?)))
             }
+            StarknetApiTransaction::Deploy(deploy) => {
+                let contract_address = match deployed_contract_address {
+                    Some(address) => address,
+                    None => calculate_contract_address(
+                        deploy.contract_address_salt,
+                        deploy.class_hash,
+                        &deploy.constructor_calldata,
+                        ContractAddress::default(),
+                    )?,
+                };
+
+                Ok(Self::DeployTransaction(DeployTransaction {
+                    tx: deploy,
+                    tx_hash,
+                    contract_address,
+                }))
+            }
             StarknetApiTransaction::DeployAccount(deploy_account) => {
                 let contract_address = match deployed_contract_address {
                     Some(address) => address,
@@ -71,27 +157,30 @@ impl Transaction {
                 };
 
                 Ok(Self::AccountTransaction(AccountTransaction::DeployAccount(
-                    DeployAccountTransaction { tx: deploy_account, tx_hash, contract_address },
+                    DeployAccountTransaction {
+                        tx: deploy_account.into(),
+                        tx_hash,
+                        contract_address,
+                    },
                 )))
             }
             StarknetApiTransaction::Invoke(invoke) => {
                 Ok(Self::AccountTransaction(AccountTransaction::Invoke(InvokeTransaction {
-                    tx: invoke,
+                    tx: invoke.into(),
                     tx_hash,
                 })))
             }
-            _ => unimplemented!(),
         }
     }
 }
 
-impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
-    fn execute_raw(
+impl L1HandlerTransaction {
+    /// Runs the transaction; the fee paid on L1 is checked unless estimating the fee.
+    fn run<S: StateReader>(
         self,
         state: &mut TransactionalState<'_, S>,
         block_context: &BlockContext,
-        _charge_fee: bool,
-        _validate: bool,
+        is_fee_estimation: bool,
     ) -> TransactionExecutionResult<TransactionExecutionInfo> {
         let tx = &self.tx;
         let tx_context = AccountTransactionContext {
@@ -101,6 +190,7 @@ impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
             signature: TransactionSignature::default(),
             nonce: tx.nonce,
             sender_address: tx.contract_address,
+            ..Default::default()
         };
         let mut resources = ExecutionResources::default();
         let mut context = EntryPointExecutionContext::new_invoke(block_context, &tx_context);
@@ -112,8 +202,12 @@ impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
             if let Some(call_info) = execute_call_info.as_ref() { vec![call_info] } else { vec![] };
         // The calldata includes the "from" field, which is not a part of the payload.
         let l1_handler_payload_size = Some(tx.calldata.0.len() - 1);
-        let state_changes =
-            state.get_actual_state_changes_for_fee_charge(block_context.fee_token_address, None)?;
+        // L1 handler transactions are paid for on L1, in ETH.
+        let fee_unit = FeeUnit::Wei;
+        let state_changes = state.get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_address(fee_unit),
+            None,
+        )?;
         let l1_gas_usage = calculate_l1_gas_usage(
             &call_infos,
             StateChangesCount::from(&state_changes),
@@ -121,11 +215,11 @@ impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
         )?;
         let actual_resources =
             calculate_tx_resources(&resources, l1_gas_usage, TransactionType::L1Handler)?;
-        let actual_fee = calculate_tx_fee(&actual_resources, &context.block_context)?;
+        let actual_fee = calculate_tx_fee(&actual_resources, &context.block_context, fee_unit)?;
         let paid_fee = self.paid_fee_on_l1;
         // For now, assert only that any amount of fee was paid.
         // The error message still indicates the required fee.
-        if paid_fee == Fee(0) {
+        if !is_fee_estimation && paid_fee == Fee(0) {
             return Err(TransactionExecutionError::InsufficientL1Fee { paid_fee, actual_fee });
         }
 
@@ -134,12 +228,98 @@ impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
             execute_call_info,
             fee_transfer_call_info: None,
             actual_fee: Fee::default(),
+            fee_unit,
+            actual_resources,
+            revert_error: None,
+        })
+    }
+}
+
+impl<S: StateReader> ExecutableTransaction<S> for L1HandlerTransaction {
+    fn execute_raw(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        _charge_fee: bool,
+        _validate: bool,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        self.run(state, block_context, false)
+    }
+
+    fn execute_raw_with_flags(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        flags: SimulationFlags,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        self.run(state, block_context, flags.skip_fee_charge)
+    }
+}
+
+impl DeployTransaction {
+    /// Deploys the contract and runs its constructor; Deploy transactions are neither validated
+    /// nor charged.
+    fn run<S: StateReader>(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        let tx_context = AccountTransactionContext {
+            transaction_hash: self.tx_hash,
+            version: self.tx.version,
+            ..Default::default()
+        };
+        let mut resources = ExecutionResources::default();
+        let mut context = EntryPointExecutionContext::new_invoke(block_context, &tx_context);
+        let mut remaining_gas = Transaction::initial_gas();
+        let execute_call_info =
+            self.run_execute(state, &mut resources, &mut context, &mut remaining_gas)?;
+
+        let call_infos =
+            if let Some(call_info) = execute_call_info.as_ref() { vec![call_info] } else { vec![] };
+        let fee_unit = tx_context.fee_unit();
+        let state_changes = state.get_actual_state_changes_for_fee_charge(
+            block_context.fee_token_address(fee_unit),
+            None,
+        )?;
+        let l1_gas_usage =
+            calculate_l1_gas_usage(&call_infos, StateChangesCount::from(&state_changes), None)?;
+        let actual_resources =
+            calculate_tx_resources(&resources, l1_gas_usage, TransactionType::Deploy)?;
+
+        Ok(TransactionExecutionInfo {
+            validate_call_info: None,
+            execute_call_info,
+            fee_transfer_call_info: None,
+            actual_fee: Fee::default(),
+            fee_unit,
             actual_resources,
             revert_error: None,
         })
     }
 }
 
+impl<S: StateReader> ExecutableTransaction<S> for DeployTransaction {
+    fn execute_raw(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        _charge_fee: bool,
+        _validate: bool,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        self.run(state, block_context)
+    }
+
+    fn execute_raw_with_flags(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        _flags: SimulationFlags,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        self.run(state, block_context)
+    }
+}
+
 impl<S: StateReader> ExecutableTransaction<S> for Transaction {
     fn execute_raw(
         self,
@@ -152,9 +332,29 @@ impl<S: StateReader> ExecutableTransaction<S> for Transaction {
             Self::AccountTransaction(account_tx) => {
                 account_tx.execute_raw(state, block_context, charge_fee, validate)
             }
+            Self::DeployTransaction(tx) => {
+                tx.execute_raw(state, block_context, charge_fee, validate)
+            }
             Self::L1HandlerTransaction(tx) => {
                 tx.execute_raw(state, block_context, charge_fee, validate)
             }
         }
     }
+
+    fn execute_raw_with_flags(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        flags: SimulationFlags,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo> {
+        match self {
+            Self::AccountTransaction(account_tx) => {
+                account_tx.execute_raw_with_flags(state, block_context, flags)
+            }
+            Self::DeployTransaction(tx) => tx.execute_raw_with_flags(state, block_context, flags),
+            Self::L1HandlerTransaction(tx) => {
+                tx.execute_raw_with_flags(state, block_context, flags)
+            }
+        }
+    }
 }
@@ -4,6 +4,7 @@ use strum_macros::EnumIter;
 #[derive(Clone, Copy, Debug, Deserialize, EnumIter, Eq, Hash, PartialEq)]
 pub enum TransactionType {
     Declare,
+    Deploy,
     DeployAccount,
     InvokeFunction,
     L1Handler,
@@ -14,28 +14,22 @@ use crate::execution::entry_point::{
     ExecutionResources,
 };
 use crate::execution::execution_utils::execute_deployment;
-use crate::state::cached_state::{CachedState, TransactionalState};
+use crate::state::cached_state::{CachedState, StateAccess, TransactionalState};
 use crate::state::errors::StateError;
 use crate::state::state_api::{State, StateReader};
-use crate::transaction::constants;
 use crate::transaction::errors::TransactionExecutionError;
-use crate::transaction::objects::{TransactionExecutionInfo, TransactionExecutionResult};
+use crate::transaction::objects::{
+    FeeEstimate, SimulationFlags, TransactionExecutionInfo, TransactionExecutionResult,
+};
 use crate::transaction::transaction_utils::{
     update_remaining_gas, verify_no_calls_to_other_contracts,
 };
+use crate::transaction::{constants, versioned_transactions};
 
 #[cfg(test)]
 #[path = "transactions_test.rs"]
 mod test;
 
-macro_rules! implement_inner_tx_getters {
-    ($(($field:ident, $field_type:ty)),*) => {
-        $(pub fn $field(&self) -> $field_type {
-            self.tx.$field.clone()
-        })*
-    };
-}
-
 macro_rules! implement_inner_tx_getter_calls {
     ($(($field:ident, $field_type:ty)),*) => {
         $(pub fn $field(&self) -> $field_type {
@@ -54,23 +48,24 @@ pub trait ExecutableTransaction<S: StateReader>: Sized {
         charge_fee: bool,
         validate: bool,
     ) -> TransactionExecutionResult<TransactionExecutionInfo> {
-        log::debug!("Executing Transaction...");
-        let mut transactional_state = CachedState::create_transactional(state);
-        let execution_result =
-            self.execute_raw(&mut transactional_state, block_context, charge_fee, validate);
-
-        match execution_result {
-            Ok(value) => {
-                transactional_state.commit();
-                log::debug!("Transaction execution complete and committed.");
-                Ok(value)
-            }
-            Err(error) => {
-                log::debug!("Transaction execution failed with: {error}");
-                transactional_state.abort();
-                Err(error)
-            }
-        }
+        execute_transactionally(state, |transactional_state| {
+            self.execute_raw(transactional_state, block_context, charge_fee, validate)
+        })
+    }
+
+    /// Same as `execute`, but also returns the state cells read and changed by the transaction.
+    fn execute_with_state_access(
+        self,
+        state: &mut CachedState<S>,
+        block_context: &BlockContext,
+        charge_fee: bool,
+        validate: bool,
+    ) -> TransactionExecutionResult<(TransactionExecutionInfo, StateAccess)> {
+        execute_transactionally(state, |transactional_state| {
+            let execution_info =
+                self.execute_raw(transactional_state, block_context, charge_fee, validate)?;
+            Ok((execution_info, transactional_state.get_state_access()?))
+        })
     }
 
     /// Executes the transaction in a transactional manner
@@ -82,6 +77,99 @@ pub trait ExecutableTransaction<S: StateReader>: Sized {
         charge_fee: bool,
         validate: bool,
     ) -> TransactionExecutionResult<TransactionExecutionInfo>;
+
+    /// Estimates the fee of the transaction, without modifying the given state.
+    fn estimate_fee(
+        self,
+        state: &mut CachedState<S>,
+        block_context: &BlockContext,
+        validate: bool,
+    ) -> TransactionExecutionResult<FeeEstimate> {
+        let mut transactional_state = CachedState::create_transactional(state);
+        let fee_estimate = self.estimate_fee_raw(&mut transactional_state, block_context, validate);
+        transactional_state.abort();
+        fee_estimate
+    }
+
+    /// Executes the transaction for fee estimation and returns its estimated fee; the changes of
+    /// the execution remain in the given state.
+    fn estimate_fee_raw(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        validate: bool,
+    ) -> TransactionExecutionResult<FeeEstimate> {
+        let flags = SimulationFlags {
+            skip_validate: !validate,
+            skip_fee_charge: true,
+            skip_nonce_check: false,
+        };
+        let execution_info = self.execute_raw_with_flags(state, block_context, flags)?;
+        if let Some(revert_error) = execution_info.revert_error {
+            return Err(TransactionExecutionError::FeeEstimationReverted { revert_error });
+        }
+
+        FeeEstimate::from_execution_info(&execution_info, block_context)
+    }
+
+    /// Same as `execute_raw`, skipping the checks given by the flags. When the fee charge is
+    /// skipped, the fee is not enforced at all: the account's balance is not checked, and neither
+    /// the fee nor the number of steps is bounded by the max fee.
+    fn execute_raw_with_flags(
+        self,
+        state: &mut TransactionalState<'_, S>,
+        block_context: &BlockContext,
+        flags: SimulationFlags,
+    ) -> TransactionExecutionResult<TransactionExecutionInfo>;
+}
+
+/// Estimates the fees of a sequence of transactions, each executed on top of the ones before it,
+/// without modifying the given state. On failure, returns the index of the failing transaction.
+pub fn estimate_fees<S: StateReader, T: ExecutableTransaction<S>>(
+    txs: impl IntoIterator<Item = T>,
+    state: &mut CachedState<S>,
+    block_context: &BlockContext,
+    validate: bool,
+) -> TransactionExecutionResult<Vec<FeeEstimate>> {
+    let mut transactional_state = CachedState::create_transactional(state);
+    let fee_estimates = txs
+        .into_iter()
+        .enumerate()
+        .map(|(tx_index, tx)| {
+            tx.estimate_fee_raw(&mut transactional_state, block_context, validate).map_err(
+                |error| TransactionExecutionError::TransactionFailed {
+                    tx_index,
+                    error: Box::new(error),
+                },
+            )
+        })
+        .collect();
+    transactional_state.abort();
+    fee_estimates
+}
+
+/// Runs the given execution on a transactional state of the given state, and commits it only if
+/// the execution succeeds.
+fn execute_transactionally<S: StateReader, T>(
+    state: &mut CachedState<S>,
+    execute: impl FnOnce(&mut TransactionalState<'_, S>) -> TransactionExecutionResult<T>,
+) -> TransactionExecutionResult<T> {
+    log::debug!("Executing Transaction...");
+    let mut transactional_state = CachedState::create_transactional(state);
+    let execution_result = execute(&mut transactional_state);
+
+    match execution_result {
+        Ok(value) => {
+            transactional_state.commit();
+            log::debug!("Transaction execution complete and committed.");
+            Ok(value)
+        }
+        Err(error) => {
+            log::debug!("Transaction execution failed with: {error}");
+            transactional_state.abort();
+            Err(error)
+        }
+    }
 }
 
 pub trait Executable<S: State> {
@@ -94,64 +182,42 @@ pub trait Executable<S: State> {
     ) -> TransactionExecutionResult<Option<CallInfo>>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DeclareTransaction {
-    tx: starknet_api::transaction::DeclareTransaction,
+    tx: versioned_transactions::DeclareTransaction,
     tx_hash: TransactionHash,
     contract_class: ContractClass,
 }
 
 impl DeclareTransaction {
     pub fn new(
-        declare_tx: starknet_api::transaction::DeclareTransaction,
+        declare_tx: versioned_transactions::DeclareTransaction,
         tx_hash: TransactionHash,
         contract_class: ContractClass,
     ) -> TransactionExecutionResult<Self> {
         let declare_version = declare_tx.version();
-        match declare_tx {
-            starknet_api::transaction::DeclareTransaction::V0(tx) => {
-                let ContractClass::V0(contract_class) = contract_class else {
-                    return Err(TransactionExecutionError::ContractClassVersionMismatch {
-                        declare_version,
-                        cairo_version: 0,
-                    });
-                };
-                Ok(Self {
-                    tx: starknet_api::transaction::DeclareTransaction::V0(tx),
-                    tx_hash,
-                    contract_class: contract_class.into(),
-                })
-            }
-            starknet_api::transaction::DeclareTransaction::V1(tx) => {
-                let ContractClass::V0(contract_class) = contract_class else {
-                    return Err(TransactionExecutionError::ContractClassVersionMismatch {
-                        declare_version,
-                        cairo_version: 0,
-                    });
-                };
-                Ok(Self {
-                    tx: starknet_api::transaction::DeclareTransaction::V1(tx),
-                    tx_hash,
-                    contract_class: contract_class.into(),
-                })
-            }
-            starknet_api::transaction::DeclareTransaction::V2(tx) => {
-                let ContractClass::V1(contract_class) = contract_class else {
-                    return Err(TransactionExecutionError::ContractClassVersionMismatch {
-                        declare_version,
-                        cairo_version: 1,
-                    });
-                };
-                Ok(Self {
-                    tx: starknet_api::transaction::DeclareTransaction::V2(tx),
-                    tx_hash,
-                    contract_class: contract_class.into(),
-                })
+        // Classes are declared in Cairo 0 up to version 1, and in Cairo 1 from version 2.
+        let cairo_version = match &declare_tx {
+            versioned_transactions::DeclareTransaction::V0(_)
+            | versioned_transactions::DeclareTransaction::V1(_) => 0,
+            versioned_transactions::DeclareTransaction::V2(_)
+            | versioned_transactions::DeclareTransaction::V3(_) => 1,
+        };
+        let contract_class = match (cairo_version, contract_class) {
+            (0, ContractClass::V0(contract_class)) => contract_class.into(),
+            (1, ContractClass::V1(contract_class)) => contract_class.into(),
+            _ => {
+                return Err(TransactionExecutionError::ContractClassVersionMismatch {
+                    declare_version,
+                    cairo_version,
+                });
             }
-        }
+        };
+
+        Ok(Self { tx: declare_tx, tx_hash, contract_class })
     }
 
-    pub fn tx(&self) -> &starknet_api::transaction::DeclareTransaction {
+    pub fn tx(&self) -> &versioned_transactions::DeclareTransaction {
         &self.tx
     }
 
@@ -176,19 +242,18 @@ impl<S: State> Executable<S> for DeclareTransaction {
     ) -> TransactionExecutionResult<Option<CallInfo>> {
         let class_hash = self.class_hash();
 
-        match &self.tx {
+        match self.tx.compiled_class_hash() {
             // No class commitment, so no need to check if the class is already declared.
-            starknet_api::transaction::DeclareTransaction::V0(_)
-            | starknet_api::transaction::DeclareTransaction::V1(_) => {
+            None => {
                 state.set_contract_class(&class_hash, self.contract_class.clone())?;
                 Ok(None)
             }
-            starknet_api::transaction::DeclareTransaction::V2(tx) => {
+            Some(compiled_class_hash) => {
                 match state.get_compiled_contract_class(&class_hash) {
                     Err(StateError::UndeclaredClassHash(_)) => {
                         // Class is undeclared; declare it.
                         state.set_contract_class(&class_hash, self.contract_class.clone())?;
-                        state.set_compiled_class_hash(class_hash, tx.compiled_class_hash)?;
+                        state.set_compiled_class_hash(class_hash, compiled_class_hash)?;
                         Ok(None)
                     }
                     Err(error) => Err(error).map_err(TransactionExecutionError::from),
@@ -205,13 +270,13 @@ impl<S: State> Executable<S> for DeclareTransaction {
 
 #[derive(Debug, Clone)]
 pub struct DeployAccountTransaction {
-    pub tx: starknet_api::transaction::DeployAccountTransaction,
+    pub tx: versioned_transactions::DeployAccountTransaction,
     pub tx_hash: TransactionHash,
     pub contract_address: ContractAddress,
 }
 
 impl DeployAccountTransaction {
-    implement_inner_tx_getters!(
+    implement_inner_tx_getter_calls!(
         (class_hash, ClassHash),
         (contract_address_salt, ContractAddressSalt),
         (max_fee, Fee),
@@ -253,9 +318,47 @@ impl<S: State> Executable<S> for DeployAccountTransaction {
     }
 }
 
+/// A deprecated Deploy transaction: deploys a contract and runs its constructor, without
+/// validation or fee.
+#[derive(Debug, Clone)]
+pub struct DeployTransaction {
+    pub tx: starknet_api::transaction::DeployTransaction,
+    pub tx_hash: TransactionHash,
+    pub contract_address: ContractAddress,
+}
+
+impl<S: State> Executable<S> for DeployTransaction {
+    fn run_execute(
+        &self,
+        state: &mut S,
+        resources: &mut ExecutionResources,
+        context: &mut EntryPointExecutionContext,
+        remaining_gas: &mut u64,
+    ) -> TransactionExecutionResult<Option<CallInfo>> {
+        let ctor_context = ConstructorContext {
+            class_hash: self.tx.class_hash,
+            code_address: None,
+            storage_address: self.contract_address,
+            caller_address: ContractAddress::default(),
+        };
+        let call_info = execute_deployment(
+            state,
+            resources,
+            context,
+            ctor_context,
+            self.tx.constructor_calldata.clone(),
+            *remaining_gas,
+        )
+        .map_err(TransactionExecutionError::ContractConstructorExecutionFailed)?;
+        update_remaining_gas(remaining_gas, &call_info);
+
+        Ok(Some(call_info))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InvokeTransaction {
-    pub tx: starknet_api::transaction::InvokeTransaction,
+    pub tx: versioned_transactions::InvokeTransaction,
     pub tx_hash: TransactionHash,
 }
 
@@ -276,8 +379,9 @@ impl<S: State> Executable<S> for InvokeTransaction {
         remaining_gas: &mut u64,
     ) -> TransactionExecutionResult<Option<CallInfo>> {
         let entry_point_selector = match &self.tx {
-            starknet_api::transaction::InvokeTransaction::V0(tx) => tx.entry_point_selector,
-            starknet_api::transaction::InvokeTransaction::V1(_) => {
+            versioned_transactions::InvokeTransaction::V0(tx) => tx.entry_point_selector,
+            versioned_transactions::InvokeTransaction::V1(_)
+            | versioned_transactions::InvokeTransaction::V3(_) => {
                 selector_from_name(constants::EXECUTE_ENTRY_POINT_NAME)
             }
         };
@@ -303,7 +407,7 @@ impl<S: State> Executable<S> for InvokeTransaction {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct L1HandlerTransaction {
     pub tx: starknet_api::transaction::L1HandlerTransaction,
     pub tx_hash: TransactionHash,
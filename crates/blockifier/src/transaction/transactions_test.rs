@@ -6,13 +6,16 @@ use cairo_vm::vm::runners::builtin_runner::{HASH_BUILTIN_NAME, RANGE_CHECK_BUILT
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
 use itertools::concat;
 use pretty_assertions::assert_eq;
-use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::core::{
+    calculate_contract_address, ClassHash, ContractAddress, Nonce, PatriciaKey,
+};
 use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_api::hash::{StarkFelt, StarkHash};
 use starknet_api::state::StorageKey;
 use starknet_api::transaction::{
-    Calldata, DeclareTransactionV0V1, DeclareTransactionV2, EventContent, EventData, EventKey, Fee,
-    InvokeTransactionV1, TransactionHash, TransactionSignature,
+    Calldata, ContractAddressSalt, DeclareTransactionV0V1, DeclareTransactionV2, EventContent,
+    EventData, EventKey, Fee, InvokeTransactionV1, Transaction as StarknetApiTransaction,
+    TransactionHash, TransactionSignature, TransactionVersion,
 };
 use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
 use test_case::test_case;
@@ -42,9 +45,8 @@ use crate::test_utils::{
     TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS, TEST_FAULTY_ACCOUNT_CONTRACT_CLASS_HASH,
 };
 use crate::transaction::account_transaction::AccountTransaction;
-use crate::transaction::constants;
 use crate::transaction::errors::TransactionExecutionError;
-use crate::transaction::objects::{ResourcesMapping, TransactionExecutionInfo};
+use crate::transaction::objects::{FeeUnit, ResourcesMapping, TransactionExecutionInfo};
 use crate::transaction::test_utils::{
     create_account_tx_for_validate_test, create_state_with_cairo1_account,
     create_state_with_falliable_validation_account, create_state_with_trivial_validation_account,
@@ -55,6 +57,7 @@ use crate::transaction::transaction_types::TransactionType;
 use crate::transaction::transactions::{
     DeclareTransaction, DeployAccountTransaction, ExecutableTransaction,
 };
+use crate::transaction::{constants, versioned_transactions};
 
 enum CairoVersion {
     Cairo0,
@@ -149,7 +152,7 @@ fn expected_fee_transfer_call_info(
     let lsb_expected_amount = stark_felt!(actual_fee.0);
     // The most significant 128 bits of the expected amount transferred.
     let msb_expected_amount = stark_felt!(0_u8);
-    let storage_address = block_context.fee_token_address;
+    let storage_address = block_context.fee_token_addresses.eth_fee_token_address;
     let expected_fee_transfer_call = CallEntryPoint {
         class_hash: Some(expected_fee_token_class_hash),
         code_address: None,
@@ -221,14 +224,13 @@ fn validate_final_balances(
     erc20_account_balance_key: StorageKey,
     expected_account_balance: u128,
 ) {
+    let fee_token_address = block_context.fee_token_addresses.eth_fee_token_address;
     let account_balance =
-        state.get_storage_at(block_context.fee_token_address, erc20_account_balance_key).unwrap();
+        state.get_storage_at(fee_token_address, erc20_account_balance_key).unwrap();
     assert_eq!(account_balance, stark_felt!(expected_account_balance));
 
     assert_eq!(
-        state
-            .get_storage_at(block_context.fee_token_address, test_erc20_sequencer_balance_key())
-            .unwrap(),
+        state.get_storage_at(fee_token_address, test_erc20_sequencer_balance_key()).unwrap(),
         stark_felt!(expected_sequencer_balance)
     );
 }
@@ -353,7 +355,8 @@ fn test_invoke_tx(
 
     // Build expected fee transfer call info.
     let expected_actual_fee =
-        calculate_tx_fee(&actual_execution_info.actual_resources, block_context).unwrap();
+        calculate_tx_fee(&actual_execution_info.actual_resources, block_context, FeeUnit::Wei)
+            .unwrap();
     let expected_fee_transfer_call_info = expected_fee_transfer_call_info(
         block_context,
         sender_address,
@@ -373,6 +376,7 @@ fn test_invoke_tx(
         execute_call_info: expected_execute_call_info,
         fee_transfer_call_info: expected_fee_transfer_call_info,
         actual_fee: expected_actual_fee,
+        fee_unit: FeeUnit::Wei,
         actual_resources: ResourcesMapping(HashMap::from([
             // 1 modified contract, 1 storage update (sender balance).
             (abi_constants::GAS_USAGE.to_string(), (2 + 2) * 612),
@@ -432,8 +436,12 @@ fn test_state_get_fee_token_balance(state: &mut CachedState<DictStateReader>) {
     AccountTransaction::Invoke(mint_tx.into()).execute(state, block_context, true, true).unwrap();
 
     // Get balance from state, and validate.
-    let (low, high) =
-        state.get_fee_token_balance(block_context, &contract_address!(recipient)).unwrap();
+    let (low, high) = state
+        .get_fee_token_balance(
+            &contract_address!(recipient),
+            &block_context.fee_token_addresses.eth_fee_token_address,
+        )
+        .unwrap();
 
     assert_eq!(low, mint_low);
     assert_eq!(high, mint_high);
@@ -481,7 +489,7 @@ fn test_max_fee_exceeds_balance(state: &mut CachedState<DictStateReader>) {
 
     // Declare.
     let invalid_tx = AccountTransaction::Declare(DeclareTransaction {
-        tx: starknet_api::transaction::DeclareTransaction::V1(DeclareTransactionV0V1 {
+        tx: versioned_transactions::DeclareTransaction::V1(DeclareTransactionV0V1 {
             max_fee: invalid_max_fee,
             ..declare_tx(TEST_EMPTY_CONTRACT_CLASS_HASH, TEST_ACCOUNT_CONTRACT_ADDRESS, None)
         }),
@@ -594,7 +602,7 @@ fn test_declare_tx(
     let contract_class =
         ContractClass::V0(ContractClassV0::from_file(TEST_EMPTY_CONTRACT_CAIRO0_PATH));
     let account_tx = AccountTransaction::Declare(DeclareTransaction {
-        tx: starknet_api::transaction::DeclareTransaction::V1(declare_tx),
+        tx: versioned_transactions::DeclareTransaction::V1(declare_tx),
         tx_hash: TransactionHash::default(),
         contract_class: contract_class.clone(),
     });
@@ -622,7 +630,8 @@ fn test_declare_tx(
 
     // Build expected fee transfer call info.
     let expected_actual_fee =
-        calculate_tx_fee(&actual_execution_info.actual_resources, block_context).unwrap();
+        calculate_tx_fee(&actual_execution_info.actual_resources, block_context, FeeUnit::Wei)
+            .unwrap();
     let expected_fee_transfer_call_info = expected_fee_transfer_call_info(
         block_context,
         expected_account_address,
@@ -642,6 +651,7 @@ fn test_declare_tx(
         execute_call_info: None,
         fee_transfer_call_info: expected_fee_transfer_call_info,
         actual_fee: expected_actual_fee,
+        fee_unit: FeeUnit::Wei,
         revert_error: None,
         actual_resources: ResourcesMapping(HashMap::from([
             // 1 modified contract, 1 storage update (sender balance).
@@ -692,7 +702,7 @@ fn test_declare_tx_v2() {
     let contract_class =
         ContractClass::V1(ContractClassV1::from_file(TEST_EMPTY_CONTRACT_CAIRO1_PATH));
     let account_tx = AccountTransaction::Declare(DeclareTransaction {
-        tx: starknet_api::transaction::DeclareTransaction::V2(declare_tx),
+        tx: versioned_transactions::DeclareTransaction::V2(declare_tx),
         tx_hash: TransactionHash::default(),
         contract_class: contract_class.clone(),
     });
@@ -714,7 +724,8 @@ fn test_declare_tx_v2() {
     ]));
 
     let expected_actual_fee =
-        calculate_tx_fee(&actual_execution_info.actual_resources, block_context).unwrap();
+        calculate_tx_fee(&actual_execution_info.actual_resources, block_context, FeeUnit::Wei)
+            .unwrap();
 
     assert_eq!(expected_actual_resources, actual_execution_info.actual_resources);
     assert_eq!(expected_actual_fee, actual_execution_info.actual_fee);
@@ -774,7 +785,7 @@ fn test_deploy_account_tx(
     let deployed_account_balance_key =
         get_storage_var_address("ERC20_balances", &[*deployed_account_address.0.key()]).unwrap();
     state.set_storage_at(
-        block_context.fee_token_address,
+        block_context.fee_token_addresses.eth_fee_token_address,
         deployed_account_balance_key,
         stark_felt!(BALANCE),
     );
@@ -812,7 +823,8 @@ fn test_deploy_account_tx(
 
     // Build expected fee transfer call info.
     let expected_actual_fee =
-        calculate_tx_fee(&actual_execution_info.actual_resources, block_context).unwrap();
+        calculate_tx_fee(&actual_execution_info.actual_resources, block_context, FeeUnit::Wei)
+            .unwrap();
     let expected_fee_transfer_call_info = expected_fee_transfer_call_info(
         block_context,
         deployed_account_address,
@@ -832,6 +844,7 @@ fn test_deploy_account_tx(
         execute_call_info: expected_execute_call_info,
         fee_transfer_call_info: expected_fee_transfer_call_info,
         actual_fee: expected_actual_fee,
+        fee_unit: FeeUnit::Wei,
         revert_error: None,
         actual_resources: ResourcesMapping(HashMap::from([
             // 1 modified contract, 1 storage update (sender balance) + 1 class_hash update.
@@ -878,6 +891,57 @@ fn test_deploy_account_tx(
     );
 }
 
+#[test]
+fn test_deploy_tx() {
+    let block_context = &BlockContext::create_for_account_testing();
+    let state = &mut create_state_with_trivial_validation_account();
+    let class_hash = class_hash!(TEST_CLASS_HASH);
+    let (storage_key, storage_value) = (stark_felt!(15_u8), stark_felt!(4_u8));
+    let deploy_tx = StarknetApiTransaction::Deploy(starknet_api::transaction::DeployTransaction {
+        version: TransactionVersion(stark_felt!(0_u8)),
+        class_hash,
+        contract_address_salt: ContractAddressSalt::default(),
+        constructor_calldata: calldata![storage_key, storage_value],
+    });
+    let deploy_tx = || {
+        Transaction::from_api(deploy_tx.clone(), TransactionHash::default(), None, None, None)
+            .unwrap()
+    };
+    let contract_address = calculate_contract_address(
+        ContractAddressSalt::default(),
+        class_hash,
+        &calldata![storage_key, storage_value],
+        ContractAddress::default(),
+    )
+    .unwrap();
+    assert_eq!(deploy_tx().tx_type(), TransactionType::Deploy);
+
+    // The constructor is run, without validation or fee.
+    let actual_execution_info = deploy_tx().execute(state, block_context, true, true).unwrap();
+    assert_eq!(actual_execution_info.validate_call_info, None);
+    assert_eq!(actual_execution_info.fee_transfer_call_info, None);
+    assert_eq!(actual_execution_info.actual_fee, Fee(0));
+    assert!(!actual_execution_info.is_reverted());
+    let execute_call = actual_execution_info.execute_call_info.unwrap().call;
+    assert_eq!(execute_call.entry_point_type, EntryPointType::Constructor);
+    assert_eq!(execute_call.storage_address, contract_address);
+
+    // Verify deployment.
+    assert_eq!(state.get_class_hash_at(contract_address).unwrap(), class_hash);
+    assert_eq!(
+        state.get_storage_at(contract_address, StorageKey::try_from(storage_key).unwrap()).unwrap(),
+        storage_value
+    );
+
+    // Negative flow: deploy to an existing address.
+    assert_matches!(
+        deploy_tx().execute(state, block_context, true, true).unwrap_err(),
+        TransactionExecutionError::ContractConstructorExecutionFailed(
+            EntryPointExecutionError::StateError(StateError::UnavailableContractAddress(_))
+        )
+    );
+}
+
 #[test]
 fn test_validate_accounts_tx() {
     fn test_validate_account_tx(tx_type: TransactionType) {
@@ -986,12 +1050,12 @@ fn test_calculate_tx_gas_usage() {
     let entry_point_selector = selector_from_name(constants::TRANSFER_ENTRY_POINT_NAME);
     let some_other_account_address = stark_felt!(TEST_FAULTY_ACCOUNT_CONTRACT_ADDRESS);
     let execute_calldata = calldata![
-        *block_context.fee_token_address.0.key(), // Contract address.
-        entry_point_selector.0,                   // EP selector.
-        stark_felt!(3_u8),                        // Calldata length.
-        some_other_account_address,               // Calldata: recipient.
-        stark_felt!(2_u8),                        // Calldata: lsb amount.
-        stark_felt!(0_u8)                         // Calldata: msb amount.
+        *block_context.fee_token_addresses.eth_fee_token_address.0.key(), // Contract address.
+        entry_point_selector.0,                                           // EP selector.
+        stark_felt!(3_u8),                                                // Calldata length.
+        some_other_account_address,                                       // Calldata: recipient.
+        stark_felt!(2_u8),                                                // Calldata: lsb amount.
+        stark_felt!(0_u8)                                                 // Calldata: msb amount.
     ];
 
     let invoke_tx = crate::test_utils::invoke_tx(
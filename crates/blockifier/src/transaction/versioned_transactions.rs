@@ -0,0 +1,354 @@
+//! Account transactions of all supported versions.
+//!
+//! `starknet_api` does not define version 3 transactions yet; they are defined here, together with
+//! their fee market and data-availability fields, and wrapped with the older versions.
+
+use std::collections::BTreeMap;
+
+use derive_more::From;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::hash::StarkFelt;
+use starknet_api::transaction::{
+    Calldata, ContractAddressSalt, DeclareTransactionV0V1, DeclareTransactionV2, Fee,
+    InvokeTransactionV0, InvokeTransactionV1, TransactionSignature, TransactionVersion,
+};
+
+/// A resource a version 3 transaction pays for.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Resource {
+    L1Gas,
+    L2Gas,
+}
+
+impl Resource {
+    /// Returns the name of the resource, as encoded (as a Cairo short string) in the transaction
+    /// info.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::L1Gas => "L1_GAS",
+            Self::L2Gas => "L2_GAS",
+        }
+    }
+}
+
+/// The maximal amount of a resource a transaction may use, and the maximal price it pays per unit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ResourceBounds {
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResourceBoundsMapping(pub BTreeMap<Resource, ResourceBounds>);
+
+/// A tip paid to the sequencer, per unit of L2 gas.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Tip(pub u64);
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PaymasterData(pub Vec<StarkFelt>);
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccountDeploymentData(pub Vec<StarkFelt>);
+
+/// The layer in which the data of a state element (e.g., the sender's nonce) is published.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DataAvailabilityMode {
+    #[default]
+    L1 = 0,
+    L2 = 1,
+}
+
+/// An invoke V3 transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InvokeTransactionV3 {
+    pub resource_bounds: ResourceBoundsMapping,
+    pub tip: Tip,
+    pub signature: TransactionSignature,
+    pub nonce: Nonce,
+    pub sender_address: ContractAddress,
+    pub calldata: Calldata,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+    pub paymaster_data: PaymasterData,
+    pub account_deployment_data: AccountDeploymentData,
+}
+
+/// A declare V3 transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeclareTransactionV3 {
+    pub resource_bounds: ResourceBoundsMapping,
+    pub tip: Tip,
+    pub signature: TransactionSignature,
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub compiled_class_hash: CompiledClassHash,
+    pub sender_address: ContractAddress,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+    pub paymaster_data: PaymasterData,
+    pub account_deployment_data: AccountDeploymentData,
+}
+
+/// A deploy account V3 transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeployAccountTransactionV3 {
+    pub resource_bounds: ResourceBoundsMapping,
+    pub tip: Tip,
+    pub signature: TransactionSignature,
+    pub nonce: Nonce,
+    pub class_hash: ClassHash,
+    pub contract_address_salt: ContractAddressSalt,
+    pub constructor_calldata: Calldata,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+    pub paymaster_data: PaymasterData,
+}
+
+/// Implements getters of the fields added in version 3; older versions have the default values.
+macro_rules! implement_v3_tx_getters {
+    ($(($field:ident, $field_type:ty)),*) => {
+        $(pub fn $field(&self) -> $field_type {
+            match self {
+                Self::V3(tx) => tx.$field.clone(),
+                _ => <$field_type>::default(),
+            }
+        })*
+    };
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, From)]
+pub enum InvokeTransaction {
+    V0(InvokeTransactionV0),
+    V1(InvokeTransactionV1),
+    V3(InvokeTransactionV3),
+}
+
+impl InvokeTransaction {
+    implement_v3_tx_getters!(
+        (resource_bounds, ResourceBoundsMapping),
+        (tip, Tip),
+        (nonce_data_availability_mode, DataAvailabilityMode),
+        (fee_data_availability_mode, DataAvailabilityMode),
+        (paymaster_data, PaymasterData),
+        (account_deployment_data, AccountDeploymentData)
+    );
+
+    pub fn version(&self) -> TransactionVersion {
+        match self {
+            Self::V0(_) => TransactionVersion(StarkFelt::from(0_u8)),
+            Self::V1(_) => TransactionVersion(StarkFelt::from(1_u8)),
+            Self::V3(_) => TransactionVersion(StarkFelt::from(3_u8)),
+        }
+    }
+
+    /// Returns the max fee; version 3 transactions bound their fee by resource bounds instead.
+    pub fn max_fee(&self) -> Fee {
+        match self {
+            Self::V0(tx) => tx.max_fee,
+            Self::V1(tx) => tx.max_fee,
+            Self::V3(_) => Fee::default(),
+        }
+    }
+
+    pub fn signature(&self) -> TransactionSignature {
+        match self {
+            Self::V0(tx) => tx.signature.clone(),
+            Self::V1(tx) => tx.signature.clone(),
+            Self::V3(tx) => tx.signature.clone(),
+        }
+    }
+
+    pub fn nonce(&self) -> Nonce {
+        match self {
+            Self::V0(_) => Nonce::default(),
+            Self::V1(tx) => tx.nonce,
+            Self::V3(tx) => tx.nonce,
+        }
+    }
+
+    pub fn sender_address(&self) -> ContractAddress {
+        match self {
+            Self::V0(tx) => tx.contract_address,
+            Self::V1(tx) => tx.sender_address,
+            Self::V3(tx) => tx.sender_address,
+        }
+    }
+
+    pub fn calldata(&self) -> Calldata {
+        match self {
+            Self::V0(tx) => tx.calldata.clone(),
+            Self::V1(tx) => tx.calldata.clone(),
+            Self::V3(tx) => tx.calldata.clone(),
+        }
+    }
+}
+
+impl From<starknet_api::transaction::InvokeTransaction> for InvokeTransaction {
+    fn from(tx: starknet_api::transaction::InvokeTransaction) -> Self {
+        match tx {
+            starknet_api::transaction::InvokeTransaction::V0(tx) => Self::V0(tx),
+            starknet_api::transaction::InvokeTransaction::V1(tx) => Self::V1(tx),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeclareTransaction {
+    V0(DeclareTransactionV0V1),
+    V1(DeclareTransactionV0V1),
+    V2(DeclareTransactionV2),
+    V3(DeclareTransactionV3),
+}
+
+impl DeclareTransaction {
+    implement_v3_tx_getters!(
+        (resource_bounds, ResourceBoundsMapping),
+        (tip, Tip),
+        (nonce_data_availability_mode, DataAvailabilityMode),
+        (fee_data_availability_mode, DataAvailabilityMode),
+        (paymaster_data, PaymasterData),
+        (account_deployment_data, AccountDeploymentData)
+    );
+
+    pub fn version(&self) -> TransactionVersion {
+        match self {
+            Self::V0(_) => TransactionVersion(StarkFelt::from(0_u8)),
+            Self::V1(_) => TransactionVersion(StarkFelt::from(1_u8)),
+            Self::V2(_) => TransactionVersion(StarkFelt::from(2_u8)),
+            Self::V3(_) => TransactionVersion(StarkFelt::from(3_u8)),
+        }
+    }
+
+    /// Returns the max fee; version 3 transactions bound their fee by resource bounds instead.
+    pub fn max_fee(&self) -> Fee {
+        match self {
+            Self::V0(tx) | Self::V1(tx) => tx.max_fee,
+            Self::V2(tx) => tx.max_fee,
+            Self::V3(_) => Fee::default(),
+        }
+    }
+
+    pub fn signature(&self) -> TransactionSignature {
+        match self {
+            Self::V0(tx) | Self::V1(tx) => tx.signature.clone(),
+            Self::V2(tx) => tx.signature.clone(),
+            Self::V3(tx) => tx.signature.clone(),
+        }
+    }
+
+    pub fn nonce(&self) -> Nonce {
+        match self {
+            Self::V0(tx) | Self::V1(tx) => tx.nonce,
+            Self::V2(tx) => tx.nonce,
+            Self::V3(tx) => tx.nonce,
+        }
+    }
+
+    pub fn sender_address(&self) -> ContractAddress {
+        match self {
+            Self::V0(tx) | Self::V1(tx) => tx.sender_address,
+            Self::V2(tx) => tx.sender_address,
+            Self::V3(tx) => tx.sender_address,
+        }
+    }
+
+    pub fn class_hash(&self) -> ClassHash {
+        match self {
+            Self::V0(tx) | Self::V1(tx) => tx.class_hash,
+            Self::V2(tx) => tx.class_hash,
+            Self::V3(tx) => tx.class_hash,
+        }
+    }
+
+    /// Returns the compiled class hash of a Cairo 1 class declaration; [None] for Cairo 0 classes.
+    pub fn compiled_class_hash(&self) -> Option<CompiledClassHash> {
+        match self {
+            Self::V0(_) | Self::V1(_) => None,
+            Self::V2(tx) => Some(tx.compiled_class_hash),
+            Self::V3(tx) => Some(tx.compiled_class_hash),
+        }
+    }
+}
+
+impl From<starknet_api::transaction::DeclareTransaction> for DeclareTransaction {
+    fn from(tx: starknet_api::transaction::DeclareTransaction) -> Self {
+        match tx {
+            starknet_api::transaction::DeclareTransaction::V0(tx) => Self::V0(tx),
+            starknet_api::transaction::DeclareTransaction::V1(tx) => Self::V1(tx),
+            starknet_api::transaction::DeclareTransaction::V2(tx) => Self::V2(tx),
+        }
+    }
+}
+
+impl From<DeclareTransactionV3> for DeclareTransaction {
+    fn from(tx: DeclareTransactionV3) -> Self {
+        Self::V3(tx)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, From)]
+pub enum DeployAccountTransaction {
+    V1(starknet_api::transaction::DeployAccountTransaction),
+    V3(DeployAccountTransactionV3),
+}
+
+impl DeployAccountTransaction {
+    implement_v3_tx_getters!(
+        (resource_bounds, ResourceBoundsMapping),
+        (tip, Tip),
+        (nonce_data_availability_mode, DataAvailabilityMode),
+        (fee_data_availability_mode, DataAvailabilityMode),
+        (paymaster_data, PaymasterData)
+    );
+
+    pub fn version(&self) -> TransactionVersion {
+        match self {
+            Self::V1(tx) => tx.version,
+            Self::V3(_) => TransactionVersion(StarkFelt::from(3_u8)),
+        }
+    }
+
+    /// Returns the max fee; version 3 transactions bound their fee by resource bounds instead.
+    pub fn max_fee(&self) -> Fee {
+        match self {
+            Self::V1(tx) => tx.max_fee,
+            Self::V3(_) => Fee::default(),
+        }
+    }
+
+    pub fn signature(&self) -> TransactionSignature {
+        match self {
+            Self::V1(tx) => tx.signature.clone(),
+            Self::V3(tx) => tx.signature.clone(),
+        }
+    }
+
+    pub fn nonce(&self) -> Nonce {
+        match self {
+            Self::V1(tx) => tx.nonce,
+            Self::V3(tx) => tx.nonce,
+        }
+    }
+
+    pub fn class_hash(&self) -> ClassHash {
+        match self {
+            Self::V1(tx) => tx.class_hash,
+            Self::V3(tx) => tx.class_hash,
+        }
+    }
+
+    pub fn contract_address_salt(&self) -> ContractAddressSalt {
+        match self {
+            Self::V1(tx) => tx.contract_address_salt,
+            Self::V3(tx) => tx.contract_address_salt,
+        }
+    }
+
+    pub fn constructor_calldata(&self) -> Calldata {
+        match self {
+            Self::V1(tx) => tx.constructor_calldata.clone(),
+            Self::V3(tx) => tx.constructor_calldata.clone(),
+        }
+    }
+}
@@ -71,6 +71,12 @@ native_blockifier_errors!(
 pub enum NativeBlockifierInputError {
     #[error(transparent)]
     ProgramError(#[from] ProgramError),
+    #[error("Transaction type {tx_type} is unsupported.")]
+    UnsupportedTransactionType { tx_type: String },
     #[error("Transaction of type {tx_type:?} is unsupported in version {version}.")]
     UnsupportedTransactionVersion { tx_type: TransactionType, version: usize },
+    #[error(
+        "Transactions of version 3 require the STRK fee token address and the STRK L1 gas price."
+    )]
+    MissingStrkFeeConfig,
 }
@@ -1,6 +1,11 @@
+use std::sync::Arc;
+
 use blockifier::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
+use blockifier::execution::execution_utils::DeprecatedProgramArgs;
+use blockifier::state::cached_state::ContractStorageKey;
+use blockifier::state::class_disk_cache::{ClassDiskCache, ClassDiskCacheResult};
 use blockifier::state::errors::StateError;
-use blockifier::state::state_api::{StateReader, StateResult};
+use blockifier::state::state_api::{SharedStateReader, StateReader, StateResult};
 use papyrus_storage::compiled_class::CasmStorageReader;
 use papyrus_storage::db::RO;
 use papyrus_storage::state::StateStorageReader;
@@ -16,109 +21,326 @@ mod test;
 
 type RawPapyrusReader<'env> = papyrus_storage::StorageTxn<'env, RO>;
 
+/// Reads the state from Papyrus storage; each read, or batch of reads, is done in a single read
+/// transaction of its own.
 pub struct PapyrusReader {
     storage_reader: StorageReader,
     latest_block: BlockNumber,
+    class_disk_cache: Option<Arc<ClassDiskCache>>,
 }
 
 impl PapyrusReader {
+    /// Reads the state at the start of the given block; i.e., after all the blocks preceding it.
     pub fn new(storage_reader: StorageReader, latest_block: BlockNumber) -> Self {
-        Self { storage_reader, latest_block }
+        Self { storage_reader, latest_block, class_disk_cache: None }
+    }
+
+    /// Reads the state right after the given committed block; e.g., to execute on top of a
+    /// historical block.
+    pub fn new_at_block(
+        storage_reader: StorageReader,
+        block_number: BlockNumber,
+    ) -> StateResult<Self> {
+        let state_marker = get_state_marker(&storage_reader)?;
+        if block_number >= state_marker {
+            return Err(StateError::StateReadError(format!(
+                "Block {block_number} is not committed; the state marker is {state_marker}."
+            )));
+        }
+
+        Ok(Self::new(storage_reader, block_number.next()))
+    }
+
+    /// Reads the state after the latest committed block.
+    pub fn new_at_latest(storage_reader: StorageReader) -> StateResult<Self> {
+        let state_marker = get_state_marker(&storage_reader)?;
+        Ok(Self::new(storage_reader, state_marker))
+    }
+
+    /// Serves compiled classes from the given disk cache when possible, and fills it with the
+    /// classes read from storage.
+    pub fn with_class_disk_cache(mut self, class_disk_cache: Arc<ClassDiskCache>) -> Self {
+        self.class_disk_cache = Some(class_disk_cache);
+        self
+    }
+
+    /// Failures of the disk cache are logged and otherwise ignored; storage is the source of truth.
+    fn get_class_from_disk_cache(&self, class_hash: ClassHash) -> Option<ContractClass> {
+        let class_disk_cache = self.class_disk_cache.as_ref()?;
+        class_disk_cache.get(class_hash).unwrap_or_else(|error| {
+            log::warn!("Failed to read class {class_hash:?} from the disk cache: {error}.");
+            None
+        })
     }
 
-    fn reader(&self) -> StateResult<RawPapyrusReader<'_>> {
-        self.storage_reader
-            .begin_ro_txn()
-            .map_err(|error| StateError::StateReadError(error.to_string()))
+    fn set_class_in_disk_cache(
+        &self,
+        class_hash: ClassHash,
+        set: impl FnOnce(&ClassDiskCache) -> ClassDiskCacheResult<()>,
+    ) {
+        let Some(class_disk_cache) = &self.class_disk_cache else { return };
+        if let Err(error) = set(class_disk_cache) {
+            log::warn!("Failed to write class {class_hash:?} to the disk cache: {error}.");
+        }
+    }
+
+    /// Returns the number of the block declaring the given Cairo 1 class, if it precedes the block
+    /// at which the state is read.
+    fn get_class_declaration_block_number(
+        &self,
+        txn: &RawPapyrusReader<'_>,
+        class_hash: &ClassHash,
+    ) -> StateResult<Option<BlockNumber>> {
+        let class_declaration_block_number = txn
+            .get_state_reader()
+            .and_then(|sr| sr.get_class_definition_block_number(class_hash))
+            .map_err(|err| StateError::StateReadError(err.to_string()))?;
+        Ok(class_declaration_block_number.filter(|&block_number| block_number < self.latest_block))
+    }
+
+    /// Performs the given reads in a single read transaction, scoped to them.
+    fn with_txn<T>(
+        &self,
+        read: impl FnOnce(&RawPapyrusReader<'_>) -> StateResult<T>,
+    ) -> StateResult<T> {
+        read(&begin_ro_txn(&self.storage_reader)?)
     }
 }
 
-// Currently unused - will soon replace the same `impl` for `PapyrusStateReader`.
-impl StateReader for PapyrusReader {
+fn begin_ro_txn(storage_reader: &StorageReader) -> StateResult<RawPapyrusReader<'_>> {
+    storage_reader.begin_ro_txn().map_err(|error| StateError::StateReadError(error.to_string()))
+}
+
+fn get_state_marker(storage_reader: &StorageReader) -> StateResult<BlockNumber> {
+    begin_ro_txn(storage_reader)?
+        .get_state_marker()
+        .map_err(|error| StateError::StateReadError(error.to_string()))
+}
+
+impl SharedStateReader for PapyrusReader {
     fn get_storage_at(
-        &mut self,
+        &self,
         contract_address: ContractAddress,
         key: StorageKey,
     ) -> StateResult<StarkFelt> {
         let state_number = StateNumber(self.latest_block);
-        self.reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_storage_at(state_number, &contract_address, &key))
-            .map_err(|error| StateError::StateReadError(error.to_string()))
+        self.with_txn(|txn| {
+            txn.get_state_reader()
+                .and_then(|sr| sr.get_storage_at(state_number, &contract_address, &key))
+                .map_err(|error| StateError::StateReadError(error.to_string()))
+        })
     }
 
-    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+    fn get_nonce_at(&self, contract_address: ContractAddress) -> StateResult<Nonce> {
         let state_number = StateNumber(self.latest_block);
-        match self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_nonce_at(state_number, &contract_address))
-        {
-            Ok(Some(nonce)) => Ok(nonce),
-            Ok(None) => Ok(Nonce::default()),
-            Err(err) => Err(StateError::StateReadError(err.to_string())),
-        }
+        self.with_txn(|txn| {
+            match txn
+                .get_state_reader()
+                .and_then(|sr| sr.get_nonce_at(state_number, &contract_address))
+            {
+                Ok(Some(nonce)) => Ok(nonce),
+                Ok(None) => Ok(Nonce::default()),
+                Err(err) => Err(StateError::StateReadError(err.to_string())),
+            }
+        })
     }
 
-    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+    fn get_class_hash_at(&self, contract_address: ContractAddress) -> StateResult<ClassHash> {
         let state_number = StateNumber(self.latest_block);
-        match self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_class_hash_at(state_number, &contract_address))
-        {
-            Ok(Some(class_hash)) => Ok(class_hash),
-            Ok(None) => Ok(ClassHash::default()),
-            Err(err) => Err(StateError::StateReadError(err.to_string())),
-        }
+        self.with_txn(|txn| {
+            match txn
+                .get_state_reader()
+                .and_then(|sr| sr.get_class_hash_at(state_number, &contract_address))
+            {
+                Ok(Some(class_hash)) => Ok(class_hash),
+                Ok(None) => Ok(ClassHash::default()),
+                Err(err) => Err(StateError::StateReadError(err.to_string())),
+            }
+        })
     }
 
     /// Returns a V1 contract if found, or a V0 contract if a V1 contract is not
     /// found, or an `Error` otherwise.
+    fn get_compiled_contract_class(&self, class_hash: &ClassHash) -> StateResult<ContractClass> {
+        // The disk cache only saves converting classes; whether a class is declared at this state
+        // is determined by storage.
+        let state_number = StateNumber(self.latest_block);
+        self.with_txn(|txn| {
+            if self.get_class_declaration_block_number(txn, class_hash)?.is_some() {
+                if let Some(contract_class) = self.get_class_from_disk_cache(*class_hash) {
+                    return Ok(contract_class);
+                }
+
+                let casm_contract_class = txn
+                    .get_casm(class_hash)
+                    .map_err(|err| StateError::StateReadError(err.to_string()))?
+                    .expect(
+                        "Should be able to fetch a Casm class if its definition exists, database \
+                         is inconsistent.",
+                    );
+                self.set_class_in_disk_cache(*class_hash, |class_disk_cache| {
+                    class_disk_cache.set_v1(*class_hash, &casm_contract_class)
+                });
+
+                return Ok(ContractClass::V1(ContractClassV1::try_from(casm_contract_class)?));
+            }
+
+            let v0_contract_class = txn
+                .get_state_reader()
+                .and_then(|sr| sr.get_deprecated_class_definition_at(state_number, class_hash))
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+
+            let Some(starknet_api_contract_class) = v0_contract_class else {
+                return Err(StateError::UndeclaredClassHash(*class_hash));
+            };
+            if let Some(contract_class) = self.get_class_from_disk_cache(*class_hash) {
+                return Ok(contract_class);
+            }
+
+            let program_args =
+                DeprecatedProgramArgs::try_from(starknet_api_contract_class.program)?;
+            let entry_points_by_type = starknet_api_contract_class.entry_points_by_type;
+            self.set_class_in_disk_cache(*class_hash, |class_disk_cache| {
+                class_disk_cache.set_v0(*class_hash, &program_args, &entry_points_by_type)
+            });
+
+            Ok(ContractClassV0::from_program_args(program_args, entry_points_by_type)?.into())
+        })
+    }
+
+    /// Returns the compiled class hash of a Cairo 1 class, as set by the state diff of the block
+    /// declaring it; deprecated and undeclared classes have the default value.
+    fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        self.with_txn(|txn| {
+            let Some(class_declaration_block_number) =
+                self.get_class_declaration_block_number(txn, &class_hash)?
+            else {
+                return Ok(CompiledClassHash::default());
+            };
+
+            let state_diff = txn
+                .get_state_diff(class_declaration_block_number)
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+            state_diff
+                .and_then(|state_diff| state_diff.declared_classes.get(&class_hash).copied())
+                .ok_or_else(|| {
+                    StateError::StateReadError(format!(
+                        "Compiled class hash of {class_hash:?} is missing from the state diff of \
+                         block {class_declaration_block_number}, database is inconsistent."
+                    ))
+                })
+        })
+    }
+
+    /// Reads all values using a single read transaction.
+    fn get_storage_batch(
+        &self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        let state_number = StateNumber(self.latest_block);
+        self.with_txn(|txn| {
+            let state_reader = txn
+                .get_state_reader()
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+            contract_storage_keys
+                .iter()
+                .map(|(contract_address, key)| {
+                    state_reader
+                        .get_storage_at(state_number, contract_address, key)
+                        .map_err(|err| StateError::StateReadError(err.to_string()))
+                })
+                .collect()
+        })
+    }
+
+    /// Reads all nonces using a single read transaction.
+    fn get_nonces_batch(&self, contract_addresses: &[ContractAddress]) -> StateResult<Vec<Nonce>> {
+        let state_number = StateNumber(self.latest_block);
+        self.with_txn(|txn| {
+            let state_reader = txn
+                .get_state_reader()
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+            contract_addresses
+                .iter()
+                .map(|contract_address| {
+                    match state_reader.get_nonce_at(state_number, contract_address) {
+                        Ok(nonce) => Ok(nonce.unwrap_or_default()),
+                        Err(err) => Err(StateError::StateReadError(err.to_string())),
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Reads all class hashes using a single read transaction.
+    fn get_class_hashes_batch(
+        &self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        let state_number = StateNumber(self.latest_block);
+        self.with_txn(|txn| {
+            let state_reader = txn
+                .get_state_reader()
+                .map_err(|err| StateError::StateReadError(err.to_string()))?;
+            contract_addresses
+                .iter()
+                .map(|contract_address| {
+                    match state_reader.get_class_hash_at(state_number, contract_address) {
+                        Ok(class_hash) => Ok(class_hash.unwrap_or_default()),
+                        Err(err) => Err(StateError::StateReadError(err.to_string())),
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+impl StateReader for PapyrusReader {
+    fn get_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> StateResult<StarkFelt> {
+        SharedStateReader::get_storage_at(self, contract_address, key)
+    }
+
+    fn get_nonce_at(&mut self, contract_address: ContractAddress) -> StateResult<Nonce> {
+        SharedStateReader::get_nonce_at(self, contract_address)
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: ContractAddress) -> StateResult<ClassHash> {
+        SharedStateReader::get_class_hash_at(self, contract_address)
+    }
+
     fn get_compiled_contract_class(
         &mut self,
         class_hash: &ClassHash,
     ) -> StateResult<ContractClass> {
-        let state_number = StateNumber(self.latest_block);
-        let class_declaration_block_number = self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_class_definition_block_number(class_hash))
-            .map_err(|err| StateError::StateReadError(err.to_string()))?;
-        let class_is_declared: bool = matches!(class_declaration_block_number,
-                    Some(block_number) if block_number <= state_number.0);
-
-        if class_is_declared {
-            let casm_contract_class = self
-                .reader()?
-                .get_casm(class_hash)
-                .map_err(|err| StateError::StateReadError(err.to_string()))?
-                .expect(
-                    "Should be able to fetch a Casm class if its definition exists, database is \
-                     inconsistent.",
-                );
-
-            return Ok(ContractClass::V1(ContractClassV1::try_from(casm_contract_class)?));
-        }
+        SharedStateReader::get_compiled_contract_class(self, class_hash)
+    }
 
-        let v0_contract_class = self
-            .reader()?
-            .get_state_reader()
-            .and_then(|sr| sr.get_deprecated_class_definition_at(state_number, class_hash))
-            .map_err(|err| StateError::StateReadError(err.to_string()))?;
+    fn get_compiled_class_hash(&mut self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
+        SharedStateReader::get_compiled_class_hash(self, class_hash)
+    }
 
-        match v0_contract_class {
-            Some(starknet_api_contract_class) => {
-                Ok(ContractClassV0::try_from(starknet_api_contract_class)?.into())
-            }
-            None => Err(StateError::UndeclaredClassHash(*class_hash)),
-        }
+    fn get_storage_batch(
+        &mut self,
+        contract_storage_keys: &[ContractStorageKey],
+    ) -> StateResult<Vec<StarkFelt>> {
+        SharedStateReader::get_storage_batch(self, contract_storage_keys)
+    }
+
+    fn get_nonces_batch(
+        &mut self,
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<Nonce>> {
+        SharedStateReader::get_nonces_batch(self, contract_addresses)
     }
 
-    fn get_compiled_class_hash(
+    fn get_class_hashes_batch(
         &mut self,
-        _class_hash: ClassHash,
-    ) -> StateResult<CompiledClassHash> {
-        todo!()
+        contract_addresses: &[ContractAddress],
+    ) -> StateResult<Vec<ClassHash>> {
+        SharedStateReader::get_class_hashes_batch(self, contract_addresses)
     }
 }
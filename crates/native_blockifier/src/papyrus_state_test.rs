@@ -1,18 +1,28 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::execution::contract_class::{ContractClass, ContractClassV0, ContractClassV1};
 use blockifier::execution::entry_point::{CallEntryPoint, CallExecution, Retdata};
 use blockifier::retdata;
-use blockifier::state::cached_state::CachedState;
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff};
+use blockifier::state::class_disk_cache::ClassDiskCache;
+use blockifier::state::errors::StateError;
+use blockifier::state::pending_state_reader::PendingStateReader;
+use blockifier::state::shared_state_reader::StateSnapshot;
 use blockifier::state::state_api::StateReader;
 use blockifier::test_utils::{
-    get_deprecated_contract_class, trivial_external_entry_point, TEST_CLASS_HASH,
-    TEST_CONTRACT_ADDRESS, TEST_CONTRACT_CAIRO0_PATH,
+    get_deprecated_contract_class, get_raw_contract_class, trivial_external_entry_point,
+    TEST_CLASS_HASH, TEST_CONTRACT_ADDRESS, TEST_CONTRACT_CAIRO0_PATH, TEST_CONTRACT_CAIRO1_PATH,
 };
-use indexmap::IndexMap;
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+use indexmap::{indexmap, IndexMap};
+use papyrus_storage::compiled_class::CasmStorageWriter;
 use papyrus_storage::state::StateStorageWriter;
 use starknet_api::block::BlockNumber;
-use starknet_api::core::{ClassHash, ContractAddress, PatriciaKey};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce, PatriciaKey};
 use starknet_api::hash::{StarkFelt, StarkHash};
-use starknet_api::state::{StateDiff, StorageKey};
+use starknet_api::state::{ContractClass as StarknetApiContractClass, StateDiff, StorageKey};
 use starknet_api::transaction::Calldata;
 use starknet_api::{calldata, class_hash, contract_address, patricia_key, stark_felt};
 
@@ -62,3 +72,322 @@ fn test_entry_point_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_batch_reads_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    let other_contract_address = contract_address!("0x200");
+    let key = StorageKey(patricia_key!("0x10"));
+    let other_key = StorageKey(patricia_key!("0x20"));
+    let value = stark_felt!(18_u8);
+    let nonce = Nonce(stark_felt!(3_u8));
+    let state_diff = StateDiff {
+        deployed_contracts: IndexMap::from([(contract_address, class_hash!(TEST_CLASS_HASH))]),
+        storage_diffs: IndexMap::from([(contract_address, IndexMap::from([(key, value)]))]),
+        nonces: IndexMap::from([(contract_address, nonce)]),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber::default(), state_diff, IndexMap::new())?
+        .commit()?;
+
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(1));
+    assert_eq!(
+        papyrus_reader
+            .get_storage_batch(&[(contract_address, key), (contract_address, other_key)])
+            .unwrap(),
+        vec![value, StarkFelt::default()]
+    );
+    assert_eq!(
+        papyrus_reader.get_nonces_batch(&[contract_address, other_contract_address]).unwrap(),
+        vec![nonce, Nonce::default()]
+    );
+    assert_eq!(
+        papyrus_reader.get_class_hashes_batch(&[contract_address, other_contract_address]).unwrap(),
+        vec![class_hash!(TEST_CLASS_HASH), ClassHash::default()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_shared_papyrus_reader() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+    let value = stark_felt!(18_u8);
+    let state_diff = StateDiff {
+        storage_diffs: IndexMap::from([(contract_address, IndexMap::from([(key, value)]))]),
+        ..Default::default()
+    };
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber::default(), state_diff, IndexMap::new())?
+        .commit()?;
+
+    // Several cached states, on different threads, read from the same snapshot.
+    let snapshot = StateSnapshot::new(PapyrusReader::new(storage_reader.clone(), BlockNumber(1)));
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            let mut state = CachedState::from(snapshot.clone());
+            scope.spawn(move || {
+                assert_eq!(state.get_storage_at(contract_address, key).unwrap(), value);
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[test]
+fn test_papyrus_reader_with_class_disk_cache() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let (class_hash, deprecated_class_hash) = (class_hash!("0x10"), class_hash!(TEST_CLASS_HASH));
+    let casm_contract_class: CasmContractClass =
+        serde_json::from_str(&get_raw_contract_class(TEST_CONTRACT_CAIRO1_PATH)).unwrap();
+    let declared_classes = IndexMap::from([(
+        class_hash,
+        (CompiledClassHash(stark_felt!("0x11")), StarknetApiContractClass::default()),
+    )]);
+    let test_contract = get_deprecated_contract_class(TEST_CONTRACT_CAIRO0_PATH);
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(
+            BlockNumber::default(),
+            StateDiff { declared_classes, ..Default::default() },
+            IndexMap::from([(deprecated_class_hash, test_contract)]),
+        )?
+        .append_casm(&class_hash, &casm_contract_class)?
+        .commit()?;
+
+    let cache_dir = std::env::temp_dir()
+        .join(format!("native_blockifier_class_disk_cache_{}", std::process::id()));
+    let class_disk_cache = Arc::new(ClassDiskCache::open(&cache_dir).unwrap());
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(1))
+        .with_class_disk_cache(class_disk_cache.clone());
+
+    // A class read from storage is stored on disk.
+    let expected_class: ContractClass =
+        ContractClassV1::try_from(casm_contract_class).unwrap().into();
+    assert_eq!(papyrus_reader.get_compiled_contract_class(&class_hash).unwrap(), expected_class);
+    assert_eq!(class_disk_cache.get(class_hash).unwrap(), Some(expected_class.clone()));
+
+    // And then served from disk.
+    assert_eq!(papyrus_reader.get_compiled_contract_class(&class_hash).unwrap(), expected_class);
+
+    // Cairo 0 classes are cached too.
+    let expected_deprecated_class: ContractClass =
+        ContractClassV0::try_from(get_deprecated_contract_class(TEST_CONTRACT_CAIRO0_PATH))
+            .unwrap()
+            .into();
+    assert_eq!(
+        papyrus_reader.get_compiled_contract_class(&deprecated_class_hash).unwrap(),
+        expected_deprecated_class
+    );
+    assert_eq!(
+        class_disk_cache.get(deprecated_class_hash).unwrap(),
+        Some(expected_deprecated_class)
+    );
+
+    // A cached class is not served at a state where it is not declared.
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(0))
+        .with_class_disk_cache(class_disk_cache.clone());
+    assert!(matches!(
+        papyrus_reader.get_compiled_contract_class(&class_hash),
+        Err(StateError::UndeclaredClassHash(undeclared_class_hash))
+        if undeclared_class_hash == class_hash
+    ));
+    let ((empty_storage_reader, _), _) = papyrus_storage::test_utils::get_test_storage();
+    let mut papyrus_reader = PapyrusReader::new(empty_storage_reader.clone(), BlockNumber(1))
+        .with_class_disk_cache(class_disk_cache);
+    assert!(matches!(
+        papyrus_reader.get_compiled_contract_class(&class_hash),
+        Err(StateError::UndeclaredClassHash(_))
+    ));
+
+    drop(papyrus_reader);
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_get_compiled_class_hash_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let (class_hash, later_class_hash) = (class_hash!("0x10"), class_hash!("0x20"));
+    let deprecated_class_hash = class_hash!(TEST_CLASS_HASH);
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x11"));
+    let declared_classes =
+        IndexMap::from([(class_hash, (compiled_class_hash, StarknetApiContractClass::default()))]);
+    let state_diff = StateDiff { declared_classes, ..Default::default() };
+    let test_contract = get_deprecated_contract_class(TEST_CONTRACT_CAIRO0_PATH);
+    let later_declared_classes = IndexMap::from([(
+        later_class_hash,
+        (CompiledClassHash(stark_felt!("0x21")), StarknetApiContractClass::default()),
+    )]);
+    let later_state_diff =
+        StateDiff { declared_classes: later_declared_classes, ..Default::default() };
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(
+            BlockNumber(0),
+            state_diff,
+            IndexMap::from([(deprecated_class_hash, test_contract)]),
+        )?
+        .append_state_diff(BlockNumber(1), StateDiff::default(), IndexMap::new())?
+        .append_state_diff(BlockNumber(2), later_state_diff, IndexMap::new())?
+        .commit()?;
+
+    // Classes declared after the latest block, deprecated classes and undeclared classes have the
+    // default compiled class hash.
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(1));
+    assert_eq!(papyrus_reader.get_compiled_class_hash(class_hash).unwrap(), compiled_class_hash);
+    for class_hash in [later_class_hash, deprecated_class_hash, class_hash!("0x30")] {
+        assert_eq!(
+            papyrus_reader.get_compiled_class_hash(class_hash).unwrap(),
+            CompiledClassHash::default()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_class_declared_at_latest_block_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let class_hash = class_hash!("0x10");
+    let compiled_class_hash = CompiledClassHash(stark_felt!("0x11"));
+    let declared_classes =
+        IndexMap::from([(class_hash, (compiled_class_hash, StarknetApiContractClass::default()))]);
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber(0), StateDiff::default(), IndexMap::new())?
+        .append_state_diff(
+            BlockNumber(1),
+            StateDiff { declared_classes, ..Default::default() },
+            IndexMap::new(),
+        )?
+        .commit()?;
+
+    // The state at the latest block precedes its declarations.
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(1));
+    assert_eq!(
+        papyrus_reader.get_compiled_class_hash(class_hash).unwrap(),
+        CompiledClassHash::default()
+    );
+    assert!(matches!(
+        papyrus_reader.get_compiled_contract_class(&class_hash),
+        Err(StateError::UndeclaredClassHash(undeclared_class_hash))
+        if undeclared_class_hash == class_hash
+    ));
+
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(2));
+    assert_eq!(papyrus_reader.get_compiled_class_hash(class_hash).unwrap(), compiled_class_hash);
+
+    Ok(())
+}
+
+#[test]
+fn test_historical_and_pending_reads_with_papyrus_state() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+    let storage_diff = |value: u8| StateDiff {
+        storage_diffs: IndexMap::from([(
+            contract_address,
+            IndexMap::from([(key, stark_felt!(value))]),
+        )]),
+        ..Default::default()
+    };
+    let declared_class_hash = class_hash!("0x20");
+    let declared_classes = IndexMap::from([(
+        declared_class_hash,
+        (CompiledClassHash(stark_felt!("0x21")), StarknetApiContractClass::default()),
+    )]);
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber(0), storage_diff(1), IndexMap::new())?
+        .append_state_diff(
+            BlockNumber(1),
+            StateDiff { declared_classes, ..storage_diff(2) },
+            IndexMap::new(),
+        )?
+        .commit()?;
+
+    // Reads at historical blocks see neither the values nor the classes of later blocks.
+    let mut papyrus_reader =
+        PapyrusReader::new_at_block(storage_reader.clone(), BlockNumber(0)).unwrap();
+    assert_eq!(papyrus_reader.get_storage_at(contract_address, key).unwrap(), stark_felt!(1_u8));
+    assert_eq!(
+        papyrus_reader.get_compiled_class_hash(declared_class_hash).unwrap(),
+        CompiledClassHash::default()
+    );
+    let mut papyrus_reader = PapyrusReader::new_at_latest(storage_reader.clone()).unwrap();
+    assert_eq!(papyrus_reader.get_storage_at(contract_address, key).unwrap(), stark_felt!(2_u8));
+    assert_eq!(
+        papyrus_reader.get_compiled_class_hash(declared_class_hash).unwrap(),
+        CompiledClassHash(stark_felt!("0x21"))
+    );
+    assert!(matches!(
+        PapyrusReader::new_at_block(storage_reader.clone(), BlockNumber(2)),
+        Err(StateError::StateReadError(_))
+    ));
+
+    // A pending block is read on top of the latest one.
+    let pending_class_hash = class_hash!(TEST_CLASS_HASH);
+    let pending_contract_class: ContractClass =
+        ContractClassV0::from_file(TEST_CONTRACT_CAIRO0_PATH).into();
+    let pending_state_diff = CommitmentStateDiff {
+        address_to_class_hash: indexmap! { contract_address => pending_class_hash },
+        address_to_nonce: indexmap! { contract_address => Nonce(stark_felt!(1_u8)) },
+        storage_updates: indexmap! {},
+        class_hash_to_compiled_class_hash: indexmap! {},
+    };
+    let mut pending_reader = PendingStateReader::new(
+        PapyrusReader::new_at_latest(storage_reader.clone()).unwrap(),
+        pending_state_diff,
+        HashMap::from([(pending_class_hash, pending_contract_class.clone())]),
+    );
+    assert_eq!(pending_reader.get_storage_at(contract_address, key).unwrap(), stark_felt!(2_u8));
+    assert_eq!(pending_reader.get_nonce_at(contract_address).unwrap(), Nonce(stark_felt!(1_u8)));
+    assert_eq!(pending_reader.get_class_hash_at(contract_address).unwrap(), pending_class_hash);
+    assert_eq!(
+        pending_reader.get_compiled_contract_class(&pending_class_hash).unwrap(),
+        pending_contract_class
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_papyrus_reader_does_not_hold_a_read_txn() -> papyrus_storage::StorageResult<()> {
+    let ((storage_reader, mut storage_writer), _) = papyrus_storage::test_utils::get_test_storage();
+
+    let contract_address = contract_address!(TEST_CONTRACT_ADDRESS);
+    let key = StorageKey(patricia_key!("0x10"));
+    let value = stark_felt!(18_u8);
+    let state_diff = StateDiff {
+        storage_diffs: IndexMap::from([(contract_address, IndexMap::from([(key, value)]))]),
+        ..Default::default()
+    };
+
+    // Each read begins its own read transaction, and so sees the storage as of the read.
+    let mut papyrus_reader = PapyrusReader::new(storage_reader.clone(), BlockNumber(1));
+    assert_eq!(papyrus_reader.get_storage_at(contract_address, key).unwrap(), StarkFelt::default());
+    storage_writer
+        .begin_rw_txn()?
+        .append_state_diff(BlockNumber::default(), state_diff, IndexMap::new())?
+        .commit()?;
+
+    assert_eq!(papyrus_reader.get_storage_at(contract_address, key).unwrap(), value);
+    assert_eq!(papyrus_reader.get_storage_batch(&[(contract_address, key)]).unwrap(), vec![value]);
+
+    Ok(())
+}
@@ -1,16 +1,20 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use blockifier::block_context::BlockContext;
+use blockifier::block_context::{BlockContext, FeeTokenAddresses, GasPrices};
 use blockifier::state::cached_state::GlobalContractCache;
+use blockifier::state::class_disk_cache::{ClassDiskCache, ClassDiskCacheResult};
+use blockifier::state::contract_class_cache::CacheCapacity;
 use pyo3::prelude::*;
 use starknet_api::block::{BlockNumber, BlockTimestamp};
 use starknet_api::core::{ChainId, ContractAddress};
 
 use crate::errors::NativeBlockifierResult;
+use crate::papyrus_state::PapyrusReader;
 use crate::py_state_diff::{PyBlockInfo, PyStateDiff};
 use crate::py_transaction_execution_info::{PyTransactionExecutionInfo, PyVmExecutionResources};
-use crate::py_utils::{int_to_chain_id, PyFelt};
+use crate::py_utils::{int_to_chain_id, py_optional_attr, PyFelt};
 use crate::storage::{Storage, StorageConfig};
 use crate::transaction_executor::TransactionExecutor;
 
@@ -21,20 +25,58 @@ pub struct PyBlockExecutor {
     pub tx_executor: Option<TransactionExecutor>,
     pub storage: Storage,
     pub global_contract_cache: GlobalContractCache,
+    pub class_disk_cache: Option<Arc<ClassDiskCache>>,
 }
 
 #[pymethods]
 impl PyBlockExecutor {
+    /// The global contract cache holds up to `global_contract_cache_size` classes, or, if
+    /// `weigh_classes_by_bytecode_length` is set, classes of up to this total bytecode length;
+    /// by default, see `GlobalContractCache::DEFAULT_CAPACITY` and its bytecode length variant.
+    /// If `class_disk_cache_dir` is given, classes are also cached on disk, and the
+    /// `n_classes_to_warm_up` most executed ones, declared in storage, are preloaded into the
+    /// global contract cache.
     #[new]
-    #[pyo3(signature = (general_config, max_recursion_depth, target_storage_config))]
+    #[pyo3(signature = (
+        general_config,
+        max_recursion_depth,
+        target_storage_config,
+        global_contract_cache_size = None,
+        weigh_classes_by_bytecode_length = false,
+        class_disk_cache_dir = None,
+        n_classes_to_warm_up = 0
+    ))]
     pub fn create(
         general_config: PyGeneralConfig,
         max_recursion_depth: usize,
         target_storage_config: StorageConfig,
+        global_contract_cache_size: Option<usize>,
+        weigh_classes_by_bytecode_length: bool,
+        class_disk_cache_dir: Option<PathBuf>,
+        n_classes_to_warm_up: usize,
     ) -> Self {
         log::debug!("Initializing Block Executor...");
         let tx_executor = None;
         let storage = Storage::new(target_storage_config).expect("Failed to initialize storage");
+        let global_contract_cache = GlobalContractCache::new(global_contract_cache_capacity(
+            global_contract_cache_size,
+            weigh_classes_by_bytecode_length,
+        ));
+        let class_disk_cache = class_disk_cache_dir.map(|dir| {
+            let class_disk_cache =
+                Arc::new(ClassDiskCache::open(dir).expect("Failed to open class disk cache"));
+            let n_loaded_classes = warm_up_global_contract_cache(
+                &storage,
+                &class_disk_cache,
+                &global_contract_cache,
+                n_classes_to_warm_up,
+            );
+            match n_loaded_classes {
+                Ok(n_loaded_classes) => log::debug!("Preloaded {n_loaded_classes} classes."),
+                Err(error) => log::warn!("Failed to preload classes: {error}."),
+            }
+            class_disk_cache
+        });
 
         log::debug!("Initialized Block Executor.");
         Self {
@@ -42,7 +84,8 @@ impl PyBlockExecutor {
             max_recursion_depth,
             tx_executor,
             storage,
-            global_contract_cache: GlobalContractCache::default(),
+            global_contract_cache,
+            class_disk_cache,
         }
     }
 
@@ -62,6 +105,7 @@ impl PyBlockExecutor {
             next_block_info,
             self.max_recursion_depth,
             self.global_contract_cache.clone(),
+            self.class_disk_cache.clone(),
         )?;
         self.tx_executor = Some(tx_executor);
 
@@ -72,6 +116,19 @@ impl PyBlockExecutor {
         self.tx_executor = None;
     }
 
+    /// Returns the hit, miss and eviction counters of the global contract cache, along with its
+    /// current number of classes and total weight.
+    pub fn get_global_contract_cache_stats(&self) -> HashMap<&'static str, u64> {
+        let stats = self.global_contract_cache.stats();
+        HashMap::from([
+            ("hits", stats.hits),
+            ("misses", stats.misses),
+            ("evictions", stats.evictions),
+            ("n_classes", stats.n_classes as u64),
+            ("total_weight", stats.total_weight as u64),
+        ])
+    }
+
     #[pyo3(signature = (tx, raw_contract_class, enough_room_for_tx))]
     pub fn execute(
         &mut self,
@@ -169,6 +226,11 @@ impl PyBlockExecutor {
         // to deallocate the transaction executor here to prevent leaks.
         self.teardown_block_execution();
         self.storage.close();
+        if let Some(class_disk_cache) = &self.class_disk_cache {
+            if let Err(error) = class_disk_cache.save_index() {
+                log::warn!("Failed to save the class disk cache index: {error}.");
+            }
+        }
     }
 
     #[pyo3(signature = (general_config, path))]
@@ -180,10 +242,36 @@ impl PyBlockExecutor {
             max_recursion_depth: 50,
             tx_executor: None,
             global_contract_cache: GlobalContractCache::default(),
+            class_disk_cache: None,
         }
     }
 }
 
+fn global_contract_cache_capacity(
+    size: Option<usize>,
+    weigh_classes_by_bytecode_length: bool,
+) -> CacheCapacity {
+    match (size, weigh_classes_by_bytecode_length) {
+        (None, false) => GlobalContractCache::DEFAULT_CAPACITY,
+        (None, true) => GlobalContractCache::DEFAULT_BYTECODE_LENGTH_CAPACITY,
+        (Some(size), false) => CacheCapacity::Classes(size),
+        (Some(size), true) => CacheCapacity::BytecodeLength(size),
+    }
+}
+
+/// Preloads the most executed classes through a reader of the latest state in storage, so that
+/// only classes declared in it are served from the global contract cache.
+fn warm_up_global_contract_cache(
+    storage: &Storage,
+    class_disk_cache: &Arc<ClassDiskCache>,
+    global_contract_cache: &GlobalContractCache,
+    n_classes: usize,
+) -> ClassDiskCacheResult<usize> {
+    let mut papyrus_reader = PapyrusReader::new_at_latest(storage.reader().clone())?
+        .with_class_disk_cache(class_disk_cache.clone());
+    class_disk_cache.warm_up(&mut papyrus_reader, global_contract_cache, n_classes)
+}
+
 impl PyBlockExecutor {
     pub fn tx_executor(&mut self) -> &mut TransactionExecutor {
         self.tx_executor.as_mut().expect("Transaction executor should be initialized")
@@ -216,13 +304,35 @@ impl FromPyObject<'_> for PyGeneralConfig {
     }
 }
 
-#[derive(FromPyObject, Clone)]
+#[derive(Clone)]
 pub struct PyOsConfig {
-    #[pyo3(from_py_with = "int_to_chain_id")]
     pub chain_id: ChainId,
+    // The ETH fee token; paid by transactions of version below 3.
     pub fee_token_address: PyFelt,
+    // The STRK fee token; paid by transactions of version 3. May be missing, in which case these
+    // transactions cannot be executed.
+    pub strk_fee_token_address: Option<PyFelt>,
+}
+
+impl FromPyObject<'_> for PyOsConfig {
+    fn extract(os_config: &PyAny) -> PyResult<Self> {
+        let chain_id = int_to_chain_id(os_config.getattr("chain_id")?)?;
+        let fee_token_address = os_config.getattr("fee_token_address")?.extract()?;
+        let strk_fee_token_address = py_optional_attr(os_config, "strk_fee_token_address")?;
+
+        Ok(Self { chain_id, fee_token_address, strk_fee_token_address })
+    }
+}
+
+/// Returns whether transactions of version 3 can be executed with the given configuration and
+/// block info; they require the STRK fee token address and gas price.
+pub fn supports_v3_txs(general_config: &PyGeneralConfig, block_info: &PyBlockInfo) -> bool {
+    general_config.starknet_os_config.strk_fee_token_address.is_some()
+        && block_info.strk_l1_gas_price.is_some()
 }
 
+/// A missing STRK fee token address or gas price is set to zero; such a block context must not be
+/// used to execute transactions of version 3 (see `supports_v3_txs`).
 pub fn into_block_context(
     general_config: &PyGeneralConfig,
     block_info: PyBlockInfo,
@@ -235,9 +345,22 @@ pub fn into_block_context(
         block_number,
         block_timestamp: BlockTimestamp(block_info.block_timestamp),
         sequencer_address: ContractAddress::try_from(block_info.sequencer_address.0)?,
-        fee_token_address: ContractAddress::try_from(starknet_os_config.fee_token_address.0)?,
+        fee_token_addresses: FeeTokenAddresses {
+            eth_fee_token_address: ContractAddress::try_from(
+                starknet_os_config.fee_token_address.0,
+            )?,
+            strk_fee_token_address: match starknet_os_config.strk_fee_token_address {
+                Some(strk_fee_token_address) => {
+                    ContractAddress::try_from(strk_fee_token_address.0)?
+                }
+                None => ContractAddress::default(),
+            },
+        },
         vm_resource_fee_cost: general_config.cairo_resource_fee_weights.clone(),
-        gas_price: block_info.gas_price,
+        gas_prices: GasPrices {
+            eth_l1_gas_price: block_info.gas_price,
+            strk_l1_gas_price: block_info.strk_l1_gas_price.unwrap_or_default(),
+        },
         invoke_tx_max_n_steps: general_config.invoke_tx_max_n_steps,
         validate_max_n_steps: general_config.validate_max_n_steps,
         max_recursion_depth,
@@ -8,7 +8,7 @@ use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::state::{StateDiff, StorageKey};
 
 use crate::errors::{NativeBlockifierError, NativeBlockifierResult};
-use crate::py_utils::PyFelt;
+use crate::py_utils::{py_optional_attr, PyFelt};
 
 #[pyclass]
 #[derive(FromPyObject)]
@@ -114,10 +114,23 @@ impl From<CommitmentStateDiff> for PyStateDiff {
     }
 }
 
-#[derive(FromPyObject)]
 pub struct PyBlockInfo {
     pub block_number: u64,
     pub block_timestamp: u64,
     pub gas_price: u128,
+    // In fri; may be missing, in which case transactions of version 3 cannot be executed.
+    pub strk_l1_gas_price: Option<u128>,
     pub sequencer_address: PyFelt,
 }
+
+impl FromPyObject<'_> for PyBlockInfo {
+    fn extract(block_info: &PyAny) -> PyResult<Self> {
+        Ok(Self {
+            block_number: block_info.getattr("block_number")?.extract()?,
+            block_timestamp: block_info.getattr("block_timestamp")?.extract()?,
+            gas_price: block_info.getattr("gas_price")?.extract()?,
+            strk_l1_gas_price: py_optional_attr(block_info, "strk_l1_gas_price")?,
+            sequencer_address: block_info.getattr("sequencer_address")?.extract()?,
+        })
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
@@ -8,9 +9,16 @@ use blockifier::transaction::objects::AccountTransactionContext;
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transaction_types::TransactionType;
 use blockifier::transaction::transactions::{
-    DeclareTransaction, DeployAccountTransaction, InvokeTransaction, L1HandlerTransaction,
+    DeclareTransaction, DeployAccountTransaction, DeployTransaction, InvokeTransaction,
+    L1HandlerTransaction,
+};
+use blockifier::transaction::versioned_transactions::{
+    self, AccountDeploymentData, DataAvailabilityMode, DeclareTransactionV3,
+    DeployAccountTransactionV3, InvokeTransactionV3, PaymasterData, Resource, ResourceBounds,
+    ResourceBoundsMapping, Tip,
 };
 use num_bigint::BigUint;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use starknet_api::core::{
     ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector, Nonce,
@@ -38,16 +46,94 @@ fn py_calldata(tx: &PyAny, attr: &str) -> NativeBlockifierResult<Calldata> {
     Ok(Calldata(Arc::from(py_felt_sequence_attr(tx, attr)?)))
 }
 
+/// A resource of a version 3 transaction, given by its (Python enum) name.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+struct PyResource(Resource);
+
+impl<'py> FromPyObject<'py> for PyResource {
+    fn extract(resource: &'py PyAny) -> PyResult<Self> {
+        let resource_name: String = resource.getattr("name")?.extract()?;
+        match resource_name.as_str() {
+            "L1_GAS" => Ok(Self(Resource::L1Gas)),
+            "L2_GAS" => Ok(Self(Resource::L2Gas)),
+            _ => Err(PyValueError::new_err(format!("Invalid resource: {resource_name}."))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, FromPyObject)]
+struct PyResourceBounds {
+    max_amount: u64,
+    max_price_per_unit: u128,
+}
+
+/// A data-availability mode, given by its (Python enum) value.
+#[derive(Clone, Copy)]
+struct PyDataAvailabilityMode(DataAvailabilityMode);
+
+impl<'py> FromPyObject<'py> for PyDataAvailabilityMode {
+    fn extract(mode: &'py PyAny) -> PyResult<Self> {
+        let mode_value: u8 = mode.getattr("value")?.extract()?;
+        match mode_value {
+            0 => Ok(Self(DataAvailabilityMode::L1)),
+            1 => Ok(Self(DataAvailabilityMode::L2)),
+            _ => {
+                Err(PyValueError::new_err(format!("Invalid data-availability mode: {mode_value}.")))
+            }
+        }
+    }
+}
+
+fn py_resource_bounds(tx: &PyAny) -> NativeBlockifierResult<ResourceBoundsMapping> {
+    let resource_bounds: HashMap<PyResource, PyResourceBounds> = py_attr(tx, "resource_bounds")?;
+    Ok(ResourceBoundsMapping(
+        resource_bounds
+            .into_iter()
+            .map(|(PyResource(resource), PyResourceBounds { max_amount, max_price_per_unit })| {
+                (resource, ResourceBounds { max_amount, max_price_per_unit })
+            })
+            .collect(),
+    ))
+}
+
+fn py_data_availability_mode(
+    tx: &PyAny,
+    attr: &str,
+) -> NativeBlockifierResult<DataAvailabilityMode> {
+    let PyDataAvailabilityMode(mode) = py_attr(tx, attr)?;
+    Ok(mode)
+}
+
+/// Returns the account data of the transaction; up to version 2, the transaction pays by max fee,
+/// and from version 3, by resource bounds.
 pub fn py_account_data_context(tx: &PyAny) -> NativeBlockifierResult<AccountTransactionContext> {
     let nonce: Option<BigUint> = py_attr(tx, "nonce")?;
     let nonce = Nonce(biguint_to_felt(nonce.unwrap_or_default())?);
-    Ok(AccountTransactionContext {
+    let common_context = AccountTransactionContext {
         transaction_hash: TransactionHash(py_felt_attr(tx, "hash_value")?),
-        max_fee: Fee(py_attr(tx, "max_fee")?),
         version: TransactionVersion(py_felt_attr(tx, "version")?),
         signature: TransactionSignature(py_felt_sequence_attr(tx, "signature")?),
         nonce,
         sender_address: ContractAddress::try_from(py_felt_attr(tx, "sender_address")?)?,
+        ..Default::default()
+    };
+    if !common_context.is_v3() {
+        return Ok(AccountTransactionContext {
+            max_fee: Fee(py_attr(tx, "max_fee")?),
+            ..common_context
+        });
+    }
+
+    Ok(AccountTransactionContext {
+        resource_bounds: py_resource_bounds(tx)?,
+        tip: Tip(py_attr(tx, "tip")?),
+        nonce_data_availability_mode: py_data_availability_mode(
+            tx,
+            "nonce_data_availability_mode",
+        )?,
+        fee_data_availability_mode: py_data_availability_mode(tx, "fee_data_availability_mode")?,
+        paymaster_data: PaymasterData(py_felt_sequence_attr(tx, "paymaster_data")?),
+        ..common_context
     })
 }
 
@@ -69,7 +155,7 @@ pub fn py_declare(
                 class_hash,
                 sender_address: account_data_context.sender_address,
             };
-            Ok(starknet_api::transaction::DeclareTransaction::V0(declare_tx))
+            Ok(versioned_transactions::DeclareTransaction::V0(declare_tx))
         }
         1 => {
             let declare_tx = DeclareTransactionV0V1 {
@@ -79,7 +165,7 @@ pub fn py_declare(
                 class_hash,
                 sender_address: account_data_context.sender_address,
             };
-            Ok(starknet_api::transaction::DeclareTransaction::V1(declare_tx))
+            Ok(versioned_transactions::DeclareTransaction::V1(declare_tx))
         }
         2 => {
             let compiled_class_hash = CompiledClassHash(py_felt_attr(tx, "compiled_class_hash")?);
@@ -91,7 +177,27 @@ pub fn py_declare(
                 sender_address: account_data_context.sender_address,
                 compiled_class_hash,
             };
-            Ok(starknet_api::transaction::DeclareTransaction::V2(declare_tx))
+            Ok(versioned_transactions::DeclareTransaction::V2(declare_tx))
+        }
+        3 => {
+            let compiled_class_hash = CompiledClassHash(py_felt_attr(tx, "compiled_class_hash")?);
+            let declare_tx = DeclareTransactionV3 {
+                resource_bounds: account_data_context.resource_bounds,
+                tip: account_data_context.tip,
+                signature: account_data_context.signature,
+                nonce: account_data_context.nonce,
+                class_hash,
+                compiled_class_hash,
+                sender_address: account_data_context.sender_address,
+                nonce_data_availability_mode: account_data_context.nonce_data_availability_mode,
+                fee_data_availability_mode: account_data_context.fee_data_availability_mode,
+                paymaster_data: account_data_context.paymaster_data,
+                account_deployment_data: AccountDeploymentData(py_felt_sequence_attr(
+                    tx,
+                    "account_deployment_data",
+                )?),
+            };
+            Ok(versioned_transactions::DeclareTransaction::V3(declare_tx))
         }
         _ => Err(NativeBlockifierInputError::UnsupportedTransactionVersion {
             tx_type: TransactionType::Declare,
@@ -100,11 +206,12 @@ pub fn py_declare(
     }?;
 
     let contract_class = match sn_api_tx {
-        starknet_api::transaction::DeclareTransaction::V0(_)
-        | starknet_api::transaction::DeclareTransaction::V1(_) => {
+        versioned_transactions::DeclareTransaction::V0(_)
+        | versioned_transactions::DeclareTransaction::V1(_) => {
             ContractClassV0::try_from_json_string(raw_contract_class)?.into()
         }
-        starknet_api::transaction::DeclareTransaction::V2(_) => {
+        versioned_transactions::DeclareTransaction::V2(_)
+        | versioned_transactions::DeclareTransaction::V3(_) => {
             ContractClassV1::try_from_json_string(raw_contract_class)?.into()
         }
     };
@@ -114,15 +221,35 @@ pub fn py_declare(
 
 pub fn py_deploy_account(tx: &PyAny) -> NativeBlockifierResult<DeployAccountTransaction> {
     let account_data_context = py_account_data_context(tx)?;
+    let class_hash = ClassHash(py_felt_attr(tx, "class_hash")?);
+    let contract_address_salt = ContractAddressSalt(py_felt_attr(tx, "contract_address_salt")?);
+    let constructor_calldata = py_calldata(tx, "constructor_calldata")?;
 
-    let tx = starknet_api::transaction::DeployAccountTransaction {
-        max_fee: account_data_context.max_fee,
-        version: account_data_context.version,
-        signature: account_data_context.signature,
-        nonce: account_data_context.nonce,
-        class_hash: ClassHash(py_felt_attr(tx, "class_hash")?),
-        contract_address_salt: ContractAddressSalt(py_felt_attr(tx, "contract_address_salt")?),
-        constructor_calldata: py_calldata(tx, "constructor_calldata")?,
+    let tx = if account_data_context.is_v3() {
+        versioned_transactions::DeployAccountTransaction::V3(DeployAccountTransactionV3 {
+            resource_bounds: account_data_context.resource_bounds,
+            tip: account_data_context.tip,
+            signature: account_data_context.signature,
+            nonce: account_data_context.nonce,
+            class_hash,
+            contract_address_salt,
+            constructor_calldata,
+            nonce_data_availability_mode: account_data_context.nonce_data_availability_mode,
+            fee_data_availability_mode: account_data_context.fee_data_availability_mode,
+            paymaster_data: account_data_context.paymaster_data,
+        })
+    } else {
+        versioned_transactions::DeployAccountTransaction::V1(
+            starknet_api::transaction::DeployAccountTransaction {
+                max_fee: account_data_context.max_fee,
+                version: account_data_context.version,
+                signature: account_data_context.signature,
+                nonce: account_data_context.nonce,
+                class_hash,
+                contract_address_salt,
+                constructor_calldata,
+            },
+        )
     };
     Ok(DeployAccountTransaction {
         tx,
@@ -131,25 +258,52 @@ pub fn py_deploy_account(tx: &PyAny) -> NativeBlockifierResult<DeployAccountTran
     })
 }
 
+pub fn py_deploy(tx: &PyAny) -> NativeBlockifierResult<DeployTransaction> {
+    let tx_hash = TransactionHash(py_felt_attr(tx, "hash_value")?);
+    let contract_address = ContractAddress::try_from(py_felt_attr(tx, "contract_address")?)?;
+    let tx = starknet_api::transaction::DeployTransaction {
+        version: TransactionVersion(py_felt_attr(tx, "version")?),
+        class_hash: ClassHash(py_felt_attr(tx, "class_hash")?),
+        contract_address_salt: ContractAddressSalt(py_felt_attr(tx, "contract_address_salt")?),
+        constructor_calldata: py_calldata(tx, "constructor_calldata")?,
+    };
+    Ok(DeployTransaction { tx, tx_hash, contract_address })
+}
+
 pub fn py_invoke_function(tx: &PyAny) -> NativeBlockifierResult<InvokeTransaction> {
     let account_data_context = py_account_data_context(tx)?;
 
     let version = usize::try_from(account_data_context.version.0)?;
     let sn_api_tx = match version {
-        0 => Ok(starknet_api::transaction::InvokeTransaction::V0(InvokeTransactionV0 {
+        0 => Ok(versioned_transactions::InvokeTransaction::V0(InvokeTransactionV0 {
             max_fee: account_data_context.max_fee,
             signature: account_data_context.signature,
             contract_address: account_data_context.sender_address,
             entry_point_selector: EntryPointSelector(py_felt_attr(tx, "entry_point_selector")?),
             calldata: py_calldata(tx, "calldata")?,
         })),
-        1 => Ok(starknet_api::transaction::InvokeTransaction::V1(InvokeTransactionV1 {
+        1 => Ok(versioned_transactions::InvokeTransaction::V1(InvokeTransactionV1 {
             max_fee: account_data_context.max_fee,
             signature: account_data_context.signature,
             nonce: account_data_context.nonce,
             sender_address: account_data_context.sender_address,
             calldata: py_calldata(tx, "calldata")?,
         })),
+        3 => Ok(versioned_transactions::InvokeTransaction::V3(InvokeTransactionV3 {
+            resource_bounds: account_data_context.resource_bounds,
+            tip: account_data_context.tip,
+            signature: account_data_context.signature,
+            nonce: account_data_context.nonce,
+            sender_address: account_data_context.sender_address,
+            calldata: py_calldata(tx, "calldata")?,
+            nonce_data_availability_mode: account_data_context.nonce_data_availability_mode,
+            fee_data_availability_mode: account_data_context.fee_data_availability_mode,
+            paymaster_data: account_data_context.paymaster_data,
+            account_deployment_data: AccountDeploymentData(py_felt_sequence_attr(
+                tx,
+                "account_deployment_data",
+            )?),
+        })),
         _ => Err(NativeBlockifierInputError::UnsupportedTransactionVersion {
             tx_type: TransactionType::InvokeFunction,
             version,
@@ -183,6 +337,7 @@ pub fn py_tx(
             let declare_tx = AccountTransaction::Declare(py_declare(tx, raw_contract_class)?);
             Ok(Transaction::AccountTransaction(declare_tx))
         }
+        "DEPLOY" => Ok(Transaction::DeployTransaction(py_deploy(tx)?)),
         "DEPLOY_ACCOUNT" => {
             let deploy_account_tx = AccountTransaction::DeployAccount(py_deploy_account(tx)?);
             Ok(Transaction::AccountTransaction(deploy_account_tx))
@@ -202,6 +357,9 @@ pub fn py_tx(
                 paid_fee_on_l1,
             }))
         }
-        _ => unimplemented!(),
+        _ => Err(NativeBlockifierInputError::UnsupportedTransactionType {
+            tx_type: tx_type.to_string(),
+        }
+        .into()),
     }
 }
@@ -18,6 +18,9 @@ pub struct PyTransactionExecutionInfo {
     pub fee_transfer_call_info: Option<PyCallInfo>,
     #[pyo3(get)]
     pub actual_fee: u128,
+    // The unit of the actual fee: "WEI" (ETH) or "FRI" (STRK).
+    #[pyo3(get)]
+    pub fee_unit: String,
     #[pyo3(get)]
     pub actual_resources: HashMap<String, usize>,
     #[pyo3(get)]
@@ -32,6 +35,10 @@ impl From<TransactionExecutionInfo> for PyTransactionExecutionInfo {
             execute_call_info: info.execute_call_info.map(PyCallInfo::from),
             fee_transfer_call_info: info.fee_transfer_call_info.map(PyCallInfo::from),
             actual_fee: info.actual_fee.0,
+            fee_unit: match serde_json::to_value(info.fee_unit) {
+                Ok(serde_json::Value::String(fee_unit)) => fee_unit,
+                _ => unreachable!("Fee units serialize to their names."),
+            },
             actual_resources: info.actual_resources.0,
             revert_error: info.revert_error,
         }
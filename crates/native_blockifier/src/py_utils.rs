@@ -95,6 +95,17 @@ where
     Ok(obj.getattr(attr)?.extract()?)
 }
 
+/// Returns the given attribute of the given object, or `None` if it is missing or `None`.
+pub fn py_optional_attr<'a, T>(obj: &'a PyAny, attr: &str) -> PyResult<Option<T>>
+where
+    T: FromPyObject<'a>,
+{
+    if !obj.hasattr(attr)? {
+        return Ok(None);
+    }
+    obj.getattr(attr)?.extract()
+}
+
 pub fn py_enum_name<T>(obj: &PyAny, attr: &str) -> NativeBlockifierResult<T>
 where
     T: for<'a> FromPyObject<'a>,
@@ -101,15 +101,15 @@ impl Storage {
         );
         let block_number = BlockNumber(py_block_info.block_number);
         let state_number = StateNumber(block_number);
+        // A single read transaction serves all the lookups of the block.
+        let ro_txn = self.reader().begin_ro_txn()?;
+        let state_reader = ro_txn.get_state_reader()?;
 
         // Deserialize contract classes.
         let mut deprecated_declared_classes = IndexMap::<ClassHash, DeprecatedContractClass>::new();
         for (class_hash, raw_class) in deprecated_declared_class_hash_to_class {
             let class_hash = ClassHash(class_hash.0);
-            let class_undeclared = self
-                .reader()
-                .begin_ro_txn()?
-                .get_state_reader()?
+            let class_undeclared = state_reader
                 .get_deprecated_class_definition_at(state_number, &class_hash)?
                 .is_none();
 
@@ -125,12 +125,8 @@ impl Storage {
         let mut replaced_classes = IndexMap::<ContractAddress, ClassHash>::new();
         for (address, class_hash) in &py_state_diff.address_to_class_hash {
             let address = ContractAddress::try_from(address.0)?;
-            let address_assigned: bool = self
-                .reader()
-                .begin_ro_txn()?
-                .get_state_reader()?
-                .get_class_hash_at(state_number, &address)?
-                .is_some();
+            let address_assigned: bool =
+                state_reader.get_class_hash_at(state_number, &address)?.is_some();
 
             if address_assigned {
                 replaced_classes.insert(address, ClassHash(class_hash.0));
@@ -145,12 +141,8 @@ impl Storage {
         let mut undeclared_casm_contracts = Vec::<(ClassHash, CasmContractClass)>::new();
         for (class_hash, (compiled_class_hash, raw_class)) in declared_class_hash_to_class {
             let class_hash = ClassHash(class_hash.0);
-            let class_undeclared = self
-                .reader()
-                .begin_ro_txn()?
-                .get_state_reader()?
-                .get_class_definition_at(state_number, &class_hash)?
-                .is_none();
+            let class_undeclared =
+                state_reader.get_class_definition_at(state_number, &class_hash)?.is_none();
 
             if class_undeclared {
                 declared_classes.insert(
@@ -162,6 +154,9 @@ impl Storage {
             }
         }
 
+        drop(state_reader);
+        drop(ro_txn);
+
         let mut append_txn = self.writer().begin_rw_txn()?;
         for (class_hash, contract_class) in undeclared_casm_contracts {
             append_txn = append_txn.append_casm(&class_hash, &contract_class)?;
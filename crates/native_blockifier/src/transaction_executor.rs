@@ -1,19 +1,20 @@
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use blockifier::block_context::BlockContext;
-use blockifier::block_execution::pre_process_block;
+use blockifier::block_execution::{self, pre_process_block};
 use blockifier::state::cached_state::{CachedState, GlobalContractCache, TransactionalState};
-use blockifier::state::state_api::{State, StateReader};
+use blockifier::state::class_disk_cache::ClassDiskCache;
+use blockifier::state::state_api::State;
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::ExecutableTransaction;
-use cairo_vm::vm::runners::cairo_runner::ExecutionResources as VmExecutionResources;
 use pyo3::prelude::*;
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::ClassHash;
 
-use crate::errors::{NativeBlockifierError, NativeBlockifierResult};
+use crate::errors::{NativeBlockifierError, NativeBlockifierInputError, NativeBlockifierResult};
 use crate::papyrus_state::PapyrusReader;
-use crate::py_block_executor::{into_block_context, PyGeneralConfig};
+use crate::py_block_executor::{into_block_context, supports_v3_txs, PyGeneralConfig};
 use crate::py_state_diff::{PyBlockInfo, PyStateDiff};
 use crate::py_transaction::py_tx;
 use crate::py_transaction_execution_info::{PyTransactionExecutionInfo, PyVmExecutionResources};
@@ -25,9 +26,14 @@ use crate::storage::Storage;
 
 pub struct TransactionExecutor {
     pub block_context: BlockContext,
+    // Whether the STRK fee token address and gas price are known; otherwise, transactions of
+    // version 3 cannot be executed.
+    supports_v3_txs: bool,
 
     // Maintained for counting purposes.
     pub executed_class_hashes: HashSet<ClassHash>,
+    // Counts the executions of classes, to preload the most executed ones in later runs.
+    class_disk_cache: Option<Arc<ClassDiskCache>>,
 
     // State-related fields.
     pub state: CachedState<PapyrusReader>,
@@ -40,19 +46,22 @@ impl TransactionExecutor {
         block_info: PyBlockInfo,
         max_recursion_depth: usize,
         global_contract_cache: GlobalContractCache,
+        class_disk_cache: Option<Arc<ClassDiskCache>>,
     ) -> NativeBlockifierResult<Self> {
         log::debug!("Initializing Transaction Executor...");
         // Assumption: storage is aligned.
         let reader = papyrus_storage.reader().clone();
 
+        let supports_v3_txs = supports_v3_txs(general_config, &block_info);
         let block_context = into_block_context(general_config, block_info, max_recursion_depth)?;
-        let state = CachedState::new(
-            PapyrusReader::new(reader, block_context.block_number),
-            global_contract_cache,
-        );
+        let mut papyrus_reader = PapyrusReader::new(reader, block_context.block_number);
+        if let Some(class_disk_cache) = &class_disk_cache {
+            papyrus_reader = papyrus_reader.with_class_disk_cache(class_disk_cache.clone());
+        }
+        let state = CachedState::new(papyrus_reader, global_contract_cache);
         let executed_class_hashes = HashSet::<ClassHash>::new();
         log::debug!("Initialized Transaction Executor.");
-        Ok(Self { block_context, executed_class_hashes, state })
+        Ok(Self { block_context, supports_v3_txs, executed_class_hashes, class_disk_cache, state })
     }
 
     /// Executes the given transaction on the state maintained by the executor.
@@ -67,6 +76,11 @@ impl TransactionExecutor {
     ) -> NativeBlockifierResult<(Py<PyTransactionExecutionInfo>, PyVmExecutionResources)> {
         let tx_type: String = py_enum_name(tx, "tx_type")?;
         let tx: Transaction = py_tx(&tx_type, tx, raw_contract_class)?;
+        if let Transaction::AccountTransaction(account_tx) = &tx {
+            if account_tx.get_account_transaction_context().is_v3() && !self.supports_v3_txs {
+                return Err(NativeBlockifierInputError::MissingStrkFeeConfig.into());
+            }
+        }
 
         let mut tx_executed_class_hashes = HashSet::<ClassHash>::new();
         let mut transactional_state = CachedState::create_transactional(&mut self.state);
@@ -114,6 +128,11 @@ impl TransactionExecutor {
         match has_enough_room_for_tx {
             Ok(_) => {
                 transactional_state.commit();
+                if let Some(class_disk_cache) = &self.class_disk_cache {
+                    for &class_hash in &tx_executed_class_hashes {
+                        class_disk_cache.record_access(class_hash);
+                    }
+                }
                 self.executed_class_hashes.extend(&tx_executed_class_hashes);
                 Ok((py_tx_execution_info, py_casm_hash_calculation_resources))
             }
@@ -168,15 +187,11 @@ pub fn get_casm_hash_calculation_resources(
     executed_class_hashes: &HashSet<ClassHash>,
     tx_executed_class_hashes: &HashSet<ClassHash>,
 ) -> NativeBlockifierResult<PyVmExecutionResources> {
-    let newly_executed_class_hashes: HashSet<&ClassHash> =
-        tx_executed_class_hashes.difference(executed_class_hashes).collect();
-
-    let mut casm_hash_computation_resources = VmExecutionResources::default();
-
-    for class_hash in newly_executed_class_hashes {
-        let class = state.get_compiled_contract_class(class_hash)?;
-        casm_hash_computation_resources += &class.estimate_casm_hash_computation_resources();
-    }
+    let casm_hash_computation_resources = block_execution::get_casm_hash_calculation_resources(
+        state,
+        executed_class_hashes,
+        tx_executed_class_hashes,
+    )?;
 
     Ok(PyVmExecutionResources::from(casm_hash_computation_resources))
 }